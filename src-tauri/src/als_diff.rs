@@ -0,0 +1,364 @@
+// Structural diff between two parsed .als versions — tracks, plugins, tempo,
+// key, time signature, arrangement length and samples — plus a one-line
+// summary for the version timeline.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::als_parser::AlsMetadata;
+use crate::db::models::{PluginInfo, SetDiff, TrackInfo, TrackRename, ValueChange};
+
+/// Tempo differences smaller than this are treated as unchanged.
+const TEMPO_EPSILON: f64 = 0.005;
+/// Arrangement length differences smaller than this (seconds) are treated as unchanged.
+const LENGTH_EPSILON: f64 = 0.01;
+/// How many names the summary lists before collapsing to a count.
+const SUMMARY_MAX_NAMES: usize = 3;
+
+/// Compare two versions of a set. `old` is the earlier version.
+pub fn diff_sets(old: &AlsMetadata, new: &AlsMetadata) -> SetDiff {
+    let (tracks_added, tracks_removed, tracks_renamed) = diff_tracks(&old.tracks, &new.tracks);
+    let (plugins_added, plugins_removed) = diff_plugins(&old.plugins, &new.plugins);
+
+    let old_samples: Vec<&str> = old.samples.iter().map(|s| s.path.as_str()).collect();
+    let new_samples: Vec<&str> = new.samples.iter().map(|s| s.path.as_str()).collect();
+    let (samples_added, samples_removed) = diff_lists(&old_samples, &new_samples);
+
+    let mut diff = SetDiff {
+        tracks_added,
+        tracks_removed,
+        tracks_renamed,
+        plugins_added,
+        plugins_removed,
+        tempo: float_change(old.bpm, new.bpm, TEMPO_EPSILON),
+        key: value_change(key_label(old), key_label(new)),
+        time_signature: value_change(
+            old.time_signature.map(|t| t.to_string()),
+            new.time_signature.map(|t| t.to_string()),
+        ),
+        arrangement_length_seconds: float_change(
+            old.arrangement_length_seconds,
+            new.arrangement_length_seconds,
+            LENGTH_EPSILON,
+        ),
+        samples_added,
+        samples_removed,
+        summary: String::new(),
+    };
+    diff.summary = summarize(&diff);
+    diff
+}
+
+/// Match tracks by Live's track Id first (so renames are detected), then by
+/// name for whatever is left — older sets or tracks whose Id changed.
+/// Returns (added, removed, renamed) in arrangement order.
+fn diff_tracks(old: &[TrackInfo], new: &[TrackInfo]) -> (Vec<String>, Vec<String>, Vec<TrackRename>) {
+    let new_by_id: HashMap<i64, usize> = new.iter()
+        .enumerate()
+        .filter_map(|(i, t)| t.live_id.map(|id| (id, i)))
+        .collect();
+
+    let mut renamed = Vec::new();
+    let mut matched_new: HashSet<usize> = HashSet::new();
+    let mut unmatched_old: Vec<&str> = Vec::new();
+    for track in old {
+        match track.live_id.and_then(|id| new_by_id.get(&id)) {
+            Some(&i) => {
+                matched_new.insert(i);
+                if new[i].name != track.name {
+                    renamed.push(TrackRename { from: track.name.clone(), to: new[i].name.clone() });
+                }
+            }
+            None => unmatched_old.push(&track.name),
+        }
+    }
+    let unmatched_new: Vec<&str> = new.iter()
+        .enumerate()
+        .filter(|(i, _)| !matched_new.contains(i))
+        .map(|(_, t)| t.name.as_str())
+        .collect();
+
+    let (added, removed) = diff_lists(&unmatched_old, &unmatched_new);
+    (added, removed, renamed)
+}
+
+/// Plugins are compared by (name, type), keeping the parser's order.
+fn diff_plugins(old: &[PluginInfo], new: &[PluginInfo]) -> (Vec<PluginInfo>, Vec<PluginInfo>) {
+    let key = |p: &PluginInfo| (p.name.clone(), p.plugin_type.clone());
+    let old_keys: HashSet<_> = old.iter().map(key).collect();
+    let new_keys: HashSet<_> = new.iter().map(key).collect();
+    let added = new.iter().filter(|p| !old_keys.contains(&key(p))).cloned().collect();
+    let removed = old.iter().filter(|p| !new_keys.contains(&key(p))).cloned().collect();
+    (added, removed)
+}
+
+/// Multiset difference of two string lists: (in new only, in old only).
+/// Duplicates count, so a second "Audio" track shows up as added.
+fn diff_lists(old: &[&str], new: &[&str]) -> (Vec<String>, Vec<String>) {
+    let mut remaining: HashMap<&str, usize> = HashMap::new();
+    for name in old {
+        *remaining.entry(name).or_default() += 1;
+    }
+    let mut added = Vec::new();
+    for name in new {
+        match remaining.get_mut(name) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => added.push(name.to_string()),
+        }
+    }
+
+    let mut removed = Vec::new();
+    for name in old {
+        if let Some(count) = remaining.get_mut(name) {
+            if *count > 0 {
+                *count -= 1;
+                removed.push(name.to_string());
+            }
+        }
+    }
+    (added, removed)
+}
+
+/// "C Minor", matching the key labels used elsewhere in the app.
+fn key_label(meta: &AlsMetadata) -> Option<String> {
+    let tonic = meta.key_tonic.as_ref()?;
+    Some(match &meta.key_scale {
+        Some(scale) => format!("{} {}", tonic, scale),
+        None => tonic.clone(),
+    })
+}
+
+fn value_change<T: PartialEq>(before: Option<T>, after: Option<T>) -> Option<ValueChange<T>> {
+    (before != after).then_some(ValueChange { before, after })
+}
+
+fn float_change(before: Option<f64>, after: Option<f64>, epsilon: f64) -> Option<ValueChange<f64>> {
+    let changed = match (before, after) {
+        (Some(a), Some(b)) => (a - b).abs() >= epsilon,
+        (a, b) => a.is_some() != b.is_some(),
+    };
+    changed.then_some(ValueChange { before, after })
+}
+
+/// One line such as "added tracks Bass, Vox; tempo 120 → 124 BPM; +3 samples".
+fn summarize(diff: &SetDiff) -> String {
+    let mut parts: Vec<String> = Vec::new();
+
+    if !diff.tracks_added.is_empty() {
+        parts.push(format!("added {}", named_list("track", &diff.tracks_added)));
+    }
+    if !diff.tracks_removed.is_empty() {
+        parts.push(format!("removed {}", named_list("track", &diff.tracks_removed)));
+    }
+    for rename in diff.tracks_renamed.iter().take(SUMMARY_MAX_NAMES) {
+        parts.push(format!("renamed {} → {}", rename.from, rename.to));
+    }
+    if diff.tracks_renamed.len() > SUMMARY_MAX_NAMES {
+        parts.push(format!("{} more renamed", diff.tracks_renamed.len() - SUMMARY_MAX_NAMES));
+    }
+
+    let plugin_names = |plugins: &[PluginInfo]| plugins.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+    if !diff.plugins_added.is_empty() {
+        parts.push(format!("added {}", named_list("plugin", &plugin_names(&diff.plugins_added))));
+    }
+    if !diff.plugins_removed.is_empty() {
+        parts.push(format!("removed {}", named_list("plugin", &plugin_names(&diff.plugins_removed))));
+    }
+
+    if let Some(tempo) = &diff.tempo {
+        parts.push(format!("tempo {} → {} BPM", fmt_opt(tempo.before, fmt_bpm), fmt_opt(tempo.after, fmt_bpm)));
+    }
+    if let Some(key) = &diff.key {
+        parts.push(format!("key {} → {}", fmt_opt(key.before.as_deref(), str::to_string), fmt_opt(key.after.as_deref(), str::to_string)));
+    }
+    if let Some(ts) = &diff.time_signature {
+        parts.push(format!("time signature {} → {}", fmt_opt(ts.before.as_deref(), str::to_string), fmt_opt(ts.after.as_deref(), str::to_string)));
+    }
+    if let Some(len) = &diff.arrangement_length_seconds {
+        parts.push(format!("length {} → {}", fmt_opt(len.before, fmt_duration), fmt_opt(len.after, fmt_duration)));
+    }
+
+    if !diff.samples_added.is_empty() {
+        parts.push(format!("+{}", count_noun(diff.samples_added.len(), "sample")));
+    }
+    if !diff.samples_removed.is_empty() {
+        parts.push(format!("−{}", count_noun(diff.samples_removed.len(), "sample")));
+    }
+
+    if parts.is_empty() {
+        return "No structural changes".to_string();
+    }
+    let mut summary = parts.join("; ");
+    // Capitalise the first letter only — names inside keep their case
+    if let Some(first) = summary.get(..1) {
+        summary = first.to_uppercase() + &summary[1..];
+    }
+    summary
+}
+
+/// "track Bass", "tracks Bass, Vox" or "5 tracks" when the list is long.
+fn named_list(noun: &str, names: &[String]) -> String {
+    if names.len() > SUMMARY_MAX_NAMES {
+        return count_noun(names.len(), noun);
+    }
+    let plural = if names.len() == 1 { "" } else { "s" };
+    format!("{}{} {}", noun, plural, names.join(", "))
+}
+
+fn count_noun(count: usize, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
+
+fn fmt_opt<T>(value: Option<T>, fmt: impl Fn(T) -> String) -> String {
+    value.map(fmt).unwrap_or_else(|| "none".to_string())
+}
+
+/// Whole BPMs print without decimals: 120, 123.5.
+fn fmt_bpm(bpm: f64) -> String {
+    let rounded = (bpm * 100.0).round() / 100.0;
+    if rounded.fract() == 0.0 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{}", rounded)
+    }
+}
+
+/// m:ss, like the rest of the UI.
+fn fmt_duration(seconds: f64) -> String {
+    let total = seconds.round() as i64;
+    format!("{}:{:02}", total / 60, total % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::als_parser::{SampleRef, TimeSignature};
+    use crate::db::models::SetTempoMap;
+
+    fn meta() -> AlsMetadata {
+        AlsMetadata {
+            bpm: Some(120.0),
+            key_tonic: Some("C".to_string()),
+            key_scale: Some("Minor".to_string()),
+            plugins: Vec::new(),
+            samples: Vec::new(),
+            tracks: Vec::new(),
+            locators: Vec::new(),
+            time_signature: Some(TimeSignature { numerator: 4, denominator: 4 }),
+            tempo_map: SetTempoMap::default(),
+            tempo_min: Some(120.0),
+            tempo_max: Some(120.0),
+            tempo_avg: Some(120.0),
+            arrangement_length_beats: Some(384.0),
+            arrangement_length_bars: Some(96.0),
+            arrangement_length_seconds: Some(192.0),
+            scene_count: 0,
+            session_clip_count: 0,
+            arrangement_clip_count: 0,
+            live_version: None,
+            midi_clips: Vec::new(),
+            pitch_class_histogram: [0.0; 12],
+            estimated_key: None,
+        }
+    }
+
+    fn track(live_id: Option<i64>, name: &str) -> TrackInfo {
+        TrackInfo {
+            live_id,
+            name: name.to_string(),
+            track_type: "audio".to_string(),
+            color_index: None,
+            is_muted: false,
+            is_soloed: false,
+            is_frozen: false,
+            devices: Vec::new(),
+            device_chain: Vec::new(),
+        }
+    }
+
+    fn plugin(name: &str) -> PluginInfo {
+        PluginInfo { name: name.to_string(), plugin_type: "vst3".to_string() }
+    }
+
+    fn sample(path: &str) -> SampleRef {
+        SampleRef {
+            path: path.to_string(),
+            filename: path.rsplit('/').next().unwrap().to_string(),
+            relative_path: None,
+            relative_path_type: None,
+            pack_name: None,
+        }
+    }
+
+    #[test]
+    fn test_identical_sets_have_no_changes() {
+        let diff = diff_sets(&meta(), &meta());
+        assert!(diff.tracks_added.is_empty() && diff.tracks_removed.is_empty());
+        assert!(diff.tempo.is_none() && diff.key.is_none() && diff.time_signature.is_none());
+        assert!(diff.arrangement_length_seconds.is_none());
+        assert_eq!(diff.summary, "No structural changes");
+    }
+
+    #[test]
+    fn test_tracks_matched_by_id_detect_renames() {
+        let mut old = meta();
+        old.tracks = vec![track(Some(1), "Drums"), track(Some(2), "Lead"), track(Some(3), "Pad")];
+        let mut new = meta();
+        new.tracks = vec![track(Some(1), "Drums"), track(Some(2), "Hook"), track(Some(4), "Vox")];
+
+        let diff = diff_sets(&old, &new);
+        assert_eq!(diff.tracks_added, vec!["Vox"]);
+        assert_eq!(diff.tracks_removed, vec!["Pad"]);
+        assert_eq!(diff.tracks_renamed, vec![TrackRename { from: "Lead".into(), to: "Hook".into() }]);
+    }
+
+    #[test]
+    fn test_tracks_without_ids_fall_back_to_names() {
+        let mut old = meta();
+        old.tracks = vec![track(None, "Audio"), track(None, "Bass")];
+        let mut new = meta();
+        new.tracks = vec![track(None, "Audio"), track(None, "Audio"), track(None, "Bass")];
+
+        let diff = diff_sets(&old, &new);
+        assert_eq!(diff.tracks_added, vec!["Audio"], "duplicate names count separately");
+        assert!(diff.tracks_removed.is_empty());
+        assert!(diff.tracks_renamed.is_empty());
+    }
+
+    #[test]
+    fn test_plugin_sample_and_value_changes() {
+        let mut old = meta();
+        old.plugins = vec![plugin("Serum"), plugin("OTT")];
+        old.samples = vec![sample("/s/kick.wav"), sample("/s/snare.wav")];
+        let mut new = meta();
+        new.plugins = vec![plugin("Serum"), plugin("Pro-Q 3")];
+        new.samples = vec![sample("/s/kick.wav"), sample("/s/clap.wav"), sample("/s/hat.wav")];
+        new.bpm = Some(124.0);
+        new.key_tonic = Some("D".to_string());
+        new.time_signature = Some(TimeSignature { numerator: 7, denominator: 8 });
+        new.arrangement_length_seconds = Some(220.0);
+
+        let diff = diff_sets(&old, &new);
+        assert_eq!(diff.plugins_added[0].name, "Pro-Q 3");
+        assert_eq!(diff.plugins_removed[0].name, "OTT");
+        assert_eq!(diff.samples_added, vec!["/s/clap.wav", "/s/hat.wav"]);
+        assert_eq!(diff.samples_removed, vec!["/s/snare.wav"]);
+        assert_eq!(diff.tempo, Some(ValueChange { before: Some(120.0), after: Some(124.0) }));
+        assert_eq!(diff.key.unwrap().after.as_deref(), Some("D Minor"));
+        assert_eq!(diff.time_signature.unwrap().after.as_deref(), Some("7/8"));
+        assert!(diff.arrangement_length_seconds.is_some());
+        assert_eq!(
+            diff.summary,
+            "Added plugin Pro-Q 3; removed plugin OTT; tempo 120 → 124 BPM; key C Minor → D Minor; \
+             time signature 4/4 → 7/8; length 3:12 → 3:40; +2 samples; −1 sample"
+        );
+    }
+
+    #[test]
+    fn test_summary_collapses_long_lists() {
+        let old = meta();
+        let mut new = meta();
+        new.tracks = (1..=5).map(|i| track(Some(i), &format!("T{}", i))).collect();
+        let diff = diff_sets(&old, &new);
+        assert_eq!(diff.summary, "Added 5 tracks");
+    }
+}
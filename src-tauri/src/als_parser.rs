@@ -1,33 +1,334 @@
-// .als file parser — extracts BPM, key, plugins, and sample references from
+// .als file parser — extracts BPM, key, time signature, plugins, tracks,
+// locators, arrangement length, MIDI clip notes and sample references from
 // Ableton Live Set files. .als files are gzip-compressed XML.
 
 use std::collections::HashSet;
 use std::io::Read;
 use std::path::Path;
 
-use crate::db::models::PluginInfo;
+use crate::db::models::{
+    ArrangementLocator, DeviceInfo, MidiClip, MidiNote, PluginInfo, SetTempoMap, TempoPoint, TimeSignatureChange,
+    TrackInfo,
+};
+
+mod key_estimate;
+mod stream;
+
+pub use key_estimate::EstimatedKey;
+pub(crate) use key_estimate::best_key;
+
+/// Compressed .als size above which `parse_als` switches from building a full
+/// XML tree to the streaming parser. Big orchestral sets decompress to
+/// hundreds of MB of XML.
+const STREAMING_THRESHOLD_BYTES: u64 = 4 * 1024 * 1024;
 
 /// A sample reference extracted from the .als file.
+#[derive(Debug, Clone, PartialEq)]
 pub struct SampleRef {
     pub path: String,
     pub filename: String,
+    /// <RelativePath> from the same FileRef (Live 10+), resolved against the
+    /// base given by `relative_path_type`.
+    pub relative_path: Option<String>,
+    pub relative_path_type: Option<i64>,
+    /// <LivePackName> when the sample came from an installed pack.
+    pub pack_name: Option<String>,
 }
 
 /// All metadata extracted from a single .als file.
+#[derive(Debug, PartialEq)]
 pub struct AlsMetadata {
     pub bpm: Option<f64>,
     pub key_tonic: Option<String>,
     pub key_scale: Option<String>,
     pub plugins: Vec<PluginInfo>,
     pub samples: Vec<SampleRef>,
+    pub tracks: Vec<TrackInfo>,
+    pub locators: Vec<ArrangementLocator>,
+    pub time_signature: Option<TimeSignature>,
+    /// Tempo and time-signature changes from the master track automation.
+    /// Tempo points are empty when the set has no readable tempo.
+    pub tempo_map: SetTempoMap,
+    /// Lowest, highest and average tempo over the arrangement (average up to
+    /// the arrangement end, or the last tempo change if there are no clips).
+    pub tempo_min: Option<f64>,
+    pub tempo_max: Option<f64>,
+    pub tempo_avg: Option<f64>,
+    /// End of the last arrangement clip, in beats, bars and seconds.
+    pub arrangement_length_beats: Option<f64>,
+    pub arrangement_length_bars: Option<f64>,
+    pub arrangement_length_seconds: Option<f64>,
+    pub scene_count: i64,
+    pub session_clip_count: i64,
+    pub arrangement_clip_count: i64,
+    /// Live version that saved the file, e.g. "11.3.13".
+    pub live_version: Option<String>,
+    pub midi_clips: Vec<MidiClip>,
+    /// Beats of MIDI note time per pitch class, C first (drum racks excluded).
+    pub pitch_class_histogram: [f64; 12],
+    /// Key guessed from `pitch_class_histogram` — the fallback for sets
+    /// without a <KeySignature>.
+    pub estimated_key: Option<EstimatedKey>,
+}
+
+/// Song time signature, e.g. 4/4 or 7/8.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeSignature {
+    pub numerator: i64,
+    pub denominator: i64,
+}
+
+impl TimeSignature {
+    /// Decode Live's packed <TimeSignature><Manual> value:
+    /// `(numerator - 1) + 99 * log2(denominator)`, so 201 is 4/4.
+    pub fn from_encoded(value: i64) -> Option<Self> {
+        if value < 0 {
+            return None;
+        }
+        let numerator = value % 99 + 1;
+        let denominator = 1i64.checked_shl((value / 99) as u32)?;
+        Some(TimeSignature { numerator, denominator })
+    }
+
+    /// Length of one bar in beats (quarter notes): 4 for 4/4, 3.5 for 7/8.
+    pub fn beats_per_bar(&self) -> f64 {
+        self.numerator as f64 * 4.0 / self.denominator as f64
+    }
+}
+
+impl std::fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+/// Song tempo over the arrangement, used to convert beat positions to seconds.
+/// Always holds at least one point, starting at beat 0.
+#[derive(Debug, Clone)]
+pub struct TempoMap {
+    pub points: Vec<TempoPoint>,
+}
+
+impl TempoMap {
+    pub fn constant(bpm: f64) -> Self {
+        TempoMap { points: vec![TempoPoint { beat: 0.0, bpm }] }
+    }
+
+    /// Build a map from raw automation breakpoints (any order). Falls back to
+    /// a constant `manual_bpm` when there are none.
+    pub fn from_points(mut points: Vec<TempoPoint>, manual_bpm: f64) -> Self {
+        if points.is_empty() {
+            return TempoMap::constant(manual_bpm);
+        }
+        // Stable sort keeps the order of step changes (two events at the same beat)
+        points.sort_by(|a, b| a.beat.total_cmp(&b.beat));
+        if points[0].beat > 0.0 {
+            let first_bpm = points[0].bpm;
+            points.insert(0, TempoPoint { beat: 0.0, bpm: first_bpm });
+        }
+        TempoMap { points }
+    }
+
+    /// Convert an arrangement position in beats to seconds from the song start,
+    /// integrating over linear tempo ramps between breakpoints.
+    pub fn beats_to_seconds(&self, beat: f64) -> f64 {
+        let mut seconds = 0.0;
+        for (i, p) in self.points.iter().enumerate() {
+            if beat <= p.beat {
+                break;
+            }
+            match self.points.get(i + 1) {
+                Some(next) if beat > next.beat => {
+                    seconds += ramp_seconds(p, next, next.beat);
+                }
+                Some(next) => {
+                    seconds += ramp_seconds(p, next, beat);
+                    break;
+                }
+                None => {
+                    seconds += (beat - p.beat) * 60.0 / p.bpm;
+                }
+            }
+        }
+        seconds
+    }
+
+    /// Inverse of `beats_to_seconds`: the arrangement position in beats
+    /// reached `seconds` after the song start.
+    pub fn seconds_to_beats(&self, seconds: f64) -> f64 {
+        let mut elapsed = 0.0;
+        for (i, p) in self.points.iter().enumerate() {
+            let remaining = seconds - elapsed;
+            if remaining <= 0.0 {
+                return p.beat;
+            }
+            match self.points.get(i + 1) {
+                Some(next) => {
+                    let segment = ramp_seconds(p, next, next.beat);
+                    if remaining < segment {
+                        return p.beat + ramp_beats(p, next, remaining);
+                    }
+                    elapsed += segment;
+                }
+                None => return p.beat + remaining * p.bpm / 60.0,
+            }
+        }
+        0.0
+    }
+
+    pub fn min_bpm(&self) -> f64 {
+        self.points.iter().map(|p| p.bpm).fold(f64::INFINITY, f64::min)
+    }
+
+    pub fn max_bpm(&self) -> f64 {
+        self.points.iter().map(|p| p.bpm).fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    /// Average tempo from the song start to `until` (in beats): the beats
+    /// played per minute of song time.
+    pub fn average_bpm(&self, until: f64) -> f64 {
+        let seconds = self.beats_to_seconds(until);
+        if seconds <= 0.0 {
+            return self.points[0].bpm;
+        }
+        until * 60.0 / seconds
+    }
+}
+
+/// Beats covered in `seconds` from `from.beat` while the tempo ramps linearly
+/// towards `to` — the inverse of `ramp_seconds`.
+fn ramp_beats(from: &TempoPoint, to: &TempoPoint, seconds: f64) -> f64 {
+    let span = to.beat - from.beat;
+    let slope = if span > 0.0 { (to.bpm - from.bpm) / span } else { 0.0 };
+    if slope.abs() < 1e-9 {
+        return seconds * from.bpm / 60.0;
+    }
+    let bpm_reached = from.bpm * (slope * seconds / 60.0).exp();
+    (bpm_reached - from.bpm) / slope
+}
+
+/// Song time signature over the arrangement, used to convert beats to bars.
+/// Always holds at least one change, at beat 0.
+#[derive(Debug, Clone)]
+pub struct TimeSignatureMap {
+    pub changes: Vec<TimeSignatureChange>,
+}
+
+impl TimeSignatureMap {
+    /// Build a map from raw automation events (any order). Falls back to the
+    /// constant `manual` signature (or 4/4) when there are none.
+    pub fn from_points(mut changes: Vec<TimeSignatureChange>, manual: Option<TimeSignature>) -> Self {
+        if changes.is_empty() {
+            let ts = manual.unwrap_or(TimeSignature { numerator: 4, denominator: 4 });
+            return TimeSignatureMap {
+                changes: vec![TimeSignatureChange { beat: 0.0, numerator: ts.numerator, denominator: ts.denominator }],
+            };
+        }
+        changes.sort_by(|a, b| a.beat.total_cmp(&b.beat));
+        if changes[0].beat > 0.0 {
+            let first = TimeSignatureChange { beat: 0.0, ..changes[0].clone() };
+            changes.insert(0, first);
+        }
+        TimeSignatureMap { changes }
+    }
+
+    /// Bars elapsed from the song start to `beat` (fractional).
+    pub fn beats_to_bars(&self, beat: f64) -> f64 {
+        let mut bars = 0.0;
+        for (i, c) in self.changes.iter().enumerate() {
+            if beat <= c.beat {
+                break;
+            }
+            let end = self.changes.get(i + 1).map_or(beat, |next| next.beat.min(beat));
+            bars += (end - c.beat) / beats_per_bar(c);
+        }
+        bars
+    }
+
+    /// Inverse of `beats_to_bars`.
+    pub fn bars_to_beats(&self, bars: f64) -> f64 {
+        let mut elapsed = 0.0;
+        for (i, c) in self.changes.iter().enumerate() {
+            let per_bar = beats_per_bar(c);
+            match self.changes.get(i + 1) {
+                Some(next) if elapsed + (next.beat - c.beat) / per_bar < bars => {
+                    elapsed += (next.beat - c.beat) / per_bar;
+                }
+                _ => return c.beat + (bars - elapsed).max(0.0) * per_bar,
+            }
+        }
+        0.0
+    }
+}
+
+fn beats_per_bar(change: &TimeSignatureChange) -> f64 {
+    TimeSignature { numerator: change.numerator, denominator: change.denominator }.beats_per_bar()
+}
+
+/// Converts song positions between seconds and bars for a stored tempo map.
+/// Sets without a stored tempo fall back to a constant `fallback_bpm`.
+pub struct SongTimeline {
+    tempo: TempoMap,
+    meter: TimeSignatureMap,
+}
+
+impl SongTimeline {
+    pub fn new(map: &SetTempoMap, fallback_bpm: Option<f64>) -> Self {
+        SongTimeline {
+            tempo: TempoMap::from_points(map.tempo_points.clone(), fallback_bpm.unwrap_or(DEFAULT_TEMPO)),
+            meter: TimeSignatureMap::from_points(map.time_signatures.clone(), None),
+        }
+    }
+
+    /// Bars elapsed at `seconds` from the song start (0.0 = the first downbeat).
+    pub fn seconds_to_bars(&self, seconds: f64) -> f64 {
+        self.meter.beats_to_bars(self.tempo.seconds_to_beats(seconds))
+    }
+
+    pub fn bars_to_seconds(&self, bars: f64) -> f64 {
+        self.tempo.beats_to_seconds(self.meter.bars_to_beats(bars))
+    }
 }
 
+/// Seconds elapsed from `from.beat` to `until` while the tempo ramps linearly
+/// from `from.bpm` (at from.beat) to `to.bpm` (at to.beat).
+fn ramp_seconds(from: &TempoPoint, to: &TempoPoint, until: f64) -> f64 {
+    let span = to.beat - from.beat;
+    let beats = until - from.beat;
+    if span <= 0.0 || beats <= 0.0 {
+        return 0.0;
+    }
+    let slope = (to.bpm - from.bpm) / span; // BPM per beat
+    if slope.abs() < 1e-9 {
+        return beats * 60.0 / from.bpm;
+    }
+    let bpm_at_until = from.bpm + slope * beats;
+    60.0 / slope * (bpm_at_until / from.bpm).ln()
+}
+
+/// Tempo Live falls back to when a set has no readable tempo.
+const DEFAULT_TEMPO: f64 = 120.0;
+
 /// Tonic integer (0-11) to note name mapping used by Ableton's KeySignature node.
 const TONIC_NAMES: [&str; 12] = ["C", "C#", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B"];
 
 /// Parse an Ableton Live Set (.als) file and extract metadata.
+/// Large files are parsed in a single streaming pass in bounded memory;
+/// smaller ones via a full XML tree. Both paths return the same metadata.
 /// Returns Err on I/O or XML parse failures — never panics.
 pub fn parse_als(path: &Path) -> Result<AlsMetadata, String> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to open .als file: {}", e))?
+        .len();
+    if size >= STREAMING_THRESHOLD_BYTES {
+        stream::parse_als_streaming(path)
+    } else {
+        parse_als_dom(path)
+    }
+}
+
+/// Decompress the whole file and walk it as a roxmltree document.
+fn parse_als_dom(path: &Path) -> Result<AlsMetadata, String> {
     // Read and decompress the gzip file
     let file = std::fs::File::open(path)
         .map_err(|e| format!("Failed to open .als file: {}", e))?;
@@ -40,18 +341,97 @@ pub fn parse_als(path: &Path) -> Result<AlsMetadata, String> {
     let doc = roxmltree::Document::parse(&xml_string)
         .map_err(|e| format!("Failed to parse .als XML: {}", e))?;
 
-    let bpm = extract_bpm(&doc);
     let (key_tonic, key_scale) = extract_key(&doc);
-    let plugins = extract_plugins(&doc);
-    let samples = extract_samples(&doc);
-
-    Ok(AlsMetadata {
-        bpm,
+    Ok(RawSet {
+        bpm: extract_bpm(&doc),
         key_tonic,
         key_scale,
-        plugins,
-        samples,
-    })
+        plugins: extract_plugins(&doc),
+        samples: extract_samples(&doc),
+        tracks: extract_tracks(&doc),
+        tempo_points: extract_tempo_points(&doc),
+        time_signature_points: extract_time_signature_points(&doc),
+        locators: extract_locators(&doc),
+        time_signature: extract_time_signature(&doc),
+        clip_stats: extract_clip_stats(&doc),
+        scene_count: extract_scene_count(&doc),
+        live_version: extract_live_version(&doc),
+        midi_clips: extract_midi_clips(&doc),
+    }.finish())
+}
+
+/// What either parse path pulls out of the XML, before the derived values
+/// (tempo map, locator times, arrangement length) are computed.
+struct RawSet {
+    bpm: Option<f64>,
+    key_tonic: Option<String>,
+    key_scale: Option<String>,
+    plugins: Vec<PluginInfo>,
+    samples: Vec<SampleRef>,
+    tracks: Vec<TrackInfo>,
+    /// Master-track tempo automation breakpoints, unsorted.
+    tempo_points: Vec<TempoPoint>,
+    /// Master-track time signature automation events, unsorted.
+    time_signature_points: Vec<TimeSignatureChange>,
+    /// Locators in document order; `seconds` is filled in by `finish`.
+    locators: Vec<ArrangementLocator>,
+    time_signature: Option<TimeSignature>,
+    clip_stats: ClipStats,
+    scene_count: i64,
+    live_version: Option<String>,
+    midi_clips: Vec<MidiClip>,
+}
+
+impl RawSet {
+    fn finish(self) -> AlsMetadata {
+        let has_tempo = self.bpm.is_some() || !self.tempo_points.is_empty();
+        let tempo_map = TempoMap::from_points(self.tempo_points, self.bpm.unwrap_or(DEFAULT_TEMPO));
+        let meter = TimeSignatureMap::from_points(self.time_signature_points, self.time_signature);
+        let locators = finish_locators(self.locators, &tempo_map);
+        let arrangement_length_beats = self.clip_stats.arrangement_end;
+        let arrangement_length_bars = arrangement_length_beats.map(|b| meter.beats_to_bars(b));
+        let arrangement_length_seconds = arrangement_length_beats.map(|b| tempo_map.beats_to_seconds(b));
+
+        // Average over the arrangement, or up to the last tempo change when
+        // there are no arrangement clips
+        let average_until = arrangement_length_beats
+            .unwrap_or_else(|| tempo_map.points.last().map_or(0.0, |p| p.beat));
+        let (tempo_min, tempo_max, tempo_avg) = if has_tempo {
+            (Some(tempo_map.min_bpm()), Some(tempo_map.max_bpm()), Some(tempo_map.average_bpm(average_until)))
+        } else {
+            (None, None, None)
+        };
+        let pitch_class_histogram = key_estimate::pitch_class_histogram(&self.midi_clips, &self.tracks);
+        let estimated_key = key_estimate::estimate_key(&pitch_class_histogram);
+
+        AlsMetadata {
+            bpm: self.bpm,
+            key_tonic: self.key_tonic,
+            key_scale: self.key_scale,
+            plugins: self.plugins,
+            samples: self.samples,
+            tracks: self.tracks,
+            locators,
+            time_signature: self.time_signature,
+            tempo_map: SetTempoMap {
+                tempo_points: if has_tempo { tempo_map.points } else { Vec::new() },
+                time_signatures: meter.changes,
+            },
+            tempo_min,
+            tempo_max,
+            tempo_avg,
+            arrangement_length_beats,
+            arrangement_length_bars,
+            arrangement_length_seconds,
+            scene_count: self.scene_count,
+            session_clip_count: self.clip_stats.session_clips,
+            arrangement_clip_count: self.clip_stats.arrangement_clips,
+            live_version: self.live_version,
+            midi_clips: self.midi_clips,
+            pitch_class_histogram,
+            estimated_key,
+        }
+    }
 }
 
 /// Find the first <Tempo> node and read its <Manual Value="..."/> child.
@@ -70,6 +450,255 @@ fn extract_bpm(doc: &roxmltree::Document) -> Option<f64> {
     None
 }
 
+/// Breakpoints of the master track's tempo automation envelope, if the tempo
+/// is automated. `TempoMap::from_points` turns them into the song tempo map.
+fn extract_tempo_points(doc: &roxmltree::Document) -> Vec<TempoPoint> {
+    master_envelope(doc, "Tempo")
+        .map(|env| {
+            env.descendants()
+                .filter(|n| n.has_tag_name("FloatEvent"))
+                .filter_map(|ev| tempo_point(ev.attribute("Time")?, ev.attribute("Value")?))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Events of the master track's time signature automation envelope, if the
+/// time signature changes over the song.
+fn extract_time_signature_points(doc: &roxmltree::Document) -> Vec<TimeSignatureChange> {
+    master_envelope(doc, "TimeSignature")
+        .map(|env| {
+            env.descendants()
+                .filter(|n| n.has_tag_name("EnumEvent"))
+                .filter_map(|ev| time_signature_point(ev.attribute("Time")?, ev.attribute("Value")?))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The automation envelope of a master track parameter (the first `param`
+/// element, e.g. <Tempo>). Its <AutomationTarget Id="N"> is what the
+/// envelope's <PointeeId> refers to.
+fn master_envelope<'a, 'input>(doc: &'a roxmltree::Document<'input>, param: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    let master = live_set_child(doc, "MasterTrack")?;
+    let target_id = master.descendants()
+        .find(|n| n.has_tag_name(param))
+        .and_then(|t| t.children().find(|c| c.has_tag_name("AutomationTarget")))
+        .and_then(|a| a.attribute("Id"))?;
+
+    master.descendants()
+        .filter(|n| n.has_tag_name("AutomationEnvelope"))
+        .find(|env| {
+            env.descendants()
+                .find(|c| c.has_tag_name("PointeeId"))
+                .and_then(|p| p.attribute("Value"))
+                == Some(target_id)
+        })
+}
+
+/// A tempo breakpoint from a <FloatEvent Time Value>. The first event sits at
+/// a huge negative time meaning "value before the song starts" — clamp it to
+/// beat 0. Non-positive tempos are ignored.
+fn tempo_point(time: &str, value: &str) -> Option<TempoPoint> {
+    let time = time.parse::<f64>().ok()?;
+    let bpm = value.parse::<f64>().ok()?;
+    (bpm > 0.0).then_some(TempoPoint { beat: time.max(0.0), bpm })
+}
+
+/// A time signature change from an <EnumEvent Time Value>, with the packed
+/// value decoded and the "before the song" time clamped to beat 0.
+fn time_signature_point(time: &str, value: &str) -> Option<TimeSignatureChange> {
+    let time = time.parse::<f64>().ok()?;
+    let ts = TimeSignature::from_encoded(value.parse::<i64>().ok()?)?;
+    Some(TimeSignatureChange { beat: time.max(0.0), numerator: ts.numerator, denominator: ts.denominator })
+}
+
+/// Extract arrangement locators (<Locators><Locators><Locator>) in document order.
+fn extract_locators(doc: &roxmltree::Document) -> Vec<ArrangementLocator> {
+    let outer = match live_set_child(doc, "Locators") {
+        Some(n) => n,
+        None => return Vec::new(),
+    };
+
+    outer.descendants()
+        .filter(|n| n.has_tag_name("Locator"))
+        .filter_map(|loc| raw_locator(child_value(loc, "Time")?, child_value(loc, "Name")))
+        .collect()
+}
+
+fn raw_locator(time: &str, name: Option<&str>) -> Option<ArrangementLocator> {
+    let beat = time.parse::<f64>().ok()?;
+    Some(ArrangementLocator {
+        name: name.unwrap_or("").trim().to_string(),
+        beat,
+        seconds: 0.0,
+    })
+}
+
+/// Sort locators by time, convert beats to seconds with the song tempo map
+/// and name unnamed ones.
+fn finish_locators(mut locators: Vec<ArrangementLocator>, tempo_map: &TempoMap) -> Vec<ArrangementLocator> {
+    locators.sort_by(|a, b| a.beat.total_cmp(&b.beat));
+
+    for (i, loc) in locators.iter_mut().enumerate() {
+        loc.seconds = tempo_map.beats_to_seconds(loc.beat);
+        // Live shows unnamed locators by their position in the list
+        if loc.name.is_empty() {
+            loc.name = format!("Locator {}", i + 1);
+        }
+    }
+
+    locators
+}
+
+/// Read the master track's <TimeSignature><Manual Value="..."/>.
+fn extract_time_signature(doc: &roxmltree::Document) -> Option<TimeSignature> {
+    let master = live_set_child(doc, "MasterTrack")?;
+    let value = master.descendants()
+        .find(|n| n.has_tag_name("TimeSignature"))
+        .and_then(|ts| child_value(ts, "Manual"))?;
+    TimeSignature::from_encoded(value.parse::<i64>().ok()?)
+}
+
+/// Clip counts and arrangement extent gathered in one pass over the tracks.
+#[derive(Default)]
+struct ClipStats {
+    session_clips: i64,
+    arrangement_clips: i64,
+    /// Latest <CurrentEnd> of any arrangement clip, in beats.
+    arrangement_end: Option<f64>,
+}
+
+/// Count clips in session clip slots (<ClipSlotList>) and in the arrangement
+/// (<ArrangerAutomation><Events>). The arrangement length is the end of the
+/// last arrangement clip; session clips don't contribute to it.
+fn extract_clip_stats(doc: &roxmltree::Document) -> ClipStats {
+    let mut stats = ClipStats::default();
+    let tracks = match live_set_child(doc, "Tracks") {
+        Some(n) => n,
+        None => return stats,
+    };
+    let is_clip = |n: &roxmltree::Node| n.has_tag_name("AudioClip") || n.has_tag_name("MidiClip");
+
+    for node in tracks.descendants() {
+        if node.has_tag_name("ClipSlotList") {
+            stats.session_clips += node.descendants().filter(is_clip).count() as i64;
+        } else if node.has_tag_name("ArrangerAutomation") {
+            let events = node.children().find(|c| c.has_tag_name("Events"));
+            for clip in events.into_iter().flat_map(|e| e.children()).filter(is_clip) {
+                stats.arrangement_clips += 1;
+                if let Some(end) = child_value(clip, "CurrentEnd").and_then(|v| v.parse::<f64>().ok()) {
+                    stats.arrangement_end = Some(stats.arrangement_end.map_or(end, |m| m.max(end)));
+                }
+            }
+        }
+    }
+    stats
+}
+
+/// Extract the MIDI clips in session clip slots and on the arrangement (the
+/// same clips `extract_clip_stats` counts) with their notes. `track_index`
+/// is the track's position in `extract_tracks`.
+fn extract_midi_clips(doc: &roxmltree::Document) -> Vec<MidiClip> {
+    let tracks_node = match live_set_child(doc, "Tracks") {
+        Some(n) => n,
+        None => return Vec::new(),
+    };
+    let is_midi_clip = |n: &roxmltree::Node| n.has_tag_name("MidiClip");
+
+    let mut clips = Vec::new();
+    let track_nodes = tracks_node.children()
+        .filter(|c| TRACK_TYPES.iter().any(|(tag, _)| c.has_tag_name(*tag)));
+    for (track_index, track) in track_nodes.enumerate() {
+        for node in track.descendants() {
+            if node.has_tag_name("ClipSlotList") {
+                for clip in node.descendants().filter(is_midi_clip) {
+                    clips.push(midi_clip(clip, track_index as i64, false));
+                }
+            } else if node.has_tag_name("ArrangerAutomation") {
+                let events = node.children().find(|c| c.has_tag_name("Events"));
+                for clip in events.into_iter().flat_map(|e| e.children()).filter(is_midi_clip) {
+                    clips.push(midi_clip(clip, track_index as i64, true));
+                }
+            }
+        }
+    }
+    clips
+}
+
+/// Read a <MidiClip>: bounds, loop and every note of every
+/// <Notes><KeyTracks><KeyTrack>. A key track's <MidiKey> gives its notes' pitch.
+fn midi_clip(clip: roxmltree::Node, track_index: i64, in_arrangement: bool) -> MidiClip {
+    let number = |node: roxmltree::Node, tag: &str| child_value(node, tag).and_then(|v| v.parse::<f64>().ok());
+    let loop_node = clip.children().find(|c| c.has_tag_name("Loop"));
+
+    let key_tracks = clip.children()
+        .find(|c| c.has_tag_name("Notes"))
+        .and_then(|n| n.children().find(|c| c.has_tag_name("KeyTracks")));
+    let mut notes = Vec::new();
+    for key_track in key_tracks.into_iter().flat_map(|k| k.children()).filter(|c| c.has_tag_name("KeyTrack")) {
+        let Some(key) = child_value(key_track, "MidiKey").and_then(|v| v.parse::<i64>().ok()) else {
+            continue;
+        };
+        let events = key_track.children().find(|c| c.has_tag_name("Notes"));
+        for event in events.into_iter().flat_map(|n| n.children()).filter(|c| c.has_tag_name("MidiNoteEvent")) {
+            let note = midi_note(
+                event.attribute("Time"),
+                event.attribute("Duration"),
+                event.attribute("Velocity"),
+                event.attribute("IsEnabled"),
+            );
+            if let Some(note) = note {
+                notes.push(MidiNote { key, ..note });
+            }
+        }
+    }
+
+    MidiClip {
+        track_index,
+        name: child_value(clip, "Name").unwrap_or("").trim().to_string(),
+        in_arrangement,
+        start: number(clip, "CurrentStart"),
+        end: number(clip, "CurrentEnd"),
+        loop_start: loop_node.and_then(|l| number(l, "LoopStart")),
+        loop_end: loop_node.and_then(|l| number(l, "LoopEnd")),
+        loop_on: loop_node.and_then(|l| child_value(l, "LoopOn")) == Some("true"),
+        notes,
+    }
+}
+
+/// A <MidiNoteEvent> from its attributes, with `key` left at 0 for the
+/// caller. Notes without a readable time, duration or velocity are dropped.
+fn midi_note(time: Option<&str>, duration: Option<&str>, velocity: Option<&str>, is_enabled: Option<&str>) -> Option<MidiNote> {
+    let number = |v: Option<&str>| v.and_then(|v| v.parse::<f64>().ok());
+    Some(MidiNote {
+        key: 0,
+        time: number(time)?,
+        duration: number(duration)?,
+        velocity: number(velocity)?,
+        is_enabled: is_enabled != Some("false"),
+    })
+}
+
+/// Number of scenes: <Scenes><Scene> in Live 11+, <SceneNames><Scene> before.
+fn extract_scene_count(doc: &roxmltree::Document) -> i64 {
+    live_set_child(doc, "Scenes")
+        .or_else(|| live_set_child(doc, "SceneNames"))
+        .map(|n| n.children().filter(|c| c.has_tag_name("Scene")).count() as i64)
+        .unwrap_or(0)
+}
+
+/// Version from the root element's Creator attribute ("Ableton Live 11.3.13").
+fn extract_live_version(doc: &roxmltree::Document) -> Option<String> {
+    live_version_from_creator(doc.root_element().attribute("Creator")?)
+}
+
+fn live_version_from_creator(creator: &str) -> Option<String> {
+    let creator = creator.trim();
+    let version = creator.strip_prefix("Ableton Live").unwrap_or(creator).trim();
+    (!version.is_empty()).then(|| version.to_string())
+}
+
 /// Find <KeySignature> → <Tonic Value="N"/> and <Scale Value="N"/>.
 /// Tonic 0-11 maps to note names; Scale 0=Major, 1=Minor.
 fn extract_key(doc: &roxmltree::Document) -> (Option<String>, Option<String>) {
@@ -80,21 +709,13 @@ fn extract_key(doc: &roxmltree::Document) -> (Option<String>, Option<String>) {
 
             for child in node.descendants() {
                 if child.has_tag_name("Tonic") {
-                    if let Some(val) = child.attribute("Value") {
-                        if let Ok(idx) = val.parse::<usize>() {
-                            if idx < TONIC_NAMES.len() {
-                                tonic = Some(TONIC_NAMES[idx].to_string());
-                            }
-                        }
+                    if let Some(name) = child.attribute("Value").and_then(tonic_name) {
+                        tonic = Some(name.to_string());
                     }
                 }
                 if child.has_tag_name("Scale") {
                     if let Some(val) = child.attribute("Value") {
-                        scale = Some(match val {
-                            "0" => "Major".to_string(),
-                            "1" => "Minor".to_string(),
-                            _ => format!("Scale {}", val),
-                        });
+                        scale = Some(scale_label(val));
                     }
                 }
             }
@@ -107,50 +728,302 @@ fn extract_key(doc: &roxmltree::Document) -> (Option<String>, Option<String>) {
     (None, None)
 }
 
+/// Note name for a <Tonic> value (0-11).
+fn tonic_name(value: &str) -> Option<&'static str> {
+    value.parse::<usize>().ok().and_then(|idx| TONIC_NAMES.get(idx).copied())
+}
+
+/// Label for a <Scale> value: 0=Major, 1=Minor.
+fn scale_label(value: &str) -> String {
+    match value {
+        "0" => "Major".to_string(),
+        "1" => "Minor".to_string(),
+        _ => format!("Scale {}", value),
+    }
+}
+
 /// Extract all unique plugins from the XML tree.
 fn extract_plugins(doc: &roxmltree::Document) -> Vec<PluginInfo> {
     let mut seen = HashSet::new();
     let mut plugins = Vec::new();
 
     for node in doc.descendants() {
-        let (name_tag, plugin_type) = match node.tag_name().name() {
-            "VstPluginInfo" => ("PlugName", "vst2"),
-            "Vst3PluginInfo" => ("Name", "vst3"),
-            "AuPluginInfo" => ("Name", "au"),
-            "MxDeviceInfo" => ("DisplayName", "max_for_live"),
-            _ => continue,
-        };
+        if let Some((name, plugin_type)) = plugin_info_name(node) {
+            push_unique_plugin(&mut seen, &mut plugins, name, plugin_type);
+        }
+    }
 
-        // For Vst3PluginInfo, use direct children only (not descendants)
-        // to avoid picking up the empty <Name> inside <Vst3Preset>
-        let name_value = if node.tag_name().name() == "Vst3PluginInfo" {
-            node.children()
-                .find(|c| c.has_tag_name(name_tag))
-                .and_then(|c| c.attribute("Value"))
-        } else {
-            node.descendants()
-                .find(|c| c.has_tag_name(name_tag))
-                .and_then(|c| c.attribute("Value"))
+    plugins
+}
+
+/// Append a plugin unless one with the same (case-insensitive) name is already listed.
+fn push_unique_plugin(seen: &mut HashSet<String>, plugins: &mut Vec<PluginInfo>, name: &str, plugin_type: &str) {
+    if seen.insert(name.to_lowercase()) {
+        plugins.push(PluginInfo {
+            name: name.to_string(),
+            plugin_type: plugin_type.to_string(),
+        });
+    }
+}
+
+/// For a plugin info element (VST2/VST3/AU/Max for Live), the tag holding its
+/// display name and the plugin type we store.
+fn plugin_info_kind(tag: &str) -> Option<(&'static str, &'static str)> {
+    match tag {
+        "VstPluginInfo" => Some(("PlugName", "vst2")),
+        "Vst3PluginInfo" => Some(("Name", "vst3")),
+        "AuPluginInfo" => Some(("Name", "au")),
+        "MxDeviceInfo" => Some(("DisplayName", "max_for_live")),
+        _ => None,
+    }
+}
+
+/// If `node` is a plugin info node (VST2/VST3/AU/Max for Live), return its
+/// trimmed display name and plugin type.
+fn plugin_info_name<'a>(node: roxmltree::Node<'a, '_>) -> Option<(&'a str, &'static str)> {
+    let (name_tag, plugin_type) = plugin_info_kind(node.tag_name().name())?;
+
+    // For Vst3PluginInfo, use direct children only (not descendants)
+    // to avoid picking up the empty <Name> inside <Vst3Preset>
+    let name_value = if node.tag_name().name() == "Vst3PluginInfo" {
+        node.children()
+            .find(|c| c.has_tag_name(name_tag))
+            .and_then(|c| c.attribute("Value"))
+    } else {
+        node.descendants()
+            .find(|c| c.has_tag_name(name_tag))
+            .and_then(|c| c.attribute("Value"))
+    };
+
+    let trimmed = name_value?.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    Some((trimmed, plugin_type))
+}
+
+/// Track element names under <LiveSet>/<Tracks> and the track_type we store for each.
+const TRACK_TYPES: [(&str, &str); 4] = [
+    ("AudioTrack", "audio"),
+    ("MidiTrack", "midi"),
+    ("GroupTrack", "group"),
+    ("ReturnTrack", "return"),
+];
+
+/// Extract every audio, MIDI, group and return track in arrangement order.
+/// The master and pre-hear tracks live outside <Tracks> and are not included.
+fn extract_tracks(doc: &roxmltree::Document) -> Vec<TrackInfo> {
+    let tracks_node = match live_set_child(doc, "Tracks") {
+        Some(n) => n,
+        None => return Vec::new(),
+    };
+
+    let mut tracks = Vec::new();
+    for node in tracks_node.children().filter(|c| c.is_element()) {
+        let track_type = match TRACK_TYPES.iter().find(|(tag, _)| node.has_tag_name(*tag)) {
+            Some((_, t)) => *t,
+            None => continue,
         };
 
-        if let Some(name) = name_value {
-            let trimmed = name.trim();
-            if !trimmed.is_empty() {
-                let key = trimmed.to_lowercase();
-                if seen.insert(key) {
-                    plugins.push(PluginInfo {
-                        name: trimmed.to_string(),
-                        plugin_type: plugin_type.to_string(),
-                    });
+        // <Name><EffectiveName/> holds what Live displays; <UserName> is empty
+        // unless the user renamed the track.
+        let name_node = node.children().find(|c| c.has_tag_name("Name"));
+        let name = name_node
+            .and_then(|n| child_value(n, "EffectiveName").or_else(|| child_value(n, "UserName")))
+            .unwrap_or("")
+            .trim()
+            .to_string();
+
+        // Live 11+ writes <Color>, older versions write <ColorIndex>
+        let color_index = child_value(node, "Color")
+            .or_else(|| child_value(node, "ColorIndex"))
+            .and_then(|v| v.parse::<i64>().ok());
+
+        let mixer = node.children()
+            .find(|c| c.has_tag_name("DeviceChain"))
+            .and_then(|dc| dc.children().find(|c| c.has_tag_name("Mixer")));
+        // Speaker "on" means the track is audible — muted when false
+        let is_muted = mixer
+            .and_then(|m| m.children().find(|c| c.has_tag_name("Speaker")))
+            .and_then(|s| child_value(s, "Manual"))
+            .map(|v| v == "false")
+            .unwrap_or(false);
+        let is_soloed = mixer
+            .and_then(|m| child_value(m, "SoloSink"))
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        let is_frozen = child_value(node, "Freeze").map(|v| v == "true").unwrap_or(false);
+
+        let mut device_chain = Vec::new();
+        if let Some(devices) = track_devices_node(node) {
+            collect_device_chain(devices, 0, None, None, &mut device_chain);
+        }
+        let devices = device_chain.iter()
+            .filter(|d| d.depth == 0)
+            .map(|d| d.name.clone())
+            .collect();
+
+        tracks.push(TrackInfo {
+            live_id: node.attribute("Id").and_then(|id| id.parse::<i64>().ok()),
+            name,
+            track_type: track_type.to_string(),
+            color_index,
+            is_muted,
+            is_soloed,
+            is_frozen,
+            devices,
+            device_chain,
+        });
+    }
+
+    tracks
+}
+
+/// Find the <Devices> list that sits directly on a track.
+fn track_devices_node<'a, 'input>(track: roxmltree::Node<'a, 'input>) -> Option<roxmltree::Node<'a, 'input>> {
+    // Live 9+ nests the devices as <DeviceChain><DeviceChain><Devices>;
+    // older sets have <DeviceChain><Devices> directly.
+    let outer = track.children().find(|c| c.has_tag_name("DeviceChain"))?;
+    outer.children()
+        .find(|c| c.has_tag_name("DeviceChain"))
+        .and_then(|inner| inner.children().find(|c| c.has_tag_name("Devices")))
+        .or_else(|| outer.children().find(|c| c.has_tag_name("Devices")))
+}
+
+/// Walk a <Devices> list depth-first, appending every device (and the contents
+/// of every rack chain) to `out`. The resulting chain_order follows signal flow
+/// for serial chains: a rack comes before its contents, which come before the
+/// next device on the same level.
+fn collect_device_chain(
+    devices: roxmltree::Node,
+    depth: i64,
+    parent_order: Option<i64>,
+    branch_name: Option<&str>,
+    out: &mut Vec<DeviceInfo>,
+) {
+    for device in devices.children().filter(|c| c.is_element()) {
+        let order = out.len() as i64;
+        let (name, device_type) = device_name_and_type(device);
+
+        let user_name = child_value(device, "UserName")
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_string());
+        // Native devices store <On><Manual Value="true"/></On>
+        let is_on = device.children()
+            .find(|c| c.has_tag_name("On"))
+            .and_then(|on| child_value(on, "Manual"))
+            .map(|v| v != "false")
+            .unwrap_or(true);
+
+        out.push(DeviceInfo {
+            chain_order: order,
+            depth,
+            parent_order,
+            branch_name: branch_name.map(|b| b.to_string()),
+            name,
+            user_name,
+            device_type,
+            is_on,
+            preset_name: device_preset_name(device),
+        });
+
+        // Rack: recurse into each chain (<Branches><...Branch>)
+        if let Some(branches) = device.children().find(|c| c.has_tag_name("Branches")) {
+            for branch in branches.children().filter(|c| c.is_element()) {
+                // Live 11+ uses <Name><EffectiveName/></Name>, older sets <Name Value=""/>
+                let branch_label = branch.children()
+                    .find(|c| c.has_tag_name("Name"))
+                    .and_then(|n| n.attribute("Value").or_else(|| child_value(n, "EffectiveName")))
+                    .map(|v| v.trim())
+                    .filter(|v| !v.is_empty());
+                // The first <Devices> in document order is the branch's own chain
+                if let Some(inner) = branch.descendants().find(|c| c.has_tag_name("Devices")) {
+                    collect_device_chain(inner, depth + 1, Some(order), branch_label, out);
                 }
             }
         }
     }
+}
 
-    plugins
+/// Name and device_type for a device element: plugin hosts use the plugin
+/// name and plugin type, racks are "rack", everything else is "native" and
+/// named by its element (e.g. "Eq8", "Compressor2").
+fn device_name_and_type(device: roxmltree::Node) -> (String, String) {
+    let tag = device.tag_name().name();
+    if is_plugin_host(tag) {
+        // Only look inside the plugin description so nothing else on the
+        // device can be mistaken for the plugin name
+        if let Some((name, plugin_type)) = device.descendants().find_map(plugin_info_name) {
+            return (name.to_string(), plugin_type.to_string());
+        }
+    }
+    (tag.to_string(), non_plugin_device_type(tag).to_string())
+}
+
+fn is_plugin_host(tag: &str) -> bool {
+    tag == "PluginDevice" || tag == "AuPluginDevice" || tag.starts_with("MxDevice")
+}
+
+fn non_plugin_device_type(tag: &str) -> &'static str {
+    if tag.ends_with("GroupDevice") { "rack" } else { "native" }
+}
+
+/// Elements holding a plugin's saved state, including its program name.
+const PLUGIN_PRESET_TAGS: [&str; 3] = ["VstPreset", "Vst3Preset", "AuPreset"];
+
+/// Best-effort preset name: the file stem of the last loaded preset
+/// (<LastPresetRef>), else the program name stored with a plugin's state.
+fn device_preset_name(device: roxmltree::Node) -> Option<String> {
+    let from_file = device.children()
+        .find(|c| c.has_tag_name("LastPresetRef"))
+        .and_then(|r| {
+            r.descendants()
+                .filter(|c| c.has_tag_name("Path") || c.has_tag_name("RelativePath"))
+                .filter_map(|c| c.attribute("Value"))
+                .find(|v| !v.trim().is_empty())
+        })
+        .and_then(preset_file_stem);
+    if from_file.is_some() {
+        return from_file;
+    }
+    // A rack's descendants belong to the devices inside it
+    if device.children().any(|c| c.has_tag_name("Branches")) {
+        return None;
+    }
+
+    device.descendants()
+        .filter(|c| PLUGIN_PRESET_TAGS.contains(&c.tag_name().name()))
+        .filter_map(|preset| child_value(preset, "ProgramName").or_else(|| child_value(preset, "Name")))
+        .map(|v| v.trim())
+        .find(|v| !v.is_empty())
+        .map(|v| v.to_string())
+}
+
+/// Preset name from a preset file path: its file stem.
+fn preset_file_stem(path: &str) -> Option<String> {
+    // Preset paths may use either separator regardless of the current OS
+    let file = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    Path::new(file).file_stem().map(|s| s.to_string_lossy().to_string())
 }
 
-/// Extract all unique sample file references from <SampleRef> → <FileRef> → <Path>.
+/// Find a direct child of <Ableton>/<LiveSet> by tag name.
+fn live_set_child<'a, 'input>(doc: &'a roxmltree::Document<'input>, tag: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    doc.root_element()
+        .children()
+        .find(|c| c.has_tag_name("LiveSet"))
+        .and_then(|ls| ls.children().find(|c| c.has_tag_name(tag)))
+}
+
+/// Read the Value attribute of the first direct child with the given tag.
+fn child_value<'a>(node: roxmltree::Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    node.children()
+        .find(|c| c.has_tag_name(tag))
+        .and_then(|c| c.attribute("Value"))
+}
+
+/// Extract all unique sample file references from <SampleRef> → <FileRef> → <Path>,
+/// with the relative path and pack hints from the same <FileRef>.
 fn extract_samples(doc: &roxmltree::Document) -> Vec<SampleRef> {
     let mut seen = HashSet::new();
     let mut samples = Vec::new();
@@ -160,20 +1033,15 @@ fn extract_samples(doc: &roxmltree::Document) -> Vec<SampleRef> {
             // Look for FileRef → Path child
             for child in node.descendants() {
                 if child.has_tag_name("FileRef") {
+                    let hints = FileRefHints {
+                        relative_path: child_value(child, "RelativePath"),
+                        relative_path_type: child_value(child, "RelativePathType"),
+                        pack_name: child_value(child, "LivePackName"),
+                    };
                     for fc in child.children() {
                         if fc.has_tag_name("Path") {
                             if let Some(path_val) = fc.attribute("Value") {
-                                let trimmed = path_val.trim();
-                                if !trimmed.is_empty() && seen.insert(trimmed.to_string()) {
-                                    let filename = Path::new(trimmed)
-                                        .file_name()
-                                        .map(|f| f.to_string_lossy().to_string())
-                                        .unwrap_or_else(|| trimmed.to_string());
-                                    samples.push(SampleRef {
-                                        path: trimmed.to_string(),
-                                        filename,
-                                    });
-                                }
+                                push_unique_sample(&mut seen, &mut samples, path_val, &hints);
                             }
                         }
                     }
@@ -184,3 +1052,627 @@ fn extract_samples(doc: &roxmltree::Document) -> Vec<SampleRef> {
 
     samples
 }
+
+/// Raw values of the location hints stored next to a sample's <Path> in its <FileRef>.
+struct FileRefHints<'a> {
+    relative_path: Option<&'a str>,
+    relative_path_type: Option<&'a str>,
+    pack_name: Option<&'a str>,
+}
+
+/// Append a sample path unless it is empty or already listed.
+fn push_unique_sample(seen: &mut HashSet<String>, samples: &mut Vec<SampleRef>, path: &str, hints: &FileRefHints) {
+    let trimmed = path.trim();
+    if !trimmed.is_empty() && seen.insert(trimmed.to_string()) {
+        let filename = Path::new(trimmed)
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_else(|| trimmed.to_string());
+        let non_empty = |v: Option<&str>| v.map(|v| v.trim()).filter(|v| !v.is_empty()).map(|v| v.to_string());
+        samples.push(SampleRef {
+            path: trimmed.to_string(),
+            filename,
+            relative_path: non_empty(hints.relative_path),
+            relative_path_type: hints.relative_path_type.and_then(|v| v.trim().parse::<i64>().ok()),
+            pack_name: non_empty(hints.pack_name),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    /// Gzip `xml` into `dir/name` and return the path, mimicking a real .als file.
+    fn write_als(dir: &Path, name: &str, xml: &str) -> std::path::PathBuf {
+        let path = dir.join(name);
+        let file = std::fs::File::create(&path).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        encoder.write_all(xml.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+        path
+    }
+
+    const TRACKS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Ableton MajorVersion="5" MinorVersion="11.0_433" Creator="Ableton Live 11.3.13">
+  <LiveSet>
+    <Tracks>
+      <GroupTrack Id="10">
+        <Name><EffectiveName Value="Drums" /><UserName Value="Drums" /></Name>
+        <Color Value="12" />
+        <DeviceChain>
+          <Mixer><Speaker><Manual Value="true" /></Speaker><SoloSink Value="true" /></Mixer>
+          <DeviceChain><Devices /></DeviceChain>
+        </DeviceChain>
+        <Freeze Value="false" />
+      </GroupTrack>
+      <MidiTrack Id="11">
+        <Name><EffectiveName Value="Bass" /><UserName Value="" /></Name>
+        <Color Value="3" />
+        <DeviceChain>
+          <Mixer><Speaker><Manual Value="false" /></Speaker><SoloSink Value="false" /></Mixer>
+          <DeviceChain>
+            <Devices>
+              <PluginDevice Id="0">
+                <PluginDesc><Vst3PluginInfo Id="0"><Name Value="Serum" /></Vst3PluginInfo></PluginDesc>
+              </PluginDevice>
+              <AudioEffectGroupDevice Id="1">
+                <Branches><AudioEffectBranch><DeviceChain><AudioToAudioDeviceChain><Devices>
+                  <PluginDevice Id="2">
+                    <PluginDesc><VstPluginInfo Id="0"><PlugName Value="OTT" /></VstPluginInfo></PluginDesc>
+                  </PluginDevice>
+                </Devices></AudioToAudioDeviceChain></DeviceChain></AudioEffectBranch></Branches>
+              </AudioEffectGroupDevice>
+              <Eq8 Id="3" />
+            </Devices>
+          </DeviceChain>
+        </DeviceChain>
+        <Freeze Value="true" />
+      </MidiTrack>
+      <AudioTrack Id="12">
+        <Name><EffectiveName Value="Vox" /></Name>
+        <ColorIndex Value="7" />
+        <DeviceChain><Devices><Compressor2 Id="0" /></Devices></DeviceChain>
+      </AudioTrack>
+      <ReturnTrack Id="13">
+        <Name><EffectiveName Value="A-Reverb" /></Name>
+        <DeviceChain><DeviceChain><Devices><Reverb Id="0" /></Devices></DeviceChain></DeviceChain>
+      </ReturnTrack>
+    </Tracks>
+    <MasterTrack>
+      <Name><EffectiveName Value="Master" /></Name>
+    </MasterTrack>
+    <MasterTempo><Tempo><Manual Value="124" /></Tempo></MasterTempo>
+  </LiveSet>
+</Ableton>"#;
+
+    #[test]
+    fn test_parse_tracks_types_and_order() {
+        let tmp = TempDir::new().unwrap();
+        let path = write_als(tmp.path(), "song.als", TRACKS_XML);
+        let meta = parse_als(&path).unwrap();
+
+        let names: Vec<&str> = meta.tracks.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["Drums", "Bass", "Vox", "A-Reverb"], "master track must not be included");
+        let types: Vec<&str> = meta.tracks.iter().map(|t| t.track_type.as_str()).collect();
+        assert_eq!(types, vec!["group", "midi", "audio", "return"]);
+        assert_eq!(meta.bpm, Some(124.0));
+    }
+
+    #[test]
+    fn test_parse_tracks_state_and_colour() {
+        let tmp = TempDir::new().unwrap();
+        let path = write_als(tmp.path(), "song.als", TRACKS_XML);
+        let meta = parse_als(&path).unwrap();
+
+        let drums = &meta.tracks[0];
+        assert_eq!(drums.color_index, Some(12));
+        assert!(!drums.is_muted);
+        assert!(drums.is_soloed);
+        assert!(!drums.is_frozen);
+
+        let bass = &meta.tracks[1];
+        assert!(bass.is_muted);
+        assert!(!bass.is_soloed);
+        assert!(bass.is_frozen);
+
+        // Older sets use <ColorIndex>
+        assert_eq!(meta.tracks[2].color_index, Some(7));
+        assert_eq!(meta.tracks[3].color_index, None);
+    }
+
+    #[test]
+    fn test_parse_track_devices() {
+        let tmp = TempDir::new().unwrap();
+        let path = write_als(tmp.path(), "song.als", TRACKS_XML);
+        let meta = parse_als(&path).unwrap();
+
+        assert!(meta.tracks[0].devices.is_empty());
+        // Rack is named by its element, not by the plugin nested inside it
+        assert_eq!(meta.tracks[1].devices, vec!["Serum", "AudioEffectGroupDevice", "Eq8"]);
+        // Pre-Live 9 layout: <DeviceChain><Devices>
+        assert_eq!(meta.tracks[2].devices, vec!["Compressor2"]);
+        assert_eq!(meta.tracks[3].devices, vec!["Reverb"]);
+    }
+
+    #[test]
+    fn test_parse_plugins_still_flattened() {
+        let tmp = TempDir::new().unwrap();
+        let path = write_als(tmp.path(), "song.als", TRACKS_XML);
+        let meta = parse_als(&path).unwrap();
+
+        let names: Vec<&str> = meta.plugins.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["Serum", "OTT"]);
+        assert_eq!(meta.plugins[0].plugin_type, "vst3");
+        assert_eq!(meta.plugins[1].plugin_type, "vst2");
+    }
+
+    #[test]
+    fn test_parse_device_chain_with_racks() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Ableton MajorVersion="5">
+  <LiveSet>
+    <Tracks>
+      <MidiTrack Id="1">
+        <Name><EffectiveName Value="Bass" /></Name>
+        <DeviceChain><DeviceChain><Devices>
+          <InstrumentGroupDevice Id="0">
+            <UserName Value="Bass Rack" />
+            <On><Manual Value="true" /></On>
+            <LastPresetRef><Value><FilePresetRef Id="0">
+              <FileRef><RelativePath Value="Presets/Racks/Reese Stack.adg" /></FileRef>
+            </FilePresetRef></Value></LastPresetRef>
+            <Branches>
+              <InstrumentBranch Id="0">
+                <Name><EffectiveName Value="Sub" /></Name>
+                <DeviceChain><MidiToAudioDeviceChain><Devices>
+                  <PluginDevice Id="1">
+                    <On><Manual Value="false" /></On>
+                    <PluginDesc><Vst3PluginInfo Id="0"><Name Value="Serum" />
+                      <Preset><Vst3Preset Id="0"><Name Value="Deep Sub" /></Vst3Preset></Preset>
+                    </Vst3PluginInfo></PluginDesc>
+                  </PluginDevice>
+                </Devices></MidiToAudioDeviceChain></DeviceChain>
+              </InstrumentBranch>
+            </Branches>
+          </InstrumentGroupDevice>
+          <PluginDevice Id="2">
+            <PluginDesc><VstPluginInfo Id="0"><PlugName Value="OTT" /></VstPluginInfo></PluginDesc>
+          </PluginDevice>
+        </Devices></DeviceChain></DeviceChain>
+      </MidiTrack>
+    </Tracks>
+  </LiveSet>
+</Ableton>"#;
+        let tmp = TempDir::new().unwrap();
+        let path = write_als(tmp.path(), "rack.als", xml);
+        let meta = parse_als(&path).unwrap();
+
+        let chain = &meta.tracks[0].device_chain;
+        let names: Vec<&str> = chain.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["InstrumentGroupDevice", "Serum", "OTT"], "depth-first order");
+        assert_eq!(meta.tracks[0].devices, vec!["InstrumentGroupDevice", "OTT"]);
+
+        let rack = &chain[0];
+        assert_eq!(rack.device_type, "rack");
+        assert_eq!(rack.user_name.as_deref(), Some("Bass Rack"));
+        assert_eq!(rack.preset_name.as_deref(), Some("Reese Stack"));
+        assert!(rack.is_on);
+
+        let serum = &chain[1];
+        assert_eq!(serum.depth, 1);
+        assert_eq!(serum.parent_order, Some(0));
+        assert_eq!(serum.branch_name.as_deref(), Some("Sub"));
+        assert_eq!(serum.device_type, "vst3");
+        assert!(!serum.is_on);
+        assert_eq!(serum.preset_name.as_deref(), Some("Deep Sub"));
+
+        let ott = &chain[2];
+        assert_eq!((ott.depth, ott.chain_order), (0, 2));
+        assert_eq!(ott.device_type, "vst2");
+        assert!(ott.is_on);
+        assert_eq!(ott.preset_name, None);
+    }
+
+    #[test]
+    fn test_parse_time_signature_and_arrangement_length() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Ableton MajorVersion="5" MinorVersion="12.0_12049" Creator="Ableton Live 12.1.5">
+  <LiveSet>
+    <Tracks>
+      <AudioTrack Id="1">
+        <Name><EffectiveName Value="Loop" /></Name>
+        <DeviceChain><MainSequencer>
+          <ClipSlotList><ClipSlot Id="0"><ClipSlot><Value>
+            <AudioClip Id="0"><CurrentEnd Value="1000" /></AudioClip>
+          </Value></ClipSlot></ClipSlot></ClipSlotList>
+          <Sample><ArrangerAutomation><Events>
+            <AudioClip Id="1"><CurrentStart Value="0" /><CurrentEnd Value="64" /></AudioClip>
+            <AudioClip Id="2"><CurrentStart Value="64" /><CurrentEnd Value="128" /></AudioClip>
+          </Events></ArrangerAutomation></Sample>
+        </MainSequencer></DeviceChain>
+      </AudioTrack>
+      <MidiTrack Id="2">
+        <Name><EffectiveName Value="Keys" /></Name>
+        <DeviceChain><MainSequencer><ClipTimeable><ArrangerAutomation><Events>
+          <MidiClip Id="0"><CurrentEnd Value="96" /></MidiClip>
+        </Events></ArrangerAutomation></ClipTimeable></MainSequencer></DeviceChain>
+      </MidiTrack>
+    </Tracks>
+    <MasterTrack>
+      <DeviceChain><Mixer>
+        <Tempo><Manual Value="120" /></Tempo>
+        <TimeSignature><Manual Value="303" /></TimeSignature>
+      </Mixer></DeviceChain>
+    </MasterTrack>
+    <Scenes><Scene Id="0" /><Scene Id="1" /><Scene Id="2" /></Scenes>
+  </LiveSet>
+</Ableton>"#;
+        let tmp = TempDir::new().unwrap();
+        let path = write_als(tmp.path(), "len.als", xml);
+        let meta = parse_als(&path).unwrap();
+
+        assert_eq!(meta.time_signature, Some(TimeSignature { numerator: 7, denominator: 8 }));
+        assert_eq!(meta.time_signature.unwrap().to_string(), "7/8");
+        // Session clip (1000) is ignored
+        assert_eq!(meta.arrangement_length_beats, Some(128.0));
+        // 7/8 bars are 3.5 beats long
+        assert!((meta.arrangement_length_bars.unwrap() - 128.0 / 3.5).abs() < 1e-9);
+        assert!((meta.arrangement_length_seconds.unwrap() - 64.0).abs() < 1e-9);
+        assert_eq!(meta.tracks[0].live_id, Some(1));
+
+        assert_eq!(meta.session_clip_count, 1);
+        assert_eq!(meta.arrangement_clip_count, 3);
+        assert_eq!(meta.scene_count, 3);
+        assert_eq!(meta.live_version.as_deref(), Some("12.1.5"));
+    }
+
+    #[test]
+    fn test_parse_empty_arrangement_stats() {
+        let tmp = TempDir::new().unwrap();
+        let path = write_als(tmp.path(), "song.als", TRACKS_XML);
+        let meta = parse_als(&path).unwrap();
+
+        assert_eq!(meta.arrangement_length_beats, None);
+        assert_eq!(meta.arrangement_length_bars, None);
+        assert_eq!((meta.session_clip_count, meta.arrangement_clip_count, meta.scene_count), (0, 0, 0));
+        assert_eq!(meta.time_signature, None);
+        assert_eq!(meta.live_version.as_deref(), Some("11.3.13"));
+    }
+
+    #[test]
+    fn test_time_signature_decoding() {
+        assert_eq!(TimeSignature::from_encoded(201), Some(TimeSignature { numerator: 4, denominator: 4 }));
+        assert_eq!(TimeSignature::from_encoded(200), Some(TimeSignature { numerator: 3, denominator: 4 }));
+        assert_eq!(TimeSignature::from_encoded(104), Some(TimeSignature { numerator: 6, denominator: 2 }));
+        assert_eq!(TimeSignature::from_encoded(-1), None);
+    }
+
+    /// Minimal set with a master-track tempo parameter, optional tempo
+    /// automation events and the given locators as (beat, name).
+    fn tempo_set_xml(manual_bpm: f64, events: &[(f64, f64)], locators: &[(f64, &str)]) -> String {
+        let events_xml: String = events.iter()
+            .map(|(t, v)| format!(r#"<FloatEvent Time="{}" Value="{}" />"#, t, v))
+            .collect();
+        let locators_xml: String = locators.iter().enumerate()
+            .map(|(i, (t, n))| format!(
+                r#"<Locator Id="{}"><Time Value="{}" /><Name Value="{}" /></Locator>"#, i, t, n
+            ))
+            .collect();
+        format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<Ableton MajorVersion="5">
+  <LiveSet>
+    <Tracks />
+    <MasterTrack>
+      <AutomationEnvelopes><Envelopes>
+        <AutomationEnvelope Id="0">
+          <EnvelopeTarget><PointeeId Value="8" /></EnvelopeTarget>
+          <Automation><Events>{}</Events></Automation>
+        </AutomationEnvelope>
+      </Envelopes></AutomationEnvelopes>
+      <DeviceChain><Mixer>
+        <Tempo><Manual Value="{}" /><AutomationTarget Id="8" /></Tempo>
+      </Mixer></DeviceChain>
+    </MasterTrack>
+    <Locators><Locators>{}</Locators></Locators>
+  </LiveSet>
+</Ableton>"#, events_xml, manual_bpm, locators_xml)
+    }
+
+    #[test]
+    fn test_locators_constant_tempo() {
+        let tmp = TempDir::new().unwrap();
+        let xml = tempo_set_xml(120.0, &[], &[(64.0, "Drop"), (0.0, "Intro"), (32.0, "")]);
+        let path = write_als(tmp.path(), "song.als", &xml);
+        let meta = parse_als(&path).unwrap();
+
+        let names: Vec<&str> = meta.locators.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["Intro", "Locator 2", "Drop"], "sorted by time, unnamed get a default");
+        assert_eq!(meta.locators[2].beat, 64.0);
+        assert!((meta.locators[2].seconds - 32.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_locators_follow_tempo_automation() {
+        let tmp = TempDir::new().unwrap();
+        // 120 BPM until beat 16, then a step to 60 BPM
+        let xml = tempo_set_xml(
+            120.0,
+            &[(-63072000.0, 120.0), (16.0, 120.0), (16.0, 60.0)],
+            &[(16.0, "Half"), (32.0, "End")],
+        );
+        let path = write_als(tmp.path(), "song.als", &xml);
+        let meta = parse_als(&path).unwrap();
+
+        assert!((meta.locators[0].seconds - 8.0).abs() < 1e-9);
+        assert!((meta.locators[1].seconds - 24.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tempo_map_linear_ramp() {
+        // 60 → 120 BPM over 8 beats: t = 60/k * ln(T1/T0) with k = 7.5 BPM/beat
+        let map = TempoMap {
+            points: vec![TempoPoint { beat: 0.0, bpm: 60.0 }, TempoPoint { beat: 8.0, bpm: 120.0 }],
+        };
+        let expected = 60.0 / 7.5 * 2f64.ln();
+        assert!((map.beats_to_seconds(8.0) - expected).abs() < 1e-9);
+        // After the last point the tempo holds at 120 BPM
+        assert!((map.beats_to_seconds(10.0) - (expected + 1.0)).abs() < 1e-9);
+        assert_eq!(map.beats_to_seconds(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_tempo_map_seconds_round_trip() {
+        let map = TempoMap::from_points(
+            vec![TempoPoint { beat: 8.0, bpm: 60.0 }, TempoPoint { beat: 0.0, bpm: 120.0 }, TempoPoint { beat: 16.0, bpm: 90.0 }],
+            120.0,
+        );
+        for beat in [0.0, 3.0, 8.0, 12.5, 16.0, 40.0] {
+            assert!((map.seconds_to_beats(map.beats_to_seconds(beat)) - beat).abs() < 1e-9, "beat {}", beat);
+        }
+        assert_eq!((map.min_bpm(), map.max_bpm()), (60.0, 120.0));
+
+        let meter = TimeSignatureMap::from_points(
+            vec![TimeSignatureChange { beat: 16.0, numerator: 3, denominator: 4 }],
+            Some(TimeSignature { numerator: 4, denominator: 4 }),
+        );
+        assert_eq!(meter.changes[0].numerator, 3, "the first change also holds before it");
+        let meter = TimeSignatureMap::from_points(
+            vec![
+                TimeSignatureChange { beat: 0.0, numerator: 4, denominator: 4 },
+                TimeSignatureChange { beat: 16.0, numerator: 3, denominator: 4 },
+            ],
+            None,
+        );
+        assert_eq!(meter.beats_to_bars(16.0), 4.0);
+        assert_eq!(meter.beats_to_bars(22.0), 6.0);
+        assert_eq!(meter.bars_to_beats(6.0), 22.0);
+        assert_eq!(meter.bars_to_beats(2.0), 8.0);
+    }
+
+    #[test]
+    fn test_parse_tempo_and_time_signature_automation() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Ableton MajorVersion="5" Creator="Ableton Live 11.3.13">
+  <LiveSet>
+    <Tracks>
+      <MidiTrack Id="1">
+        <DeviceChain><MainSequencer><ClipTimeable><ArrangerAutomation><Events>
+          <MidiClip Id="0"><CurrentStart Value="0" /><CurrentEnd Value="28" /></MidiClip>
+        </Events></ArrangerAutomation></ClipTimeable></MainSequencer></DeviceChain>
+      </MidiTrack>
+    </Tracks>
+    <MasterTrack>
+      <AutomationEnvelopes><Envelopes>
+        <AutomationEnvelope Id="0">
+          <EnvelopeTarget><PointeeId Value="9" /></EnvelopeTarget>
+          <Automation><Events>
+            <EnumEvent Id="1" Time="-63072000" Value="201" />
+            <EnumEvent Id="2" Time="16" Value="200" />
+          </Events></Automation>
+        </AutomationEnvelope>
+        <AutomationEnvelope Id="1">
+          <EnvelopeTarget><PointeeId Value="8" /></EnvelopeTarget>
+          <Automation><Events>
+            <FloatEvent Id="3" Time="-63072000" Value="120" />
+            <FloatEvent Id="4" Time="16" Value="120" />
+            <FloatEvent Id="5" Time="16" Value="60" />
+          </Events></Automation>
+        </AutomationEnvelope>
+      </Envelopes></AutomationEnvelopes>
+      <DeviceChain><Mixer>
+        <Tempo><Manual Value="120" /><AutomationTarget Id="8" /></Tempo>
+        <TimeSignature><Manual Value="201" /><AutomationTarget Id="9" /></TimeSignature>
+      </Mixer></DeviceChain>
+    </MasterTrack>
+  </LiveSet>
+</Ableton>"#;
+        let meta = parse_both(xml);
+
+        let signatures: Vec<(f64, i64, i64)> = meta.tempo_map.time_signatures.iter()
+            .map(|c| (c.beat, c.numerator, c.denominator))
+            .collect();
+        assert_eq!(signatures, vec![(0.0, 4, 4), (16.0, 3, 4)]);
+        assert_eq!(meta.tempo_map.tempo_points.len(), 3);
+        // Four bars of 4/4, then four of 3/4
+        assert_eq!(meta.arrangement_length_bars, Some(8.0));
+        // 16 beats at 120 BPM, 12 beats at 60 BPM
+        assert!((meta.arrangement_length_seconds.unwrap() - 20.0).abs() < 1e-9);
+        assert_eq!((meta.tempo_min, meta.tempo_max), (Some(60.0), Some(120.0)));
+        assert!((meta.tempo_avg.unwrap() - 84.0).abs() < 1e-9);
+
+        let timeline = SongTimeline::new(&meta.tempo_map, None);
+        assert!((timeline.seconds_to_bars(14.0) - 6.0).abs() < 1e-9);
+        assert!((timeline.bars_to_seconds(6.0) - 14.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_without_tempo_has_no_tempo_range() {
+        let meta = parse_both(r#"<?xml version="1.0" encoding="UTF-8"?><Ableton><LiveSet><Tracks /></LiveSet></Ableton>"#);
+        assert_eq!((meta.tempo_min, meta.tempo_max, meta.tempo_avg), (None, None, None));
+        assert!(meta.tempo_map.tempo_points.is_empty());
+    }
+
+    #[test]
+    fn test_parse_rejects_non_gzip() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("broken.als");
+        std::fs::write(&path, b"not gzip").unwrap();
+        assert!(parse_als(&path).is_err());
+    }
+
+    const LIVE11_FIXTURE: &str = include_str!("../tests/fixtures/als/live11_set.xml");
+    const LIVE9_FIXTURE: &str = include_str!("../tests/fixtures/als/live9_set.xml");
+
+    /// Parse `xml` with both the tree and the streaming parser, assert they
+    /// agree and return the result.
+    fn parse_both(xml: &str) -> AlsMetadata {
+        let tmp = TempDir::new().unwrap();
+        let path = write_als(tmp.path(), "set.als", xml);
+        let tree = parse_als_dom(&path).unwrap();
+        let streamed = stream::parse_als_streaming(&path).unwrap();
+        assert_eq!(tree, streamed);
+        streamed
+    }
+
+    #[test]
+    fn test_streaming_matches_tree_live11_fixture() {
+        let meta = parse_both(LIVE11_FIXTURE);
+
+        let tracks: Vec<(&str, &str)> = meta.tracks.iter()
+            .map(|t| (t.name.as_str(), t.track_type.as_str()))
+            .collect();
+        assert_eq!(tracks, vec![("Drums", "group"), ("Break & Fills", "audio"), ("Lead", "midi"), ("A-Reverb", "return")]);
+        assert!(meta.tracks[1].is_muted && meta.tracks[1].is_frozen);
+        assert!(meta.tracks[2].is_soloed);
+
+        let lead: Vec<(&str, Option<&str>, Option<&str>)> = meta.tracks[2].device_chain.iter()
+            .map(|d| (d.name.as_str(), d.branch_name.as_deref(), d.preset_name.as_deref()))
+            .collect();
+        assert_eq!(lead, vec![
+            ("InstrumentGroupDevice", None, Some("Lead Stack")),
+            ("Serum", Some("Saw"), Some("LD Glassy")),
+            ("Reverb", Some("Saw"), None),
+            ("Diva", None, Some("Bright Pad")),
+            ("serum", None, Some("Init")),
+        ]);
+
+        let plugins: Vec<&str> = meta.plugins.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(plugins, vec!["LFO Tool", "Serum", "Diva"]);
+        assert_eq!(meta.samples.len(), 2);
+        assert_eq!(meta.samples[0].relative_path.as_deref(), Some("Samples/Imported/amen.wav"));
+        assert_eq!(meta.samples[0].relative_path_type, Some(3));
+        assert_eq!(meta.samples[0].pack_name, None);
+        assert_eq!(meta.samples[1].relative_path, None);
+        assert_eq!((meta.key_tonic.as_deref(), meta.key_scale.as_deref()), (Some("A"), Some("Minor")));
+        assert_eq!(meta.bpm, Some(128.0));
+
+        let locators: Vec<&str> = meta.locators.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(locators, vec!["Intro", "Drop", "Locator 3"]);
+        assert!((meta.locators[1].seconds - 30.0).abs() < 1e-9);
+        assert_eq!(meta.arrangement_length_bars, Some(32.0));
+        assert_eq!((meta.session_clip_count, meta.arrangement_clip_count, meta.scene_count), (3, 3, 4));
+        assert_eq!(meta.live_version.as_deref(), Some("11.3.13"));
+
+        let clips: Vec<(i64, &str, bool, usize)> = meta.midi_clips.iter()
+            .map(|c| (c.track_index, c.name.as_str(), c.in_arrangement, c.notes.len()))
+            .collect();
+        assert_eq!(clips, vec![(2, "hook", false, 0), (2, "", false, 0), (2, "", true, 2)]);
+        let arranged = &meta.midi_clips[2];
+        assert_eq!((arranged.start, arranged.end), (Some(0.0), Some(128.0)));
+        assert_eq!((arranged.loop_start, arranged.loop_end, arranged.loop_on), (Some(0.0), Some(16.0), false));
+        // The key track without a <MidiKey> is dropped
+        assert!(arranged.notes.iter().all(|n| n.key == 69 && n.is_enabled));
+        assert_eq!(arranged.notes[1].velocity, 110.0);
+    }
+
+    #[test]
+    fn test_streaming_matches_tree_live9_fixture() {
+        let meta = parse_both(LIVE9_FIXTURE);
+
+        let chords = &meta.tracks[0];
+        assert_eq!(chords.color_index, Some(4));
+        assert!(chords.is_muted);
+        assert_eq!(chords.devices, vec!["AudioEffectGroupDevice"]);
+        let verb = &chords.device_chain[1];
+        assert_eq!((verb.name.as_str(), verb.branch_name.as_deref()), ("ValhallaVintageVerb", Some("Wet")));
+        assert_eq!(verb.preset_name.as_deref(), Some("Huge Hall"));
+
+        assert_eq!(meta.samples[0].filename, "vox take 3.aif");
+        assert_eq!(meta.time_signature.map(|t| t.to_string()).as_deref(), Some("3/4"));
+        assert_eq!(meta.scene_count, 2);
+        assert_eq!(meta.bpm, Some(92.5));
+
+        // Live 9 sets have no <KeySignature> — the key comes from the notes
+        assert_eq!(meta.key_tonic, None);
+        let clip = &meta.midi_clips[0];
+        assert_eq!((clip.name.as_str(), clip.loop_on, clip.notes.len()), ("Dm progression", true, 11));
+        assert!(!clip.notes.iter().find(|n| n.key == 61).unwrap().is_enabled);
+        // Disabled notes and notes past the loop end don't count
+        assert_eq!(meta.pitch_class_histogram[1], 0.0);
+        assert_eq!(meta.pitch_class_histogram[4], 1.0);
+        assert_eq!(meta.pitch_class_histogram[2], 8.0);
+        assert_eq!(meta.estimated_key.as_ref().map(|k| k.to_string()).as_deref(), Some("D Minor"));
+    }
+
+    #[test]
+    fn test_streaming_matches_tree_inline_sets() {
+        parse_both(TRACKS_XML);
+        parse_both(&tempo_set_xml(120.0, &[], &[(64.0, "Drop"), (0.0, "Intro"), (32.0, "")]));
+        parse_both(&tempo_set_xml(90.0, &[(-63072000.0, 90.0), (16.0, 150.0)], &[(8.0, "Build"), (24.0, "")]));
+    }
+
+    #[test]
+    fn test_streaming_matches_tree_large_set() {
+        // 200 tracks, each with a rack holding a plugin and a stretch of opaque plugin state
+        let state = "0F".repeat(2048);
+        let tracks: String = (0..200).map(|i| format!(r#"
+      <AudioTrack Id="{i}">
+        <Name><EffectiveName Value="Strings {i}" /></Name>
+        <DeviceChain><DeviceChain><Devices>
+          <AudioEffectGroupDevice Id="0"><Branches><AudioEffectBranch Id="0">
+            <Name><EffectiveName Value="Verb" /></Name>
+            <DeviceChain><AudioToAudioDeviceChain><Devices>
+              <PluginDevice Id="1"><PluginDesc><Vst3PluginInfo Id="0"><Name Value="Plugin {p}" />
+                <Preset><Vst3Preset Id="0"><ProcessorState>{state}</ProcessorState></Vst3Preset></Preset>
+              </Vst3PluginInfo></PluginDesc></PluginDevice>
+            </Devices></AudioToAudioDeviceChain></DeviceChain>
+          </AudioEffectBranch></Branches></AudioEffectGroupDevice>
+        </Devices></DeviceChain></DeviceChain>
+        <MainSequencer><Sample><ArrangerAutomation><Events>
+          <AudioClip Id="0"><CurrentEnd Value="{end}" /><SampleRef><FileRef><Path Value="/Samples/strings_{i}.wav" /></FileRef></SampleRef></AudioClip>
+        </Events></ArrangerAutomation></Sample></MainSequencer>
+      </AudioTrack>"#, i = i, p = i % 12, state = state, end = 64 + i)).collect();
+        let xml = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<Ableton MajorVersion="5" Creator="Ableton Live 12.0.5">
+  <LiveSet>
+    <Tracks>{}</Tracks>
+    <MasterTrack><DeviceChain><Mixer><Tempo><Manual Value="96" /></Tempo></Mixer></DeviceChain></MasterTrack>
+  </LiveSet>
+</Ableton>"#, tracks);
+
+        let meta = parse_both(&xml);
+        assert_eq!(meta.tracks.len(), 200);
+        assert_eq!(meta.plugins.len(), 12);
+        assert_eq!(meta.samples.len(), 200);
+        assert_eq!(meta.arrangement_length_beats, Some(263.0));
+    }
+
+    #[test]
+    fn test_streaming_rejects_malformed_xml() {
+        let tmp = TempDir::new().unwrap();
+        let truncated = &LIVE11_FIXTURE[..LIVE11_FIXTURE.len() / 2];
+        let cases = [
+            ("truncated.als", truncated),
+            ("mismatched.als", "<Ableton><LiveSet><Tracks></LiveSet></Ableton>"),
+            ("empty.als", ""),
+        ];
+        for (name, xml) in cases {
+            let path = write_als(tmp.path(), name, xml);
+            assert!(parse_als_dom(&path).is_err(), "{}: tree parser accepted it", name);
+            assert!(stream::parse_als_streaming(&path).is_err(), "{}: streaming parser accepted it", name);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+// Key estimation from MIDI note content, for sets from Live versions that
+// don't write a <KeySignature>. A duration-weighted pitch-class histogram is
+// correlated with the Krumhansl–Kessler key profiles.
+
+use super::TONIC_NAMES;
+use crate::db::models::{MidiClip, TrackInfo};
+
+/// Krumhansl–Kessler probe-tone ratings, tonic first.
+const MAJOR_PROFILE: [f64; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f64; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+/// Less note time than this (in beats) says too little to guess a key.
+const MIN_NOTE_BEATS: f64 = 4.0;
+/// A key needs at least this many distinct pitch classes to be told apart
+/// from its relatives.
+const MIN_PITCH_CLASSES: usize = 3;
+
+/// Best-matching key for a pitch-class histogram.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EstimatedKey {
+    pub tonic: String,
+    pub scale: String,
+    /// Pearson correlation with the key's profile, -1 to 1.
+    pub correlation: f64,
+}
+
+impl std::fmt::Display for EstimatedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.tonic, self.scale)
+    }
+}
+
+/// Beats of enabled note time per pitch class (C first) across the clips.
+/// Tracks with a Drum Rack are left out — their notes pick pads, not
+/// pitches. In a looping clip only notes starting inside the loop count.
+pub fn pitch_class_histogram(clips: &[MidiClip], tracks: &[TrackInfo]) -> [f64; 12] {
+    let mut histogram = [0.0; 12];
+    for clip in clips {
+        let is_drums = tracks.get(clip.track_index as usize)
+            .is_some_and(|t| t.device_chain.iter().any(|d| d.name == "DrumGroupDevice"));
+        if is_drums {
+            continue;
+        }
+        let loop_region = match (clip.loop_on, clip.loop_start, clip.loop_end) {
+            (true, Some(start), Some(end)) if end > start => Some((start, end)),
+            _ => None,
+        };
+        for note in &clip.notes {
+            if !note.is_enabled || note.duration <= 0.0 {
+                continue;
+            }
+            if loop_region.is_some_and(|(start, end)| note.time < start || note.time >= end) {
+                continue;
+            }
+            histogram[note.key.rem_euclid(12) as usize] += note.duration;
+        }
+    }
+    histogram
+}
+
+/// The major or minor key whose profile correlates best with the histogram,
+/// or None when there are too few notes to tell.
+pub fn estimate_key(histogram: &[f64; 12]) -> Option<EstimatedKey> {
+    let total: f64 = histogram.iter().sum();
+    let distinct = histogram.iter().filter(|&&w| w > 0.0).count();
+    if total < MIN_NOTE_BEATS || distinct < MIN_PITCH_CLASSES {
+        return None;
+    }
+    Some(best_key(histogram))
+}
+
+/// The major or minor key whose profile correlates best with a pitch-class
+/// weighting (C first) — note time here, spectral energy for audio.
+pub(crate) fn best_key(weights: &[f64; 12]) -> EstimatedKey {
+    let mut best: Option<(usize, &str, f64)> = None;
+    for tonic in 0..12 {
+        for (scale, profile) in [("Major", &MAJOR_PROFILE), ("Minor", &MINOR_PROFILE)] {
+            let rotated: [f64; 12] = std::array::from_fn(|pc| profile[(pc + 12 - tonic) % 12]);
+            let r = correlation(weights, &rotated);
+            if best.is_none_or(|(_, _, best_r)| r > best_r) {
+                best = Some((tonic, scale, r));
+            }
+        }
+    }
+    let (tonic, scale, correlation) = best.expect("24 candidate keys");
+    EstimatedKey {
+        tonic: TONIC_NAMES[tonic].to_string(),
+        scale: scale.to_string(),
+        correlation,
+    }
+}
+
+fn correlation(a: &[f64; 12], b: &[f64; 12]) -> f64 {
+    let mean = |v: &[f64; 12]| v.iter().sum::<f64>() / 12.0;
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for i in 0..12 {
+        let (da, db) = (a[i] - mean_a, b[i] - mean_b);
+        cov += da * db;
+        var_a += da * da;
+        var_b += db * db;
+    }
+    if var_a == 0.0 || var_b == 0.0 {
+        return 0.0;
+    }
+    cov / (var_a * var_b).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{DeviceInfo, MidiNote};
+
+    fn clip(track_index: i64, keys: &[i64]) -> MidiClip {
+        MidiClip {
+            track_index,
+            name: String::new(),
+            in_arrangement: false,
+            start: Some(0.0),
+            end: Some(keys.len() as f64),
+            loop_start: None,
+            loop_end: None,
+            loop_on: false,
+            notes: keys.iter().enumerate().map(|(i, &key)| MidiNote {
+                key,
+                time: i as f64,
+                duration: 1.0,
+                velocity: 100.0,
+                is_enabled: true,
+            }).collect(),
+        }
+    }
+
+    fn track(devices: &[&str]) -> TrackInfo {
+        TrackInfo {
+            live_id: None,
+            name: String::new(),
+            track_type: "midi".into(),
+            color_index: None,
+            is_muted: false,
+            is_soloed: false,
+            is_frozen: false,
+            devices: devices.iter().map(|d| d.to_string()).collect(),
+            device_chain: devices.iter().enumerate().map(|(i, d)| DeviceInfo {
+                chain_order: i as i64,
+                depth: 0,
+                parent_order: None,
+                branch_name: None,
+                name: d.to_string(),
+                user_name: None,
+                device_type: "native".into(),
+                is_on: true,
+                preset_name: None,
+            }).collect(),
+        }
+    }
+
+    #[test]
+    fn test_estimate_key_from_scales() {
+        // A natural minor, weighted towards the A minor triad
+        let a_minor = clip(0, &[57, 59, 60, 62, 64, 65, 67, 69, 57, 60, 64, 69, 45, 45]);
+        let hist = pitch_class_histogram(&[a_minor], &[track(&["Operator"])]);
+        assert_eq!(hist[9], 6.0);
+        assert_eq!(estimate_key(&hist).unwrap().to_string(), "A Minor");
+
+        // Eb major arpeggios
+        let eb_major = clip(0, &[63, 67, 70, 75, 63, 67, 70, 68, 65, 62]);
+        let hist = pitch_class_histogram(&[eb_major], &[track(&[])]);
+        assert_eq!(estimate_key(&hist).unwrap().to_string(), "Eb Major");
+    }
+
+    #[test]
+    fn test_histogram_skips_drums_disabled_notes_and_out_of_loop() {
+        let tracks = [track(&["DrumGroupDevice"]), track(&["Wavetable"])];
+        let mut melodic = clip(1, &[60, 62, 64, 65]);
+        melodic.notes[1].is_enabled = false;
+        melodic.loop_on = true;
+        melodic.loop_start = Some(0.0);
+        melodic.loop_end = Some(3.0);
+        let hist = pitch_class_histogram(&[clip(0, &[36, 38, 42]), melodic], &tracks);
+
+        let mut expected = [0.0; 12];
+        expected[0] = 1.0;
+        expected[4] = 1.0;
+        assert_eq!(hist, expected);
+        // Two pitch classes over two beats is not enough to call a key
+        assert_eq!(estimate_key(&hist), None);
+    }
+}
@@ -0,0 +1,1092 @@
+// Streaming .als parser — reads the gzip-compressed XML as a stream of events
+// and keeps only the state the metadata needs, so memory stays bounded no
+// matter how large the set is. Produces exactly what the roxmltree extractors
+// in the parent module produce; each handler below notes which one it mirrors.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use super::{
+    is_plugin_host, live_version_from_creator, non_plugin_device_type, plugin_info_kind, preset_file_stem,
+    midi_note, push_unique_plugin, push_unique_sample, raw_locator, scale_label, tempo_point, time_signature_point,
+    tonic_name,
+    AlsMetadata, ClipStats, FileRefHints, RawSet, SampleRef, TempoPoint, TimeSignature, PLUGIN_PRESET_TAGS,
+    TRACK_TYPES,
+};
+use crate::db::models::{ArrangementLocator, DeviceInfo, MidiClip, MidiNote, PluginInfo, TimeSignatureChange, TrackInfo};
+
+/// Parse an .als file in one pass without building an XML tree.
+pub(super) fn parse_als_streaming(path: &Path) -> Result<AlsMetadata, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open .als file: {}", e))?;
+    let mut reader = Reader::from_reader(BufReader::new(flate2::read::GzDecoder::new(file)));
+    reader.config_mut().expand_empty_elements = true;
+
+    let mut parser = StreamParser::default();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf).map_err(read_error)? {
+            Event::Start(e) => parser.start(&e)?,
+            Event::End(_) => parser.end()?,
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    parser.finish()
+}
+
+fn read_error(e: quick_xml::Error) -> String {
+    match e {
+        quick_xml::Error::Io(e) => format!("Failed to decompress .als file: {}", e),
+        e => xml_error(e),
+    }
+}
+
+fn xml_error(e: impl std::fmt::Display) -> String {
+    format!("Failed to parse .als XML: {}", e)
+}
+
+/// Element names the handlers look up as "first child named X".
+/// Everything else is `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tag {
+    LiveSet,
+    Tracks,
+    MasterTrack,
+    Locators,
+    Locator,
+    Scenes,
+    SceneNames,
+    Scene,
+    Tempo,
+    Manual,
+    KeySignature,
+    Tonic,
+    Scale,
+    SampleRef,
+    FileRef,
+    Path,
+    RelativePath,
+    RelativePathType,
+    LivePackName,
+    Name,
+    EffectiveName,
+    UserName,
+    Color,
+    ColorIndex,
+    DeviceChain,
+    Mixer,
+    Speaker,
+    SoloSink,
+    Freeze,
+    Devices,
+    On,
+    LastPresetRef,
+    Branches,
+    ProgramName,
+    ClipSlotList,
+    ArrangerAutomation,
+    Events,
+    AudioClip,
+    MidiClip,
+    CurrentStart,
+    CurrentEnd,
+    Loop,
+    LoopStart,
+    LoopEnd,
+    LoopOn,
+    Notes,
+    KeyTracks,
+    KeyTrack,
+    MidiKey,
+    MidiNoteEvent,
+    AutomationTarget,
+    AutomationEnvelope,
+    PointeeId,
+    FloatEvent,
+    EnumEvent,
+    TimeSignature,
+    Time,
+    Other,
+}
+
+impl Tag {
+    fn from_name(name: &str) -> Tag {
+        match name {
+            "LiveSet" => Tag::LiveSet,
+            "Tracks" => Tag::Tracks,
+            "MasterTrack" => Tag::MasterTrack,
+            "Locators" => Tag::Locators,
+            "Locator" => Tag::Locator,
+            "Scenes" => Tag::Scenes,
+            "SceneNames" => Tag::SceneNames,
+            "Scene" => Tag::Scene,
+            "Tempo" => Tag::Tempo,
+            "Manual" => Tag::Manual,
+            "KeySignature" => Tag::KeySignature,
+            "Tonic" => Tag::Tonic,
+            "Scale" => Tag::Scale,
+            "SampleRef" => Tag::SampleRef,
+            "FileRef" => Tag::FileRef,
+            "Path" => Tag::Path,
+            "RelativePath" => Tag::RelativePath,
+            "RelativePathType" => Tag::RelativePathType,
+            "LivePackName" => Tag::LivePackName,
+            "Name" => Tag::Name,
+            "EffectiveName" => Tag::EffectiveName,
+            "UserName" => Tag::UserName,
+            "Color" => Tag::Color,
+            "ColorIndex" => Tag::ColorIndex,
+            "DeviceChain" => Tag::DeviceChain,
+            "Mixer" => Tag::Mixer,
+            "Speaker" => Tag::Speaker,
+            "SoloSink" => Tag::SoloSink,
+            "Freeze" => Tag::Freeze,
+            "Devices" => Tag::Devices,
+            "On" => Tag::On,
+            "LastPresetRef" => Tag::LastPresetRef,
+            "Branches" => Tag::Branches,
+            "ProgramName" => Tag::ProgramName,
+            "ClipSlotList" => Tag::ClipSlotList,
+            "ArrangerAutomation" => Tag::ArrangerAutomation,
+            "Events" => Tag::Events,
+            "AudioClip" => Tag::AudioClip,
+            "MidiClip" => Tag::MidiClip,
+            "CurrentStart" => Tag::CurrentStart,
+            "CurrentEnd" => Tag::CurrentEnd,
+            "Loop" => Tag::Loop,
+            "LoopStart" => Tag::LoopStart,
+            "LoopEnd" => Tag::LoopEnd,
+            "LoopOn" => Tag::LoopOn,
+            "Notes" => Tag::Notes,
+            "KeyTracks" => Tag::KeyTracks,
+            "KeyTrack" => Tag::KeyTrack,
+            "MidiKey" => Tag::MidiKey,
+            "MidiNoteEvent" => Tag::MidiNoteEvent,
+            "AutomationTarget" => Tag::AutomationTarget,
+            "AutomationEnvelope" => Tag::AutomationEnvelope,
+            "PointeeId" => Tag::PointeeId,
+            "FloatEvent" => Tag::FloatEvent,
+            "EnumEvent" => Tag::EnumEvent,
+            "TimeSignature" => Tag::TimeSignature,
+            "Time" => Tag::Time,
+            _ => Tag::Other,
+        }
+    }
+
+    fn bit(self) -> u128 {
+        if self == Tag::Other { 0 } else { 1u128 << (self as u32) }
+    }
+
+    fn is_clip(self) -> bool {
+        self == Tag::AudioClip || self == Tag::MidiClip
+    }
+}
+
+/// Which of a track's two candidate device lists a device belongs to
+/// (see `track_devices_node`).
+#[derive(Debug, Clone, Copy)]
+enum DeviceList {
+    /// <DeviceChain><DeviceChain><Devices> (Live 9+)
+    Inner,
+    /// <DeviceChain><Devices> (older sets)
+    Outer,
+}
+
+/// One open element.
+struct Frame {
+    tag: Tag,
+    /// Known tags of the children seen so far, for "first child named X".
+    seen: u128,
+    role: Role,
+}
+
+/// What an open element means to the extractors. Elements that only matter
+/// through their parent (e.g. <Manual> under <Speaker>) have no role.
+enum Role {
+    None,
+    Root,
+    LiveSet,
+    Tracks,
+    Track,
+    TrackName,
+    TrackDeviceChain,
+    TrackInnerDeviceChain,
+    Mixer,
+    Speaker,
+    DeviceList { list: DeviceList, depth: i64, parent_order: Option<i64>, branch: Option<usize> },
+    Device(DeviceAcc),
+    DeviceOn { is_on: bool },
+    PresetRef { path: Option<String> },
+    Branches { list: DeviceList, depth: i64, parent_order: i64 },
+    Branch(BranchAcc),
+    BranchName { value: Option<String>, effective_name: Option<String> },
+    PluginInfo(PluginInfoAcc),
+    PluginPreset { program_name: Option<String>, name: Option<String> },
+    FileRef(FileRefAcc),
+    KeySignature(KeyAcc),
+    MasterTrack,
+    MasterTempo,
+    MasterTimeSignature,
+    Envelope(EnvelopeAcc),
+    Locators,
+    Locator { time: Option<String>, name: Option<String> },
+    Scenes,
+    SceneNames,
+    ArrangerAutomation,
+    ArrangerEvents,
+    /// A clip in a session slot or on the arrangement; `midi` accumulates
+    /// the clip if it is a MIDI clip.
+    Clip { in_arrangement: bool, midi: Option<MidiClip> },
+    ClipLoop { start: Option<f64>, end: Option<f64>, on: bool },
+    ClipNotes,
+    KeyTracks,
+    KeyTrack { key: Option<i64>, notes: Vec<MidiNote> },
+    KeyTrackNotes(Vec<MidiNote>),
+}
+
+struct TrackAcc {
+    live_id: Option<i64>,
+    track_type: &'static str,
+    effective_name: Option<String>,
+    user_name: Option<String>,
+    color: Option<String>,
+    color_index: Option<String>,
+    is_muted: bool,
+    is_soloed: bool,
+    is_frozen: bool,
+    inner_devices: Option<Vec<DeviceInfo>>,
+    outer_devices: Option<Vec<DeviceInfo>>,
+}
+
+impl TrackAcc {
+    fn devices_mut(&mut self, list: DeviceList) -> Option<&mut Vec<DeviceInfo>> {
+        match list {
+            DeviceList::Inner => self.inner_devices.as_mut(),
+            DeviceList::Outer => self.outer_devices.as_mut(),
+        }
+    }
+
+    fn finish(self) -> TrackInfo {
+        let name = self.effective_name.or(self.user_name).unwrap_or_default().trim().to_string();
+        let color_index = self.color.or(self.color_index).and_then(|v| v.parse::<i64>().ok());
+        let device_chain = self.inner_devices.or(self.outer_devices).unwrap_or_default();
+        let devices = device_chain.iter()
+            .filter(|d| d.depth == 0)
+            .map(|d| d.name.clone())
+            .collect();
+        TrackInfo {
+            live_id: self.live_id,
+            name,
+            track_type: self.track_type.to_string(),
+            color_index,
+            is_muted: self.is_muted,
+            is_soloed: self.is_soloed,
+            is_frozen: self.is_frozen,
+            devices,
+            device_chain,
+        }
+    }
+}
+
+/// A device whose placeholder sits at `index` in its track's device list;
+/// the remaining fields are filled in when the element closes.
+struct DeviceAcc {
+    list: DeviceList,
+    index: usize,
+    depth: i64,
+    tag: String,
+    user_name: Option<String>,
+    is_on: bool,
+    has_branches: bool,
+    preset_path: Option<String>,
+    program_preset: Option<String>,
+    plugin: Option<(String, &'static str)>,
+}
+
+struct BranchAcc {
+    list: DeviceList,
+    depth: i64,
+    parent_order: i64,
+    label: Option<String>,
+    /// Indices of the devices directly in this chain.
+    members: Vec<usize>,
+    has_devices: bool,
+}
+
+struct PluginInfoAcc {
+    name_tag: &'static str,
+    plugin_type: &'static str,
+    /// VST3 reads its direct <Name> child only (see `plugin_info_name`).
+    direct_child_only: bool,
+    /// Value of the first matching name element, once seen.
+    name: Option<Option<String>>,
+}
+
+/// A sample's <FileRef>: its <Path> values and the location hints beside them.
+#[derive(Default)]
+struct FileRefAcc {
+    paths: Vec<String>,
+    relative_path: Option<String>,
+    relative_path_type: Option<String>,
+    pack_name: Option<String>,
+}
+
+struct KeyAcc {
+    /// Document position of the <KeySignature>, so nested ones resolve in
+    /// document order like `extract_key`.
+    order: usize,
+    tonic: Option<String>,
+    scale: Option<String>,
+}
+
+struct EnvelopeAcc {
+    pointee: Option<Option<String>>,
+    points: Vec<TempoPoint>,
+    signature_points: Vec<TimeSignatureChange>,
+}
+
+#[derive(Default)]
+struct StreamParser {
+    stack: Vec<Frame>,
+    root_seen: bool,
+    live_version: Option<String>,
+
+    bpm: Option<f64>,
+    bpm_seen: bool,
+    key_signatures_seen: usize,
+    key: Option<(usize, String, Option<String>)>,
+    plugins: Vec<PluginInfo>,
+    plugins_seen: HashSet<String>,
+    samples: Vec<SampleRef>,
+    samples_seen: HashSet<String>,
+    open_sample_refs: usize,
+
+    in_tracks: bool,
+    track: Option<TrackAcc>,
+    tracks: Vec<TrackInfo>,
+    open_clip_slot_lists: i64,
+    clip_stats: ClipStats,
+    midi_clips: Vec<MidiClip>,
+
+    in_master: bool,
+    master_tempo_seen: bool,
+    tempo_target: Option<String>,
+    envelopes: Vec<EnvelopeAcc>,
+    time_signature_seen: bool,
+    time_signature: Option<String>,
+    time_signature_target: Option<String>,
+
+    in_locators: bool,
+    locators: Vec<ArrangementLocator>,
+    scenes: Option<i64>,
+    scene_names: Option<i64>,
+}
+
+impl StreamParser {
+    fn start(&mut self, e: &BytesStart) -> Result<(), String> {
+        let local_name = e.local_name();
+        let name = std::str::from_utf8(local_name.as_ref()).map_err(xml_error)?;
+        let tag = Tag::from_name(name);
+
+        let Some(parent) = self.stack.last_mut() else {
+            if self.root_seen {
+                return Err(xml_error("multiple root elements"));
+            }
+            self.root_seen = true;
+            // Mirrors extract_live_version
+            self.live_version = attr(e, "Creator")?.and_then(|c| live_version_from_creator(&c));
+            self.stack.push(Frame { tag, seen: 0, role: Role::Root });
+            return Ok(());
+        };
+        let first = parent.seen & tag.bit() == 0;
+        parent.seen |= tag.bit();
+        let parent_tag = parent.tag;
+
+        self.match_descendants(e, tag, name, parent_tag)?;
+        let role = match self.child_role(e, tag, name, first)? {
+            Some(role) => role,
+            None => self.element_role(tag, name),
+        };
+        self.stack.push(Frame { tag, seen: 0, role });
+        Ok(())
+    }
+
+    /// Handlers that look at every element regardless of where it sits.
+    fn match_descendants(&mut self, e: &BytesStart, tag: Tag, name: &str, parent_tag: Tag) -> Result<(), String> {
+        match tag {
+            // extract_bpm: first <Tempo> with a <Manual Value> child
+            Tag::Manual if parent_tag == Tag::Tempo && !self.bpm_seen => {
+                if let Some(value) = attr(e, "Value")? {
+                    self.bpm_seen = true;
+                    self.bpm = value.parse::<f64>().ok();
+                }
+            }
+            // extract_key
+            Tag::Tonic => {
+                if let Some(tonic) = attr(e, "Value")?.as_deref().and_then(tonic_name) {
+                    for acc in self.open_key_signatures() {
+                        acc.tonic = Some(tonic.to_string());
+                    }
+                }
+            }
+            Tag::Scale => {
+                if let Some(value) = attr(e, "Value")? {
+                    for acc in self.open_key_signatures() {
+                        acc.scale = Some(scale_label(&value));
+                    }
+                }
+            }
+            Tag::SampleRef => self.open_sample_refs += 1,
+            Tag::ClipSlotList => self.open_clip_slot_lists += 1,
+            Tag::AudioClip | Tag::MidiClip if self.in_tracks => {
+                // extract_clip_stats: each enclosing <ClipSlotList> counts the clip
+                self.clip_stats.session_clips += self.open_clip_slot_lists;
+            }
+            Tag::PointeeId if self.in_master => {
+                let value = attr(e, "Value")?;
+                for frame in self.stack.iter_mut() {
+                    if let Role::Envelope(env) = &mut frame.role {
+                        env.pointee.get_or_insert_with(|| value.clone());
+                    }
+                }
+            }
+            Tag::FloatEvent if self.in_master => {
+                let (time, value) = (attr(e, "Time")?, attr(e, "Value")?);
+                if let Some(point) = time.zip(value).and_then(|(t, v)| tempo_point(&t, &v)) {
+                    for frame in self.stack.iter_mut() {
+                        if let Role::Envelope(env) = &mut frame.role {
+                            env.points.push(point.clone());
+                        }
+                    }
+                }
+            }
+            Tag::EnumEvent if self.in_master => {
+                let (time, value) = (attr(e, "Time")?, attr(e, "Value")?);
+                if let Some(point) = time.zip(value).and_then(|(t, v)| time_signature_point(&t, &v)) {
+                    for frame in self.stack.iter_mut() {
+                        if let Role::Envelope(env) = &mut frame.role {
+                            env.signature_points.push(point.clone());
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        // device_preset_name: first non-empty path under <LastPresetRef>
+        if tag == Tag::Path || tag == Tag::RelativePath {
+            if let Some(path) = attr(e, "Value")?.filter(|v| !v.trim().is_empty()) {
+                for frame in self.stack.iter_mut() {
+                    if let Role::PresetRef { path: found @ None } = &mut frame.role {
+                        *found = Some(path.clone());
+                    }
+                }
+            }
+        }
+
+        // plugin_info_name: first name element inside a plugin info node
+        let depth = self.stack.len();
+        let mut name_value = None;
+        for (i, frame) in self.stack.iter_mut().enumerate() {
+            if let Role::PluginInfo(info) = &mut frame.role {
+                let in_scope = !info.direct_child_only || i + 1 == depth;
+                if info.name.is_none() && info.name_tag == name && in_scope {
+                    if name_value.is_none() {
+                        name_value = Some(attr(e, "Value")?);
+                    }
+                    info.name = name_value.clone();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Roles given by the parent's role: the fixed paths under <LiveSet>,
+    /// tracks, device chains, the master track and locators.
+    fn child_role(&mut self, e: &BytesStart, tag: Tag, name: &str, first: bool) -> Result<Option<Role>, String> {
+        let parent = self.stack.len() - 1;
+        let role = match (&mut self.stack[parent].role, tag) {
+            (Role::Root, Tag::LiveSet) if first => Role::LiveSet,
+
+            // live_set_child
+            (Role::LiveSet, Tag::Tracks) if first => {
+                self.in_tracks = true;
+                Role::Tracks
+            }
+            (Role::LiveSet, Tag::MasterTrack) if first => {
+                self.in_master = true;
+                Role::MasterTrack
+            }
+            (Role::LiveSet, Tag::Locators) if first => {
+                self.in_locators = true;
+                Role::Locators
+            }
+            (Role::LiveSet, Tag::Scenes) if first => {
+                self.scenes = Some(0);
+                Role::Scenes
+            }
+            (Role::LiveSet, Tag::SceneNames) if first => {
+                self.scene_names = Some(0);
+                Role::SceneNames
+            }
+
+            // extract_scene_count
+            (Role::Scenes, Tag::Scene) => {
+                *self.scenes.get_or_insert(0) += 1;
+                Role::None
+            }
+            (Role::SceneNames, Tag::Scene) => {
+                *self.scene_names.get_or_insert(0) += 1;
+                Role::None
+            }
+
+            // extract_tracks
+            (Role::Tracks, _) => match TRACK_TYPES.iter().find(|(t, _)| *t == name) {
+                Some((_, track_type)) => {
+                    self.track = Some(TrackAcc {
+                        live_id: attr(e, "Id")?.and_then(|id| id.parse::<i64>().ok()),
+                        track_type,
+                        effective_name: None,
+                        user_name: None,
+                        color: None,
+                        color_index: None,
+                        is_muted: false,
+                        is_soloed: false,
+                        is_frozen: false,
+                        inner_devices: None,
+                        outer_devices: None,
+                    });
+                    Role::Track
+                }
+                None => return Ok(None),
+            },
+            (Role::Track, Tag::Name) if first => Role::TrackName,
+            (Role::Track, Tag::Color) if first => {
+                let value = attr(e, "Value")?;
+                self.track_mut().color = value;
+                Role::None
+            }
+            (Role::Track, Tag::ColorIndex) if first => {
+                let value = attr(e, "Value")?;
+                self.track_mut().color_index = value;
+                Role::None
+            }
+            (Role::Track, Tag::Freeze) if first => {
+                let value = attr(e, "Value")?;
+                self.track_mut().is_frozen = value.as_deref() == Some("true");
+                Role::None
+            }
+            (Role::Track, Tag::DeviceChain) if first => Role::TrackDeviceChain,
+            (Role::TrackName, Tag::EffectiveName) if first => {
+                let value = attr(e, "Value")?;
+                self.track_mut().effective_name = value;
+                Role::None
+            }
+            (Role::TrackName, Tag::UserName) if first => {
+                let value = attr(e, "Value")?;
+                self.track_mut().user_name = value;
+                Role::None
+            }
+            (Role::TrackDeviceChain, Tag::DeviceChain) if first => Role::TrackInnerDeviceChain,
+            (Role::TrackDeviceChain, Tag::Mixer) if first => Role::Mixer,
+            (Role::TrackDeviceChain, Tag::Devices) if first => {
+                self.track_mut().outer_devices = Some(Vec::new());
+                Role::DeviceList { list: DeviceList::Outer, depth: 0, parent_order: None, branch: None }
+            }
+            (Role::TrackInnerDeviceChain, Tag::Devices) if first => {
+                self.track_mut().inner_devices = Some(Vec::new());
+                Role::DeviceList { list: DeviceList::Inner, depth: 0, parent_order: None, branch: None }
+            }
+            (Role::Mixer, Tag::Speaker) if first => Role::Speaker,
+            (Role::Mixer, Tag::SoloSink) if first => {
+                let value = attr(e, "Value")?;
+                self.track_mut().is_soloed = value.as_deref() == Some("true");
+                Role::None
+            }
+            (Role::Speaker, Tag::Manual) if first => {
+                let value = attr(e, "Value")?;
+                self.track_mut().is_muted = value.as_deref() == Some("false");
+                Role::None
+            }
+
+            // collect_device_chain
+            (Role::DeviceList { list, depth, parent_order, branch }, _) => {
+                let (list, depth, parent_order, branch) = (*list, *depth, *parent_order, *branch);
+                let Some(devices) = self.track.as_mut().and_then(|t| t.devices_mut(list)) else {
+                    return Ok(None);
+                };
+                let index = devices.len();
+                devices.push(DeviceInfo {
+                    chain_order: index as i64,
+                    depth,
+                    parent_order,
+                    branch_name: None,
+                    name: String::new(),
+                    user_name: None,
+                    device_type: String::new(),
+                    is_on: true,
+                    preset_name: None,
+                });
+                if let Some(Role::Branch(b)) = branch.map(|i| &mut self.stack[i].role) {
+                    b.members.push(index);
+                }
+                Role::Device(DeviceAcc {
+                    list,
+                    index,
+                    depth,
+                    tag: name.to_string(),
+                    user_name: None,
+                    is_on: true,
+                    has_branches: false,
+                    preset_path: None,
+                    program_preset: None,
+                    plugin: None,
+                })
+            }
+            (Role::Device(device), Tag::UserName) if first => {
+                device.user_name = attr(e, "Value")?
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty());
+                Role::None
+            }
+            (Role::Device(_), Tag::On) if first => Role::DeviceOn { is_on: true },
+            (Role::DeviceOn { is_on }, Tag::Manual) if first => {
+                *is_on = attr(e, "Value")?.as_deref() != Some("false");
+                Role::None
+            }
+            (Role::Device(_), Tag::LastPresetRef) if first => Role::PresetRef { path: None },
+            (Role::Device(device), Tag::Branches) if first => {
+                device.has_branches = true;
+                Role::Branches { list: device.list, depth: device.depth + 1, parent_order: device.index as i64 }
+            }
+            (Role::Branches { list, depth, parent_order }, _) => Role::Branch(BranchAcc {
+                list: *list,
+                depth: *depth,
+                parent_order: *parent_order,
+                label: None,
+                members: Vec::new(),
+                has_devices: false,
+            }),
+            (Role::Branch(_), Tag::Name) if first => Role::BranchName {
+                value: attr(e, "Value")?,
+                effective_name: None,
+            },
+            (Role::BranchName { effective_name, .. }, Tag::EffectiveName) if first => {
+                *effective_name = attr(e, "Value")?;
+                Role::None
+            }
+            (Role::PluginPreset { program_name, .. }, Tag::ProgramName) if first => {
+                *program_name = attr(e, "Value")?;
+                Role::None
+            }
+            (Role::PluginPreset { name: preset_name, .. }, Tag::Name) if first => {
+                *preset_name = attr(e, "Value")?;
+                Role::None
+            }
+
+            // extract_samples: every <Path> of a <FileRef> inside a <SampleRef>
+            (Role::FileRef(file_ref), Tag::Path) => {
+                if let Some(path) = attr(e, "Value")? {
+                    file_ref.paths.push(path);
+                }
+                Role::None
+            }
+            (Role::FileRef(file_ref), Tag::RelativePath) if first => {
+                file_ref.relative_path = attr(e, "Value")?;
+                Role::None
+            }
+            (Role::FileRef(file_ref), Tag::RelativePathType) if first => {
+                file_ref.relative_path_type = attr(e, "Value")?;
+                Role::None
+            }
+            (Role::FileRef(file_ref), Tag::LivePackName) if first => {
+                file_ref.pack_name = attr(e, "Value")?;
+                Role::None
+            }
+
+            // extract_tempo_points / extract_time_signature
+            (Role::MasterTempo, Tag::AutomationTarget) if first => {
+                self.tempo_target = attr(e, "Id")?;
+                Role::None
+            }
+            (Role::MasterTimeSignature, Tag::Manual) if first => {
+                self.time_signature = attr(e, "Value")?;
+                Role::None
+            }
+            (Role::MasterTimeSignature, Tag::AutomationTarget) if first => {
+                self.time_signature_target = attr(e, "Id")?;
+                Role::None
+            }
+
+            // extract_locators
+            (Role::Locator { time, .. }, Tag::Time) if first => {
+                *time = attr(e, "Value")?;
+                Role::None
+            }
+            (Role::Locator { name: locator_name, .. }, Tag::Name) if first => {
+                *locator_name = attr(e, "Value")?;
+                Role::None
+            }
+
+            // extract_clip_stats
+            (Role::ArrangerAutomation, Tag::Events) if first => Role::ArrangerEvents,
+            (Role::ArrangerEvents, _) if tag.is_clip() => {
+                self.clip_stats.arrangement_clips += 1;
+                let midi = (tag == Tag::MidiClip).then(|| self.new_midi_clip(true)).flatten();
+                Role::Clip { in_arrangement: true, midi }
+            }
+            (Role::Clip { in_arrangement, midi }, Tag::CurrentEnd) if first => {
+                let end = attr(e, "Value")?.and_then(|v| v.parse::<f64>().ok());
+                if let (true, Some(end)) = (*in_arrangement, end) {
+                    let stats = &mut self.clip_stats;
+                    stats.arrangement_end = Some(stats.arrangement_end.map_or(end, |m| m.max(end)));
+                }
+                if let Some(clip) = midi {
+                    clip.end = end;
+                }
+                Role::None
+            }
+
+            // extract_midi_clips / midi_clip
+            (Role::Clip { midi: Some(clip), .. }, Tag::CurrentStart) if first => {
+                clip.start = attr(e, "Value")?.and_then(|v| v.parse::<f64>().ok());
+                Role::None
+            }
+            (Role::Clip { midi: Some(clip), .. }, Tag::Name) if first => {
+                clip.name = attr(e, "Value")?.unwrap_or_default().trim().to_string();
+                Role::None
+            }
+            (Role::Clip { midi: Some(_), .. }, Tag::Loop) if first => Role::ClipLoop { start: None, end: None, on: false },
+            (Role::ClipLoop { start, .. }, Tag::LoopStart) if first => {
+                *start = attr(e, "Value")?.and_then(|v| v.parse::<f64>().ok());
+                Role::None
+            }
+            (Role::ClipLoop { end, .. }, Tag::LoopEnd) if first => {
+                *end = attr(e, "Value")?.and_then(|v| v.parse::<f64>().ok());
+                Role::None
+            }
+            (Role::ClipLoop { on, .. }, Tag::LoopOn) if first => {
+                *on = attr(e, "Value")?.as_deref() == Some("true");
+                Role::None
+            }
+            (Role::Clip { midi: Some(_), .. }, Tag::Notes) if first => Role::ClipNotes,
+            (Role::ClipNotes, Tag::KeyTracks) if first => Role::KeyTracks,
+            (Role::KeyTracks, Tag::KeyTrack) => Role::KeyTrack { key: None, notes: Vec::new() },
+            (Role::KeyTrack { .. }, Tag::Notes) if first => Role::KeyTrackNotes(Vec::new()),
+            (Role::KeyTrack { key, .. }, Tag::MidiKey) if first => {
+                *key = attr(e, "Value")?.and_then(|v| v.parse::<i64>().ok());
+                Role::None
+            }
+            (Role::KeyTrackNotes(notes), Tag::MidiNoteEvent) => {
+                let (time, duration) = (attr(e, "Time")?, attr(e, "Duration")?);
+                let (velocity, is_enabled) = (attr(e, "Velocity")?, attr(e, "IsEnabled")?);
+                if let Some(note) = midi_note(time.as_deref(), duration.as_deref(), velocity.as_deref(), is_enabled.as_deref()) {
+                    notes.push(note);
+                }
+                Role::None
+            }
+
+            _ => return Ok(None),
+        };
+        Ok(Some(role))
+    }
+
+    /// Roles that only depend on the element itself (and which subtree it is in).
+    fn element_role(&mut self, tag: Tag, name: &str) -> Role {
+        match tag {
+            // collect_device_chain: a rack chain's devices are its first <Devices> descendant
+            Tag::Devices => {
+                let branch = self.stack.iter_mut().enumerate().rev().find_map(|(i, f)| match &mut f.role {
+                    Role::Branch(b) => Some((i, b)),
+                    _ => None,
+                });
+                if let Some((i, b)) = branch {
+                    if !b.has_devices {
+                        b.has_devices = true;
+                        return Role::DeviceList {
+                            list: b.list,
+                            depth: b.depth,
+                            parent_order: Some(b.parent_order),
+                            branch: Some(i),
+                        };
+                    }
+                }
+                Role::None
+            }
+            Tag::FileRef if self.open_sample_refs > 0 => Role::FileRef(FileRefAcc::default()),
+            Tag::KeySignature => {
+                self.key_signatures_seen += 1;
+                Role::KeySignature(KeyAcc { order: self.key_signatures_seen, tonic: None, scale: None })
+            }
+            Tag::Tempo if self.in_master && !self.master_tempo_seen => {
+                self.master_tempo_seen = true;
+                Role::MasterTempo
+            }
+            Tag::TimeSignature if self.in_master && !self.time_signature_seen => {
+                self.time_signature_seen = true;
+                Role::MasterTimeSignature
+            }
+            Tag::AutomationEnvelope if self.in_master => {
+                Role::Envelope(EnvelopeAcc { pointee: None, points: Vec::new(), signature_points: Vec::new() })
+            }
+            Tag::Locator if self.in_locators => Role::Locator { time: None, name: None },
+            Tag::ArrangerAutomation if self.in_tracks => Role::ArrangerAutomation,
+            // extract_midi_clips: session clips are the MIDI clips anywhere in a <ClipSlotList>
+            Tag::MidiClip if self.open_clip_slot_lists > 0 => match self.new_midi_clip(false) {
+                Some(clip) => Role::Clip { in_arrangement: false, midi: Some(clip) },
+                None => Role::None,
+            },
+            Tag::Other => {
+                if let Some((name_tag, plugin_type)) = plugin_info_kind(name) {
+                    Role::PluginInfo(PluginInfoAcc {
+                        name_tag,
+                        plugin_type,
+                        direct_child_only: name == "Vst3PluginInfo",
+                        name: None,
+                    })
+                } else if PLUGIN_PRESET_TAGS.contains(&name) {
+                    Role::PluginPreset { program_name: None, name: None }
+                } else {
+                    Role::None
+                }
+            }
+            _ => Role::None,
+        }
+    }
+
+    fn end(&mut self) -> Result<(), String> {
+        let frame = self.stack.pop().ok_or_else(|| xml_error("unexpected closing tag"))?;
+        match frame.tag {
+            Tag::SampleRef => self.open_sample_refs -= 1,
+            Tag::ClipSlotList => self.open_clip_slot_lists -= 1,
+            _ => {}
+        }
+
+        match frame.role {
+            Role::Tracks => self.in_tracks = false,
+            Role::MasterTrack => self.in_master = false,
+            Role::Locators => self.in_locators = false,
+            Role::Track => {
+                if let Some(track) = self.track.take() {
+                    self.tracks.push(track.finish());
+                }
+            }
+            Role::Device(device) => {
+                let (name, device_type) = match device.plugin {
+                    Some((name, plugin_type)) if is_plugin_host(&device.tag) => (name, plugin_type),
+                    _ => (device.tag.clone(), non_plugin_device_type(&device.tag)),
+                };
+                let preset_name = match device.preset_path.as_deref().and_then(preset_file_stem) {
+                    Some(stem) => Some(stem),
+                    None if device.has_branches => None,
+                    None => device.program_preset,
+                };
+                if let Some(d) = self.track.as_mut()
+                    .and_then(|t| t.devices_mut(device.list))
+                    .and_then(|list| list.get_mut(device.index))
+                {
+                    d.name = name;
+                    d.device_type = device_type.to_string();
+                    d.user_name = device.user_name;
+                    d.is_on = device.is_on;
+                    d.preset_name = preset_name;
+                }
+            }
+            Role::DeviceOn { is_on } => {
+                if let Some(Role::Device(device)) = self.stack.last_mut().map(|f| &mut f.role) {
+                    device.is_on = is_on;
+                }
+            }
+            Role::PresetRef { path } => {
+                if let Some(Role::Device(device)) = self.stack.last_mut().map(|f| &mut f.role) {
+                    device.preset_path = path;
+                }
+            }
+            Role::BranchName { value, effective_name } => {
+                if let Some(Role::Branch(branch)) = self.stack.last_mut().map(|f| &mut f.role) {
+                    branch.label = value.or(effective_name)
+                        .map(|v| v.trim().to_string())
+                        .filter(|v| !v.is_empty());
+                }
+            }
+            Role::Branch(branch) => {
+                if let Some(devices) = self.track.as_mut().and_then(|t| t.devices_mut(branch.list)) {
+                    for i in branch.members {
+                        if let Some(d) = devices.get_mut(i) {
+                            d.branch_name = branch.label.clone();
+                        }
+                    }
+                }
+            }
+            Role::PluginInfo(info) => {
+                let name = info.name.flatten().map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+                if let Some(name) = name {
+                    push_unique_plugin(&mut self.plugins_seen, &mut self.plugins, &name, info.plugin_type);
+                    // device_name_and_type: a plugin host is named after the first plugin inside it
+                    for frame in self.stack.iter_mut() {
+                        if let Role::Device(device) = &mut frame.role {
+                            device.plugin.get_or_insert_with(|| (name.clone(), info.plugin_type));
+                        }
+                    }
+                }
+            }
+            Role::PluginPreset { program_name, name } => {
+                let value = program_name.or(name).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+                if let Some(value) = value {
+                    for frame in self.stack.iter_mut() {
+                        if let Role::Device(device) = &mut frame.role {
+                            device.program_preset.get_or_insert_with(|| value.clone());
+                        }
+                    }
+                }
+            }
+            Role::KeySignature(acc) => {
+                if let Some(tonic) = acc.tonic {
+                    if self.key.as_ref().is_none_or(|(order, _, _)| acc.order < *order) {
+                        self.key = Some((acc.order, tonic, acc.scale));
+                    }
+                }
+            }
+            Role::FileRef(file_ref) => {
+                let hints = FileRefHints {
+                    relative_path: file_ref.relative_path.as_deref(),
+                    relative_path_type: file_ref.relative_path_type.as_deref(),
+                    pack_name: file_ref.pack_name.as_deref(),
+                };
+                for path in &file_ref.paths {
+                    push_unique_sample(&mut self.samples_seen, &mut self.samples, path, &hints);
+                }
+            }
+            Role::ClipLoop { start, end, on } => {
+                if let Some(Role::Clip { midi: Some(clip), .. }) = self.stack.last_mut().map(|f| &mut f.role) {
+                    clip.loop_start = start;
+                    clip.loop_end = end;
+                    clip.loop_on = on;
+                }
+            }
+            Role::KeyTrackNotes(events) => {
+                if let Some(Role::KeyTrack { notes, .. }) = self.stack.last_mut().map(|f| &mut f.role) {
+                    *notes = events;
+                }
+            }
+            Role::KeyTrack { key: Some(key), notes } => {
+                let clip = self.stack.iter_mut().rev().find_map(|f| match &mut f.role {
+                    Role::Clip { midi: Some(clip), .. } => Some(clip),
+                    _ => None,
+                });
+                if let Some(clip) = clip {
+                    clip.notes.extend(notes.into_iter().map(|n| MidiNote { key, ..n }));
+                }
+            }
+            Role::Clip { midi: Some(clip), .. } => self.midi_clips.push(clip),
+            Role::Envelope(env) => self.envelopes.push(env),
+            Role::Locator { time, name } => {
+                if let Some(locator) = time.and_then(|t| raw_locator(&t, name.as_deref())) {
+                    self.locators.push(locator);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<AlsMetadata, String> {
+        if !self.root_seen {
+            return Err(xml_error("no root element"));
+        }
+        if !self.stack.is_empty() {
+            return Err(xml_error("unexpected end of document"));
+        }
+
+        let envelope_for = |target: &Option<String>| {
+            let target = target.as_ref()?;
+            self.envelopes.iter()
+                .find(|env| env.pointee.as_ref().and_then(|p| p.as_ref()) == Some(target))
+        };
+        let tempo_points = envelope_for(&self.tempo_target)
+            .map(|env| env.points.clone())
+            .unwrap_or_default();
+        let time_signature_points = envelope_for(&self.time_signature_target)
+            .map(|env| env.signature_points.clone())
+            .unwrap_or_default();
+        let time_signature = self.time_signature
+            .and_then(|v| v.parse::<i64>().ok())
+            .and_then(TimeSignature::from_encoded);
+        let (key_tonic, key_scale) = match self.key {
+            Some((_, tonic, scale)) => (Some(tonic), scale),
+            None => (None, None),
+        };
+
+        Ok(RawSet {
+            bpm: self.bpm,
+            key_tonic,
+            key_scale,
+            plugins: self.plugins,
+            samples: self.samples,
+            tracks: self.tracks,
+            tempo_points,
+            time_signature_points,
+            locators: self.locators,
+            time_signature,
+            clip_stats: self.clip_stats,
+            scene_count: self.scenes.or(self.scene_names).unwrap_or(0),
+            live_version: self.live_version,
+            midi_clips: self.midi_clips,
+        }.finish())
+    }
+
+    /// An empty MIDI clip on the open track, if there is one. Tracks are
+    /// pushed when they close, so the open track's index is the count so far.
+    fn new_midi_clip(&self, in_arrangement: bool) -> Option<MidiClip> {
+        self.track.as_ref()?;
+        Some(MidiClip {
+            track_index: self.tracks.len() as i64,
+            name: String::new(),
+            in_arrangement,
+            start: None,
+            end: None,
+            loop_start: None,
+            loop_end: None,
+            loop_on: false,
+            notes: Vec::new(),
+        })
+    }
+
+    fn track_mut(&mut self) -> &mut TrackAcc {
+        self.track.as_mut().expect("track role without an open track")
+    }
+
+    fn open_key_signatures(&mut self) -> impl Iterator<Item = &mut KeyAcc> {
+        self.stack.iter_mut().filter_map(|f| match &mut f.role {
+            Role::KeySignature(acc) => Some(acc),
+            _ => None,
+        })
+    }
+}
+
+/// Read an attribute the way roxmltree does: whitespace characters are
+/// normalized to spaces, then entities are unescaped.
+fn attr(e: &BytesStart, key: &str) -> Result<Option<String>, String> {
+    for a in e.attributes() {
+        let a = a.map_err(xml_error)?;
+        if a.key.local_name().as_ref() != key.as_bytes() {
+            continue;
+        }
+        let raw = std::str::from_utf8(&a.value).map_err(xml_error)?;
+        let normalized = if raw.contains(['\t', '\n', '\r']) {
+            raw.replace("\r\n", " ").replace(['\t', '\n', '\r'], " ")
+        } else {
+            raw.to_string()
+        };
+        let value = quick_xml::escape::unescape(&normalized).map_err(xml_error)?;
+        return Ok(Some(value.into_owned()));
+    }
+    Ok(None)
+}
@@ -0,0 +1,168 @@
+// Key estimation from audio: spectral magnitude between C2 and C7 is folded
+// into a chromagram, which is matched against the Krumhansl–Kessler key
+// profiles like the pitch-class histogram of a set's MIDI notes.
+
+use super::spectrum::{frame_len_for, Stft};
+use crate::als_parser::{best_key, EstimatedKey};
+
+/// Frequency range folded into the chromagram. Below it semitones are closer
+/// together than the frequency bins; above it overtones blur the picture.
+const MIN_FREQ: f64 = 65.4;
+const MAX_FREQ: f64 = 2093.0;
+/// Correlation with the best key's profile below which the audio is too
+/// atonal (drums, noise) to name a key.
+const MIN_CORRELATION: f64 = 0.5;
+/// Less audio than this, in analysis frames, says too little.
+const MIN_FRAMES: usize = 20;
+
+/// Accumulates interleaved samples and estimates their key on `finish`.
+pub struct KeyEstimator {
+    stft: Stft,
+    /// Pitch class (C = 0) of each spectrum bin inside the frequency range
+    bin_pitch_classes: Vec<Option<usize>>,
+    chroma: [f64; 12],
+    frames: usize,
+}
+
+impl KeyEstimator {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        // ~170 ms frames resolve semitones down to C2
+        let frame_len = frame_len_for(sample_rate, 1.0 / 6.0);
+        let bin_pitch_classes = (0..=frame_len / 2)
+            .map(|bin| {
+                let freq = bin as f64 * sample_rate as f64 / frame_len as f64;
+                if !(MIN_FREQ..=MAX_FREQ).contains(&freq) {
+                    return None;
+                }
+                let midi_note = 69.0 + 12.0 * (freq / 440.0).log2();
+                Some((midi_note.round() as i64).rem_euclid(12) as usize)
+            })
+            .collect();
+        KeyEstimator {
+            stft: Stft::new(channels, frame_len, frame_len / 2),
+            bin_pitch_classes,
+            chroma: [0.0; 12],
+            frames: 0,
+        }
+    }
+
+    pub fn add(&mut self, samples: &[f32]) {
+        let bin_pitch_classes = &self.bin_pitch_classes;
+        let chroma = &mut self.chroma;
+        let frames = &mut self.frames;
+        self.stft.add(samples, |magnitudes| {
+            for (&magnitude, pitch_class) in magnitudes.iter().zip(bin_pitch_classes) {
+                if let Some(pc) = *pitch_class {
+                    chroma[pc] += magnitude as f64;
+                }
+            }
+            *frames += 1;
+        });
+    }
+
+    /// The best-matching key, or None for audio too short, quiet or
+    /// atonal to tell.
+    pub fn finish(self) -> Option<EstimatedKey> {
+        if self.frames < MIN_FRAMES || self.chroma.iter().sum::<f64>() <= f64::EPSILON {
+            return None;
+        }
+        Some(best_key(&self.chroma)).filter(|key| key.correlation >= MIN_CORRELATION)
+    }
+}
+
+/// Whether two keys ("A Minor") agree, counting relative major and minor
+/// (same notes, different tonic) as agreeing, since audio alone often can't
+/// tell them apart.
+pub fn keys_agree(a: &str, b: &str) -> bool {
+    match (parse_key(a), parse_key(b)) {
+        (Some(a), Some(b)) => a == b || relative(a) == b,
+        _ => a.trim().eq_ignore_ascii_case(b.trim()),
+    }
+}
+
+/// Tonic pitch class and whether the key is minor.
+fn parse_key(key: &str) -> Option<(usize, bool)> {
+    let (tonic, scale) = key.trim().split_once(' ')?;
+    let minor = match scale.to_ascii_lowercase().as_str() {
+        "major" => false,
+        "minor" => true,
+        _ => return None,
+    };
+    let mut chars = tonic.chars();
+    let natural = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0, 'D' => 2, 'E' => 4, 'F' => 5, 'G' => 7, 'A' => 9, 'B' => 11,
+        _ => return None,
+    };
+    let accidental: i64 = match chars.as_str() {
+        "" => 0,
+        "#" | "♯" => 1,
+        "b" | "♭" => -1,
+        _ => return None,
+    };
+    Some(((natural + accidental).rem_euclid(12) as usize, minor))
+}
+
+fn relative((tonic, minor): (usize, bool)) -> (usize, bool) {
+    if minor {
+        ((tonic + 3) % 12, false)
+    } else {
+        ((tonic + 9) % 12, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    /// Stereo sine triads, one per second, each note with a weaker octave
+    /// overtone.
+    fn chords(rate: u32, progression: &[[i64; 3]]) -> Vec<f32> {
+        let mut samples = Vec::new();
+        for chord in progression {
+            for n in 0..rate as usize {
+                let t = n as f64 / rate as f64;
+                let s: f64 = chord
+                    .iter()
+                    .map(|&note| {
+                        let freq = 440.0 * 2f64.powf((note - 69) as f64 / 12.0);
+                        (2.0 * PI * freq * t).sin() + 0.3 * (4.0 * PI * freq * t).sin()
+                    })
+                    .sum();
+                let s = (s * 0.15) as f32;
+                samples.extend([s, s]);
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn test_chord_progressions() {
+        // I–IV–V–I in C major, and i–iv–V–i in A minor
+        let c_major = [[60, 64, 67], [53, 57, 60], [55, 59, 62], [60, 64, 67]];
+        let a_minor = [[57, 60, 64], [50, 53, 57], [52, 56, 59], [57, 60, 64]];
+        for (rate, progression, expected) in [(44100, &c_major, "C Major"), (48000, &a_minor, "A Minor")] {
+            let mut estimator = KeyEstimator::new(rate, 2);
+            estimator.add(&chords(rate, progression));
+            let key = estimator.finish().unwrap();
+            assert_eq!(key.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_silence_has_no_key() {
+        let mut estimator = KeyEstimator::new(48000, 2);
+        estimator.add(&vec![0.0; 48000 * 2 * 4]);
+        assert_eq!(estimator.finish(), None);
+    }
+
+    #[test]
+    fn test_keys_agree() {
+        assert!(keys_agree("A Minor", "A Minor"));
+        assert!(keys_agree("C Major", "A Minor"));
+        assert!(keys_agree("Eb Major", "C Minor"));
+        assert!(keys_agree("D# Minor", "Eb Minor"));
+        assert!(!keys_agree("C Major", "G Major"));
+        assert!(!keys_agree("C Major", "C Minor"));
+    }
+}
@@ -0,0 +1,65 @@
+// Measurements of decoded audio: loudness, tempo and key. Each measurement
+// accumulates interleaved samples block by block, so one decoding pass can
+// feed all of them.
+
+pub mod key;
+pub mod loudness;
+mod spectrum;
+pub mod tempo;
+
+use std::path::Path;
+
+use crate::als_parser::EstimatedKey;
+use crate::scanner::audio_decode::AudioDecoder;
+use loudness::{Loudness, LoudnessMeter};
+
+/// What was measured on a bounce.
+#[derive(Debug, Clone)]
+pub struct BounceAnalysis {
+    pub loudness: Loudness,
+    /// None without a steady beat
+    pub bpm: Option<f64>,
+    /// None when too atonal to tell
+    pub key: Option<EstimatedKey>,
+}
+
+/// Runs every measurement over the same samples.
+pub struct BounceAnalyzer {
+    loudness: LoudnessMeter,
+    tempo: tempo::TempoEstimator,
+    key: key::KeyEstimator,
+}
+
+impl BounceAnalyzer {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        BounceAnalyzer {
+            loudness: LoudnessMeter::new(sample_rate, channels),
+            tempo: tempo::TempoEstimator::new(sample_rate, channels),
+            key: key::KeyEstimator::new(sample_rate, channels),
+        }
+    }
+
+    pub fn add(&mut self, samples: &[f32]) {
+        self.loudness.add(samples);
+        self.tempo.add(samples);
+        self.key.add(samples);
+    }
+
+    pub fn finish(self) -> BounceAnalysis {
+        BounceAnalysis {
+            loudness: self.loudness.finish(),
+            bpm: self.tempo.finish(),
+            key: self.key.finish(),
+        }
+    }
+}
+
+/// Decode an audio file and analyse it.
+pub fn analyze_file(path: &Path) -> Result<BounceAnalysis, String> {
+    let mut decoder = AudioDecoder::open(path)?;
+    let mut analyzer = BounceAnalyzer::new(decoder.sample_rate, decoder.channels);
+    while let Some(block) = decoder.next_block()? {
+        analyzer.add(block);
+    }
+    Ok(analyzer.finish())
+}
@@ -0,0 +1,77 @@
+// Short-time Fourier transform over a stream of interleaved samples, shared
+// by the tempo and key estimators. Channels are mixed down to mono first.
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+pub(crate) struct Stft {
+    channels: usize,
+    frame_len: usize,
+    hop: usize,
+    window: Vec<f32>,
+    fft: Arc<dyn Fft<f32>>,
+    /// Mono samples not yet consumed by a full frame
+    pending: Vec<f32>,
+    buffer: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    magnitudes: Vec<f32>,
+}
+
+impl Stft {
+    /// Hann-windowed frames of `frame_len` samples (a power of two), `hop`
+    /// samples apart.
+    pub fn new(channels: u16, frame_len: usize, hop: usize) -> Self {
+        let fft = FftPlanner::new().plan_fft_forward(frame_len);
+        let window = (0..frame_len)
+            .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / frame_len as f32).cos())
+            .collect();
+        Stft {
+            channels: channels.max(1) as usize,
+            frame_len,
+            hop,
+            window,
+            scratch: vec![Complex::default(); fft.get_inplace_scratch_len()],
+            fft,
+            pending: Vec::with_capacity(frame_len * 2),
+            buffer: vec![Complex::default(); frame_len],
+            magnitudes: vec![0.0; frame_len / 2 + 1],
+        }
+    }
+
+    /// Mix down a block and call `on_frame` with the magnitude spectrum
+    /// (bins 0 to frame_len / 2) of each frame it completes.
+    pub fn add(&mut self, samples: &[f32], mut on_frame: impl FnMut(&[f32])) {
+        let scale = 1.0 / self.channels as f32;
+        self.pending.extend(samples.chunks_exact(self.channels).map(|frame| frame.iter().sum::<f32>() * scale));
+
+        let mut start = 0;
+        while self.pending.len() - start >= self.frame_len {
+            let frame = &self.pending[start..start + self.frame_len];
+            for ((out, &s), &w) in self.buffer.iter_mut().zip(frame).zip(&self.window) {
+                *out = Complex::new(s * w, 0.0);
+            }
+            self.fft.process_with_scratch(&mut self.buffer, &mut self.scratch);
+            for (m, c) in self.magnitudes.iter_mut().zip(&self.buffer) {
+                *m = c.norm();
+            }
+            on_frame(&self.magnitudes);
+            start += self.hop;
+        }
+        self.pending.drain(..start);
+    }
+}
+
+/// The power of two nearest to `seconds` of audio, for frame lengths that
+/// cover about the same time at any sample rate.
+pub(crate) fn frame_len_for(sample_rate: u32, seconds: f64) -> usize {
+    let target = (sample_rate as f64 * seconds).max(2.0) as usize;
+    let upper = target.next_power_of_two();
+    if upper - target > target - upper / 2 {
+        upper / 2
+    } else {
+        upper
+    }
+}
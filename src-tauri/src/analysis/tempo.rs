@@ -0,0 +1,193 @@
+// Tempo estimation from audio: an onset-strength envelope (spectral flux of
+// log-compressed magnitudes) is autocorrelated, and the strongest beat period
+// between 60 and 200 BPM wins, weighted towards 120 BPM to settle
+// half/double-tempo ambiguity.
+
+use super::spectrum::{frame_len_for, Stft};
+
+const MIN_BPM: f64 = 60.0;
+const MAX_BPM: f64 = 200.0;
+/// Centre of the tempo prior, and its spread in octaves.
+const PRIOR_BPM: f64 = 120.0;
+const PRIOR_OCTAVES: f64 = 1.0;
+/// Less audio than this holds too few beats to measure.
+const MIN_SECONDS: f64 = 6.0;
+/// The winning period's autocorrelation, relative to the envelope's
+/// variance, below which the audio has no steady beat.
+const MIN_PERIODICITY: f64 = 0.1;
+/// Compression of spectral magnitudes before differencing: ln(1 + C·|X|).
+const LOG_COMPRESSION: f32 = 100.0;
+
+/// Accumulates interleaved samples and estimates their tempo on `finish`.
+pub struct TempoEstimator {
+    stft: Stft,
+    /// Onset envelope frames per second
+    frame_rate: f64,
+    previous: Vec<f32>,
+    onsets: Vec<f64>,
+}
+
+impl TempoEstimator {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        // ~23 ms frames at 50% overlap: an envelope of ~86 frames per second
+        let frame_len = frame_len_for(sample_rate, 0.023);
+        let hop = frame_len / 2;
+        TempoEstimator {
+            stft: Stft::new(channels, frame_len, hop),
+            frame_rate: sample_rate as f64 / hop as f64,
+            previous: Vec::new(),
+            onsets: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, samples: &[f32]) {
+        let previous = &mut self.previous;
+        let onsets = &mut self.onsets;
+        self.stft.add(samples, |magnitudes| {
+            let compressed = magnitudes.iter().map(|&m| (1.0 + LOG_COMPRESSION * m).ln());
+            if previous.is_empty() {
+                previous.extend(compressed);
+                return;
+            }
+            let mut flux = 0.0;
+            for (prev, now) in previous.iter_mut().zip(compressed) {
+                flux += (now - *prev).max(0.0) as f64;
+                *prev = now;
+            }
+            onsets.push(flux);
+        });
+    }
+
+    /// The tempo in BPM to 0.1, or None without a steady beat.
+    pub fn finish(self) -> Option<f64> {
+        let n = self.onsets.len();
+        if (n as f64) < self.frame_rate * MIN_SECONDS {
+            return None;
+        }
+        let mean = self.onsets.iter().sum::<f64>() / n as f64;
+        let envelope: Vec<f64> = self.onsets.iter().map(|o| o - mean).collect();
+
+        let min_lag = (self.frame_rate * 60.0 / MAX_BPM).floor().max(1.0) as usize;
+        let max_lag = (self.frame_rate * 60.0 / MIN_BPM).ceil() as usize;
+        // Multiples of the period are searched too, to refine it
+        let acf = autocorrelation(&envelope, (max_lag * 4 + 2).min(n / 2));
+        if acf.len() <= max_lag + 1 || acf[0] <= f64::EPSILON {
+            return None;
+        }
+
+        let prior = |lag: f64| {
+            let octaves = (self.frame_rate * 60.0 / lag / PRIOR_BPM).log2() / PRIOR_OCTAVES;
+            (-0.5 * octaves * octaves).exp()
+        };
+        let best_lag = (min_lag..=max_lag)
+            .filter(|&lag| acf[lag] > acf[lag - 1] && acf[lag] >= acf[lag + 1])
+            .max_by(|&a, &b| (acf[a] * prior(a as f64)).total_cmp(&(acf[b] * prior(b as f64))))?;
+        if acf[best_lag] / acf[0] < MIN_PERIODICITY {
+            return None;
+        }
+
+        // The peak at the largest multiple of the period pins it down best
+        let mut period = best_lag as f64;
+        for multiple in [4, 3, 2] {
+            let centre = best_lag * multiple;
+            if centre + multiple + 1 >= acf.len() {
+                continue;
+            }
+            let peak = (centre - multiple..=centre + multiple).max_by(|&a, &b| acf[a].total_cmp(&acf[b]))?;
+            period = refine_peak(&acf, peak) / multiple as f64;
+            break;
+        }
+        let bpm = self.frame_rate * 60.0 / period;
+        Some((bpm * 10.0).round() / 10.0)
+    }
+}
+
+/// Unbiased autocorrelation of `x` for lags 0 to `max_lag` inclusive.
+fn autocorrelation(x: &[f64], max_lag: usize) -> Vec<f64> {
+    (0..=max_lag.min(x.len().saturating_sub(1)))
+        .map(|lag| {
+            let sum: f64 = x.iter().zip(&x[lag..]).map(|(a, b)| a * b).sum();
+            sum / (x.len() - lag) as f64
+        })
+        .collect()
+}
+
+/// Fractional position of the peak at `i`, from a parabola through it and
+/// its neighbours.
+fn refine_peak(values: &[f64], i: usize) -> f64 {
+    if i == 0 || i + 1 >= values.len() {
+        return i as f64;
+    }
+    let (a, b, c) = (values[i - 1], values[i], values[i + 1]);
+    let denominator = a - 2.0 * b + c;
+    if denominator.abs() <= f64::EPSILON {
+        return i as f64;
+    }
+    i as f64 + 0.5 * (a - c) / denominator
+}
+
+/// Whether two tempos agree, counting half and double tempo as agreeing —
+/// the beat level a detector locks onto is a matter of taste.
+pub fn tempos_agree(a: f64, b: f64) -> bool {
+    const TOLERANCE: f64 = 0.02;
+    [1.0, 2.0, 0.5].iter().any(|ratio| ((a * ratio) / b - 1.0).abs() <= TOLERANCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mono clicks: 5 ms noise-like bursts on every beat, accented on the
+    /// downbeat, as a drum machine would play them.
+    fn click_track(rate: u32, bpm: f64, seconds: f64) -> Vec<f32> {
+        let mut samples = vec![0.0f32; (rate as f64 * seconds) as usize];
+        let beat = rate as f64 * 60.0 / bpm;
+        let burst = (rate as f64 * 0.005) as usize;
+        let mut seed = 12345u32;
+        let mut k = 0;
+        while (k as f64 * beat) as usize + burst < samples.len() {
+            let start = (k as f64 * beat) as usize;
+            let gain = if k % 4 == 0 { 0.8 } else { 0.5 };
+            for n in 0..burst {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                let noise = (seed >> 8) as f32 / (1 << 24) as f32 * 2.0 - 1.0;
+                samples[start + n] = gain * noise * (1.0 - n as f32 / burst as f32);
+            }
+            k += 1;
+        }
+        samples
+    }
+
+    #[test]
+    fn test_click_track_tempo() {
+        for (rate, bpm) in [(44100, 128.0), (48000, 93.0), (44100, 174.0)] {
+            let mut estimator = TempoEstimator::new(rate, 1);
+            estimator.add(&click_track(rate, bpm, 12.0));
+            let estimate = estimator.finish().unwrap();
+            assert!((estimate - bpm).abs() <= 0.5, "{} BPM read as {}", bpm, estimate);
+        }
+    }
+
+    #[test]
+    fn test_no_beat() {
+        // A steady tone and a clip too short to count are both unmeasurable
+        let rate = 44100;
+        let tone: Vec<f32> = (0..rate * 10).map(|n| (n as f32 * 0.05).sin() * 0.5).collect();
+        let mut estimator = TempoEstimator::new(rate, 1);
+        estimator.add(&tone);
+        assert_eq!(estimator.finish(), None);
+
+        let mut estimator = TempoEstimator::new(rate, 1);
+        estimator.add(&click_track(rate, 120.0, 3.0));
+        assert_eq!(estimator.finish(), None);
+    }
+
+    #[test]
+    fn test_tempos_agree() {
+        assert!(tempos_agree(128.0, 127.9));
+        assert!(tempos_agree(87.0, 174.0));
+        assert!(tempos_agree(140.0, 70.2));
+        assert!(!tempos_agree(120.0, 128.0));
+        assert!(!tempos_agree(90.0, 120.0));
+    }
+}
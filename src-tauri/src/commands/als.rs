@@ -1,6 +1,6 @@
 use tauri::State;
 use crate::db::DbState;
-use crate::db::models::{PluginInfo, SampleWithStatus};
+use crate::db::models::{MidiClip, PitchProfile, PluginInfo, SampleWithStatus, SetTempoMap, TempoKeyCheck, TrackInfo};
 use crate::db::queries;
 
 #[tauri::command]
@@ -9,8 +9,42 @@ pub fn get_project_plugins(state: State<DbState>, project_id: i64) -> Result<Vec
     queries::get_project_plugins(&conn, project_id)
 }
 
+#[tauri::command]
+pub fn get_project_tracks(state: State<DbState>, project_id: i64) -> Result<Vec<TrackInfo>, String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    queries::get_project_tracks(&conn, project_id)
+}
+
 #[tauri::command]
 pub fn get_project_samples(state: State<DbState>, project_id: i64) -> Result<Vec<SampleWithStatus>, String> {
     let conn = state.0.lock().map_err(|e| e.to_string())?;
     queries::get_project_samples(&conn, project_id)
 }
+
+#[tauri::command]
+pub fn get_project_midi_clips(state: State<DbState>, project_id: i64) -> Result<Vec<MidiClip>, String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    queries::get_project_midi_clips(&conn, project_id)
+}
+
+#[tauri::command]
+pub fn get_project_pitch_profile(state: State<DbState>, project_id: i64) -> Result<PitchProfile, String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    queries::get_pitch_profile(&conn, project_id)
+}
+
+#[tauri::command]
+pub fn get_project_tempo_key_check(state: State<DbState>, project_id: i64) -> Result<TempoKeyCheck, String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    queries::get_tempo_key_check(&conn, project_id)
+}
+
+#[tauri::command]
+pub fn get_set_tempo_map(state: State<DbState>, project_id: i64) -> Result<Option<SetTempoMap>, String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    let project = queries::get_project_by_id(&conn, project_id)?;
+    match project.current_set_path {
+        Some(set_path) => queries::get_set_tempo_map(&conn, &set_path),
+        None => Ok(None),
+    }
+}
@@ -1,7 +1,12 @@
-use tauri::State;
+use std::path::Path;
+use tauri::{AppHandle, Manager, State};
 use crate::db::DbState;
 use crate::db::models::Bounce;
+use crate::analysis;
 use crate::db::queries;
+use crate::scanner::peaks::{self, WaveformPeaks};
+use crate::scanner::riff_metadata;
+use crate::scanner::walker;
 
 #[tauri::command]
 pub fn get_bounces_for_project(state: State<DbState>, project_id: i64) -> Result<Vec<Bounce>, String> {
@@ -14,3 +19,66 @@ pub fn update_bounce_notes(state: State<DbState>, id: i64, notes: String) -> Res
     let conn = state.0.lock().map_err(|e| e.to_string())?;
     queries::update_bounce_notes(&conn, id, &notes)
 }
+
+/// Measure the loudness, tempo and key of a bounce now. Scans analyse new and
+/// changed bounces; this covers ones scanned before they were analysed.
+#[tauri::command]
+pub async fn analyze_bounce(state: State<'_, DbState>, id: i64) -> Result<Bounce, String> {
+    let bounce = {
+        let conn = state.0.lock().map_err(|e| e.to_string())?;
+        queries::get_bounce(&conn, id)?
+    };
+    // Stamped with the file's modified time as decoded, which the next scan
+    // records as the bounce's
+    let modified_time = walker::get_modified_time(Path::new(&bounce.bounce_path));
+    // Decoding takes a while, so it runs off the main thread with the
+    // database unlocked
+    let bounce_path = bounce.bounce_path.clone();
+    let measured = tauri::async_runtime::spawn_blocking(move || analysis::analyze_file(Path::new(&bounce_path)))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    queries::set_bounce_analysis(&conn, &bounce.bounce_path, &measured, &modified_time)?;
+    queries::fill_tempo_key_from_bounces(&conn, bounce.project_id)?;
+    queries::get_bounce(&conn, id)
+}
+
+/// Write a copy of a WAV bounce to `dest_path` with the project's name, BPM
+/// and key, the artist name and the bounce's notes in its bext, iXML and
+/// LIST/INFO chunks. The audio data is copied unchanged.
+#[tauri::command]
+pub async fn export_tagged_bounce(state: State<'_, DbState>, id: i64, dest_path: String) -> Result<(), String> {
+    let (bounce, tags) = {
+        let conn = state.0.lock().map_err(|e| e.to_string())?;
+        (queries::get_bounce(&conn, id)?, queries::get_bounce_tags(&conn, id)?)
+    };
+    if !matches!(bounce.format.as_deref(), Some("WAV" | "RF64" | "BW64")) {
+        return Err("Only WAV bounces can carry bext and iXML metadata".to_string());
+    }
+    if Path::new(&dest_path) == Path::new(&bounce.bounce_path) {
+        return Err("Choose a different file to write the tagged copy to".to_string());
+    }
+    // Copying a long bounce takes a while, so it runs off the main thread
+    tauri::async_runtime::spawn_blocking(move || {
+        riff_metadata::write_tagged_copy(Path::new(&bounce.bounce_path), Path::new(&dest_path), &tags)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Waveform peaks of a bounce, from the peak cache (filled in by scans) or
+/// computed now if the bounce changed since it was last scanned.
+#[tauri::command]
+pub async fn get_bounce_peaks(app: AppHandle, bounce_path: String) -> Result<WaveformPeaks, String> {
+    if !Path::new(&bounce_path).exists() {
+        return Err(format!("Bounce file not found: {}", bounce_path));
+    }
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    // Decoding a bounce takes a while, so it runs off the main thread
+    tauri::async_runtime::spawn_blocking(move || {
+        peaks::load_peaks(&peaks::cache_dir(&app_data_dir), Path::new(&bounce_path))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -1,4 +1,6 @@
+use rusqlite::Connection;
 use tauri::State;
+use crate::als_parser::SongTimeline;
 use crate::db::DbState;
 use crate::db::models::Marker;
 use crate::db::queries;
@@ -39,3 +41,30 @@ pub fn delete_marker(state: State<DbState>, id: i64) -> Result<(), String> {
     let conn = state.0.lock().map_err(|e| e.to_string())?;
     queries::delete_marker(&conn, id)
 }
+
+/// Convert marker positions in seconds to bars (0.0 = the first downbeat),
+/// following the tempo and time signature changes of the current set.
+#[tauri::command]
+pub fn seconds_to_bars(state: State<DbState>, project_id: i64, seconds: Vec<f64>) -> Result<Vec<f64>, String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    let timeline = song_timeline(&conn, project_id)?;
+    Ok(seconds.iter().map(|&s| timeline.seconds_to_bars(s)).collect())
+}
+
+#[tauri::command]
+pub fn bars_to_seconds(state: State<DbState>, project_id: i64, bars: Vec<f64>) -> Result<Vec<f64>, String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    let timeline = song_timeline(&conn, project_id)?;
+    Ok(bars.iter().map(|&b| timeline.bars_to_seconds(b)).collect())
+}
+
+/// Timeline of the project's current set. Sets that haven't been parsed yet
+/// fall back to the project BPM in 4/4.
+fn song_timeline(conn: &Connection, project_id: i64) -> Result<SongTimeline, String> {
+    let project = queries::get_project_by_id(conn, project_id)?;
+    let tempo_map = match &project.current_set_path {
+        Some(set_path) => queries::get_set_tempo_map(conn, set_path)?,
+        None => None,
+    };
+    Ok(SongTimeline::new(&tempo_map.unwrap_or_default(), project.bpm))
+}
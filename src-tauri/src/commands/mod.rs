@@ -25,3 +25,4 @@ pub mod collections;
 pub mod bulk;
 pub mod health;
 pub mod updater;
+pub mod relink;
@@ -54,10 +54,9 @@ pub fn quick_create_project(state: State<DbState>, name: String, parent_folder:
     std::fs::create_dir_all(&project_path)
         .map_err(|e| format!("Failed to create project folder: {}", e))?;
 
-    // Create Bounces subfolder (use default name "Bounces")
+    // Create the bounce subfolder named for the library root it's in
     let conn = state.0.lock().map_err(|e| e.to_string())?;
-    let bounce_folder_name = queries::get_setting(&conn, "bounce_folder_name")?
-        .unwrap_or_else(|| "Bounces".to_string());
+    let bounce_folder_name = queries::bounce_folder_for(&conn, &project_path_str)?;
     let bounces_dir = project_path.join(&bounce_folder_name);
     std::fs::create_dir_all(&bounces_dir)
         .map_err(|e| format!("Failed to create bounces folder: {}", e))?;
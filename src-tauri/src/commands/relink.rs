@@ -0,0 +1,50 @@
+use tauri::State;
+use crate::db::DbState;
+use crate::db::models::{CollectResult, RelinkChoice, RelinkReport, RelinkResult, SampleIndexSummary};
+use crate::relink;
+
+/// Re-index the sample folders configured in the "sample_roots" setting.
+#[tauri::command]
+pub async fn index_sample_folders(state: State<'_, DbState>) -> Result<SampleIndexSummary, String> {
+    let (roots, known) = {
+        let conn = state.0.lock().map_err(|e| e.to_string())?;
+        (relink::sample_roots(&conn)?, relink::index::load_index(&conn)?)
+    };
+    // Walking and hashing whole sample libraries takes a while, so it runs
+    // off the main thread with the database unlocked
+    let scan = tauri::async_runtime::spawn_blocking(move || relink::index::scan_sample_roots(&roots, &known))
+        .await
+        .map_err(|e| e.to_string())?;
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    relink::index::apply_sample_scan(&conn, scan)
+}
+
+/// Dry run: list replacement candidates for the project's missing samples.
+#[tauri::command]
+pub fn preview_sample_relink(state: State<DbState>, project_id: i64) -> Result<RelinkReport, String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    relink::preview_relink(&conn, project_id)
+}
+
+#[tauri::command]
+pub fn apply_sample_relink(state: State<DbState>, project_id: i64, choices: Vec<RelinkChoice>) -> Result<RelinkResult, String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    relink::apply_relink(&conn, project_id, &choices)
+}
+
+/// Copy every sample from outside the project into Samples/Imported and
+/// point the current set at the copies.
+#[tauri::command]
+pub async fn collect_project_samples(state: State<'_, DbState>, project_id: i64) -> Result<CollectResult, String> {
+    let mut collect = {
+        let conn = state.0.lock().map_err(|e| e.to_string())?;
+        relink::collect::Collect::plan(&conn, project_id)?
+    };
+    // Copying can take a while, so it runs off the main thread with the
+    // database unlocked
+    let collect = tauri::async_runtime::spawn_blocking(move || collect.copy().map(|_| collect))
+        .await
+        .map_err(|e| e.to_string())??;
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    collect.finish(&conn)
+}
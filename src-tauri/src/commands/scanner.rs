@@ -3,26 +3,20 @@ use tauri::Emitter;
 use crate::db::DbState;
 use crate::db::models::{ScanSummary, DiscoveredProject};
 use crate::db::queries;
-use crate::scanner::walker;
+use crate::scanner::{peaks, walker};
 use crate::scanner::walker::ScanProgress;
 
 /// Kicks off a full library scan on a background thread so the UI stays
 /// responsive and can render progress events in real time. The command
 /// returns immediately; the "scan-progress" event with stage "complete"
-/// signals the frontend when the scan is finished.
+/// signals the frontend when the scan is finished. Projects unchanged since
+/// the last scan are skipped unless `full` is set.
 #[tauri::command]
-pub fn scan_library(app: AppHandle) -> Result<(), String> {
+pub fn scan_library(app: AppHandle, full: Option<bool>) -> Result<(), String> {
     // Spawn the heavy scan work on a background thread so the main thread
     // remains free to deliver events and keep the WebView responsive.
     std::thread::spawn(move || {
         let state = app.state::<DbState>();
-        let conn = match state.0.lock() {
-            Ok(c) => c,
-            Err(e) => {
-                log::error!("Failed to lock DB for scan: {e}");
-                return;
-            }
-        };
 
         let app_data_dir = match app.path().app_data_dir() {
             Ok(d) => d,
@@ -32,27 +26,36 @@ pub fn scan_library(app: AppHandle) -> Result<(), String> {
             }
         };
 
-        let root_folder = match queries::get_setting(&conn, "root_folder") {
-            Ok(Some(f)) => f,
-            Ok(None) => {
-                log::error!("Root folder not configured");
-                return;
-            }
-            Err(e) => {
-                log::error!("Failed to get root_folder setting: {e}");
-                return;
-            }
+        // Only hold the DB lock to read settings; the scan locks it again for
+        // its write transaction once every project folder has been read.
+        let (roots, options) = {
+            let conn = match state.0.lock() {
+                Ok(c) => c,
+                Err(e) => {
+                    log::error!("Failed to lock DB for scan: {e}");
+                    return;
+                }
+            };
+
+            let roots = match queries::get_library_roots(&conn) {
+                Ok(r) => r,
+                Err(e) => {
+                    log::error!("Failed to get library roots: {e}");
+                    return;
+                }
+            };
+
+            let options = walker::ScanOptions {
+                peak_cache_dir: Some(peaks::cache_dir(&app_data_dir)),
+                ..walker::ScanOptions::from_settings(&conn, full.unwrap_or(false))
+            };
+            (roots, options)
         };
 
-        let bounce_folder_name = queries::get_setting(&conn, "bounce_folder_name")
-            .ok()
-            .flatten()
-            .unwrap_or_else(|| "Bounces".to_string());
-
-        match walker::scan_library(&conn, &root_folder, &bounce_folder_name, &app_data_dir, &app) {
+        match walker::scan_library(&state.0, &roots, &app, &options) {
             Ok(summary) => {
-                log::info!("Scan complete: {} found, {} new, {} updated, {} missing, {} errors",
-                    summary.found, summary.new, summary.updated, summary.missing, summary.errors.len());
+                log::info!("Scan complete: {} found, {} changed, {} skipped, {} missing, {} errors",
+                    summary.found, summary.changed, summary.skipped, summary.missing, summary.errors.len());
             }
             Err(e) => {
                 log::error!("Scan failed: {e}");
@@ -60,7 +63,10 @@ pub fn scan_library(app: AppHandle) -> Result<(), String> {
         }
 
         // Generate covers for new projects
-        walker::generate_missing_covers(&conn, &app_data_dir, &app);
+        match state.0.lock() {
+            Ok(conn) => walker::generate_missing_covers(&conn, &app_data_dir, &app),
+            Err(e) => log::error!("Failed to lock DB for covers: {e}"),
+        }
 
         // Signal the frontend that scanning is done
         app.emit("scan-progress", ScanProgress {
@@ -74,15 +80,19 @@ pub fn scan_library(app: AppHandle) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub fn refresh_library(app: AppHandle, state: State<DbState>) -> Result<ScanSummary, String> {
-    let conn = state.0.lock().map_err(|e| e.to_string())?;
+pub fn refresh_library(app: AppHandle, state: State<DbState>, full: Option<bool>) -> Result<ScanSummary, String> {
     let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
 
-    let bounce_folder_name = queries::get_setting(&conn, "bounce_folder_name")?
-        .unwrap_or_else(|| "Bounces".to_string());
-
-    let summary = walker::refresh_library(&conn, &bounce_folder_name, &app_data_dir, &app)?;
+    let options = {
+        let conn = state.0.lock().map_err(|e| e.to_string())?;
+        walker::ScanOptions {
+            peak_cache_dir: Some(peaks::cache_dir(&app_data_dir)),
+            ..walker::ScanOptions::from_settings(&conn, full.unwrap_or(false))
+        }
+    };
+    let summary = walker::refresh_library(&state.0, &app, &options)?;
 
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
     // Generate covers for projects that need them (fast — ~50ms per cover at 300x300)
     walker::generate_missing_covers(&conn, &app_data_dir, &app);
 
@@ -99,52 +109,66 @@ pub fn refresh_library(app: AppHandle, state: State<DbState>) -> Result<ScanSumm
 pub fn discover_untracked_projects(state: State<DbState>) -> Result<Vec<DiscoveredProject>, String> {
     let conn = state.0.lock().map_err(|e| e.to_string())?;
 
-    let root_folder = queries::get_setting(&conn, "root_folder")?
-        .ok_or("Root folder not configured. Please set it in Settings.")?;
-
-    walker::discover_untracked_projects(&conn, &root_folder)
+    let roots = queries::get_library_roots(&conn)?;
+    walker::discover_untracked_projects(&conn, &roots)
 }
 
 #[tauri::command]
-pub fn add_project(app: AppHandle, state: State<DbState>, folder_path: String) -> Result<ScanSummary, String> {
-    let conn = state.0.lock().map_err(|e| e.to_string())?;
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-
-    let bounce_folder_name = queries::get_setting(&conn, "bounce_folder_name")?
-        .unwrap_or_else(|| "Bounces".to_string());
-
-    let summary = walker::add_single_project(&conn, &folder_path, &bounce_folder_name, &app_data_dir, &app)?;
-
-    // Generate cover for the new project
-    walker::generate_missing_covers(&conn, &app_data_dir, &app);
-
-    app.emit("scan-progress", ScanProgress {
-        current: 0, total: 0,
-        project_name: String::new(),
-        stage: "complete".to_string(),
-    }).ok();
-
-    Ok(summary)
+pub async fn add_project(app: AppHandle, folder_path: String) -> Result<ScanSummary, String> {
+    // Reading a project (hashing its sets, decoding its bounces) takes a
+    // while, so it runs off the main thread; the database is only locked to
+    // write
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<DbState>();
+        let options = import_options(&app)?;
+        let summary = walker::add_single_project(&state.0, &folder_path, &options, |progress| {
+            app.emit("scan-progress", progress).ok();
+        })?;
+        finish_import(&app)?;
+        Ok(summary)
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-pub fn import_projects(app: AppHandle, state: State<DbState>, projects: Vec<DiscoveredProject>) -> Result<ScanSummary, String> {
-    let conn = state.0.lock().map_err(|e| e.to_string())?;
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-
-    let bounce_folder_name = queries::get_setting(&conn, "bounce_folder_name")?
-        .unwrap_or_else(|| "Bounces".to_string());
+pub async fn import_projects(app: AppHandle, projects: Vec<DiscoveredProject>) -> Result<ScanSummary, String> {
+    // Runs off the main thread, like add_project
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<DbState>();
+        let options = import_options(&app)?;
+        let summary = walker::import_projects(&state.0, &projects, &options, |progress| {
+            app.emit("scan-progress", progress).ok();
+        })?;
+        finish_import(&app)?;
+        Ok(summary)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
-    let summary = walker::import_projects(&conn, &projects, &bounce_folder_name, &app_data_dir, &app)?;
+/// Scan options for adding or importing projects.
+fn import_options(app: &AppHandle) -> Result<walker::ScanOptions, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let state = app.state::<DbState>();
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    Ok(walker::ScanOptions {
+        peak_cache_dir: Some(peaks::cache_dir(&app_data_dir)),
+        ..walker::ScanOptions::from_settings(&conn, true)
+    })
+}
 
-    // Generate covers for imported projects
-    walker::generate_missing_covers(&conn, &app_data_dir, &app);
+/// Generate covers for added or imported projects and signal the frontend
+/// that they are in.
+fn finish_import(app: &AppHandle) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let state = app.state::<DbState>();
+    walker::generate_missing_covers(&*state.0.lock().map_err(|e| e.to_string())?, &app_data_dir, app);
 
     app.emit("scan-progress", ScanProgress {
         current: 0, total: 0,
         project_name: String::new(),
         stage: "complete".to_string(),
     }).ok();
-
-    Ok(summary)
+    Ok(())
 }
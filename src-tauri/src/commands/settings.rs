@@ -1,7 +1,8 @@
-use tauri::State;
+use tauri::{AppHandle, State};
 use crate::db::DbState;
-use crate::db::models::Setting;
+use crate::db::models::{LibraryRoot, Setting};
 use crate::db::queries;
+use crate::scanner::watcher;
 
 #[tauri::command]
 pub fn get_settings(state: State<DbState>) -> Result<Vec<Setting>, String> {
@@ -17,3 +18,37 @@ pub fn update_settings(state: State<DbState>, settings: Vec<Setting>) -> Result<
     }
     Ok(())
 }
+
+#[tauri::command]
+pub fn get_library_roots(state: State<DbState>) -> Result<Vec<LibraryRoot>, String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    queries::get_library_roots(&conn)
+}
+
+#[tauri::command]
+pub fn add_library_root(app: AppHandle, state: State<DbState>, path: String) -> Result<LibraryRoot, String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    let root = queries::add_library_root(&conn, &path)?;
+    // The watcher reads the roots itself, so release the DB first
+    drop(conn);
+    watcher::restart_watcher(&app);
+    Ok(root)
+}
+
+#[tauri::command]
+pub fn update_library_root(app: AppHandle, state: State<DbState>, root: LibraryRoot) -> Result<(), String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    queries::update_library_root(&conn, &root)?;
+    drop(conn);
+    watcher::restart_watcher(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn remove_library_root(app: AppHandle, state: State<DbState>, id: i64) -> Result<(), String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    queries::remove_library_root(&conn, id)?;
+    drop(conn);
+    watcher::restart_watcher(&app);
+    Ok(())
+}
@@ -1,44 +1,94 @@
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use tauri::Manager;
+use sha2::{Digest, Sha256};
+use tauri::{Manager, State};
 
-use crate::mp3;
+use crate::db::models::BounceTags;
+use crate::db::queries;
+use crate::db::DbState;
+use crate::mp3::{self, Mp3Options};
 
-/// Convert a WAV bounce to MP3 and copy the file to the clipboard for sharing.
+/// Convert a bounce to a tagged MP3, with the encoding options from
+/// settings, and copy the file to the clipboard for sharing.
 #[tauri::command]
-pub fn share_bounce(app_handle: tauri::AppHandle, bounce_path: String) -> Result<String, String> {
-    let wav_path = Path::new(&bounce_path);
-    if !wav_path.exists() {
-        return Err(format!("WAV file not found: {}", bounce_path));
+pub async fn share_bounce(app_handle: tauri::AppHandle, state: State<'_, DbState>, id: i64) -> Result<String, String> {
+    let (bounce, tags, options) = {
+        let conn = state.0.lock().map_err(|e| e.to_string())?;
+        (queries::get_bounce(&conn, id)?, queries::get_bounce_tags(&conn, id)?, mp3::load_options(&conn)?)
+    };
+    let bounce_path = PathBuf::from(&bounce.bounce_path);
+    if !bounce_path.exists() {
+        return Err(format!("Bounce file not found: {}", bounce.bounce_path));
     }
 
-    // Determine mp3 cache directory
+    // Determine mp3 cache directory: a folder per bounce, holding a folder
+    // per combination of options and tags so the file name stays the
+    // bounce's
     let app_data_dir = app_handle
         .path()
         .app_data_dir()
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
-    let cache_dir = app_data_dir.join("mp3_cache");
+    let bounce_dir = app_data_dir.join("mp3_cache").join(source_key(&bounce_path));
+    let cache_dir = bounce_dir.join(cache_key(&options, &tags));
 
-    // Build output filename: same stem as WAV but with .mp3
-    let stem = wav_path
+    // Build output filename: same stem as the bounce but with .mp3
+    let stem = bounce_path
         .file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("bounce");
     let mp3_filename = format!("{}.mp3", stem);
     let mp3_path = cache_dir.join(&mp3_filename);
 
-    // Check if cached MP3 is still valid (WAV hasn't been modified since)
-    if !is_cache_valid(&wav_path, &mp3_path) {
-        mp3::convert_wav_to_mp3(wav_path, &mp3_path)?;
-    }
+    // Decoding and encoding a long bounce takes a while, so it runs off the
+    // main thread
+    tauri::async_runtime::spawn_blocking(move || {
+        // Check if cached MP3 is still valid (bounce hasn't been modified since)
+        if !is_cache_valid(&bounce_path, &mp3_path) {
+            mp3::bounce_to_mp3(&bounce_path, &mp3_path, &options, Some(&tags))?;
+            prune_stale_mp3s(&bounce_dir, &cache_dir);
+        }
+
+        // Copy the MP3 file to clipboard using PowerShell
+        copy_file_to_clipboard(&mp3_path)?;
+
+        Ok(mp3_filename)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Short hash of the bounce's path. `Song.wav` and `Song.flac` both make a
+/// `Song.mp3`, so each bounce gets its own cache folder.
+fn source_key(bounce_path: &Path) -> String {
+    short_hash(bounce_path.to_string_lossy().as_bytes())
+}
+
+/// Short hash of everything besides the bounce itself that goes into its
+/// MP3, including when the cover image last changed.
+fn cache_key(options: &Mp3Options, tags: &BounceTags) -> String {
+    let cover_modified = tags.cover_path.as_deref()
+        .and_then(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok());
+    short_hash(format!("{:?}|{:?}|{:?}", options, tags, cover_modified).as_bytes())
+}
 
-    // Copy the MP3 file to clipboard using PowerShell
-    copy_file_to_clipboard(&mp3_path)?;
+fn short_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().take(8).map(|b| format!("{:02x}", b)).collect()
+}
 
-    Ok(mp3_filename)
+/// Delete the MP3s of this bounce made with earlier options or tags.
+fn prune_stale_mp3s(bounce_dir: &Path, current_dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(bounce_dir) else { return };
+    for dir in entries.flatten().map(|e| e.path()) {
+        if dir == current_dir || !dir.is_dir() {
+            continue;
+        }
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            log::warn!("Failed to delete stale MP3s in {}: {}", dir.display(), e);
+        }
+    }
 }
 
-/// Check if a cached MP3 file exists and is newer than the source WAV.
+/// Check if a cached MP3 file exists and is newer than the source bounce.
 fn is_cache_valid(wav_path: &Path, mp3_path: &PathBuf) -> bool {
     if !mp3_path.exists() {
         return false;
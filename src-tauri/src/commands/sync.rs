@@ -633,6 +633,7 @@ fn upload_bounce_mp3s_inline(
 
     let temp_dir = std::env::temp_dir().join("setcrate-mp3");
     std::fs::create_dir_all(&temp_dir).ok();
+    let options = crate::mp3::load_options(conn)?;
 
     for (local_id, wav_path, bounce_remote_id, project_remote_id) in &bounces {
         let wav = std::path::Path::new(wav_path.as_str());
@@ -647,8 +648,16 @@ fn upload_bounce_mp3s_inline(
         let mp3_filename = format!("{}.mp3", stem);
         let temp_mp3 = temp_dir.join(&mp3_filename);
 
+        let tags = match crate::db::queries::get_bounce_tags(conn, *local_id) {
+            Ok(tags) => tags,
+            Err(e) => {
+                log::warn!("Failed to read tags for bounce {}: {}", local_id, e);
+                continue;
+            }
+        };
+
         log::info!("Converting bounce {} to MP3: {}", local_id, wav_path);
-        if let Err(e) = crate::mp3::convert_wav_to_mp3(wav, &temp_mp3) {
+        if let Err(e) = crate::mp3::bounce_to_mp3(wav, &temp_mp3, &options, Some(&tags)) {
             log::warn!("MP3 conversion failed for bounce {}: {}", local_id, e);
             continue;
         }
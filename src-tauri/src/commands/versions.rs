@@ -1,7 +1,10 @@
+use std::path::Path;
 use tauri::State;
 use crate::db::DbState;
-use crate::db::models::{VersionTimelineEntry, VersionNote};
+use crate::db::models::{AbletonSet, SetBackup, SetDiff, VersionTimelineEntry, VersionNote};
 use crate::db::queries;
+use crate::{als_diff, als_parser};
+use crate::scanner::{backups, walker};
 
 #[tauri::command]
 pub fn get_version_timeline(state: State<DbState>, project_id: i64) -> Result<Vec<VersionTimelineEntry>, String> {
@@ -20,3 +23,53 @@ pub fn delete_version_note(state: State<DbState>, set_id: i64) -> Result<(), Str
     let conn = state.0.lock().map_err(|e| e.to_string())?;
     queries::delete_version_note(&conn, set_id)
 }
+
+/// Parse two versions of a set and describe what changed from `old_set_id` to `new_set_id`.
+#[tauri::command]
+pub async fn diff_ableton_sets(state: State<'_, DbState>, old_set_id: i64, new_set_id: i64) -> Result<SetDiff, String> {
+    // Look up paths, then release the lock before the (slow) parse
+    let (old_path, new_path) = {
+        let conn = state.0.lock().map_err(|e| e.to_string())?;
+        (
+            queries::get_set_by_id(&conn, old_set_id)?.set_path,
+            queries::get_set_by_id(&conn, new_set_id)?.set_path,
+        )
+    };
+    // The timeline diffs every adjacent pair at once, so the parses run off
+    // the main thread
+    tauri::async_runtime::spawn_blocking(move || {
+        let old = als_parser::parse_als(Path::new(&old_path))?;
+        let new = als_parser::parse_als(Path::new(&new_path))?;
+        Ok(als_diff::diff_sets(&old, &new))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Older versions of the project's sets from Live's Backup folder, newest first.
+#[tauri::command]
+pub fn get_set_backups(state: State<DbState>, project_id: i64) -> Result<Vec<SetBackup>, String> {
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    queries::get_set_backups(&conn, project_id)
+}
+
+/// Copy a backup into the project folder as a new set, next to the existing
+/// ones. Nothing is overwritten.
+#[tauri::command]
+pub fn restore_set_backup(state: State<DbState>, backup_id: i64) -> Result<AbletonSet, String> {
+    let (backup, project_path) = {
+        let conn = state.0.lock().map_err(|e| e.to_string())?;
+        let backup = queries::get_set_backup(&conn, backup_id)?;
+        let project_path = queries::get_project_by_id(&conn, backup.project_id)?.project_path;
+        (backup, project_path)
+    };
+
+    let restored = backups::restore_backup(Path::new(&backup.backup_path), Path::new(&project_path))?;
+    let file_size = std::fs::metadata(&restored).ok().map(|m| m.len() as i64);
+    let modified_time = walker::get_modified_time(&restored);
+
+    let conn = state.0.lock().map_err(|e| e.to_string())?;
+    let set = queries::add_ableton_set(&conn, backup.project_id, &restored.to_string_lossy(), &modified_time, file_size)?;
+    log::info!("Restored {} as {}", backup.backup_path, set.set_path);
+    Ok(set)
+}
@@ -329,6 +329,96 @@ pub fn run_migrations(conn: &Connection) -> Result<(), String> {
         if version < 13 {
             migrate_v12_to_v13(conn)?;
         }
+
+        // Migration v13 → v14: per-project track list from .als parsing
+        if version < 14 {
+            migrate_v13_to_v14(conn)?;
+        }
+
+        // Migration v14 → v15: per-track device chains
+        if version < 15 {
+            migrate_v14_to_v15(conn)?;
+        }
+
+        // Migration v15 → v16: arrangement and clip statistics on projects
+        if version < 16 {
+            migrate_v15_to_v16(conn)?;
+        }
+
+        // Migration v16 → v17: sample relative paths and location classes
+        if version < 17 {
+            migrate_v16_to_v17(conn)?;
+        }
+
+        // Migration v17 → v18: sample folder index for the relinker
+        if version < 18 {
+            migrate_v17_to_v18(conn)?;
+        }
+
+        // Migration v18 → v19: MIDI clip notes and set pitch profiles
+        if version < 19 {
+            migrate_v18_to_v19(conn)?;
+        }
+
+        // Migration v19 → v20: tempo maps on sets and tempo ranges on projects
+        if version < 20 {
+            migrate_v19_to_v20(conn)?;
+        }
+
+        // Migration v20 → v21: folder snapshots for incremental scans
+        if version < 21 {
+            migrate_v20_to_v21(conn)?;
+        }
+
+        // Migration v21 → v22: set content hashes for move detection
+        if version < 22 {
+            migrate_v21_to_v22(conn)?;
+        }
+
+        // Migration v22 → v23: library roots
+        if version < 23 {
+            migrate_v22_to_v23(conn)?;
+        }
+
+        // Migration v23 → v24: default scan ignore patterns
+        if version < 24 {
+            migrate_v23_to_v24(conn)?;
+        }
+
+        // Migration v24 → v25: Live's set backups
+        if version < 25 {
+            migrate_v24_to_v25(conn)?;
+        }
+
+        // Migration v25 → v26: bounce formats
+        if version < 26 {
+            migrate_v25_to_v26(conn)?;
+        }
+
+        // Migration v26 → v27: bounce sample formats
+        if version < 27 {
+            migrate_v26_to_v27(conn)?;
+        }
+
+        // Migration v27 → v28: bounce loudness
+        if version < 28 {
+            migrate_v27_to_v28(conn)?;
+        }
+
+        // Migration v28 → v29: tempo and key detected from bounces
+        if version < 29 {
+            migrate_v28_to_v29(conn)?;
+        }
+
+        // Migration v29 → v30: embedded WAV bounce metadata
+        if version < 30 {
+            migrate_v29_to_v30(conn)?;
+        }
+
+        // Migration v30 → v31: sync columns missing from fresh databases
+        if version < 31 {
+            migrate_v30_to_v31(conn)?;
+        }
     }
 
     Ok(())
@@ -343,8 +433,20 @@ fn migrate_v8_to_v9(conn: &Connection) -> Result<(), String> {
         );"
     ).map_err(|e| format!("Migration v9 failed (sync_meta): {}", e))?;
 
-    // Add sync tracking columns to all syncable tables.
-    // Each ALTER TABLE is wrapped in .ok() to handle partial migrations gracefully.
+    add_sync_columns(conn);
+
+    // Bump version
+    conn.execute_batch(
+        "INSERT INTO schema_version (version) VALUES (9);"
+    ).map_err(|e| format!("Migration v9 failed (version bump): {}", e))?;
+
+    log::info!("Migrated database to schema version 9 (sync tracking columns)");
+    Ok(())
+}
+
+/// Add sync tracking columns to all syncable tables.
+/// Each ALTER TABLE is wrapped in .ok() to handle partial migrations gracefully.
+fn add_sync_columns(conn: &Connection) {
     let syncable_tables = [
         "projects",
         "tags",
@@ -379,14 +481,6 @@ fn migrate_v8_to_v9(conn: &Connection) -> Result<(), String> {
     conn.execute(
         "ALTER TABLE project_tags ADD COLUMN sync_updated_at TEXT", [],
     ).ok();
-
-    // Bump version
-    conn.execute_batch(
-        "INSERT INTO schema_version (version) VALUES (9);"
-    ).map_err(|e| format!("Migration v9 failed (version bump): {}", e))?;
-
-    log::info!("Migrated database to schema version 9 (sync tracking columns)");
-    Ok(())
 }
 
 fn migrate_v12_to_v13(conn: &Connection) -> Result<(), String> {
@@ -460,6 +554,393 @@ fn migrate_v12_to_v13(conn: &Connection) -> Result<(), String> {
     Ok(())
 }
 
+fn migrate_v13_to_v14(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS project_tracks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            track_index INTEGER NOT NULL DEFAULT 0,
+            name TEXT NOT NULL,
+            track_type TEXT NOT NULL DEFAULT 'audio',
+            color_index INTEGER,
+            is_muted INTEGER NOT NULL DEFAULT 0,
+            is_soloed INTEGER NOT NULL DEFAULT 0,
+            is_frozen INTEGER NOT NULL DEFAULT 0,
+            devices TEXT NOT NULL DEFAULT '[]'
+        );
+        CREATE INDEX IF NOT EXISTS idx_project_tracks_project_id ON project_tracks(project_id);"
+    ).map_err(|e| format!("Migration v14 tables failed: {}", e))?;
+
+    // Clear the parse marker so the next scan re-parses every .als and backfills tracks
+    conn.execute("UPDATE projects SET als_parsed_at = NULL", []).ok();
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (14);")
+        .map_err(|e| format!("Migration v14 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 14 (project_tracks)");
+    Ok(())
+}
+
+fn migrate_v14_to_v15(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS project_track_devices (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            track_index INTEGER NOT NULL,
+            chain_order INTEGER NOT NULL,
+            depth INTEGER NOT NULL DEFAULT 0,
+            parent_order INTEGER,
+            branch_name TEXT,
+            name TEXT NOT NULL,
+            user_name TEXT,
+            device_type TEXT NOT NULL DEFAULT 'native',
+            is_on INTEGER NOT NULL DEFAULT 1,
+            preset_name TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_project_track_devices_project_track ON project_track_devices(project_id, track_index);
+        CREATE INDEX IF NOT EXISTS idx_project_track_devices_name ON project_track_devices(name);"
+    ).map_err(|e| format!("Migration v15 tables failed: {}", e))?;
+
+    // Re-parse every .als on the next scan to backfill device chains
+    conn.execute("UPDATE projects SET als_parsed_at = NULL", []).ok();
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (15);")
+        .map_err(|e| format!("Migration v15 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 15 (project_track_devices)");
+    Ok(())
+}
+
+fn migrate_v15_to_v16(conn: &Connection) -> Result<(), String> {
+    // Run each ALTER TABLE individually so partial failures don't block the rest
+    let alter_stmts = [
+        "ALTER TABLE projects ADD COLUMN arrangement_length_bars REAL",
+        "ALTER TABLE projects ADD COLUMN arrangement_length_seconds REAL",
+        "ALTER TABLE projects ADD COLUMN time_signature TEXT",
+        "ALTER TABLE projects ADD COLUMN scene_count INTEGER",
+        "ALTER TABLE projects ADD COLUMN session_clip_count INTEGER",
+        "ALTER TABLE projects ADD COLUMN arrangement_clip_count INTEGER",
+        "ALTER TABLE projects ADD COLUMN live_version TEXT",
+    ];
+    for stmt in &alter_stmts {
+        conn.execute(stmt, []).ok(); // Ignore "duplicate column" errors
+    }
+
+    // Re-parse every .als on the next scan to backfill the new columns
+    conn.execute("UPDATE projects SET als_parsed_at = NULL", []).ok();
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (16);")
+        .map_err(|e| format!("Migration v16 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 16 (arrangement stats)");
+    Ok(())
+}
+
+fn migrate_v16_to_v17(conn: &Connection) -> Result<(), String> {
+    // Run each ALTER TABLE individually so partial failures don't block the rest
+    let alter_stmts = [
+        "ALTER TABLE project_samples ADD COLUMN relative_path TEXT",
+        "ALTER TABLE project_samples ADD COLUMN resolved_path TEXT",
+        "ALTER TABLE project_samples ADD COLUMN location TEXT NOT NULL DEFAULT 'external'",
+    ];
+    for stmt in &alter_stmts {
+        conn.execute(stmt, []).ok(); // Ignore "duplicate column" errors
+    }
+
+    // Re-parse every .als on the next scan so samples are re-checked against
+    // their relative paths and classified
+    conn.execute("UPDATE projects SET als_parsed_at = NULL", []).ok();
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (17);")
+        .map_err(|e| format!("Migration v17 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 17 (sample locations)");
+    Ok(())
+}
+
+fn migrate_v17_to_v18(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sample_index (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL UNIQUE,
+            root TEXT NOT NULL,
+            filename TEXT NOT NULL,
+            file_size INTEGER NOT NULL,
+            modified_time INTEGER NOT NULL,
+            content_hash TEXT NOT NULL,
+            indexed_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_sample_index_filename ON sample_index(filename COLLATE NOCASE);
+        CREATE INDEX IF NOT EXISTS idx_sample_index_root ON sample_index(root);"
+    ).map_err(|e| format!("Migration v18 tables failed: {}", e))?;
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (18);")
+        .map_err(|e| format!("Migration v18 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 18 (sample_index)");
+    Ok(())
+}
+
+fn migrate_v18_to_v19(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS project_midi_clips (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            track_index INTEGER NOT NULL,
+            clip_index INTEGER NOT NULL,
+            name TEXT NOT NULL DEFAULT '',
+            in_arrangement INTEGER NOT NULL DEFAULT 0,
+            start_beat REAL,
+            end_beat REAL,
+            loop_start REAL,
+            loop_end REAL,
+            loop_on INTEGER NOT NULL DEFAULT 0,
+            notes TEXT NOT NULL DEFAULT '[]'
+        );
+        CREATE INDEX IF NOT EXISTS idx_project_midi_clips_project_id ON project_midi_clips(project_id);"
+    ).map_err(|e| format!("Migration v19 tables failed: {}", e))?;
+
+    // Run each ALTER TABLE individually so partial failures don't block the rest
+    let alter_stmts = [
+        "ALTER TABLE projects ADD COLUMN pitch_class_histogram TEXT",
+        "ALTER TABLE projects ADD COLUMN estimated_key TEXT",
+    ];
+    for stmt in &alter_stmts {
+        conn.execute(stmt, []).ok(); // Ignore "duplicate column" errors
+    }
+
+    // Re-parse every .als on the next scan to backfill MIDI clips and
+    // estimate keys for sets without a key signature
+    conn.execute("UPDATE projects SET als_parsed_at = NULL", []).ok();
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (19);")
+        .map_err(|e| format!("Migration v19 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 19 (MIDI clips and pitch profile)");
+    Ok(())
+}
+
+fn migrate_v19_to_v20(conn: &Connection) -> Result<(), String> {
+    // Run each ALTER TABLE individually so partial failures don't block the rest
+    let alter_stmts = [
+        "ALTER TABLE projects ADD COLUMN tempo_min REAL",
+        "ALTER TABLE projects ADD COLUMN tempo_max REAL",
+        "ALTER TABLE projects ADD COLUMN tempo_avg REAL",
+        "ALTER TABLE ableton_sets ADD COLUMN tempo_map TEXT",
+    ];
+    for stmt in &alter_stmts {
+        conn.execute(stmt, []).ok(); // Ignore "duplicate column" errors
+    }
+
+    // Re-parse every .als on the next scan to read tempo and time signature automation
+    conn.execute("UPDATE projects SET als_parsed_at = NULL", []).ok();
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (20);")
+        .map_err(|e| format!("Migration v20 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 20 (tempo maps)");
+    Ok(())
+}
+
+fn migrate_v20_to_v21(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS scan_snapshots (
+            dir_path TEXT PRIMARY KEY,
+            mtime INTEGER NOT NULL,
+            size INTEGER NOT NULL,
+            file_count INTEGER NOT NULL,
+            scanned_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        INSERT OR IGNORE INTO settings (key, value) VALUES ('scan_animation', 'true');"
+    ).map_err(|e| format!("Migration v21 tables failed: {}", e))?;
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (21);")
+        .map_err(|e| format!("Migration v21 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 21 (scan snapshots)");
+    Ok(())
+}
+
+fn migrate_v21_to_v22(conn: &Connection) -> Result<(), String> {
+    // Sets scanned before this keep a NULL hash; move detection falls back to
+    // filename and size for them
+    conn.execute("ALTER TABLE ableton_sets ADD COLUMN content_hash TEXT", []).ok(); // Ignore "duplicate column" errors
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (22);")
+        .map_err(|e| format!("Migration v22 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 22 (set content hashes)");
+    Ok(())
+}
+
+fn migrate_v22_to_v23(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS library_roots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL UNIQUE,
+            bounce_folder_name TEXT NOT NULL DEFAULT 'Bounces',
+            genre_depth INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );"
+    ).map_err(|e| format!("Migration v23 tables failed: {}", e))?;
+
+    // The single root folder becomes the first library root
+    conn.execute(
+        "INSERT OR IGNORE INTO library_roots (path, bounce_folder_name) \
+         SELECT value, COALESCE((SELECT value FROM settings WHERE key = 'bounce_folder_name'), 'Bounces') \
+         FROM settings WHERE key = 'root_folder' AND value != ''",
+        [],
+    ).map_err(|e| format!("Migration v23 root folder failed: {}", e))?;
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (23);")
+        .map_err(|e| format!("Migration v23 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 23 (library roots)");
+    Ok(())
+}
+
+fn migrate_v23_to_v24(conn: &Connection) -> Result<(), String> {
+    // Default scan ignore patterns; existing libraries get them too (projects
+    // already under them are skipped by scans, not flagged missing)
+    conn.execute_batch(
+        "INSERT OR IGNORE INTO settings (key, value) VALUES ('scan_ignore_patterns', '[\"Trash/\", \"$RECYCLE.BIN/\", \"Templates/\"]');"
+    ).map_err(|e| format!("Migration v24 settings failed: {}", e))?;
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (24);")
+        .map_err(|e| format!("Migration v24 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 24 (scan ignore patterns)");
+    Ok(())
+}
+
+fn migrate_v24_to_v25(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS set_backups (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            set_id INTEGER REFERENCES ableton_sets(id) ON DELETE SET NULL,
+            backup_path TEXT NOT NULL UNIQUE,
+            backed_up_at TEXT,
+            modified_time TEXT NOT NULL,
+            file_size INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_set_backups_project_id ON set_backups(project_id);"
+    ).map_err(|e| format!("Migration v25 tables failed: {}", e))?;
+
+    // Existing backups are indexed by the next scan: folder snapshots now
+    // count the Backup folder, so projects that have one no longer match
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (25);")
+        .map_err(|e| format!("Migration v25 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 25 (set backups)");
+    Ok(())
+}
+
+fn migrate_v25_to_v26(conn: &Connection) -> Result<(), String> {
+    conn.execute("ALTER TABLE bounces ADD COLUMN format TEXT", []).ok(); // Ignore "duplicate column" errors
+    conn.execute("ALTER TABLE bounces ADD COLUMN sample_rate INTEGER", []).ok();
+    conn.execute("ALTER TABLE bounces ADD COLUMN bit_depth INTEGER", []).ok();
+    conn.execute("ALTER TABLE bounces ADD COLUMN channels INTEGER", []).ok();
+
+    // Snapshots are only a cache; without them the next scan re-reads every
+    // bounce folder, recording formats and picking up AIFF, FLAC and MP3 files
+    conn.execute("DELETE FROM scan_snapshots", [])
+        .map_err(|e| format!("Migration v26 snapshots failed: {}", e))?;
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (26);")
+        .map_err(|e| format!("Migration v26 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 26 (bounce formats)");
+    Ok(())
+}
+
+fn migrate_v26_to_v27(conn: &Connection) -> Result<(), String> {
+    conn.execute("ALTER TABLE bounces ADD COLUMN sample_format TEXT", []).ok(); // Ignore "duplicate column" errors
+
+    // Re-read every bounce folder so WAV bounces get their sample format and
+    // RF64 or unfinalised files their real duration
+    conn.execute("DELETE FROM scan_snapshots", [])
+        .map_err(|e| format!("Migration v27 snapshots failed: {}", e))?;
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (27);")
+        .map_err(|e| format!("Migration v27 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 27 (bounce sample formats)");
+    Ok(())
+}
+
+fn migrate_v27_to_v28(conn: &Connection) -> Result<(), String> {
+    // Loudness of each bounce, and the modified_time it was measured at
+    conn.execute("ALTER TABLE bounces ADD COLUMN integrated_lufs REAL", []).ok(); // Ignore "duplicate column" errors
+    conn.execute("ALTER TABLE bounces ADD COLUMN short_term_max_lufs REAL", []).ok();
+    conn.execute("ALTER TABLE bounces ADD COLUMN loudness_range_lu REAL", []).ok();
+    conn.execute("ALTER TABLE bounces ADD COLUMN sample_peak_dbfs REAL", []).ok();
+    conn.execute("ALTER TABLE bounces ADD COLUMN true_peak_dbtp REAL", []).ok();
+    conn.execute("ALTER TABLE bounces ADD COLUMN loudness_mtime TEXT", []).ok();
+
+    // Re-read every bounce folder so existing bounces get measured
+    conn.execute("DELETE FROM scan_snapshots", [])
+        .map_err(|e| format!("Migration v28 snapshots failed: {}", e))?;
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (28);")
+        .map_err(|e| format!("Migration v28 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 28 (bounce loudness)");
+    Ok(())
+}
+
+fn migrate_v28_to_v29(conn: &Connection) -> Result<(), String> {
+    // Tempo and key detected from each bounce's audio
+    conn.execute("ALTER TABLE bounces ADD COLUMN detected_bpm REAL", []).ok(); // Ignore "duplicate column" errors
+    conn.execute("ALTER TABLE bounces ADD COLUMN detected_key TEXT", []).ok();
+    // Tempo and key as the current .als has them, apart from user edits to bpm/musical_key
+    conn.execute("ALTER TABLE projects ADD COLUMN als_bpm REAL", []).ok();
+    conn.execute("ALTER TABLE projects ADD COLUMN als_key TEXT", []).ok();
+
+    // Re-parse every .als on the next scan to record its tempo and key
+    conn.execute("UPDATE projects SET als_parsed_at = NULL", []).ok();
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (29);")
+        .map_err(|e| format!("Migration v29 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 29 (detected tempo and key)");
+    Ok(())
+}
+
+fn migrate_v29_to_v30(conn: &Connection) -> Result<(), String> {
+    // bext, iXML and LIST/INFO metadata read from WAV bounces, as JSON
+    conn.execute("ALTER TABLE bounces ADD COLUMN embedded_metadata TEXT", []).ok(); // Ignore "duplicate column" errors
+
+    // Re-read every bounce folder so existing WAV bounces get their metadata
+    conn.execute("DELETE FROM scan_snapshots", [])
+        .map_err(|e| format!("Migration v30 snapshots failed: {}", e))?;
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (30);")
+        .map_err(|e| format!("Migration v30 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 30 (embedded bounce metadata)");
+    Ok(())
+}
+
+fn migrate_v30_to_v31(conn: &Connection) -> Result<(), String> {
+    // Databases created from schema.sql before v31 never got the v9 sync
+    // columns or the sync_meta table
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sync_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL DEFAULT ''
+        );"
+    ).map_err(|e| format!("Migration v31 sync_meta failed: {}", e))?;
+    add_sync_columns(conn);
+
+    conn.execute_batch("INSERT INTO schema_version (version) VALUES (31);")
+        .map_err(|e| format!("Migration v31 version bump failed: {}", e))?;
+
+    log::info!("Migrated database to schema version 31 (sync columns on new databases)");
+    Ok(())
+}
+
 // ============================================================================
 // MIGRATION TEMPLATE
 // ============================================================================
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -30,6 +32,26 @@ pub struct Project {
     pub cover_url: Option<String>,
     pub has_missing_deps: bool,
     pub als_parsed_at: Option<i64>,
+    #[serde(flatten)]
+    pub arrangement: ArrangementStats,
+}
+
+/// Arrangement and clip statistics from the project's current .als.
+/// All None until the set has been parsed.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ArrangementStats {
+    pub arrangement_length_bars: Option<f64>,
+    pub arrangement_length_seconds: Option<f64>,
+    pub time_signature: Option<String>, // "4/4"
+    pub scene_count: Option<i64>,
+    pub session_clip_count: Option<i64>,
+    pub arrangement_clip_count: Option<i64>,
+    pub live_version: Option<String>,   // "11.3.13"
+    /// Lowest, highest and average song tempo over the arrangement, from the
+    /// tempo automation (all equal to the set tempo when it isn't automated).
+    pub tempo_min: Option<f64>,
+    pub tempo_max: Option<f64>,
+    pub tempo_avg: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -49,6 +71,18 @@ pub struct AbletonSet {
     pub file_size: Option<i64>,
 }
 
+/// A timestamped copy of a set from the project's Backup folder.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetBackup {
+    pub id: i64,
+    pub project_id: i64,
+    pub set_id: Option<i64>, // None when the set it was taken from is gone
+    pub backup_path: String,
+    pub backed_up_at: Option<String>, // from the filename, local time
+    pub modified_time: String,
+    pub file_size: Option<i64>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Bounce {
     pub id: i64,
@@ -57,6 +91,59 @@ pub struct Bounce {
     pub modified_time: String,
     pub duration_seconds: Option<f64>,
     pub notes: String,
+    pub format: Option<String>, // "WAV" | "RF64" | "BW64" | "AIFF" | "FLAC" | "MP3"
+    pub sample_rate: Option<i64>,
+    pub bit_depth: Option<i64>, // None for MP3
+    pub channels: Option<i64>,
+    pub sample_format: Option<String>, // "pcm" | "float" for uncompressed audio
+    pub integrated_lufs: Option<f64>,
+    pub short_term_max_lufs: Option<f64>,
+    pub loudness_range_lu: Option<f64>,
+    pub sample_peak_dbfs: Option<f64>,
+    pub true_peak_dbtp: Option<f64>,
+    /// The modified_time the bounce was analysed at; None if never analysed
+    pub loudness_mtime: Option<String>,
+    /// None without a steady beat or until analysed
+    pub detected_bpm: Option<f64>,
+    pub detected_key: Option<String>, // "A Minor"
+    /// Metadata embedded in a WAV bounce; None when it has none
+    pub embedded_metadata: Option<BounceMetadata>,
+}
+
+/// Metadata embedded in a WAV file's bext, iXML and LIST/INFO chunks.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct BounceMetadata {
+    pub bext: Option<BextInfo>,
+    /// iXML leaf elements by path below the root ("PROJECT", "ASWG/tempo")
+    pub ixml: BTreeMap<String, String>,
+    /// LIST/INFO tags by ID ("INAM" is the title, "IART" the artist)
+    pub info: BTreeMap<String, String>,
+}
+
+/// What SetCrate writes into an exported bounce: the project's name, tempo
+/// and key, the artist name from settings, the bounce's notes and the
+/// project's cover image.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BounceTags {
+    pub title: String,
+    pub artist: Option<String>,
+    pub bpm: Option<f64>,
+    pub key: Option<String>,
+    pub comment: Option<String>,
+    pub cover_path: Option<String>,
+}
+
+/// The text fields of a Broadcast Wave (EBU Tech 3285) bext chunk.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct BextInfo {
+    pub description: String,
+    pub originator: String,
+    pub originator_reference: String,
+    pub origination_date: String, // "yyyy-mm-dd"
+    pub origination_time: String, // "hh:mm:ss"
+    /// First sample's position, in samples since midnight
+    pub time_reference: u64,
+    pub coding_history: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -87,9 +174,40 @@ pub struct ScanSummary {
     pub new: usize,
     pub updated: usize,
     pub missing: usize,
+    /// Projects processed because they were new or changed on disk.
+    pub changed: usize,
+    /// Projects left alone because nothing changed since the last scan.
+    pub skipped: usize,
     pub errors: Vec<String>,
 }
 
+/// A folder of projects scanned into the library.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LibraryRoot {
+    pub id: i64,
+    pub path: String,
+    /// Name of the bounce subfolder inside this root's projects.
+    pub bounce_folder_name: String,
+    /// Folder level below the root that names a project's genre:
+    /// 1 = root/Techno/Track A → "Techno", 2 = root/Artist/Techno/Track A → "Techno",
+    /// 0 = no genre labels.
+    pub genre_depth: i64,
+    /// Whether the folder is reachable right now (not stored). Projects on an
+    /// offline root are never flagged missing.
+    #[serde(default)]
+    pub online: bool,
+}
+
+/// What a project folder looked like when it was last scanned.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DirSnapshot {
+    /// Latest modification time (Unix nanoseconds) of the folder, its files
+    /// and its bounce folder.
+    pub mtime: i64,
+    pub size: i64,
+    pub file_count: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DiscoveredProject {
     pub path: String,
@@ -208,9 +326,15 @@ pub struct ProjectFilters {
     pub sort_by: Option<String>,
     pub sort_dir: Option<String>,
     pub collection_id: Option<i64>,
+    pub min_arrangement_bars: Option<f64>,
+    pub max_arrangement_bars: Option<f64>,
+    pub time_signature: Option<String>,
+    /// BPM range; a project matches if any tempo it plays at falls inside.
+    pub min_bpm: Option<f64>,
+    pub max_bpm: Option<f64>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct PluginInfo {
     pub name: String,
     pub plugin_type: String,
@@ -221,6 +345,189 @@ pub struct SampleWithStatus {
     pub path: String,
     pub filename: String,
     pub is_missing: bool,
+    pub relative_path: Option<String>,
+    pub resolved_path: Option<String>, // where the file was found, if not at `path`
+    pub location: String, // "project" | "user_library" | "core_library" | "pack" | "external"
+}
+
+/// An audio file under one of the user's sample folders.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct IndexedSample {
+    pub path: String,
+    pub root: String,
+    pub filename: String,
+    pub file_size: i64,
+    pub modified_time: i64,
+    pub content_hash: String, // SHA-256, hex
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SampleIndexSummary {
+    pub files: usize,
+    pub hashed: usize, // new or changed since the last index run
+    pub removed: usize,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RelinkCandidate {
+    pub path: String,
+    pub file_size: i64,
+    pub content_hash: String,
+    pub size_matches: Option<bool>, // None when the set doesn't record the original size
+    pub duplicates: usize, // other indexed copies with the same content
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RelinkEntry {
+    pub sample_path: String,
+    pub filename: String,
+    pub original_size: Option<i64>,
+    pub candidates: Vec<RelinkCandidate>, // best first
+    pub proposed_path: Option<String>,
+}
+
+/// Dry-run result: what relinking would change, without touching the set.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RelinkReport {
+    pub project_id: i64,
+    pub set_path: String,
+    pub entries: Vec<RelinkEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RelinkChoice {
+    pub sample_path: String,
+    pub new_path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RelinkResult {
+    pub set_path: String,
+    pub backup_path: Option<String>,
+    pub relinked: usize,
+}
+
+/// Outcome of "Collect All and Save" for one project.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CollectResult {
+    pub set_path: String,
+    pub files_copied: usize,
+    pub files_reused: usize, // an identical copy was already in Samples/Imported
+    pub bytes_copied: u64,
+    pub missing: Vec<String>, // samples that couldn't be found, left as they were
+    pub backup_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TrackInfo {
+    /// Live's track Id — stable across saves of the same set, so it tells a
+    /// renamed track from a new one when diffing versions. Not persisted.
+    #[serde(skip)]
+    pub live_id: Option<i64>,
+    pub name: String,
+    pub track_type: String, // "audio" | "midi" | "group" | "return"
+    pub color_index: Option<i64>,
+    pub is_muted: bool,
+    pub is_soloed: bool,
+    pub is_frozen: bool,
+    pub devices: Vec<String>,
+    pub device_chain: Vec<DeviceInfo>,
+}
+
+/// One device on a track, flattened depth-first so racks precede their contents.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DeviceInfo {
+    pub chain_order: i64,
+    pub depth: i64,                  // 0 = directly on the track
+    pub parent_order: Option<i64>,   // chain_order of the containing rack
+    pub branch_name: Option<String>, // rack chain the device sits in
+    pub name: String,
+    pub user_name: Option<String>,
+    pub device_type: String, // "native" | "rack" | "vst2" | "vst3" | "au" | "max_for_live"
+    pub is_on: bool,
+    pub preset_name: Option<String>,
+}
+
+/// A MIDI clip from a track's session clip slots or the arrangement.
+/// Positions are in beats; note times are relative to the clip start.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MidiClip {
+    pub track_index: i64, // position in the set's track list
+    pub name: String,
+    pub in_arrangement: bool,
+    pub start: Option<f64>,
+    pub end: Option<f64>,
+    pub loop_start: Option<f64>,
+    pub loop_end: Option<f64>,
+    pub loop_on: bool,
+    pub notes: Vec<MidiNote>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MidiNote {
+    pub key: i64, // MIDI note number, 60 = C3 in Live
+    pub time: f64,
+    pub duration: f64,
+    pub velocity: f64,
+    pub is_enabled: bool,
+}
+
+/// Pitch content of a project's MIDI clips, for key estimation.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PitchProfile {
+    /// Beats of note time per pitch class, C first.
+    pub histogram: Vec<f64>,
+    /// Best-matching key ("A Minor"), if the notes say enough to guess.
+    pub estimated_key: Option<String>,
+    pub midi_clip_count: i64,
+    pub note_count: i64,
+}
+
+/// The tempo and key of a project's current .als next to those detected from
+/// its newest analysed bounce. A mismatch is flagged only when both sides are
+/// known; half/double tempo and relative keys count as agreeing.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TempoKeyCheck {
+    pub als_bpm: Option<f64>,
+    pub als_key: Option<String>,
+    pub bounce_path: Option<String>,
+    pub detected_bpm: Option<f64>,
+    pub detected_key: Option<String>,
+    pub tempo_mismatch: bool,
+    pub key_mismatch: bool,
+}
+
+/// A tempo breakpoint: `bpm` at arrangement position `beat`.
+/// Live ramps linearly between consecutive breakpoints.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TempoPoint {
+    pub beat: f64,
+    pub bpm: f64,
+}
+
+/// The song switches to `numerator`/`denominator` at arrangement position `beat`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TimeSignatureChange {
+    pub beat: f64,
+    pub numerator: i64,
+    pub denominator: i64,
+}
+
+/// Tempo and time-signature changes over a set's arrangement, stored per set.
+/// Beats are quarter notes from the song start; both lists are sorted and
+/// start at beat 0.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct SetTempoMap {
+    pub tempo_points: Vec<TempoPoint>,
+    pub time_signatures: Vec<TimeSignatureChange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ArrangementLocator {
+    pub name: String,
+    pub beat: f64,
+    pub seconds: f64,
 }
 
 // ── Version Timeline types ──
@@ -242,6 +549,37 @@ pub struct VersionTimelineEntry {
     pub note_id: Option<i64>,
 }
 
+/// Before/after pair for a value that changed between two set versions.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ValueChange<T> {
+    pub before: Option<T>,
+    pub after: Option<T>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TrackRename {
+    pub from: String,
+    pub to: String,
+}
+
+/// Structural differences between two .als versions. Change fields are None
+/// when the value is the same in both sets.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetDiff {
+    pub tracks_added: Vec<String>,
+    pub tracks_removed: Vec<String>,
+    pub tracks_renamed: Vec<TrackRename>,
+    pub plugins_added: Vec<PluginInfo>,
+    pub plugins_removed: Vec<PluginInfo>,
+    pub tempo: Option<ValueChange<f64>>,
+    pub key: Option<ValueChange<String>>,
+    pub time_signature: Option<ValueChange<String>>,
+    pub arrangement_length_seconds: Option<ValueChange<f64>>,
+    pub samples_added: Vec<String>,
+    pub samples_removed: Vec<String>,
+    pub summary: String, // one-line description for the version timeline
+}
+
 // ── Collections types ──
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
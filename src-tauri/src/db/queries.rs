@@ -21,7 +21,11 @@
 // ============================================================================
 
 use rusqlite::{params, Connection, OptionalExtension};
+use crate::analysis::BounceAnalysis;
+use crate::analysis::key::keys_agree;
+use crate::analysis::tempo::tempos_agree;
 use crate::db::models::*;
+use std::path::Path;
 
 // ============================================================================
 // SYNC TRACKING
@@ -113,7 +117,10 @@ pub fn get_projects(conn: &Connection, filters: &ProjectFilters) -> Result<Vec<P
          p.in_rotation, p.notes, p.artwork_path, p.current_set_path, p.archived, p.missing, p.progress, \
          p.last_worked_on, p.created_at, p.updated_at, \
          p.cover_type, p.cover_locked, p.cover_seed, p.cover_style_preset, p.cover_asset_id, p.cover_updated_at, \
-         p.cover_url, p.has_missing_deps, p.als_parsed_at \
+         p.cover_url, p.has_missing_deps, p.als_parsed_at, \
+         p.arrangement_length_bars, p.arrangement_length_seconds, p.time_signature, \
+         p.scene_count, p.session_clip_count, p.arrangement_clip_count, p.live_version, \
+         p.tempo_min, p.tempo_max, p.tempo_avg \
          FROM projects p"
     );
     let mut conditions: Vec<String> = Vec::new();
@@ -162,6 +169,40 @@ pub fn get_projects(conn: &Connection, filters: &ProjectFilters) -> Result<Vec<P
         conditions.push(format!("p.last_worked_on >= datetime('now', '-{} days')", days));
     }
 
+    // Arrangement length filter (in bars) — unparsed projects never match
+    if let Some(min_bars) = filters.min_arrangement_bars {
+        conditions.push(format!("p.arrangement_length_bars >= ?{}", param_idx));
+        param_idx += 1;
+        param_values.push(Box::new(min_bars));
+    }
+    if let Some(max_bars) = filters.max_arrangement_bars {
+        conditions.push(format!("p.arrangement_length_bars <= ?{}", param_idx));
+        param_idx += 1;
+        param_values.push(Box::new(max_bars));
+    }
+
+    // Time signature filter
+    if let Some(ref time_signature) = filters.time_signature {
+        if !time_signature.is_empty() {
+            conditions.push(format!("p.time_signature = ?{}", param_idx));
+            param_idx += 1;
+            param_values.push(Box::new(time_signature.clone()));
+        }
+    }
+
+    // BPM filter — the project's tempo range must overlap the filter range.
+    // Projects whose set hasn't been parsed fall back to their BPM field.
+    if let Some(min_bpm) = filters.min_bpm {
+        conditions.push(format!("COALESCE(p.tempo_max, p.bpm) >= ?{}", param_idx));
+        param_idx += 1;
+        param_values.push(Box::new(min_bpm));
+    }
+    if let Some(max_bpm) = filters.max_bpm {
+        conditions.push(format!("COALESCE(p.tempo_min, p.bpm) <= ?{}", param_idx));
+        param_idx += 1;
+        param_values.push(Box::new(max_bpm));
+    }
+
     // Tag filter
     if let Some(ref tag_ids) = filters.tag_ids {
         if !tag_ids.is_empty() {
@@ -284,6 +325,7 @@ pub fn get_projects(conn: &Connection, filters: &ProjectFilters) -> Result<Vec<P
         Some("updated_at") => format!("p.updated_at {} NULLS LAST", dir),
         Some("in_rotation") => format!("p.in_rotation {}, p.name ASC", dir),
         Some("progress") => format!("p.progress {} NULLS LAST, p.name ASC", dir),
+        Some("arrangement_length") => format!("p.arrangement_length_bars {} NULLS LAST, p.name ASC", dir),
         _ => format!("p.last_worked_on {} NULLS LAST", dir),
     } };
     sql.push_str(&format!(" ORDER BY {}", sort_clause));
@@ -322,6 +364,18 @@ pub fn get_projects(conn: &Connection, filters: &ProjectFilters) -> Result<Vec<P
                 cover_url: row.get(24)?,
                 has_missing_deps: row.get::<_, i64>(25).unwrap_or(0) != 0,
                 als_parsed_at: row.get(26)?,
+                arrangement: ArrangementStats {
+                    arrangement_length_bars: row.get(27)?,
+                    arrangement_length_seconds: row.get(28)?,
+                    time_signature: row.get(29)?,
+                    scene_count: row.get(30)?,
+                    session_clip_count: row.get(31)?,
+                    arrangement_clip_count: row.get(32)?,
+                    live_version: row.get(33)?,
+                    tempo_min: row.get(34)?,
+                    tempo_max: row.get(35)?,
+                    tempo_avg: row.get(36)?,
+                },
             })
         })
         .map_err(|e| e.to_string())?
@@ -343,7 +397,10 @@ pub fn get_project_by_id(conn: &Connection, id: i64) -> Result<Project, String>
          in_rotation, notes, artwork_path, current_set_path, archived, missing, progress, \
          last_worked_on, created_at, updated_at, \
          cover_type, cover_locked, cover_seed, cover_style_preset, cover_asset_id, cover_updated_at, \
-         cover_url, has_missing_deps, als_parsed_at \
+         cover_url, has_missing_deps, als_parsed_at, \
+         arrangement_length_bars, arrangement_length_seconds, time_signature, \
+         scene_count, session_clip_count, arrangement_clip_count, live_version, \
+         tempo_min, tempo_max, tempo_avg \
          FROM projects WHERE id = ?1",
         params![id],
         |row| {
@@ -376,6 +433,18 @@ pub fn get_project_by_id(conn: &Connection, id: i64) -> Result<Project, String>
                 cover_url: row.get(24)?,
                 has_missing_deps: row.get::<_, i64>(25).unwrap_or(0) != 0,
                 als_parsed_at: row.get(26)?,
+                arrangement: ArrangementStats {
+                    arrangement_length_bars: row.get(27)?,
+                    arrangement_length_seconds: row.get(28)?,
+                    time_signature: row.get(29)?,
+                    scene_count: row.get(30)?,
+                    session_clip_count: row.get(31)?,
+                    arrangement_clip_count: row.get(32)?,
+                    live_version: row.get(33)?,
+                    tempo_min: row.get(34)?,
+                    tempo_max: row.get(35)?,
+                    tempo_avg: row.get(36)?,
+                },
             })
         },
     ).map_err(|e| format!("Project not found: {}", e))?;
@@ -681,13 +750,49 @@ pub fn replace_project_samples(conn: &Connection, project_id: i64, samples: &[Sa
         .map_err(|e| e.to_string())?;
     for sample in samples {
         conn.execute(
-            "INSERT INTO project_samples (project_id, path, filename, is_missing) VALUES (?1, ?2, ?3, ?4)",
-            params![project_id, sample.path, sample.filename, sample.is_missing as i64],
+            "INSERT INTO project_samples (project_id, path, filename, is_missing, relative_path, resolved_path, location) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                project_id, sample.path, sample.filename, sample.is_missing as i64,
+                sample.relative_path, sample.resolved_path, sample.location
+            ],
         ).map_err(|e| e.to_string())?;
     }
     Ok(())
 }
 
+/// Replace all tracks for a project (delete + reinsert), preserving arrangement order.
+/// Each track's device chain is stored alongside it in project_track_devices.
+pub fn replace_project_tracks(conn: &Connection, project_id: i64, tracks: &[TrackInfo]) -> Result<(), String> {
+    conn.execute("DELETE FROM project_tracks WHERE project_id = ?1", params![project_id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM project_track_devices WHERE project_id = ?1", params![project_id])
+        .map_err(|e| e.to_string())?;
+    for (idx, track) in tracks.iter().enumerate() {
+        let devices_json = serde_json::to_string(&track.devices).map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO project_tracks (project_id, track_index, name, track_type, color_index, is_muted, is_soloed, is_frozen, devices) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                project_id, idx as i64, track.name, track.track_type, track.color_index,
+                track.is_muted as i64, track.is_soloed as i64, track.is_frozen as i64, devices_json
+            ],
+        ).map_err(|e| e.to_string())?;
+        for device in &track.device_chain {
+            conn.execute(
+                "INSERT INTO project_track_devices (project_id, track_index, chain_order, depth, parent_order, branch_name, name, user_name, device_type, is_on, preset_name) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    project_id, idx as i64, device.chain_order, device.depth, device.parent_order,
+                    device.branch_name, device.name, device.user_name, device.device_type,
+                    device.is_on as i64, device.preset_name
+                ],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
 /// Set the has_missing_deps flag on a project.
 pub fn set_has_missing_deps(conn: &Connection, project_id: i64, has_missing: bool) -> Result<(), String> {
     conn.execute(
@@ -697,6 +802,21 @@ pub fn set_has_missing_deps(conn: &Connection, project_id: i64, has_missing: boo
     Ok(())
 }
 
+/// Store arrangement and clip statistics from the latest .als parse.
+pub fn set_arrangement_stats(conn: &Connection, project_id: i64, stats: &ArrangementStats) -> Result<(), String> {
+    conn.execute(
+        "UPDATE projects SET arrangement_length_bars = ?1, arrangement_length_seconds = ?2, time_signature = ?3, \
+         scene_count = ?4, session_clip_count = ?5, arrangement_clip_count = ?6, live_version = ?7, \
+         tempo_min = ?8, tempo_max = ?9, tempo_avg = ?10 WHERE id = ?11",
+        params![
+            stats.arrangement_length_bars, stats.arrangement_length_seconds, stats.time_signature,
+            stats.scene_count, stats.session_clip_count, stats.arrangement_clip_count, stats.live_version,
+            stats.tempo_min, stats.tempo_max, stats.tempo_avg, project_id
+        ],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 /// Record when the .als file was last parsed (Unix seconds of file mtime).
 pub fn set_als_parsed_at(conn: &Connection, project_id: i64, mtime: i64) -> Result<(), String> {
     conn.execute(
@@ -706,6 +826,16 @@ pub fn set_als_parsed_at(conn: &Connection, project_id: i64, mtime: i64) -> Resu
     Ok(())
 }
 
+/// Forget when the project's .als was last parsed, so the next parse runs
+/// even if the file's mtime hasn't moved.
+pub fn clear_als_parsed_at(conn: &Connection, project_id: i64) -> Result<(), String> {
+    conn.execute(
+        "UPDATE projects SET als_parsed_at = NULL WHERE id = ?1",
+        params![project_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 /// Get the als_parsed_at value for a project.
 pub fn get_als_parsed_at(conn: &Connection, project_id: i64) -> Result<Option<i64>, String> {
     conn.query_row(
@@ -734,13 +864,17 @@ pub fn get_project_plugins(conn: &Connection, project_id: i64) -> Result<Vec<Plu
 /// Get all samples for a project.
 pub fn get_project_samples(conn: &Connection, project_id: i64) -> Result<Vec<SampleWithStatus>, String> {
     let mut stmt = conn.prepare(
-        "SELECT path, filename, is_missing FROM project_samples WHERE project_id = ?1 ORDER BY filename"
+        "SELECT path, filename, is_missing, relative_path, resolved_path, location \
+         FROM project_samples WHERE project_id = ?1 ORDER BY filename"
     ).map_err(|e| e.to_string())?;
     let samples = stmt.query_map(params![project_id], |row| {
         Ok(SampleWithStatus {
             path: row.get(0)?,
             filename: row.get(1)?,
             is_missing: row.get::<_, i64>(2)? != 0,
+            relative_path: row.get(3)?,
+            resolved_path: row.get(4)?,
+            location: row.get(5)?,
         })
     }).map_err(|e| e.to_string())?
       .filter_map(|r| r.ok())
@@ -748,6 +882,166 @@ pub fn get_project_samples(conn: &Connection, project_id: i64) -> Result<Vec<Sam
     Ok(samples)
 }
 
+/// Get all tracks for a project in arrangement order, with their device chains.
+pub fn get_project_tracks(conn: &Connection, project_id: i64) -> Result<Vec<TrackInfo>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT name, track_type, color_index, is_muted, is_soloed, is_frozen, devices \
+         FROM project_tracks WHERE project_id = ?1 ORDER BY track_index"
+    ).map_err(|e| e.to_string())?;
+    let mut tracks: Vec<TrackInfo> = stmt.query_map(params![project_id], |row| {
+        let devices_json: String = row.get(6)?;
+        Ok(TrackInfo {
+            live_id: None,
+            name: row.get(0)?,
+            track_type: row.get(1)?,
+            color_index: row.get(2)?,
+            is_muted: row.get::<_, i64>(3)? != 0,
+            is_soloed: row.get::<_, i64>(4)? != 0,
+            is_frozen: row.get::<_, i64>(5)? != 0,
+            devices: serde_json::from_str(&devices_json).unwrap_or_default(),
+            device_chain: Vec::new(),
+        })
+    }).map_err(|e| e.to_string())?
+      .filter_map(|r| r.ok())
+      .collect();
+
+    let mut stmt = conn.prepare(
+        "SELECT track_index, chain_order, depth, parent_order, branch_name, name, user_name, device_type, is_on, preset_name \
+         FROM project_track_devices WHERE project_id = ?1 ORDER BY track_index, chain_order"
+    ).map_err(|e| e.to_string())?;
+    let devices = stmt.query_map(params![project_id], |row| {
+        Ok((row.get::<_, i64>(0)?, DeviceInfo {
+            chain_order: row.get(1)?,
+            depth: row.get(2)?,
+            parent_order: row.get(3)?,
+            branch_name: row.get(4)?,
+            name: row.get(5)?,
+            user_name: row.get(6)?,
+            device_type: row.get(7)?,
+            is_on: row.get::<_, i64>(8)? != 0,
+            preset_name: row.get(9)?,
+        }))
+    }).map_err(|e| e.to_string())?;
+    for (track_index, device) in devices.filter_map(|r| r.ok()) {
+        if let Some(track) = tracks.get_mut(track_index as usize) {
+            track.device_chain.push(device);
+        }
+    }
+    Ok(tracks)
+}
+
+/// Replace all MIDI clips for a project (delete + reinsert). Notes are stored
+/// as JSON on their clip.
+pub fn replace_project_midi_clips(conn: &Connection, project_id: i64, clips: &[MidiClip]) -> Result<(), String> {
+    conn.execute("DELETE FROM project_midi_clips WHERE project_id = ?1", params![project_id])
+        .map_err(|e| e.to_string())?;
+    for (idx, clip) in clips.iter().enumerate() {
+        let notes_json = serde_json::to_string(&clip.notes).map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO project_midi_clips (project_id, track_index, clip_index, name, in_arrangement, start_beat, end_beat, loop_start, loop_end, loop_on, notes) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                project_id, clip.track_index, idx as i64, clip.name, clip.in_arrangement as i64,
+                clip.start, clip.end, clip.loop_start, clip.loop_end, clip.loop_on as i64, notes_json
+            ],
+        ).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Get a project's MIDI clips in document order.
+pub fn get_project_midi_clips(conn: &Connection, project_id: i64) -> Result<Vec<MidiClip>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT track_index, name, in_arrangement, start_beat, end_beat, loop_start, loop_end, loop_on, notes \
+         FROM project_midi_clips WHERE project_id = ?1 ORDER BY clip_index"
+    ).map_err(|e| e.to_string())?;
+    let clips = stmt.query_map(params![project_id], |row| {
+        let notes_json: String = row.get(8)?;
+        Ok(MidiClip {
+            track_index: row.get(0)?,
+            name: row.get(1)?,
+            in_arrangement: row.get::<_, i64>(2)? != 0,
+            start: row.get(3)?,
+            end: row.get(4)?,
+            loop_start: row.get(5)?,
+            loop_end: row.get(6)?,
+            loop_on: row.get::<_, i64>(7)? != 0,
+            notes: serde_json::from_str(&notes_json).unwrap_or_default(),
+        })
+    }).map_err(|e| e.to_string())?
+      .filter_map(|r| r.ok())
+      .collect();
+    Ok(clips)
+}
+
+/// Store the pitch-class histogram and estimated key from the latest .als parse.
+pub fn set_pitch_profile(conn: &Connection, project_id: i64, histogram: &[f64; 12], estimated_key: Option<&str>) -> Result<(), String> {
+    let histogram_json = serde_json::to_string(histogram).map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE projects SET pitch_class_histogram = ?1, estimated_key = ?2 WHERE id = ?3",
+        params![histogram_json, estimated_key, project_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Pitch content of a project's MIDI clips. The histogram is all zeros until
+/// the set has been parsed.
+pub fn get_pitch_profile(conn: &Connection, project_id: i64) -> Result<PitchProfile, String> {
+    let (histogram_json, estimated_key): (Option<String>, Option<String>) = conn.query_row(
+        "SELECT pitch_class_histogram, estimated_key FROM projects WHERE id = ?1",
+        params![project_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).map_err(|e| e.to_string())?;
+    let histogram = histogram_json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_else(|| vec![0.0; 12]);
+
+    let (midi_clip_count, note_count) = get_project_midi_clips(conn, project_id)?
+        .iter()
+        .fold((0, 0), |(clips, notes), c| (clips + 1, notes + c.notes.len() as i64));
+
+    Ok(PitchProfile { histogram, estimated_key, midi_clip_count, note_count })
+}
+
+/// Store the tempo map parsed from a set.
+pub fn set_set_tempo_map(conn: &Connection, set_path: &str, tempo_map: &SetTempoMap) -> Result<(), String> {
+    let json = serde_json::to_string(tempo_map).map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE ableton_sets SET tempo_map = ?1 WHERE set_path = ?2",
+        params![json, set_path],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// The stored tempo map of a set, or None if it hasn't been parsed yet.
+pub fn get_set_tempo_map(conn: &Connection, set_path: &str) -> Result<Option<SetTempoMap>, String> {
+    let json: Option<String> = conn.query_row(
+        "SELECT tempo_map FROM ableton_sets WHERE set_path = ?1",
+        params![set_path],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())?.flatten();
+    Ok(json.and_then(|j| serde_json::from_str(&j).ok()))
+}
+
+/// The snapshot of a project folder taken at its last successful scan.
+pub fn get_scan_snapshot(conn: &Connection, dir_path: &str) -> Result<Option<DirSnapshot>, String> {
+    conn.query_row(
+        "SELECT mtime, size, file_count FROM scan_snapshots WHERE dir_path = ?1",
+        params![dir_path],
+        |row| Ok(DirSnapshot { mtime: row.get(0)?, size: row.get(1)?, file_count: row.get(2)? }),
+    ).optional().map_err(|e| e.to_string())
+}
+
+pub fn set_scan_snapshot(conn: &Connection, dir_path: &str, snapshot: &DirSnapshot) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO scan_snapshots (dir_path, mtime, size, file_count, scanned_at) \
+         VALUES (?1, ?2, ?3, ?4, datetime('now')) \
+         ON CONFLICT(dir_path) DO UPDATE SET mtime = ?2, size = ?3, file_count = ?4, scanned_at = datetime('now')",
+        params![dir_path, snapshot.mtime, snapshot.size, snapshot.file_count],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 /// Get plugins text for FTS indexing.
 fn get_plugins_text_for_fts(conn: &Connection, project_id: i64) -> String {
     let mut stmt = match conn.prepare("SELECT name FROM project_plugins WHERE project_id = ?1") {
@@ -761,6 +1055,83 @@ fn get_plugins_text_for_fts(conn: &Connection, project_id: i64) -> String {
     names.join(" ")
 }
 
+// ============================================================================
+// SAMPLE INDEX
+// ============================================================================
+
+fn row_to_indexed_sample(row: &rusqlite::Row) -> rusqlite::Result<IndexedSample> {
+    Ok(IndexedSample {
+        path: row.get(0)?,
+        root: row.get(1)?,
+        filename: row.get(2)?,
+        file_size: row.get(3)?,
+        modified_time: row.get(4)?,
+        content_hash: row.get(5)?,
+    })
+}
+
+/// Insert or refresh an indexed file.
+pub fn upsert_indexed_sample(conn: &Connection, sample: &IndexedSample) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO sample_index (path, root, filename, file_size, modified_time, content_hash) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6) \
+         ON CONFLICT(path) DO UPDATE SET root = ?2, filename = ?3, file_size = ?4, modified_time = ?5, \
+         content_hash = ?6, indexed_at = datetime('now')",
+        params![
+            sample.path, sample.root, sample.filename, sample.file_size,
+            sample.modified_time, sample.content_hash
+        ],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Every indexed file, to compare a new walk of the sample folders against.
+pub fn get_indexed_samples(conn: &Connection) -> Result<Vec<IndexedSample>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT path, root, filename, file_size, modified_time, content_hash FROM sample_index"
+    ).map_err(|e| e.to_string())?;
+    let samples = stmt.query_map([], row_to_indexed_sample)
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(samples)
+}
+
+pub fn delete_indexed_sample(conn: &Connection, path: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM sample_index WHERE path = ?1", params![path])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Indexed files with this filename (case-insensitive), in path order.
+pub fn find_indexed_samples_by_filename(conn: &Connection, filename: &str) -> Result<Vec<IndexedSample>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT path, root, filename, file_size, modified_time, content_hash \
+         FROM sample_index WHERE filename = ?1 COLLATE NOCASE ORDER BY path"
+    ).map_err(|e| e.to_string())?;
+    let samples = stmt.query_map(params![filename], row_to_indexed_sample)
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(samples)
+}
+
+pub fn get_set_by_id(conn: &Connection, set_id: i64) -> Result<AbletonSet, String> {
+    conn.query_row(
+        "SELECT id, project_id, set_path, modified_time, file_size FROM ableton_sets WHERE id = ?1",
+        params![set_id],
+        |row| {
+            Ok(AbletonSet {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                set_path: row.get(2)?,
+                modified_time: row.get(3)?,
+                file_size: row.get(4)?,
+            })
+        },
+    ).map_err(|e| e.to_string())
+}
+
 pub fn get_sets_for_project(conn: &Connection, project_id: i64) -> Result<Vec<AbletonSet>, String> {
     let mut stmt = conn
         .prepare("SELECT id, project_id, set_path, modified_time, file_size FROM ableton_sets WHERE project_id = ?1 ORDER BY modified_time DESC")
@@ -781,21 +1152,44 @@ pub fn get_sets_for_project(conn: &Connection, project_id: i64) -> Result<Vec<Ab
     Ok(sets)
 }
 
+const BOUNCE_COLUMNS: &str = "id, project_id, bounce_path, modified_time, duration_seconds, notes, \
+    format, sample_rate, bit_depth, channels, sample_format, \
+    integrated_lufs, short_term_max_lufs, loudness_range_lu, sample_peak_dbfs, true_peak_dbtp, loudness_mtime, \
+    detected_bpm, detected_key, embedded_metadata";
+
+fn map_bounce(row: &rusqlite::Row) -> rusqlite::Result<Bounce> {
+    Ok(Bounce {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        bounce_path: row.get(2)?,
+        modified_time: row.get(3)?,
+        duration_seconds: row.get(4)?,
+        notes: row.get::<_, String>(5).unwrap_or_default(),
+        format: row.get(6)?,
+        sample_rate: row.get(7)?,
+        bit_depth: row.get(8)?,
+        channels: row.get(9)?,
+        sample_format: row.get(10)?,
+        integrated_lufs: row.get(11)?,
+        short_term_max_lufs: row.get(12)?,
+        loudness_range_lu: row.get(13)?,
+        sample_peak_dbfs: row.get(14)?,
+        true_peak_dbtp: row.get(15)?,
+        loudness_mtime: row.get(16)?,
+        detected_bpm: row.get(17)?,
+        detected_key: row.get(18)?,
+        embedded_metadata: row
+            .get::<_, Option<String>>(19)?
+            .and_then(|json| serde_json::from_str(&json).ok()),
+    })
+}
+
 pub fn get_bounces_for_project(conn: &Connection, project_id: i64) -> Result<Vec<Bounce>, String> {
     let mut stmt = conn
-        .prepare("SELECT id, project_id, bounce_path, modified_time, duration_seconds, notes FROM bounces WHERE project_id = ?1 ORDER BY modified_time DESC")
+        .prepare(&format!("SELECT {} FROM bounces WHERE project_id = ?1 ORDER BY modified_time DESC", BOUNCE_COLUMNS))
         .map_err(|e| e.to_string())?;
     let bounces = stmt
-        .query_map(params![project_id], |row| {
-            Ok(Bounce {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                bounce_path: row.get(2)?,
-                modified_time: row.get(3)?,
-                duration_seconds: row.get(4)?,
-                notes: row.get::<_, String>(5).unwrap_or_default(),
-            })
-        })
+        .query_map(params![project_id], map_bounce)
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
@@ -1193,6 +1587,64 @@ pub fn delete_marker(conn: &Connection, id: i64) -> Result<(), String> {
     Ok(())
 }
 
+/// Marker type for markers imported from Ableton arrangement locators.
+pub const LOCATOR_MARKER_TYPE: &str = "locator";
+
+/// Sync imported arrangement locators into the markers table.
+/// Only rows with type 'locator' are touched — user-created markers are left alone.
+/// Existing locator markers are updated in place (matched by name first, then by
+/// order) so their ids, task links and sync state survive a re-scan.
+pub fn sync_locator_markers(conn: &Connection, project_id: i64, locators: &[ArrangementLocator]) -> Result<(), String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp_seconds, text FROM markers WHERE project_id = ?1 AND type = ?2 ORDER BY timestamp_seconds ASC"
+    ).map_err(|e| e.to_string())?;
+    let existing: Vec<(i64, f64, String)> = stmt
+        .query_map(params![project_id, LOCATOR_MARKER_TYPE], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    drop(stmt);
+
+    let mut used = vec![false; existing.len()];
+    let mut unmatched: Vec<&ArrangementLocator> = Vec::new();
+
+    // Pass 1: same name → keep the row, move it if the locator moved
+    for loc in locators {
+        match existing.iter().enumerate().position(|(i, (_, _, text))| !used[i] && *text == loc.name) {
+            Some(i) => {
+                used[i] = true;
+                let (id, ts, _) = &existing[i];
+                if (ts - loc.seconds).abs() > 1e-3 {
+                    update_marker(conn, *id, Some(loc.seconds), None, None)?;
+                }
+            }
+            None => unmatched.push(loc),
+        }
+    }
+
+    // Pass 2: renamed locators reuse leftover rows in order; anything else is new
+    for loc in unmatched {
+        match used.iter().position(|u| !u) {
+            Some(i) => {
+                used[i] = true;
+                update_marker(conn, existing[i].0, Some(loc.seconds), None, Some(loc.name.clone()))?;
+            }
+            None => {
+                create_marker(conn, project_id, None, loc.seconds, LOCATOR_MARKER_TYPE, &loc.name)?;
+            }
+        }
+    }
+
+    // Locators removed in Live
+    for (i, (id, _, _)) in existing.iter().enumerate() {
+        if !used[i] {
+            delete_marker(conn, *id)?;
+        }
+    }
+
+    Ok(())
+}
+
 // ── Task queries ──
 
 pub fn get_tasks_for_project(conn: &Connection, project_id: i64) -> Result<Vec<ProjectTask>, String> {
@@ -1786,22 +2238,125 @@ pub fn update_bounce_notes(conn: &Connection, id: i64, notes: &str) -> Result<Bo
     ).map_err(|e| e.to_string())?;
     mark_dirty(conn, "bounces", id);
 
+    get_bounce(conn, id)
+}
+
+pub fn get_bounce(conn: &Connection, id: i64) -> Result<Bounce, String> {
     conn.query_row(
-        "SELECT id, project_id, bounce_path, modified_time, duration_seconds, notes FROM bounces WHERE id = ?1",
+        &format!("SELECT {} FROM bounces WHERE id = ?1", BOUNCE_COLUMNS),
         params![id],
-        |row| {
-            Ok(Bounce {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                bounce_path: row.get(2)?,
-                modified_time: row.get(3)?,
-                duration_seconds: row.get(4)?,
-                notes: row.get::<_, String>(5).unwrap_or_default(),
-            })
-        },
+        map_bounce,
     ).map_err(|e| format!("Bounce not found: {}", e))
 }
 
+// ============================================================================
+// BOUNCE ANALYSIS
+// ============================================================================
+
+/// Store what was measured on a bounce, on the file as of `measured_mtime`.
+pub fn set_bounce_analysis(conn: &Connection, bounce_path: &str, analysis: &BounceAnalysis, measured_mtime: &str) -> Result<(), String> {
+    let loudness = &analysis.loudness;
+    conn.execute(
+        "UPDATE bounces SET integrated_lufs = ?1, short_term_max_lufs = ?2, loudness_range_lu = ?3, \
+         sample_peak_dbfs = ?4, true_peak_dbtp = ?5, detected_bpm = ?6, detected_key = ?7, loudness_mtime = ?8 \
+         WHERE bounce_path = ?9",
+        params![
+            loudness.integrated_lufs,
+            loudness.short_term_max_lufs,
+            loudness.loudness_range_lu,
+            loudness.sample_peak_dbfs,
+            loudness.true_peak_dbtp,
+            analysis.bpm,
+            analysis.key.as_ref().map(|k| k.to_string()),
+            measured_mtime,
+            bounce_path,
+        ],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Clear the measurements of a project's bounces that changed since they
+/// were analysed.
+pub fn clear_stale_analysis(conn: &Connection, project_id: i64) -> Result<(), String> {
+    conn.execute(
+        "UPDATE bounces SET integrated_lufs = NULL, short_term_max_lufs = NULL, loudness_range_lu = NULL, \
+         sample_peak_dbfs = NULL, true_peak_dbtp = NULL, detected_bpm = NULL, detected_key = NULL, loudness_mtime = NULL \
+         WHERE project_id = ?1 AND loudness_mtime IS NOT NULL AND loudness_mtime != modified_time",
+        params![project_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Record the tempo and key the current .als states, to check detected
+/// values against. Unlike bpm/musical_key these never hold user edits.
+pub fn set_als_tempo_key(conn: &Connection, project_id: i64, bpm: Option<f64>, key: Option<&str>) -> Result<(), String> {
+    conn.execute(
+        "UPDATE projects SET als_bpm = ?1, als_key = ?2 WHERE id = ?3",
+        params![bpm, key, project_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// The newest analysed bounce's path, tempo and key.
+type DetectedTempoKey = (String, Option<f64>, Option<String>);
+
+fn latest_detected_tempo_key(conn: &Connection, project_id: i64) -> Result<Option<DetectedTempoKey>, String> {
+    conn.query_row(
+        "SELECT bounce_path, detected_bpm, detected_key FROM bounces \
+         WHERE project_id = ?1 AND (detected_bpm IS NOT NULL OR detected_key IS NOT NULL) \
+         ORDER BY modified_time DESC LIMIT 1",
+        params![project_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ).optional().map_err(|e| e.to_string())
+}
+
+/// Fill an empty bpm/musical_key from the newest analysed bounce. Runs after
+/// the .als metadata is stored, so the set's own values come first.
+pub fn fill_tempo_key_from_bounces(conn: &Connection, project_id: i64) -> Result<(), String> {
+    if let Some((_, bpm, key)) = latest_detected_tempo_key(conn, project_id)? {
+        if let Some(bpm) = bpm {
+            set_bpm_if_empty(conn, project_id, bpm)?;
+        }
+        if let Some(key) = key {
+            set_key_if_empty(conn, project_id, &key)?;
+        }
+    }
+    Ok(())
+}
+
+/// The .als tempo and key next to those detected from the newest analysed
+/// bounce, with disagreements flagged.
+pub fn get_tempo_key_check(conn: &Connection, project_id: i64) -> Result<TempoKeyCheck, String> {
+    let (als_bpm, als_key): (Option<f64>, Option<String>) = conn.query_row(
+        "SELECT als_bpm, als_key FROM projects WHERE id = ?1",
+        params![project_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).map_err(|e| e.to_string())?;
+    let (bounce_path, detected_bpm, detected_key) = match latest_detected_tempo_key(conn, project_id)? {
+        Some((path, bpm, key)) => (Some(path), bpm, key),
+        None => (None, None, None),
+    };
+    let tempo_mismatch = matches!((als_bpm, detected_bpm), (Some(a), Some(d)) if !tempos_agree(a, d));
+    let key_mismatch = matches!((&als_key, &detected_key), (Some(a), Some(d)) if !keys_agree(a, d));
+    Ok(TempoKeyCheck { als_bpm, als_key, bounce_path, detected_bpm, detected_key, tempo_mismatch, key_mismatch })
+}
+
+/// The tags an exported copy of a bounce carries. Empty project fields,
+/// settings and notes are left out.
+pub fn get_bounce_tags(conn: &Connection, bounce_id: i64) -> Result<BounceTags, String> {
+    let bounce = get_bounce(conn, bounce_id)?;
+    let project = get_project_by_id(conn, bounce.project_id)?;
+    let non_empty = |s: String| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+    Ok(BounceTags {
+        title: project.name,
+        artist: get_setting(conn, "artist_name")?.and_then(non_empty),
+        bpm: project.bpm.filter(|&bpm| bpm > 0.0),
+        key: non_empty(project.musical_key),
+        comment: non_empty(bounce.notes),
+        cover_path: project.artwork_path.and_then(non_empty),
+    })
+}
+
 // ============================================================================
 // VERSION TIMELINE
 // ============================================================================
@@ -1836,6 +2391,56 @@ pub fn get_version_timeline(conn: &Connection, project_id: i64) -> Result<Vec<Ve
     Ok(entries)
 }
 
+fn map_set_backup(row: &rusqlite::Row) -> rusqlite::Result<SetBackup> {
+    Ok(SetBackup {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        set_id: row.get(2)?,
+        backup_path: row.get(3)?,
+        backed_up_at: row.get(4)?,
+        modified_time: row.get(5)?,
+        file_size: row.get(6)?,
+    })
+}
+
+/// Backups from the project's Backup folder, newest first.
+pub fn get_set_backups(conn: &Connection, project_id: i64) -> Result<Vec<SetBackup>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, set_id, backup_path, backed_up_at, modified_time, file_size \
+         FROM set_backups WHERE project_id = ?1 \
+         ORDER BY COALESCE(backed_up_at, modified_time) DESC"
+    ).map_err(|e| e.to_string())?;
+
+    let backups = stmt.query_map(params![project_id], map_set_backup)
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(backups)
+}
+
+pub fn get_set_backup(conn: &Connection, backup_id: i64) -> Result<SetBackup, String> {
+    conn.query_row(
+        "SELECT id, project_id, set_id, backup_path, backed_up_at, modified_time, file_size \
+         FROM set_backups WHERE id = ?1",
+        params![backup_id],
+        map_set_backup,
+    ).map_err(|e| format!("Backup not found: {}", e))
+}
+
+/// Record a set written by the app (such as a restored backup) so it shows
+/// up before the next scan.
+pub fn add_ableton_set(conn: &Connection, project_id: i64, set_path: &str, modified_time: &str, file_size: Option<i64>) -> Result<AbletonSet, String> {
+    conn.execute(
+        "INSERT INTO ableton_sets (project_id, set_path, modified_time, file_size) VALUES (?1, ?2, ?3, ?4) \
+         ON CONFLICT(set_path) DO UPDATE SET modified_time = ?3, file_size = ?4",
+        params![project_id, set_path, modified_time, file_size],
+    ).map_err(|e| e.to_string())?;
+    let id: i64 = conn.query_row(
+        "SELECT id FROM ableton_sets WHERE set_path = ?1", params![set_path], |row| row.get(0),
+    ).map_err(|e| e.to_string())?;
+    get_set_by_id(conn, id)
+}
+
 pub fn upsert_version_note(conn: &Connection, set_id: i64, project_id: i64, note: &str) -> Result<VersionNote, String> {
     conn.execute(
         "INSERT INTO version_notes (set_id, project_id, note) VALUES (?1, ?2, ?3) \
@@ -2105,7 +2710,24 @@ pub fn get_collection_project_ids(conn: &Connection, collection_id: i64) -> Resu
     Ok(ids)
 }
 
+/// Column for an arrangement-statistics smart rule field.
+fn arrangement_stat_column(field: &str) -> &'static str {
+    match field {
+        "arrangement_bars" => "p.arrangement_length_bars",
+        "arrangement_seconds" => "p.arrangement_length_seconds",
+        "scene_count" => "p.scene_count",
+        "session_clips" => "p.session_clip_count",
+        "tempo_min" => "p.tempo_min",
+        "tempo_max" => "p.tempo_max",
+        "tempo_avg" => "p.tempo_avg",
+        _ => "p.arrangement_clip_count",
+    }
+}
+
 /// Evaluate a smart collection's rules and return matching project IDs.
+/// Projects whose set hasn't been parsed have no arrangement statistics
+/// (NULL, not 0), so like the library filters they match no rule on them:
+/// "Arrangement Bars < 32" doesn't pull in every unparsed project.
 pub fn evaluate_smart_collection(conn: &Connection, collection_id: i64) -> Result<Vec<i64>, String> {
     let rules = get_smart_collection_rules(conn, collection_id)?;
     if rules.is_empty() {
@@ -2152,18 +2774,60 @@ pub fn evaluate_smart_collection(conn: &Connection, collection_id: i64) -> Resul
                 param_values.push(Box::new(val));
             }
 
-            // String fields: key, genre, status
-            ("key" | "genre" | "status", "is") => {
-                let col = match rule.field.as_str() {
-                    "key" => "p.musical_key", "genre" => "p.genre_label", _ => "p.status"
-                };
-                conditions.push(format!("{} = ?{}", col, param_idx));
+            // Arrangement statistics from the parsed .als
+            ("arrangement_bars" | "arrangement_seconds" | "scene_count" | "session_clips" | "arrangement_clips"
+             | "tempo_min" | "tempo_max" | "tempo_avg",
+             op @ ("gt" | "lt" | "eq" | "gte" | "lte")) => {
+                let cmp = match op { "gt" => ">", "lt" => "<", "eq" => "=", "gte" => ">=", "lte" => "<=", _ => "=" };
+                conditions.push(format!("{} {} ?{}", arrangement_stat_column(&rule.field), cmp, param_idx));
                 param_idx += 1;
-                param_values.push(Box::new(rule.value.clone()));
+                let val: f64 = rule.value.parse().unwrap_or(0.0);
+                param_values.push(Box::new(val));
             }
-            ("key" | "genre" | "status", "is_not") => {
-                let col = match rule.field.as_str() {
-                    "key" => "p.musical_key", "genre" => "p.genre_label", _ => "p.status"
+            ("arrangement_bars" | "arrangement_seconds" | "scene_count" | "session_clips" | "arrangement_clips"
+             | "tempo_min" | "tempo_max" | "tempo_avg", "between") => {
+                // value is JSON like [16, 32]
+                if let Ok(vals) = serde_json::from_str::<Vec<f64>>(&rule.value) {
+                    if vals.len() == 2 {
+                        let col = arrangement_stat_column(&rule.field);
+                        conditions.push(format!("{} >= ?{} AND {} <= ?{}", col, param_idx, col, param_idx + 1));
+                        param_idx += 2;
+                        param_values.push(Box::new(vals[0]));
+                        param_values.push(Box::new(vals[1]));
+                    }
+                }
+            }
+            ("time_signature" | "live_version", "is") => {
+                let col = if rule.field == "time_signature" { "p.time_signature" } else { "p.live_version" };
+                conditions.push(format!("{} = ?{}", col, param_idx));
+                param_idx += 1;
+                param_values.push(Box::new(rule.value.clone()));
+            }
+            ("time_signature" | "live_version", "is_not") => {
+                let col = if rule.field == "time_signature" { "p.time_signature" } else { "p.live_version" };
+                conditions.push(format!("{} != ?{}", col, param_idx));
+                param_idx += 1;
+                param_values.push(Box::new(rule.value.clone()));
+            }
+            ("live_version", "starts_with") => {
+                // "11" matches 11.0 through 11.3.13
+                conditions.push(format!("p.live_version LIKE ?{}", param_idx));
+                param_idx += 1;
+                param_values.push(Box::new(format!("{}%", rule.value)));
+            }
+
+            // String fields: key, genre, status
+            ("key" | "genre" | "status", "is") => {
+                let col = match rule.field.as_str() {
+                    "key" => "p.musical_key", "genre" => "p.genre_label", _ => "p.status"
+                };
+                conditions.push(format!("{} = ?{}", col, param_idx));
+                param_idx += 1;
+                param_values.push(Box::new(rule.value.clone()));
+            }
+            ("key" | "genre" | "status", "is_not") => {
+                let col = match rule.field.as_str() {
+                    "key" => "p.musical_key", "genre" => "p.genre_label", _ => "p.status"
                 };
                 conditions.push(format!("{} != ?{}", col, param_idx));
                 param_idx += 1;
@@ -2198,6 +2862,51 @@ pub fn evaluate_smart_collection(conn: &Connection, collection_id: i64) -> Resul
                 param_values.push(Box::new(format!("%{}%", rule.value)));
             }
 
+            // Device chain filter: "[track:] DeviceA > DeviceB > ..." — devices appear on
+            // the same track in that order (not necessarily adjacent), matched by name,
+            // user-given name or preset.
+            ("device_chain", "matches") => {
+                let (track_pattern, chain) = match rule.value.split_once(':') {
+                    Some((track, chain)) => (Some(track.trim()), chain),
+                    None => (None, rule.value.as_str()),
+                };
+                let steps: Vec<&str> = chain.split('>').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+                if steps.is_empty() {
+                    continue;
+                }
+
+                let mut joins = String::new();
+                let mut wheres: Vec<String> = Vec::new();
+                for (i, step) in steps.iter().enumerate() {
+                    if i > 0 {
+                        joins.push_str(&format!(
+                            " JOIN project_track_devices d{i} ON d{i}.project_id = d0.project_id \
+                             AND d{i}.track_index = d0.track_index AND d{i}.chain_order > d{prev}.chain_order",
+                            i = i, prev = i - 1
+                        ));
+                    }
+                    wheres.push(format!(
+                        "(d{i}.name LIKE ?{p} OR d{i}.user_name LIKE ?{p} OR d{i}.preset_name LIKE ?{p})",
+                        i = i, p = param_idx
+                    ));
+                    param_idx += 1;
+                    param_values.push(Box::new(format!("%{}%", step)));
+                }
+                if let Some(track) = track_pattern.filter(|t| !t.is_empty()) {
+                    joins.push_str(
+                        " JOIN project_tracks tr ON tr.project_id = d0.project_id AND tr.track_index = d0.track_index",
+                    );
+                    wheres.push(format!("tr.name LIKE ?{}", param_idx));
+                    param_idx += 1;
+                    param_values.push(Box::new(format!("%{}%", track)));
+                }
+                conditions.push(format!(
+                    "p.id IN (SELECT d0.project_id FROM project_track_devices d0{} WHERE {})",
+                    joins,
+                    wheres.join(" AND ")
+                ));
+            }
+
             // Boolean fields
             ("in_rotation", "is") => {
                 let val: i64 = if rule.value == "true" { 1 } else { 0 };
@@ -2298,6 +3007,117 @@ pub fn bulk_add_to_collection(conn: &Connection, project_ids: &[i64], collection
     Ok(())
 }
 
+// ============================================================================
+// LIBRARY ROOTS
+// ============================================================================
+
+pub fn get_library_roots(conn: &Connection) -> Result<Vec<LibraryRoot>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, path, bounce_folder_name, genre_depth FROM library_roots ORDER BY id"
+    ).map_err(|e| e.to_string())?;
+    let mut roots = stmt.query_map([], |row| {
+        Ok(LibraryRoot {
+            id: row.get(0)?,
+            path: row.get(1)?,
+            bounce_folder_name: row.get(2)?,
+            genre_depth: row.get(3)?,
+            online: false,
+        })
+    }).map_err(|e| e.to_string())?
+      .collect::<Result<Vec<_>, _>>()
+      .map_err(|e| e.to_string())?;
+    for root in &mut roots {
+        root.online = root_is_online(conn, &root.path);
+    }
+    Ok(roots)
+}
+
+/// Whether a library root's drive is attached. An unmounted drive can leave
+/// its mount point behind as an empty folder, so a root that can't be read,
+/// or that is empty while the library still has projects in it, is offline.
+pub fn root_is_online(conn: &Connection, path: &str) -> bool {
+    let mut entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+    if entries.next().is_some() {
+        return true;
+    }
+    let prefix = format!(
+        "{}{}%",
+        escape_like(path.trim_end_matches(['/', '\\'])),
+        escape_like(std::path::MAIN_SEPARATOR_STR),
+    );
+    let has_projects: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM projects WHERE project_path LIKE ?1 ESCAPE '\\')",
+        params![prefix],
+        |row| row.get(0),
+    ).unwrap_or(false);
+    !has_projects
+}
+
+/// Escape `%`, `_` and `\` for a LIKE pattern with `ESCAPE '\'`.
+pub(crate) fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Add a library root using the default bounce folder name and genre depth.
+pub fn add_library_root(conn: &Connection, path: &str) -> Result<LibraryRoot, String> {
+    let bounce_folder_name = get_setting(conn, "bounce_folder_name")?
+        .unwrap_or_else(|| "Bounces".to_string());
+    conn.execute(
+        "INSERT INTO library_roots (path, bounce_folder_name) VALUES (?1, ?2)",
+        params![path, bounce_folder_name],
+    ).map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    sync_root_folder_setting(conn)?;
+    get_library_roots(conn)?
+        .into_iter()
+        .find(|r| r.id == id)
+        .ok_or_else(|| "Library root not found after insert".to_string())
+}
+
+pub fn update_library_root(conn: &Connection, root: &LibraryRoot) -> Result<(), String> {
+    conn.execute(
+        "UPDATE library_roots SET path = ?1, bounce_folder_name = ?2, genre_depth = ?3 WHERE id = ?4",
+        params![root.path, root.bounce_folder_name, root.genre_depth.max(0), root.id],
+    ).map_err(|e| e.to_string())?;
+    sync_root_folder_setting(conn)
+}
+
+/// Remove a library root. Its projects stay in the library.
+pub fn remove_library_root(conn: &Connection, id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM library_roots WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    sync_root_folder_setting(conn)
+}
+
+/// Keep the `root_folder` setting (where new projects are created) pointing
+/// at the first library root.
+fn sync_root_folder_setting(conn: &Connection) -> Result<(), String> {
+    let first: Option<String> = conn.query_row(
+        "SELECT path FROM library_roots ORDER BY id LIMIT 1", [], |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())?;
+    set_setting(conn, "root_folder", first.as_deref().unwrap_or(""))
+}
+
+/// The library root a path lies in (the innermost one, if roots are nested).
+pub fn library_root_for<'a>(roots: &'a [LibraryRoot], path: &Path) -> Option<&'a LibraryRoot> {
+    roots.iter()
+        .filter(|r| path.starts_with(&r.path))
+        .max_by_key(|r| r.path.len())
+}
+
+/// Bounce folder name for a project: its root's, or the default for
+/// projects outside every root.
+pub fn bounce_folder_for(conn: &Connection, project_path: &str) -> Result<String, String> {
+    let roots = get_library_roots(conn)?;
+    if let Some(root) = library_root_for(&roots, Path::new(project_path)) {
+        return Ok(root.bounce_folder_name.clone());
+    }
+    Ok(get_setting(conn, "bounce_folder_name")?.unwrap_or_else(|| "Bounces".to_string()))
+}
+
 // ============================================================================
 // LIBRARY HEALTH
 // ============================================================================
@@ -2433,7 +3253,7 @@ mod tests {
     fn test_migration_creates_v13_tables() {
         let conn = test_db();
         let version: i64 = conn.query_row("SELECT MAX(version) FROM schema_version", [], |r| r.get(0)).unwrap();
-        assert_eq!(version, 13);
+        assert!(version >= 13);
 
         // Verify all 4 new tables exist
         for table in &["collections", "smart_collection_rules", "collection_projects", "version_notes"] {
@@ -2465,7 +3285,563 @@ mod tests {
         // Running migrations again should not fail
         crate::db::migrations::run_migrations(&conn).unwrap();
         let version: i64 = conn.query_row("SELECT MAX(version) FROM schema_version", [], |r| r.get(0)).unwrap();
-        assert_eq!(version, 13);
+        assert_eq!(version, 31);
+    }
+
+    #[test]
+    fn test_migration_creates_v14_tables() {
+        let conn = test_db();
+        let exists: bool = conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='project_tracks'",
+            [], |r| r.get::<_, i64>(0),
+        ).map(|c| c > 0).unwrap();
+        assert!(exists, "project_tracks table should exist");
+    }
+
+    #[test]
+    fn test_migration_creates_v15_tables() {
+        let conn = test_db();
+        let exists: bool = conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='project_track_devices'",
+            [], |r| r.get::<_, i64>(0),
+        ).map(|c| c > 0).unwrap();
+        assert!(exists, "project_track_devices table should exist");
+    }
+
+    #[test]
+    fn test_migration_creates_v16_columns() {
+        let conn = test_db();
+        for col in ["arrangement_length_bars", "time_signature", "scene_count", "live_version"] {
+            let exists: bool = conn.query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('projects') WHERE name = ?1",
+                params![col], |r| r.get::<_, i64>(0),
+            ).map(|c| c > 0).unwrap();
+            assert!(exists, "projects.{} column should exist", col);
+        }
+    }
+
+    #[test]
+    fn test_migration_creates_v17_columns() {
+        let conn = test_db();
+        for col in ["relative_path", "resolved_path", "location"] {
+            let exists: bool = conn.query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('project_samples') WHERE name = ?1",
+                params![col], |r| r.get::<_, i64>(0),
+            ).map(|c| c > 0).unwrap();
+            assert!(exists, "project_samples.{} column should exist", col);
+        }
+    }
+
+    #[test]
+    fn test_migration_creates_v18_tables() {
+        let conn = test_db();
+        let exists: bool = conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='sample_index'",
+            [], |r| r.get::<_, i64>(0),
+        ).map(|c| c > 0).unwrap();
+        assert!(exists, "sample_index table should exist");
+    }
+
+    #[test]
+    fn test_migration_creates_v19_table_and_columns() {
+        let conn = test_db();
+        let exists: bool = conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='project_midi_clips'",
+            [], |r| r.get::<_, i64>(0),
+        ).map(|c| c > 0).unwrap();
+        assert!(exists, "project_midi_clips table should exist");
+        for col in ["pitch_class_histogram", "estimated_key"] {
+            let exists: bool = conn.query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('projects') WHERE name = ?1",
+                params![col], |r| r.get::<_, i64>(0),
+            ).map(|c| c > 0).unwrap();
+            assert!(exists, "projects.{} column should exist", col);
+        }
+    }
+
+    #[test]
+    fn test_scan_snapshot_roundtrip() {
+        let conn = test_db();
+        assert_eq!(get_scan_snapshot(&conn, "/music/Song").unwrap(), None);
+        let mut snapshot = DirSnapshot { mtime: 1_700_000_000_000_000_000, size: 2048, file_count: 3 };
+        set_scan_snapshot(&conn, "/music/Song", &snapshot).unwrap();
+        snapshot.size = 4096;
+        set_scan_snapshot(&conn, "/music/Song", &snapshot).unwrap();
+        assert_eq!(get_scan_snapshot(&conn, "/music/Song").unwrap(), Some(snapshot));
+    }
+
+    #[test]
+    fn test_migration_creates_v20_columns() {
+        let conn = test_db();
+        for (table, col) in [("projects", "tempo_min"), ("projects", "tempo_max"), ("projects", "tempo_avg"), ("ableton_sets", "tempo_map")] {
+            let exists: bool = conn.query_row(
+                &format!("SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name = ?1", table),
+                params![col], |r| r.get::<_, i64>(0),
+            ).map(|c| c > 0).unwrap();
+            assert!(exists, "{}.{} column should exist", table, col);
+        }
+    }
+
+    #[test]
+    fn test_library_roots() {
+        let conn = test_db();
+        let ssd = add_library_root(&conn, "/music/ssd").unwrap();
+        let mut nas = add_library_root(&conn, "/mnt/nas").unwrap();
+        assert_eq!(ssd.bounce_folder_name, "Bounces");
+        assert_eq!(get_setting(&conn, "root_folder").unwrap().as_deref(), Some("/music/ssd"));
+
+        nas.bounce_folder_name = "Renders".into();
+        nas.genre_depth = 2;
+        update_library_root(&conn, &nas).unwrap();
+        assert_eq!(bounce_folder_for(&conn, "/mnt/nas/Artist/Jam").unwrap(), "Renders");
+        assert_eq!(bounce_folder_for(&conn, "/mnt/nas-old/Jam").unwrap(), "Bounces");
+
+        remove_library_root(&conn, ssd.id).unwrap();
+        assert_eq!(get_setting(&conn, "root_folder").unwrap().as_deref(), Some("/mnt/nas"));
+        let roots = get_library_roots(&conn).unwrap();
+        assert_eq!(roots.len(), 1);
+        assert_eq!((roots[0].genre_depth, roots[0].online), (2, false));
+    }
+
+    #[test]
+    fn test_empty_root_with_projects_is_offline() {
+        let conn = test_db();
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path().join("NAS");
+        std::fs::create_dir(&root).unwrap();
+        let root_str = root.to_string_lossy().to_string();
+        add_library_root(&conn, &root_str).unwrap();
+        // A new, empty root is online
+        assert!(get_library_roots(&conn).unwrap()[0].online);
+
+        // An empty root the library has projects in is a drive's bare mount point
+        insert_project(&conn, "Jam", &root.join("Jam").to_string_lossy());
+        assert!(!get_library_roots(&conn).unwrap()[0].online);
+
+        std::fs::create_dir(root.join("Jam")).unwrap();
+        assert!(get_library_roots(&conn).unwrap()[0].online);
+    }
+
+    // ========================================================================
+    // Project Samples
+    // ========================================================================
+
+    #[test]
+    fn test_replace_project_samples_keeps_location() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Moved", "/music/Moved Project");
+        let samples = vec![
+            SampleWithStatus {
+                path: "D:/Old/Moved Project/Samples/vox.wav".into(),
+                filename: "vox.wav".into(),
+                is_missing: false,
+                relative_path: Some("Samples/vox.wav".into()),
+                resolved_path: Some("/music/Moved Project/Samples/vox.wav".into()),
+                location: "project".into(),
+            },
+            SampleWithStatus {
+                path: "/Library/Core Library/Samples/hat.aif".into(),
+                filename: "hat.aif".into(),
+                is_missing: true,
+                relative_path: None,
+                resolved_path: None,
+                location: "core_library".into(),
+            },
+        ];
+        replace_project_samples(&conn, pid, &samples).unwrap();
+
+        let stored = get_project_samples(&conn, pid).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!((stored[0].filename.as_str(), stored[0].location.as_str()), ("hat.aif", "core_library"));
+        assert!(stored[0].is_missing);
+        assert_eq!(stored[1].resolved_path.as_deref(), Some("/music/Moved Project/Samples/vox.wav"));
+        assert_eq!(stored[1].relative_path.as_deref(), Some("Samples/vox.wav"));
+    }
+
+    // ========================================================================
+    // Project Tracks
+    // ========================================================================
+
+    fn make_track(name: &str, track_type: &str, devices: &[&str]) -> TrackInfo {
+        TrackInfo {
+            live_id: None,
+            name: name.to_string(),
+            track_type: track_type.to_string(),
+            color_index: Some(3),
+            is_muted: false,
+            is_soloed: false,
+            is_frozen: false,
+            devices: devices.iter().map(|d| d.to_string()).collect(),
+            device_chain: devices.iter().enumerate().map(|(i, d)| make_device(i as i64, d)).collect(),
+        }
+    }
+
+    fn make_device(chain_order: i64, name: &str) -> DeviceInfo {
+        DeviceInfo {
+            chain_order,
+            depth: 0,
+            parent_order: None,
+            branch_name: None,
+            name: name.to_string(),
+            user_name: None,
+            device_type: "native".to_string(),
+            is_on: true,
+            preset_name: None,
+        }
+    }
+
+    #[test]
+    fn test_project_tracks_empty() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Track", "/track");
+        assert!(get_project_tracks(&conn, pid).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_replace_project_tracks_preserves_order_and_devices() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Track", "/track");
+        let mut bass = make_track("Bass", "midi", &["Serum", "OTT"]);
+        bass.is_muted = true;
+        let tracks = vec![
+            make_track("Drums", "group", &[]),
+            bass,
+            make_track("Reverb", "return", &["Reverb"]),
+        ];
+        replace_project_tracks(&conn, pid, &tracks).unwrap();
+
+        let stored = get_project_tracks(&conn, pid).unwrap();
+        assert_eq!(stored.len(), 3);
+        assert_eq!(stored[0].name, "Drums");
+        assert_eq!(stored[1].name, "Bass");
+        assert_eq!(stored[1].track_type, "midi");
+        assert!(stored[1].is_muted);
+        assert_eq!(stored[1].devices, vec!["Serum", "OTT"]);
+        assert_eq!(stored[2].color_index, Some(3));
+    }
+
+    #[test]
+    fn test_replace_project_tracks_replaces_existing() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Track", "/track");
+        replace_project_tracks(&conn, pid, &[make_track("Old", "audio", &[])]).unwrap();
+        replace_project_tracks(&conn, pid, &[make_track("New", "audio", &[])]).unwrap();
+
+        let stored = get_project_tracks(&conn, pid).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].name, "New");
+    }
+
+    #[test]
+    fn test_midi_clips_and_pitch_profile_roundtrip() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Keys", "/keys");
+        assert_eq!(get_pitch_profile(&conn, pid).unwrap().histogram, vec![0.0; 12]);
+
+        let note = |key: i64, time: f64| MidiNote { key, time, duration: 0.5, velocity: 100.0, is_enabled: true };
+        let clip = MidiClip {
+            track_index: 1,
+            name: "Chords".to_string(),
+            in_arrangement: true,
+            start: Some(16.0),
+            end: Some(32.0),
+            loop_start: Some(0.0),
+            loop_end: Some(16.0),
+            loop_on: true,
+            notes: vec![note(60, 0.0), note(64, 0.0), note(67, 0.5)],
+        };
+        replace_project_midi_clips(&conn, pid, std::slice::from_ref(&clip)).unwrap();
+        let mut histogram = [0.0; 12];
+        histogram[0] = 0.5;
+        set_pitch_profile(&conn, pid, &histogram, Some("C Major")).unwrap();
+
+        assert_eq!(get_project_midi_clips(&conn, pid).unwrap(), vec![clip]);
+        let profile = get_pitch_profile(&conn, pid).unwrap();
+        assert_eq!(profile.histogram[0], 0.5);
+        assert_eq!(profile.estimated_key.as_deref(), Some("C Major"));
+        assert_eq!((profile.midi_clip_count, profile.note_count), (1, 3));
+    }
+
+    #[test]
+    fn test_project_track_device_chain_roundtrip() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Chain", "/chain");
+        let mut rack = make_track("Keys", "midi", &[]);
+        let mut inner = make_device(1, "Serum");
+        inner.depth = 1;
+        inner.parent_order = Some(0);
+        inner.branch_name = Some("Layer".to_string());
+        inner.device_type = "vst3".to_string();
+        inner.is_on = false;
+        inner.preset_name = Some("Big Pad".to_string());
+        rack.device_chain = vec![make_device(0, "Instrument Rack"), inner];
+        replace_project_tracks(&conn, pid, &[make_track("Drums", "audio", &["EQ Eight"]), rack]).unwrap();
+
+        let stored = get_project_tracks(&conn, pid).unwrap();
+        assert_eq!(stored[0].device_chain.len(), 1);
+        let chain = &stored[1].device_chain;
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0].name, "Instrument Rack");
+        assert_eq!(chain[1].parent_order, Some(0));
+        assert_eq!(chain[1].branch_name.as_deref(), Some("Layer"));
+        assert!(!chain[1].is_on);
+        assert_eq!(chain[1].preset_name.as_deref(), Some("Big Pad"));
+
+        replace_project_tracks(&conn, pid, &[]).unwrap();
+        let leftover: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM project_track_devices WHERE project_id = ?1", params![pid], |r| r.get(0),
+        ).unwrap();
+        assert_eq!(leftover, 0);
+    }
+
+    #[test]
+    fn test_smart_collection_device_chain_rule() {
+        let conn = test_db();
+        let wanted = insert_project(&conn, "Wanted", "/wanted");
+        let reversed = insert_project(&conn, "Reversed", "/reversed");
+        let wrong_track = insert_project(&conn, "Wrong Track", "/wrong");
+        replace_project_tracks(&conn, wanted, &[make_track("Sub Bass", "midi", &["Serum", "EQ Eight", "OTT"])]).unwrap();
+        replace_project_tracks(&conn, reversed, &[make_track("Bass", "midi", &["OTT", "Serum"])]).unwrap();
+        replace_project_tracks(&conn, wrong_track, &[make_track("Lead", "midi", &["Serum", "OTT"])]).unwrap();
+
+        let col = create_collection(&conn, "Bass chains", "smart", "").unwrap();
+        set_smart_collection_rules(&conn, col.id, &[
+            SmartCollectionRuleInput { field: "device_chain".into(), operator: "matches".into(), value: "bass: Serum > OTT".into() },
+        ]).unwrap();
+        assert_eq!(evaluate_smart_collection(&conn, col.id).unwrap(), vec![wanted]);
+
+        set_smart_collection_rules(&conn, col.id, &[
+            SmartCollectionRuleInput { field: "device_chain".into(), operator: "matches".into(), value: "Serum > OTT".into() },
+        ]).unwrap();
+        let mut ids = evaluate_smart_collection(&conn, col.id).unwrap();
+        ids.sort();
+        assert_eq!(ids, vec![wanted, wrong_track]);
+    }
+
+    // ========================================================================
+    // Arrangement Stats
+    // ========================================================================
+
+    fn set_stats(conn: &Connection, project_id: i64, bars: f64, time_signature: &str, live_version: &str) {
+        set_arrangement_stats(conn, project_id, &ArrangementStats {
+            arrangement_length_bars: Some(bars),
+            arrangement_length_seconds: Some(bars * 2.0),
+            time_signature: Some(time_signature.to_string()),
+            scene_count: Some(8),
+            session_clip_count: Some(12),
+            arrangement_clip_count: Some(30),
+            live_version: Some(live_version.to_string()),
+            tempo_min: None,
+            tempo_max: None,
+            tempo_avg: None,
+        }).unwrap();
+    }
+
+    #[test]
+    fn test_set_arrangement_stats_roundtrip() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Stats", "/stats");
+        assert!(get_project_by_id(&conn, pid).unwrap().arrangement.arrangement_length_bars.is_none());
+
+        set_stats(&conn, pid, 96.0, "4/4", "11.3.13");
+        let stats = get_project_by_id(&conn, pid).unwrap().arrangement;
+        assert_eq!(stats.arrangement_length_bars, Some(96.0));
+        assert_eq!(stats.arrangement_length_seconds, Some(192.0));
+        assert_eq!(stats.time_signature.as_deref(), Some("4/4"));
+        assert_eq!((stats.scene_count, stats.session_clip_count, stats.arrangement_clip_count), (Some(8), Some(12), Some(30)));
+        assert_eq!(stats.live_version.as_deref(), Some("11.3.13"));
+    }
+
+    #[test]
+    fn test_get_projects_filters_by_arrangement_stats() {
+        let conn = test_db();
+        let sketch = insert_project(&conn, "Sketch", "/sketch");
+        let song = insert_project(&conn, "Song", "/song");
+        let waltz = insert_project(&conn, "Waltz", "/waltz");
+        let _unparsed = insert_project(&conn, "Unparsed", "/unparsed");
+        set_stats(&conn, sketch, 16.0, "4/4", "11.3.13");
+        set_stats(&conn, song, 120.0, "4/4", "12.1");
+        set_stats(&conn, waltz, 24.0, "3/4", "10.1.30");
+
+        let mut filters = ProjectFilters {
+            statuses: None, tag_ids: None, genres: None, in_rotation: None,
+            min_rating: None, updated_since_days: None, search_query: None,
+            show_archived: None, sort_by: Some("arrangement_length".to_string()), sort_dir: Some("asc".to_string()),
+            collection_id: None,
+            min_arrangement_bars: None, max_arrangement_bars: Some(32.0), time_signature: None,
+            min_bpm: None, max_bpm: None,
+        };
+        let ids: Vec<i64> = get_projects(&conn, &filters).unwrap().iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![sketch, waltz], "unparsed projects never match a length filter");
+
+        filters.time_signature = Some("4/4".to_string());
+        let ids: Vec<i64> = get_projects(&conn, &filters).unwrap().iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![sketch]);
+
+        filters.max_arrangement_bars = None;
+        filters.min_arrangement_bars = Some(64.0);
+        let ids: Vec<i64> = get_projects(&conn, &filters).unwrap().iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![song]);
+    }
+
+    #[test]
+    fn test_get_projects_bpm_filter_uses_tempo_range() {
+        let conn = test_db();
+        let constant = insert_project(&conn, "Constant", "/constant");
+        let ramp = insert_project(&conn, "Ramp", "/ramp");
+        let unparsed = insert_project(&conn, "Unparsed", "/unparsed");
+        set_bpm_if_empty(&conn, constant, 128.0).unwrap();
+        set_bpm_if_empty(&conn, ramp, 90.0).unwrap();
+        set_bpm_if_empty(&conn, unparsed, 100.0).unwrap();
+        set_stats(&conn, constant, 64.0, "4/4", "12.1");
+        set_arrangement_stats(&conn, ramp, &ArrangementStats {
+            tempo_min: Some(90.0),
+            tempo_max: Some(140.0),
+            tempo_avg: Some(115.0),
+            ..Default::default()
+        }).unwrap();
+
+        let mut filters = ProjectFilters {
+            statuses: None, tag_ids: None, genres: None, in_rotation: None,
+            min_rating: None, updated_since_days: None, search_query: None,
+            show_archived: None, sort_by: Some("name".to_string()), sort_dir: Some("asc".to_string()),
+            collection_id: None,
+            min_arrangement_bars: None, max_arrangement_bars: None, time_signature: None,
+            min_bpm: Some(120.0), max_bpm: Some(130.0),
+        };
+        let ids: Vec<i64> = get_projects(&conn, &filters).unwrap().iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![constant, ramp], "a tempo ramp matches any range it passes through");
+
+        filters.min_bpm = Some(95.0);
+        filters.max_bpm = Some(105.0);
+        let ids: Vec<i64> = get_projects(&conn, &filters).unwrap().iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![ramp, unparsed], "projects without a tempo range fall back to their BPM");
+
+        let project = get_project_by_id(&conn, ramp).unwrap();
+        assert_eq!(project.arrangement.tempo_avg, Some(115.0));
+    }
+
+    #[test]
+    fn test_set_tempo_map_roundtrip() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Tempo", "/tempo");
+        conn.execute(
+            "INSERT INTO ableton_sets (project_id, set_path, modified_time) VALUES (?1, '/tempo/Song.als', datetime('now'))",
+            params![pid],
+        ).unwrap();
+        assert_eq!(get_set_tempo_map(&conn, "/tempo/Song.als").unwrap(), None);
+        assert_eq!(get_set_tempo_map(&conn, "/tempo/Other.als").unwrap(), None);
+
+        let map = SetTempoMap {
+            tempo_points: vec![TempoPoint { beat: 0.0, bpm: 120.0 }, TempoPoint { beat: 16.0, bpm: 60.0 }],
+            time_signatures: vec![TimeSignatureChange { beat: 0.0, numerator: 7, denominator: 8 }],
+        };
+        set_set_tempo_map(&conn, "/tempo/Song.als", &map).unwrap();
+        assert_eq!(get_set_tempo_map(&conn, "/tempo/Song.als").unwrap(), Some(map));
+    }
+
+    #[test]
+    fn test_smart_collection_arrangement_rules() {
+        let conn = test_db();
+        let sketch = insert_project(&conn, "Sketch", "/sketch");
+        let song = insert_project(&conn, "Song", "/song");
+        let _unparsed = insert_project(&conn, "Unparsed", "/unparsed");
+        set_stats(&conn, sketch, 16.0, "4/4", "11.3.13");
+        set_stats(&conn, song, 120.0, "4/4", "12.1");
+
+        let col = create_collection(&conn, "Sketches", "smart", "").unwrap();
+        set_smart_collection_rules(&conn, col.id, &[
+            SmartCollectionRuleInput { field: "arrangement_bars".into(), operator: "lt".into(), value: "32".into() },
+        ]).unwrap();
+        assert_eq!(evaluate_smart_collection(&conn, col.id).unwrap(), vec![sketch], "unparsed sets are not 0 bars");
+
+        set_smart_collection_rules(&conn, col.id, &[
+            SmartCollectionRuleInput { field: "arrangement_clips".into(), operator: "between".into(), value: "[20,40]".into() },
+            SmartCollectionRuleInput { field: "live_version".into(), operator: "starts_with".into(), value: "12".into() },
+        ]).unwrap();
+        assert_eq!(evaluate_smart_collection(&conn, col.id).unwrap(), vec![song]);
+
+        set_smart_collection_rules(&conn, col.id, &[
+            SmartCollectionRuleInput { field: "time_signature".into(), operator: "is_not".into(), value: "4/4".into() },
+        ]).unwrap();
+        assert!(evaluate_smart_collection(&conn, col.id).unwrap().is_empty());
+    }
+
+    // ========================================================================
+    // Locator Markers
+    // ========================================================================
+
+    fn locator(name: &str, seconds: f64) -> ArrangementLocator {
+        ArrangementLocator { name: name.to_string(), beat: seconds * 2.0, seconds }
+    }
+
+    fn locator_markers(conn: &Connection, pid: i64) -> Vec<Marker> {
+        get_markers_for_project(conn, pid).unwrap()
+            .into_iter()
+            .filter(|m| m.marker_type == LOCATOR_MARKER_TYPE)
+            .collect()
+    }
+
+    #[test]
+    fn test_sync_locators_creates_markers() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Track", "/track");
+        sync_locator_markers(&conn, pid, &[locator("Intro", 0.0), locator("Drop", 32.0)]).unwrap();
+
+        let markers = locator_markers(&conn, pid);
+        assert_eq!(markers.len(), 2);
+        assert_eq!(markers[0].text, "Intro");
+        assert_eq!(markers[1].text, "Drop");
+        assert_eq!(markers[1].timestamp_seconds, 32.0);
+        assert!(markers[1].bounce_id.is_none());
+    }
+
+    #[test]
+    fn test_sync_locators_leaves_user_markers_alone() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Track", "/track");
+        let user = create_marker(&conn, pid, None, 10.0, "note", "kick too loud").unwrap();
+        sync_locator_markers(&conn, pid, &[locator("Drop", 32.0)]).unwrap();
+        sync_locator_markers(&conn, pid, &[]).unwrap();
+
+        let all = get_markers_for_project(&conn, pid).unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].id, user.id);
+        assert_eq!(all[0].text, "kick too loud");
+    }
+
+    #[test]
+    fn test_sync_locators_updates_in_place() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Track", "/track");
+        sync_locator_markers(&conn, pid, &[locator("Intro", 0.0), locator("Drop", 32.0)]).unwrap();
+        let before = locator_markers(&conn, pid);
+
+        // Drop moved, Intro renamed
+        sync_locator_markers(&conn, pid, &[locator("Start", 0.0), locator("Drop", 48.0)]).unwrap();
+        let after = locator_markers(&conn, pid);
+
+        assert_eq!(after.len(), 2);
+        let drop_before = before.iter().find(|m| m.text == "Drop").unwrap();
+        let drop_after = after.iter().find(|m| m.text == "Drop").unwrap();
+        assert_eq!(drop_before.id, drop_after.id, "moved locator should keep its marker row");
+        assert_eq!(drop_after.timestamp_seconds, 48.0);
+
+        let intro_before = before.iter().find(|m| m.text == "Intro").unwrap();
+        let start_after = after.iter().find(|m| m.text == "Start").unwrap();
+        assert_eq!(intro_before.id, start_after.id, "renamed locator should reuse its marker row");
+    }
+
+    #[test]
+    fn test_sync_locators_removes_deleted() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Track", "/track");
+        sync_locator_markers(&conn, pid, &[locator("Intro", 0.0), locator("Drop", 32.0)]).unwrap();
+        sync_locator_markers(&conn, pid, &[locator("Drop", 32.0)]).unwrap();
+
+        let markers = locator_markers(&conn, pid);
+        assert_eq!(markers.len(), 1);
+        assert_eq!(markers[0].text, "Drop");
     }
 
     // ========================================================================
@@ -2619,6 +3995,28 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_set_backups_newest_first() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Test", "/test/proj");
+        let sid = insert_set(&conn, pid, "/test/proj/Song.als", None);
+        conn.execute_batch(&format!(
+            "INSERT INTO set_backups (project_id, set_id, backup_path, backed_up_at, modified_time) VALUES \
+             ({pid}, {sid}, '/test/proj/Backup/Song [2024-01-01 100000].als', '2024-01-01 10:00:00', '2024-01-01 10:00:00'), \
+             ({pid}, NULL, '/test/proj/Backup/Old [2024-02-01 100000].als', '2024-02-01 10:00:00', '2024-02-01 10:00:00');"
+        )).unwrap();
+
+        let backups = get_set_backups(&conn, pid).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].set_id, None);
+        assert_eq!(backups[1].set_id, Some(sid));
+        assert_eq!(get_set_backup(&conn, backups[1].id).unwrap().backed_up_at.as_deref(), Some("2024-01-01 10:00:00"));
+
+        // Deleting the set keeps its backups
+        conn.execute("DELETE FROM ableton_sets WHERE id = ?1", params![sid]).unwrap();
+        assert!(get_set_backups(&conn, pid).unwrap().iter().all(|b| b.set_id.is_none()));
+    }
+
     // ========================================================================
     // Project Quick-Create
     // ========================================================================
@@ -2649,6 +4047,8 @@ mod tests {
             statuses: None, tag_ids: None, genres: None, in_rotation: None,
             min_rating: None, updated_since_days: None, search_query: None,
             show_archived: None, sort_by: None, sort_dir: None, collection_id: None,
+            min_arrangement_bars: None, max_arrangement_bars: None, time_signature: None,
+            min_bpm: None, max_bpm: None,
         };
         let projects = get_projects(&conn, &filters).unwrap();
         assert!(projects.iter().any(|p| p.name == "Searchable"));
@@ -2663,6 +4063,8 @@ mod tests {
             min_rating: None, updated_since_days: None,
             search_query: Some("UniqueFtsName".to_string()),
             show_archived: None, sort_by: None, sort_dir: None, collection_id: None,
+            min_arrangement_bars: None, max_arrangement_bars: None, time_signature: None,
+            min_bpm: None, max_bpm: None,
         };
         let projects = get_projects(&conn, &filters).unwrap();
         assert_eq!(projects.len(), 1);
@@ -3084,6 +4486,8 @@ mod tests {
             min_rating: None, updated_since_days: None, search_query: None,
             show_archived: None, sort_by: None, sort_dir: None,
             collection_id: Some(col.id),
+            min_arrangement_bars: None, max_arrangement_bars: None, time_signature: None,
+            min_bpm: None, max_bpm: None,
         };
         let projects = get_projects(&conn, &filters).unwrap();
         assert_eq!(projects.len(), 1);
@@ -3104,6 +4508,8 @@ mod tests {
             min_rating: None, updated_since_days: None, search_query: None,
             show_archived: None, sort_by: None, sort_dir: None,
             collection_id: Some(col.id),
+            min_arrangement_bars: None, max_arrangement_bars: None, time_signature: None,
+            min_bpm: None, max_bpm: None,
         };
         let projects = get_projects(&conn, &filters).unwrap();
         assert_eq!(projects.len(), 1);
@@ -3120,6 +4526,8 @@ mod tests {
             min_rating: None, updated_since_days: None, search_query: None,
             show_archived: None, sort_by: None, sort_dir: None,
             collection_id: Some(col.id),
+            min_arrangement_bars: None, max_arrangement_bars: None, time_signature: None,
+            min_bpm: None, max_bpm: None,
         };
         let projects = get_projects(&conn, &filters).unwrap();
         assert!(projects.is_empty());
@@ -3327,4 +4735,74 @@ mod tests {
         assert_eq!(health.total_disk_size_bytes, 1000, "NULL file_size should not be counted");
         assert_eq!(health.total_als_files, 2);
     }
+
+    #[test]
+    fn test_detected_tempo_key_fills_and_flags() {
+        use crate::als_parser::EstimatedKey;
+        use crate::analysis::loudness::Loudness;
+
+        let conn = test_db();
+        let pid = insert_project(&conn, "Song", "/song");
+        for (path, mtime) in [("/song/Bounces/v1.wav", "2026-01-01"), ("/song/Bounces/v2.wav", "2026-02-01")] {
+            conn.execute(
+                "INSERT INTO bounces (project_id, bounce_path, modified_time) VALUES (?1, ?2, ?3)",
+                params![pid, path, mtime],
+            ).unwrap();
+        }
+        let analysis = |bpm: f64, tonic: &str| BounceAnalysis {
+            loudness: Loudness {
+                integrated_lufs: None,
+                short_term_max_lufs: None,
+                loudness_range_lu: None,
+                sample_peak_dbfs: None,
+                true_peak_dbtp: None,
+            },
+            bpm: Some(bpm),
+            key: Some(EstimatedKey { tonic: tonic.to_string(), scale: "Minor".to_string(), correlation: 0.8 }),
+        };
+        set_bounce_analysis(&conn, "/song/Bounces/v1.wav", &analysis(100.0, "C"), "2026-01-01").unwrap();
+        set_bounce_analysis(&conn, "/song/Bounces/v2.wav", &analysis(87.0, "A"), "2026-02-01").unwrap();
+
+        // Nothing from the .als: the newest bounce fills both, nothing to flag
+        fill_tempo_key_from_bounces(&conn, pid).unwrap();
+        let project = get_project_by_id(&conn, pid).unwrap();
+        assert_eq!((project.bpm, project.musical_key.as_str()), (Some(87.0), "A Minor"));
+        let check = get_tempo_key_check(&conn, pid).unwrap();
+        assert_eq!(check.bounce_path.as_deref(), Some("/song/Bounces/v2.wav"));
+        assert!(!check.tempo_mismatch && !check.key_mismatch);
+
+        // Double tempo and the relative major agree; a different key doesn't
+        set_als_tempo_key(&conn, pid, Some(174.0), Some("C Major")).unwrap();
+        let check = get_tempo_key_check(&conn, pid).unwrap();
+        assert!(!check.tempo_mismatch && !check.key_mismatch);
+        set_als_tempo_key(&conn, pid, Some(120.0), Some("E Minor")).unwrap();
+        let check = get_tempo_key_check(&conn, pid).unwrap();
+        assert!(check.tempo_mismatch && check.key_mismatch);
+
+        // A changed bounce loses its measurements and the older one is used
+        conn.execute("UPDATE bounces SET modified_time = '2026-03-01' WHERE bounce_path = '/song/Bounces/v2.wav'", []).unwrap();
+        clear_stale_analysis(&conn, pid).unwrap();
+        let check = get_tempo_key_check(&conn, pid).unwrap();
+        assert_eq!((check.detected_bpm, check.detected_key.as_deref()), (Some(100.0), Some("C Minor")));
+    }
+
+    #[test]
+    fn test_bounce_tags_leave_out_empty_fields() {
+        let conn = test_db();
+        let pid = insert_project(&conn, "Night Drive", "/night");
+        let bid = insert_bounce(&conn, pid, "/night/Bounces/mix.wav");
+        let tags = get_bounce_tags(&conn, bid).unwrap();
+        assert_eq!(tags.title, "Night Drive");
+        assert_eq!((tags.artist, tags.bpm, tags.key, tags.comment), (None, None, None, None));
+
+        set_setting(&conn, "artist_name", "Kay").unwrap();
+        set_bpm_if_empty(&conn, pid, 124.0).unwrap();
+        set_key_if_empty(&conn, pid, "F# Minor").unwrap();
+        update_bounce_notes(&conn, bid, " louder kick ").unwrap();
+        let tags = get_bounce_tags(&conn, bid).unwrap();
+        assert_eq!(tags.artist.as_deref(), Some("Kay"));
+        assert_eq!(tags.bpm, Some(124.0));
+        assert_eq!(tags.key.as_deref(), Some("F# Minor"));
+        assert_eq!(tags.comment.as_deref(), Some("louder kick"));
+    }
 }
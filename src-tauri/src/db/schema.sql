@@ -4,7 +4,13 @@ CREATE TABLE IF NOT EXISTS schema_version (
     applied_at TEXT NOT NULL DEFAULT (datetime('now'))
 );
 
-INSERT INTO schema_version (version) VALUES (13);
+INSERT INTO schema_version (version) VALUES (31);
+
+-- Sync key-value pairs (session tokens, cursors)
+CREATE TABLE IF NOT EXISTS sync_meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL DEFAULT ''
+);
 
 -- Settings (key-value pairs)
 CREATE TABLE IF NOT EXISTS settings (
@@ -15,6 +21,8 @@ CREATE TABLE IF NOT EXISTS settings (
 -- Default settings
 INSERT OR IGNORE INTO settings (key, value) VALUES ('bounce_folder_name', 'Bounces');
 INSERT OR IGNORE INTO settings (key, value) VALUES ('scan_on_launch', 'true');
+INSERT OR IGNORE INTO settings (key, value) VALUES ('scan_animation', 'true');
+INSERT OR IGNORE INTO settings (key, value) VALUES ('scan_ignore_patterns', '["Trash/", "$RECYCLE.BIN/", "Templates/"]');
 
 -- Projects
 CREATE TABLE IF NOT EXISTS projects (
@@ -44,7 +52,24 @@ CREATE TABLE IF NOT EXISTS projects (
     cover_updated_at TEXT,
     cover_url TEXT,
     has_missing_deps INTEGER NOT NULL DEFAULT 0,
-    als_parsed_at INTEGER
+    als_parsed_at INTEGER,
+    arrangement_length_bars REAL,
+    arrangement_length_seconds REAL,
+    time_signature TEXT,
+    scene_count INTEGER,
+    session_clip_count INTEGER,
+    arrangement_clip_count INTEGER,
+    live_version TEXT,
+    pitch_class_histogram TEXT,
+    estimated_key TEXT,
+    tempo_min REAL,
+    tempo_max REAL,
+    tempo_avg REAL,
+    als_bpm REAL,
+    als_key TEXT,
+    remote_id INTEGER,
+    sync_status TEXT NOT NULL DEFAULT 'unsynced',
+    sync_updated_at TEXT
 );
 
 CREATE INDEX IF NOT EXISTS idx_projects_status ON projects(status);
@@ -58,11 +83,47 @@ CREATE TABLE IF NOT EXISTS ableton_sets (
     project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
     set_path TEXT NOT NULL UNIQUE,
     modified_time TEXT NOT NULL,
-    file_size INTEGER
+    file_size INTEGER,
+    tempo_map TEXT,
+    content_hash TEXT,
+    remote_id INTEGER,
+    sync_status TEXT NOT NULL DEFAULT 'unsynced',
+    sync_updated_at TEXT
 );
 
 CREATE INDEX IF NOT EXISTS idx_ableton_sets_project_id ON ableton_sets(project_id);
 
+-- Set backups (timestamped copies Live keeps in the project's Backup folder)
+CREATE TABLE IF NOT EXISTS set_backups (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    set_id INTEGER REFERENCES ableton_sets(id) ON DELETE SET NULL,
+    backup_path TEXT NOT NULL UNIQUE,
+    backed_up_at TEXT,
+    modified_time TEXT NOT NULL,
+    file_size INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_set_backups_project_id ON set_backups(project_id);
+
+-- Library roots (folders of projects, each scanned with its own rules)
+CREATE TABLE IF NOT EXISTS library_roots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    path TEXT NOT NULL UNIQUE,
+    bounce_folder_name TEXT NOT NULL DEFAULT 'Bounces',
+    genre_depth INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Scan snapshots (per project folder, for incremental scans)
+CREATE TABLE IF NOT EXISTS scan_snapshots (
+    dir_path TEXT PRIMARY KEY,
+    mtime INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    file_count INTEGER NOT NULL,
+    scanned_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
 -- Bounces
 CREATE TABLE IF NOT EXISTS bounces (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -71,7 +132,24 @@ CREATE TABLE IF NOT EXISTS bounces (
     modified_time TEXT NOT NULL,
     duration_seconds REAL,
     mp3_url TEXT,
-    notes TEXT NOT NULL DEFAULT ''
+    notes TEXT NOT NULL DEFAULT '',
+    format TEXT,
+    sample_rate INTEGER,
+    bit_depth INTEGER,
+    channels INTEGER,
+    sample_format TEXT,
+    integrated_lufs REAL,
+    short_term_max_lufs REAL,
+    loudness_range_lu REAL,
+    sample_peak_dbfs REAL,
+    true_peak_dbtp REAL,
+    loudness_mtime TEXT,
+    detected_bpm REAL,
+    detected_key TEXT,
+    embedded_metadata TEXT,
+    remote_id INTEGER,
+    sync_status TEXT NOT NULL DEFAULT 'unsynced',
+    sync_updated_at TEXT
 );
 
 CREATE INDEX IF NOT EXISTS idx_bounces_project_id ON bounces(project_id);
@@ -79,13 +157,18 @@ CREATE INDEX IF NOT EXISTS idx_bounces_project_id ON bounces(project_id);
 -- Tags
 CREATE TABLE IF NOT EXISTS tags (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
-    name TEXT NOT NULL UNIQUE
+    name TEXT NOT NULL UNIQUE,
+    remote_id INTEGER,
+    sync_status TEXT NOT NULL DEFAULT 'unsynced',
+    sync_updated_at TEXT
 );
 
 -- Project Tags (many-to-many)
 CREATE TABLE IF NOT EXISTS project_tags (
     project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
     tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    sync_status TEXT NOT NULL DEFAULT 'unsynced',
+    sync_updated_at TEXT,
     PRIMARY KEY (project_id, tag_id)
 );
 
@@ -98,7 +181,10 @@ CREATE TABLE IF NOT EXISTS sessions (
     started_at TEXT NOT NULL DEFAULT (datetime('now')),
     ended_at TEXT,
     duration_seconds INTEGER,
-    note TEXT NOT NULL DEFAULT ''
+    note TEXT NOT NULL DEFAULT '',
+    remote_id INTEGER,
+    sync_status TEXT NOT NULL DEFAULT 'unsynced',
+    sync_updated_at TEXT
 );
 
 CREATE INDEX IF NOT EXISTS idx_sessions_project_id ON sessions(project_id);
@@ -112,7 +198,10 @@ CREATE TABLE IF NOT EXISTS markers (
     type TEXT NOT NULL DEFAULT 'note',
     text TEXT NOT NULL DEFAULT '',
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
-    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    remote_id INTEGER,
+    sync_status TEXT NOT NULL DEFAULT 'unsynced',
+    sync_updated_at TEXT
 );
 
 CREATE INDEX IF NOT EXISTS idx_markers_project_id ON markers(project_id);
@@ -128,7 +217,10 @@ CREATE TABLE IF NOT EXISTS tasks (
     linked_marker_id INTEGER REFERENCES markers(id) ON DELETE SET NULL,
     linked_timestamp_seconds REAL,
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
-    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    remote_id INTEGER,
+    sync_status TEXT NOT NULL DEFAULT 'unsynced',
+    sync_updated_at TEXT
 );
 
 CREATE INDEX IF NOT EXISTS idx_tasks_project_id ON tasks(project_id);
@@ -141,7 +233,10 @@ CREATE TABLE IF NOT EXISTS project_references (
     title TEXT,
     notes TEXT NOT NULL DEFAULT '',
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
-    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    remote_id INTEGER,
+    sync_status TEXT NOT NULL DEFAULT 'unsynced',
+    sync_updated_at TEXT
 );
 
 CREATE INDEX IF NOT EXISTS idx_project_references_project_id ON project_references(project_id);
@@ -155,7 +250,10 @@ CREATE TABLE IF NOT EXISTS assets (
     asset_type TEXT NOT NULL DEFAULT 'generic',
     tags TEXT NOT NULL DEFAULT '',
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
-    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    remote_id INTEGER,
+    sync_status TEXT NOT NULL DEFAULT 'unsynced',
+    sync_updated_at TEXT
 );
 
 CREATE INDEX IF NOT EXISTS idx_assets_project_id ON assets(project_id);
@@ -167,6 +265,9 @@ CREATE TABLE IF NOT EXISTS mood_board (
     asset_id INTEGER NOT NULL REFERENCES assets(id) ON DELETE CASCADE,
     sort_order INTEGER NOT NULL DEFAULT 0,
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    remote_id INTEGER,
+    sync_status TEXT NOT NULL DEFAULT 'unsynced',
+    sync_updated_at TEXT,
     UNIQUE(project_id, asset_id)
 );
 CREATE INDEX IF NOT EXISTS idx_mood_board_project_id ON mood_board(project_id);
@@ -177,7 +278,10 @@ CREATE TABLE IF NOT EXISTS project_notes (
     project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
     content TEXT NOT NULL DEFAULT '',
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
-    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    remote_id INTEGER,
+    sync_status TEXT NOT NULL DEFAULT 'unsynced',
+    sync_updated_at TEXT
 );
 
 CREATE INDEX IF NOT EXISTS idx_project_notes_project_id ON project_notes(project_id);
@@ -197,6 +301,9 @@ CREATE TABLE IF NOT EXISTS spotify_references (
     notes TEXT NOT NULL DEFAULT '',
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
     updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    remote_id INTEGER,
+    sync_status TEXT NOT NULL DEFAULT 'unsynced',
+    sync_updated_at TEXT,
     UNIQUE(project_id, spotify_id)
 );
 
@@ -219,11 +326,82 @@ CREATE TABLE IF NOT EXISTS project_samples (
     project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
     path TEXT NOT NULL,
     filename TEXT NOT NULL,
-    is_missing INTEGER NOT NULL DEFAULT 0
+    is_missing INTEGER NOT NULL DEFAULT 0,
+    relative_path TEXT,
+    resolved_path TEXT,
+    location TEXT NOT NULL DEFAULT 'external'
 );
 
 CREATE INDEX IF NOT EXISTS idx_project_samples_project_id ON project_samples(project_id);
 
+-- Sample Index (audio files under the user's sample folders, for relinking missing samples)
+CREATE TABLE IF NOT EXISTS sample_index (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    path TEXT NOT NULL UNIQUE,
+    root TEXT NOT NULL,
+    filename TEXT NOT NULL,
+    file_size INTEGER NOT NULL,
+    modified_time INTEGER NOT NULL,
+    content_hash TEXT NOT NULL,
+    indexed_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_sample_index_filename ON sample_index(filename COLLATE NOCASE);
+CREATE INDEX IF NOT EXISTS idx_sample_index_root ON sample_index(root);
+
+-- Project Tracks (extracted from .als files, in arrangement order)
+CREATE TABLE IF NOT EXISTS project_tracks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    track_index INTEGER NOT NULL DEFAULT 0,
+    name TEXT NOT NULL,
+    track_type TEXT NOT NULL DEFAULT 'audio',
+    color_index INTEGER,
+    is_muted INTEGER NOT NULL DEFAULT 0,
+    is_soloed INTEGER NOT NULL DEFAULT 0,
+    is_frozen INTEGER NOT NULL DEFAULT 0,
+    devices TEXT NOT NULL DEFAULT '[]'
+);
+
+CREATE INDEX IF NOT EXISTS idx_project_tracks_project_id ON project_tracks(project_id);
+
+-- Project Track Devices (ordered device chain per track, racks flattened depth-first)
+CREATE TABLE IF NOT EXISTS project_track_devices (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    track_index INTEGER NOT NULL,
+    chain_order INTEGER NOT NULL,
+    depth INTEGER NOT NULL DEFAULT 0,
+    parent_order INTEGER,
+    branch_name TEXT,
+    name TEXT NOT NULL,
+    user_name TEXT,
+    device_type TEXT NOT NULL DEFAULT 'native',
+    is_on INTEGER NOT NULL DEFAULT 1,
+    preset_name TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_project_track_devices_project_track ON project_track_devices(project_id, track_index);
+CREATE INDEX IF NOT EXISTS idx_project_track_devices_name ON project_track_devices(name);
+
+-- Project MIDI Clips (session and arrangement clips with their notes as JSON)
+CREATE TABLE IF NOT EXISTS project_midi_clips (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    track_index INTEGER NOT NULL,
+    clip_index INTEGER NOT NULL,
+    name TEXT NOT NULL DEFAULT '',
+    in_arrangement INTEGER NOT NULL DEFAULT 0,
+    start_beat REAL,
+    end_beat REAL,
+    loop_start REAL,
+    loop_end REAL,
+    loop_on INTEGER NOT NULL DEFAULT 0,
+    notes TEXT NOT NULL DEFAULT '[]'
+);
+
+CREATE INDEX IF NOT EXISTS idx_project_midi_clips_project_id ON project_midi_clips(project_id);
+
 -- Collections (smart and manual)
 CREATE TABLE IF NOT EXISTS collections (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -9,11 +9,15 @@ mod mp3;
 mod supabase;
 mod license;
 mod als_parser;
+mod als_diff;
+mod analysis;
+mod relink;
 
 use db::DbState;
 use spotify::{SpotifyState, SpotifyInner};
 use soundcloud::{SoundCloudState, SoundCloudInner};
 use supabase::{SupabaseState, SupabaseClient, SyncTrigger};
+use scanner::watcher::WatcherState;
 use std::sync::Mutex;
 use tauri::Manager;
 
@@ -44,6 +48,10 @@ pub fn run() {
             })));
             app.manage(SupabaseState(Mutex::new(SupabaseClient::new())));
             app.manage(SyncTrigger(Mutex::new(None)));
+            app.manage(WatcherState(Mutex::new(None)));
+
+            // Keep the library up to date as sets and bounces change on disk
+            scanner::watcher::restart_watcher(app.handle());
 
             // Set window icon explicitly (bundle.icon only applies to release builds)
             if let Some(window) = app.get_webview_window("main") {
@@ -63,6 +71,10 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             commands::settings::get_settings,
             commands::settings::update_settings,
+            commands::settings::get_library_roots,
+            commands::settings::add_library_root,
+            commands::settings::update_library_root,
+            commands::settings::remove_library_root,
             commands::scanner::scan_library,
             commands::scanner::refresh_library,
             commands::scanner::discover_untracked_projects,
@@ -91,6 +103,8 @@ pub fn run() {
             commands::markers::create_marker,
             commands::markers::update_marker,
             commands::markers::delete_marker,
+            commands::markers::seconds_to_bars,
+            commands::markers::bars_to_seconds,
             commands::tasks::get_tasks,
             commands::tasks::create_task,
             commands::tasks::update_task,
@@ -144,13 +158,24 @@ pub fn run() {
             commands::license::deactivate_license_key,
             commands::license::get_checkout_url,
             commands::als::get_project_plugins,
+            commands::als::get_project_tracks,
             commands::als::get_project_samples,
+            commands::als::get_project_midi_clips,
+            commands::als::get_project_pitch_profile,
+            commands::als::get_project_tempo_key_check,
+            commands::als::get_set_tempo_map,
             // v1.1.0 — Bounce notes
             commands::bounces::update_bounce_notes,
+            commands::bounces::get_bounce_peaks,
+            commands::bounces::analyze_bounce,
+            commands::bounces::export_tagged_bounce,
             // v1.1.0 — Version timeline
             commands::versions::get_version_timeline,
+            commands::versions::get_set_backups,
+            commands::versions::restore_set_backup,
             commands::versions::upsert_version_note,
             commands::versions::delete_version_note,
+            commands::versions::diff_ableton_sets,
             // v1.1.0 — Project quick-create
             commands::projects::quick_create_project,
             // v1.1.0 — Collections
@@ -172,6 +197,11 @@ pub fn run() {
             commands::bulk::bulk_add_to_collection,
             // v1.1.0 — Health dashboard
             commands::health::get_library_health,
+            // Missing-sample relinker
+            commands::relink::index_sample_folders,
+            commands::relink::preview_sample_relink,
+            commands::relink::apply_sample_relink,
+            commands::relink::collect_project_samples,
             // Update checker
             commands::updater::check_for_update,
         ])
@@ -0,0 +1,191 @@
+// ID3v2.4 tags for MP3 exports. LAME's own tagging only writes ID3v1/v2.3
+// Latin-1 text of up to 250 characters, so the tag is built here instead:
+// UTF-8 text frames, a comment and the cover image.
+
+use crate::db::models::BounceTags;
+
+/// Text encoding byte for UTF-8, new in ID3v2.4.
+const UTF8: u8 = 0x03;
+/// APIC picture type for the front cover.
+const FRONT_COVER: u8 = 0x03;
+/// Tag and frame sizes are 28-bit syncsafe integers.
+const MAX_SIZE: usize = (1 << 28) - 1;
+
+/// A cover image to embed, with its MIME type ("image/png").
+pub struct Cover<'a> {
+    pub mime: &'a str,
+    pub data: &'a [u8],
+}
+
+/// An ID3v2.4 tag holding the title (TIT2), artist (TPE1), BPM (TBPM), key
+/// (TKEY), comment (COMM) and cover (APIC) of a bounce. Fields that are
+/// missing, or that don't fit their frame, are left out.
+pub fn id3v2_tag(tags: &BounceTags, cover: Option<&Cover>) -> Vec<u8> {
+    let mut frames = Vec::new();
+    text_frame(&mut frames, b"TIT2", &tags.title);
+    if let Some(artist) = &tags.artist {
+        text_frame(&mut frames, b"TPE1", artist);
+    }
+    // TBPM is a whole number of beats per minute
+    if let Some(bpm) = tags.bpm.map(f64::round).filter(|&bpm| bpm >= 1.0) {
+        text_frame(&mut frames, b"TBPM", &format!("{}", bpm as u32));
+    }
+    if let Some(key) = tags.key.as_deref().and_then(tkey) {
+        text_frame(&mut frames, b"TKEY", &key);
+    }
+    if let Some(comment) = &tags.comment {
+        // Language, then an empty short description
+        let mut body = vec![UTF8];
+        body.extend_from_slice(b"eng\0");
+        body.extend_from_slice(comment.as_bytes());
+        frame(&mut frames, b"COMM", &body);
+    }
+    if let Some(cover) = cover {
+        // MIME type is Latin-1; the description is empty
+        let mut body = vec![UTF8];
+        body.extend_from_slice(cover.mime.as_bytes());
+        body.extend_from_slice(&[0, FRONT_COVER, 0]);
+        body.extend_from_slice(cover.data);
+        frame(&mut frames, b"APIC", &body);
+    }
+
+    let mut tag = Vec::with_capacity(10 + frames.len());
+    tag.extend_from_slice(b"ID3");
+    tag.extend_from_slice(&[4, 0, 0]); // v2.4.0, no flags
+    tag.extend_from_slice(&syncsafe(frames.len()));
+    tag.extend_from_slice(&frames);
+    tag
+}
+
+/// The MP3 stream after any ID3v2 tags at its start.
+pub fn strip_id3v2(mut data: &[u8]) -> &[u8] {
+    while data.len() >= 10 && &data[..3] == b"ID3" {
+        let size = data[6..10].iter().fold(0usize, |size, &b| size << 7 | (b & 0x7F) as usize);
+        // A footer repeats the 10-byte header at the end
+        let footer = if data[5] & 0x10 != 0 { 10 } else { 0 };
+        data = &data[(10 + size + footer).min(data.len())..];
+    }
+    data
+}
+
+/// The TKEY spelling of a key ("F# Minor" → "F#m"): tonic, optional
+/// accidental and "m" for minor. None for keys it can't express.
+fn tkey(key: &str) -> Option<String> {
+    let (tonic, scale) = key.trim().split_once(' ')?;
+    let mut chars = tonic.chars();
+    let natural = chars.next()?.to_ascii_uppercase();
+    if !('A'..='G').contains(&natural) {
+        return None;
+    }
+    let accidental = match chars.as_str() {
+        "" => "",
+        "#" | "♯" => "#",
+        "b" | "♭" => "b",
+        _ => return None,
+    };
+    let minor = match scale.to_ascii_lowercase().as_str() {
+        "major" => "",
+        "minor" => "m",
+        _ => return None,
+    };
+    Some(format!("{}{}{}", natural, accidental, minor))
+}
+
+fn text_frame(frames: &mut Vec<u8>, id: &[u8; 4], text: &str) {
+    let mut body = vec![UTF8];
+    body.extend_from_slice(text.as_bytes());
+    frame(frames, id, &body);
+}
+
+fn frame(frames: &mut Vec<u8>, id: &[u8; 4], body: &[u8]) {
+    if frames.len() + 10 + body.len() > MAX_SIZE {
+        log::warn!("Leaving {} out of the ID3 tag: {} bytes is too large", String::from_utf8_lossy(id), body.len());
+        return;
+    }
+    frames.extend_from_slice(id);
+    frames.extend_from_slice(&syncsafe(body.len()));
+    frames.extend_from_slice(&[0, 0]);
+    frames.extend_from_slice(body);
+}
+
+/// Seven bits per byte, so the size never looks like an MPEG sync word.
+fn syncsafe(size: usize) -> [u8; 4] {
+    debug_assert!(size <= MAX_SIZE);
+    [(size >> 21) as u8 & 0x7F, (size >> 14) as u8 & 0x7F, (size >> 7) as u8 & 0x7F, size as u8 & 0x7F]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags() -> BounceTags {
+        BounceTags {
+            title: "Night Drive".to_string(),
+            artist: Some("Kay".to_string()),
+            bpm: Some(123.6),
+            key: Some("F# Minor".to_string()),
+            comment: Some("louder kick".to_string()),
+            cover_path: None,
+        }
+    }
+
+    /// Frame ID and body of each frame in a tag.
+    fn frames(tag: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut frames = Vec::new();
+        let mut rest = &tag[10..];
+        while rest.len() >= 10 {
+            let size = rest[4..8].iter().fold(0usize, |size, &b| size << 7 | b as usize);
+            frames.push((String::from_utf8(rest[..4].to_vec()).unwrap(), rest[10..10 + size].to_vec()));
+            rest = &rest[10 + size..];
+        }
+        frames
+    }
+
+    #[test]
+    fn test_tag_frames() {
+        let cover = Cover { mime: "image/png", data: &[0x89, b'P', b'N', b'G'] };
+        let tag = id3v2_tag(&tags(), Some(&cover));
+        assert_eq!(&tag[..6], b"ID3\x04\x00\x00");
+        assert_eq!(tag[6..10], syncsafe(tag.len() - 10));
+
+        let frames = frames(&tag);
+        let ids: Vec<&str> = frames.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["TIT2", "TPE1", "TBPM", "TKEY", "COMM", "APIC"]);
+        assert_eq!(frames[0].1, b"\x03Night Drive");
+        assert_eq!(frames[2].1, b"\x03124");
+        assert_eq!(frames[3].1, b"\x03F#m");
+        assert_eq!(frames[4].1, b"\x03eng\x00louder kick");
+        assert_eq!(frames[5].1, b"\x03image/png\x00\x03\x00\x89PNG");
+    }
+
+    #[test]
+    fn test_missing_fields_are_left_out() {
+        let tags = BounceTags { artist: None, bpm: None, key: Some("Atonal".to_string()), comment: None, ..tags() };
+        let tag = id3v2_tag(&tags, None);
+        let ids: Vec<String> = frames(&tag).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, ["TIT2"]);
+    }
+
+    #[test]
+    fn test_syncsafe_and_strip() {
+        assert_eq!(syncsafe(0x7F), [0, 0, 0, 0x7F]);
+        assert_eq!(syncsafe(0x80), [0, 0, 1, 0]);
+        assert_eq!(syncsafe(MAX_SIZE), [0x7F; 4]);
+
+        let audio = [0xFF, 0xFB, 0x90, 0x00];
+        let mut mp3 = id3v2_tag(&tags(), None);
+        mp3.extend(id3v2_tag(&tags(), None));
+        mp3.extend_from_slice(&audio);
+        assert_eq!(strip_id3v2(&mp3), audio);
+        assert_eq!(strip_id3v2(&audio), audio);
+    }
+
+    #[test]
+    fn test_tkey() {
+        assert_eq!(tkey("C Major").as_deref(), Some("C"));
+        assert_eq!(tkey("Eb Minor").as_deref(), Some("Ebm"));
+        assert_eq!(tkey("a♯ minor").as_deref(), Some("A#m"));
+        assert_eq!(tkey("H Major"), None);
+        assert_eq!(tkey("C Dorian"), None);
+    }
+}
@@ -1,228 +1,335 @@
-use std::fs::File;
-use std::io::{Read, Seek, SeekFrom, Write};
-use std::mem::MaybeUninit;
+mod id3;
+
+use std::num::NonZeroU32;
 use std::path::Path;
 
-use mp3lame_encoder::{Builder, FlushNoGap, InterleavedPcm, MonoPcm};
+use mp3lame_encoder::{Bitrate, Builder, DualPcm, Encoder, FlushGap, Mode, MonoPcm, Quality, VbrMode};
+use rusqlite::Connection;
+
+use crate::db::models::BounceTags;
+use crate::db::queries;
+use crate::scanner::audio_decode::AudioDecoder;
 
-/// Convert a WAV file to MP3 at 320kbps using LAME encoder.
-pub fn convert_wav_to_mp3(wav_path: &Path, output_path: &Path) -> Result<(), String> {
-    let mut file = File::open(wav_path).map_err(|e| format!("Failed to open WAV: {}", e))?;
+/// Output sample rates of MPEG-1 Layer III. LAME resamples to these.
+pub const SAMPLE_RATES: [u32; 3] = [32_000, 44_100, 48_000];
 
-    // Read RIFF header (12 bytes)
-    let mut riff_header = [0u8; 12];
-    file.read_exact(&mut riff_header)
-        .map_err(|e| format!("Failed to read RIFF header: {}", e))?;
+/// Constant bitrate in kbps, or LAME's VBR quality from 0 (best, ~245 kbps)
+/// to 9 (smallest, ~65 kbps).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mp3Bitrate {
+    Cbr(u16),
+    Vbr(u8),
+}
 
-    if &riff_header[0..4] != b"RIFF" || &riff_header[8..12] != b"WAVE" {
-        return Err("Not a valid WAV file".to_string());
+/// How bounces are encoded to MP3.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mp3Options {
+    pub bitrate: Mp3Bitrate,
+    /// Output sample rate, or None to keep the source's (LAME picks the
+    /// nearest one MP3 supports)
+    pub sample_rate: Option<u32>,
+    /// Fold all channels down to mono
+    pub mono: bool,
+}
+
+impl Default for Mp3Options {
+    fn default() -> Self {
+        Mp3Options { bitrate: Mp3Bitrate::Cbr(320), sample_rate: None, mono: false }
     }
+}
+
+/// MP3 encoding options from settings: "mp3_mode" ("cbr" or "vbr"),
+/// "mp3_bitrate" (kbps), "mp3_vbr_quality" (0-9), "mp3_sample_rate" (Hz,
+/// empty to keep the source's) and "mp3_mono". Missing or invalid values
+/// fall back to the defaults.
+pub fn load_options(conn: &Connection) -> Result<Mp3Options, String> {
+    let defaults = Mp3Options::default();
+    let number = |key: &str| -> Result<Option<u32>, String> {
+        Ok(queries::get_setting(conn, key)?.and_then(|v| v.trim().parse().ok()))
+    };
+    let bitrate = match queries::get_setting(conn, "mp3_mode")?.as_deref() {
+        Some("vbr") => Mp3Bitrate::Vbr(number("mp3_vbr_quality")?.filter(|&q| q <= 9).unwrap_or(2) as u8),
+        _ => match number("mp3_bitrate")?.and_then(|kbps| u16::try_from(kbps).ok()) {
+            Some(kbps) => Mp3Bitrate::Cbr(kbps),
+            None => defaults.bitrate,
+        },
+    };
+    Ok(Mp3Options {
+        bitrate,
+        sample_rate: number("mp3_sample_rate")?.filter(|rate| SAMPLE_RATES.contains(rate)),
+        mono: queries::get_setting(conn, "mp3_mono")?.as_deref() == Some("true"),
+    })
+}
 
-    let mut sample_rate: u32 = 0;
-    let mut channels: u16 = 0;
-    let mut bits_per_sample: u16 = 0;
-    let mut data_size: u32 = 0;
-    let mut data_offset: u64 = 0;
-    let mut found_fmt = false;
-    let mut found_data = false;
-
-    // Iterate through RIFF chunks to find fmt and data
-    loop {
-        let mut chunk_header = [0u8; 8];
-        match file.read_exact(&mut chunk_header) {
-            Ok(_) => {}
-            Err(_) => break,
+/// Write an MP3 of a bounce to `output_path`, led by an ID3v2.4 tag when
+/// `tags` are given. MP3 bounces keep their audio as it is (any ID3v2 tag
+/// is replaced); WAV, AIFF and FLAC bounces are encoded with `options`.
+pub fn bounce_to_mp3(
+    bounce_path: &Path,
+    output_path: &Path,
+    options: &Mp3Options,
+    tags: Option<&BounceTags>,
+) -> Result<(), String> {
+    let ext = bounce_path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+    let audio = match ext.as_str() {
+        "mp3" => {
+            let data = std::fs::read(bounce_path).map_err(|e| format!("Failed to read MP3: {}", e))?;
+            id3::strip_id3v2(&data).to_vec()
         }
+        "wav" | "aif" | "aiff" | "aifc" | "flac" => encode_mp3(bounce_path, options)?,
+        _ => return Err(format!("Can't convert .{} bounces to MP3; only WAV, AIFF, FLAC and MP3 bounces are supported", ext)),
+    };
 
-        let chunk_id = [
-            chunk_header[0],
-            chunk_header[1],
-            chunk_header[2],
-            chunk_header[3],
-        ];
-        let chunk_size = u32::from_le_bytes([
-            chunk_header[4],
-            chunk_header[5],
-            chunk_header[6],
-            chunk_header[7],
-        ]);
-
-        if &chunk_id == b"fmt " {
-            let mut fmt_data = vec![0u8; chunk_size as usize];
-            file.read_exact(&mut fmt_data)
-                .map_err(|e| format!("Failed to read fmt chunk: {}", e))?;
-
-            if fmt_data.len() >= 16 {
-                channels = u16::from_le_bytes([fmt_data[2], fmt_data[3]]);
-                sample_rate =
-                    u32::from_le_bytes([fmt_data[4], fmt_data[5], fmt_data[6], fmt_data[7]]);
-                bits_per_sample = u16::from_le_bytes([fmt_data[14], fmt_data[15]]);
-                found_fmt = true;
-            }
-        } else if &chunk_id == b"data" {
-            data_size = chunk_size;
-            data_offset = file
-                .stream_position()
-                .map_err(|e| format!("Failed to get position: {}", e))?;
-            found_data = true;
-            break;
-        } else {
-            // Skip unknown chunk (pad to even boundary)
-            let skip = if chunk_size % 2 == 1 {
-                chunk_size + 1
-            } else {
-                chunk_size
-            };
-            file.seek(SeekFrom::Current(skip as i64))
-                .map_err(|e| format!("Failed to skip chunk: {}", e))?;
+    let mut mp3 = match tags {
+        Some(tags) => {
+            let cover = tags.cover_path.as_deref().and_then(read_cover);
+            let cover = cover.as_ref().map(|(mime, data)| id3::Cover { mime, data });
+            id3::id3v2_tag(tags, cover.as_ref())
         }
-    }
+        None => Vec::new(),
+    };
+    mp3.extend_from_slice(&audio);
 
-    if !found_fmt || !found_data {
-        return Err("WAV file missing fmt or data chunk".to_string());
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create output directory: {}", e))?;
     }
+    // Written aside and renamed so a half-written file is never mistaken for
+    // a finished one
+    let partial = output_path.with_extension("mp3.partial");
+    std::fs::write(&partial, &mp3).map_err(|e| format!("Failed to write MP3 file: {}", e))?;
+    std::fs::rename(&partial, output_path).map_err(|e| {
+        std::fs::remove_file(&partial).ok();
+        format!("Failed to write MP3 file: {}", e)
+    })
+}
 
-    if sample_rate == 0 || channels == 0 || bits_per_sample == 0 {
-        return Err("Invalid WAV format parameters".to_string());
+/// Encode an audio file to an MP3 stream with the LAME encoder. More than two
+/// channels fold down to stereo, odd channels to the left and even to the
+/// right.
+pub fn encode_mp3(audio_path: &Path, options: &Mp3Options) -> Result<Vec<u8>, String> {
+    let mut decoder = AudioDecoder::open(audio_path)?;
+    let channels = decoder.channels.max(1) as usize;
+    let mono = options.mono || channels == 1;
+    let mut encoder = build_encoder(decoder.sample_rate, mono, options)?;
+
+    let mut mp3 = Vec::new();
+    let (mut left, mut right) = (Vec::new(), Vec::new());
+    while let Some(block) = decoder.next_block()? {
+        left.clear();
+        right.clear();
+        for frame in block.chunks_exact(channels) {
+            if mono {
+                left.push(frame.iter().sum::<f32>() / channels as f32);
+            } else {
+                left.push(fold(frame.iter().step_by(2)));
+                right.push(fold(frame.iter().skip(1).step_by(2)));
+            }
+        }
+        mp3.reserve(mp3lame_encoder::max_required_buffer_size(left.len()));
+        let encoded = if mono {
+            encoder.encode_to_vec(MonoPcm(&left[..]), &mut mp3)
+        } else {
+            encoder.encode_to_vec(DualPcm { left: &left[..], right: &right[..] }, &mut mp3)
+        };
+        encoded.map_err(|e| format!("MP3 encode error: {:?}", e))?;
     }
 
-    // Read raw PCM data
-    file.seek(SeekFrom::Start(data_offset))
-        .map_err(|e| format!("Failed to seek to data: {}", e))?;
+    // Padded so the last samples make it out past the encoder's delay
+    mp3.reserve(7200);
+    encoder.flush_to_vec::<FlushGap>(&mut mp3).map_err(|e| format!("MP3 flush error: {:?}", e))?;
 
-    let mut raw_data = vec![0u8; data_size as usize];
-    file.read_exact(&mut raw_data)
-        .map_err(|e| format!("Failed to read PCM data: {}", e))?;
+    // The first frame is a placeholder for the Xing/LAME header, which holds
+    // the length and encoder delay players need to seek VBR files and play
+    // them gaplessly
+    let mut header = Vec::with_capacity(encoder.lame_tag_size());
+    if encoder.lame_tag_encode_to_vec(&mut header).is_some() && header.len() <= mp3.len() {
+        mp3[..header.len()].copy_from_slice(&header);
+    }
+    Ok(mp3)
+}
 
-    // Convert raw bytes to i16 samples
-    let samples_i16 = convert_to_i16(&raw_data, bits_per_sample)?;
+/// Mean of the channels folded into one side.
+fn fold<'a>(samples: impl Iterator<Item = &'a f32>) -> f32 {
+    let (sum, count) = samples.fold((0.0, 0), |(sum, count), s| (sum + s, count + 1));
+    sum / count.max(1) as f32
+}
 
-    // Set up LAME encoder
-    let mut encoder = Builder::new().ok_or("Failed to create LAME encoder")?;
-    encoder
+fn build_encoder(sample_rate: u32, mono: bool, options: &Mp3Options) -> Result<Encoder, String> {
+    let mut builder = Builder::new().ok_or("Failed to create LAME encoder")?;
+    builder
         .set_sample_rate(sample_rate)
         .map_err(|e| format!("Failed to set sample rate: {:?}", e))?;
-    encoder
-        .set_num_channels(channels as u8)
+    builder
+        .set_output_sample_rate(options.sample_rate.and_then(NonZeroU32::new))
+        .map_err(|e| format!("Unsupported MP3 sample rate {:?}: {:?}", options.sample_rate, e))?;
+    builder
+        .set_num_channels(if mono { 1 } else { 2 })
         .map_err(|e| format!("Failed to set channels: {:?}", e))?;
-    encoder
-        .set_brate(mp3lame_encoder::Birtate::Kbps320)
-        .map_err(|e| format!("Failed to set bitrate: {:?}", e))?;
-    encoder
-        .set_quality(mp3lame_encoder::Quality::Best)
+    builder
+        .set_mode(if mono { Mode::Mono } else { Mode::JointStereo })
+        .map_err(|e| format!("Failed to set channel mode: {:?}", e))?;
+    match options.bitrate {
+        Mp3Bitrate::Cbr(kbps) => builder
+            .set_brate(cbr_bitrate(kbps)?)
+            .map_err(|e| format!("Failed to set bitrate: {:?}", e))?,
+        Mp3Bitrate::Vbr(quality) => {
+            builder
+                .set_vbr_mode(VbrMode::Mtrh)
+                .map_err(|e| format!("Failed to set VBR mode: {:?}", e))?;
+            builder
+                .set_vbr_quality(quality_level(quality)?)
+                .map_err(|e| format!("Failed to set VBR quality: {:?}", e))?;
+        }
+    }
+    builder
+        .set_quality(Quality::Best)
         .map_err(|e| format!("Failed to set quality: {:?}", e))?;
+    builder.build().map_err(|e| format!("Failed to build LAME encoder: {:?}", e))
+}
 
-    let mut encoder = encoder
-        .build()
-        .map_err(|e| format!("Failed to build LAME encoder: {:?}", e))?;
-
-    // Encode based on channel count
-    let mp3_data = if channels == 1 {
-        let input = MonoPcm(&samples_i16);
-        let buf_size = mp3lame_encoder::max_required_buffer_size(samples_i16.len());
-        let mut mp3_buf: Vec<MaybeUninit<u8>> = vec![MaybeUninit::uninit(); buf_size];
-        let encoded_size = encoder
-            .encode(input, &mut mp3_buf)
-            .map_err(|e| format!("MP3 encode error: {:?}", e))?;
-
-        let mut result = unsafe {
-            let ptr = mp3_buf.as_ptr() as *const u8;
-            std::slice::from_raw_parts(ptr, encoded_size).to_vec()
-        };
+fn cbr_bitrate(kbps: u16) -> Result<Bitrate, String> {
+    Ok(match kbps {
+        8 => Bitrate::Kbps8,
+        16 => Bitrate::Kbps16,
+        24 => Bitrate::Kbps24,
+        32 => Bitrate::Kbps32,
+        40 => Bitrate::Kbps40,
+        48 => Bitrate::Kbps48,
+        64 => Bitrate::Kbps64,
+        80 => Bitrate::Kbps80,
+        96 => Bitrate::Kbps96,
+        112 => Bitrate::Kbps112,
+        128 => Bitrate::Kbps128,
+        160 => Bitrate::Kbps160,
+        192 => Bitrate::Kbps192,
+        224 => Bitrate::Kbps224,
+        256 => Bitrate::Kbps256,
+        320 => Bitrate::Kbps320,
+        _ => return Err(format!("Unsupported MP3 bitrate: {} kbps", kbps)),
+    })
+}
 
-        // Flush remaining frames
-        let mut flush_buf: Vec<MaybeUninit<u8>> = vec![MaybeUninit::uninit(); 7200];
-        let flush_size = encoder
-            .flush::<FlushNoGap>(&mut flush_buf)
-            .map_err(|e| format!("MP3 flush error: {:?}", e))?;
-        unsafe {
-            let ptr = flush_buf.as_ptr() as *const u8;
-            result.extend_from_slice(std::slice::from_raw_parts(ptr, flush_size));
+fn quality_level(quality: u8) -> Result<Quality, String> {
+    Ok(match quality {
+        0 => Quality::Best,
+        1 => Quality::SecondBest,
+        2 => Quality::NearBest,
+        3 => Quality::VeryNice,
+        4 => Quality::Nice,
+        5 => Quality::Good,
+        6 => Quality::Decent,
+        7 => Quality::Ok,
+        8 => Quality::SecondWorst,
+        9 => Quality::Worst,
+        _ => return Err(format!("Unsupported VBR quality: {}", quality)),
+    })
+}
+
+/// The MIME type and bytes of a cover image, or None if it can't be read.
+fn read_cover(path: &str) -> Option<(&'static str, Vec<u8>)> {
+    let path = Path::new(path);
+    let mime = match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        _ => return None,
+    };
+    match std::fs::read(path) {
+        Ok(data) => Some((mime, data)),
+        Err(e) => {
+            log::debug!("Leaving cover out of MP3 tag, can't read {}: {}", path.display(), e);
+            None
         }
+    }
+}
 
-        result
-    } else {
-        // Stereo (or multi-channel treated as interleaved stereo)
-        let input = InterleavedPcm(&samples_i16);
-        let num_samples = samples_i16.len() / channels as usize;
-        let buf_size = mp3lame_encoder::max_required_buffer_size(num_samples);
-        let mut mp3_buf: Vec<MaybeUninit<u8>> = vec![MaybeUninit::uninit(); buf_size];
-        let encoded_size = encoder
-            .encode(input, &mut mp3_buf)
-            .map_err(|e| format!("MP3 encode error: {:?}", e))?;
-
-        let mut result = unsafe {
-            let ptr = mp3_buf.as_ptr() as *const u8;
-            std::slice::from_raw_parts(ptr, encoded_size).to_vec()
-        };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
 
-        // Flush remaining frames
-        let mut flush_buf: Vec<MaybeUninit<u8>> = vec![MaybeUninit::uninit(); 7200];
-        let flush_size = encoder
-            .flush::<FlushNoGap>(&mut flush_buf)
-            .map_err(|e| format!("MP3 flush error: {:?}", e))?;
-        unsafe {
-            let ptr = flush_buf.as_ptr() as *const u8;
-            result.extend_from_slice(std::slice::from_raw_parts(ptr, flush_size));
+    fn decode_all(path: &Path) -> (u32, u16, usize) {
+        let mut decoder = AudioDecoder::open(path).unwrap();
+        let mut samples = 0;
+        while let Some(block) = decoder.next_block().unwrap() {
+            samples += block.len();
         }
+        (decoder.sample_rate, decoder.channels, samples)
+    }
 
-        result
-    };
+    #[test]
+    fn test_encode_options() {
+        let tmp = TempDir::new().unwrap();
+        let wav = tmp.path().join("mix.wav");
+        std::fs::write(&wav, include_bytes!("../../tests/fixtures/wav/rf64_pcm24.wav")).unwrap();
 
-    // Ensure parent directory exists
-    if let Some(parent) = output_path.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create output directory: {}", e))?;
+        let cbr = tmp.path().join("cbr.mp3");
+        bounce_to_mp3(&wav, &cbr, &Mp3Options { bitrate: Mp3Bitrate::Cbr(128), ..Default::default() }, None).unwrap();
+        let (rate, channels, _) = decode_all(&cbr);
+        assert_eq!((rate, channels), (48000, 2));
+
+        let options = Mp3Options { bitrate: Mp3Bitrate::Vbr(4), sample_rate: Some(32_000), mono: true };
+        let vbr = tmp.path().join("vbr.mp3");
+        bounce_to_mp3(&wav, &vbr, &options, None).unwrap();
+        let (rate, channels, samples) = decode_all(&vbr);
+        assert_eq!((rate, channels), (32_000, 1));
+        assert!(samples > 0);
+        assert!(!tmp.path().join("vbr.mp3.partial").exists());
+
+        let bad = Mp3Options { bitrate: Mp3Bitrate::Cbr(100), ..Default::default() };
+        assert!(bounce_to_mp3(&wav, &vbr, &bad, None).is_err());
     }
 
-    // Write MP3 file
-    let mut out_file =
-        File::create(output_path).map_err(|e| format!("Failed to create MP3 file: {}", e))?;
-    out_file
-        .write_all(&mp3_data)
-        .map_err(|e| format!("Failed to write MP3 data: {}", e))?;
+    #[test]
+    fn test_tagged_mp3() {
+        let tmp = TempDir::new().unwrap();
+        let wav = tmp.path().join("mix.wav");
+        let cover = tmp.path().join("cover.png");
+        std::fs::write(&wav, include_bytes!("../../tests/fixtures/wav/pcm16_stereo.wav")).unwrap();
+        std::fs::write(&cover, b"\x89PNG\r\n\x1a\n").unwrap();
+        let tags = BounceTags {
+            title: "Night Drive".to_string(),
+            artist: Some("Kay".to_string()),
+            bpm: Some(124.0),
+            key: Some("A Minor".to_string()),
+            comment: None,
+            cover_path: Some(cover.to_string_lossy().to_string()),
+        };
 
-    Ok(())
-}
+        let mp3 = tmp.path().join("mix.mp3");
+        bounce_to_mp3(&wav, &mp3, &Mp3Options::default(), Some(&tags)).unwrap();
+        let data = std::fs::read(&mp3).unwrap();
+        assert_eq!(&data[..4], b"ID3\x04");
+        assert!(data.windows(14).any(|w| w == b"\x03image/png\x00\x03\x00\x89"));
+        assert_eq!(decode_all(&mp3).1, 2);
 
-/// Convert raw PCM bytes to i16 samples, handling 16-bit, 24-bit, and 32-bit formats.
-fn convert_to_i16(raw: &[u8], bits_per_sample: u16) -> Result<Vec<i16>, String> {
-    match bits_per_sample {
-        16 => {
-            if raw.len() % 2 != 0 {
-                return Err("Invalid 16-bit PCM data length".to_string());
-            }
-            Ok(raw
-                .chunks_exact(2)
-                .map(|c| i16::from_le_bytes([c[0], c[1]]))
-                .collect())
-        }
-        24 => {
-            if raw.len() % 3 != 0 {
-                return Err("Invalid 24-bit PCM data length".to_string());
-            }
-            Ok(raw
-                .chunks_exact(3)
-                .map(|c| {
-                    // 24-bit to i32, then scale down to i16
-                    let val =
-                        ((c[2] as i32) << 24 | (c[1] as i32) << 16 | (c[0] as i32) << 8) >> 8;
-                    (val >> 8) as i16
-                })
-                .collect())
-        }
-        32 => {
-            if raw.len() % 4 != 0 {
-                return Err("Invalid 32-bit PCM data length".to_string());
-            }
-            Ok(raw
-                .chunks_exact(4)
-                .map(|c| {
-                    let val = i32::from_le_bytes([c[0], c[1], c[2], c[3]]);
-                    (val >> 16) as i16
-                })
-                .collect())
-        }
-        _ => Err(format!("Unsupported bits per sample: {}", bits_per_sample)),
+        // Re-exporting an MP3 replaces its tag rather than stacking another
+        let retagged = tmp.path().join("retagged.mp3");
+        let tags = BounceTags { title: "Night Drive II".to_string(), cover_path: None, ..tags };
+        bounce_to_mp3(&mp3, &retagged, &Mp3Options::default(), Some(&tags)).unwrap();
+        let data = std::fs::read(&retagged).unwrap();
+        assert_eq!(data.windows(3).filter(|w| w == b"ID3").count(), 1);
+        assert!(data.windows(14).any(|w| w == b"Night Drive II"));
+        assert_eq!(id3::strip_id3v2(&data), id3::strip_id3v2(&std::fs::read(&mp3).unwrap()));
+    }
+
+    #[test]
+    fn test_mp3_options_from_settings() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        assert_eq!(load_options(&conn).unwrap(), Mp3Options::default());
+
+        queries::set_setting(&conn, "mp3_mode", "vbr").unwrap();
+        queries::set_setting(&conn, "mp3_vbr_quality", "4").unwrap();
+        queries::set_setting(&conn, "mp3_sample_rate", "44100").unwrap();
+        queries::set_setting(&conn, "mp3_mono", "true").unwrap();
+        let options = load_options(&conn).unwrap();
+        assert_eq!(options, Mp3Options { bitrate: Mp3Bitrate::Vbr(4), sample_rate: Some(44100), mono: true });
+
+        queries::set_setting(&conn, "mp3_mode", "cbr").unwrap();
+        queries::set_setting(&conn, "mp3_bitrate", "192").unwrap();
+        queries::set_setting(&conn, "mp3_sample_rate", "").unwrap();
+        let options = load_options(&conn).unwrap();
+        assert_eq!((options.bitrate, options.sample_rate), (Mp3Bitrate::Cbr(192), None));
     }
 }
@@ -0,0 +1,307 @@
+// Collect All and Save — copies every sample from outside the project into
+// <project>/Samples/Imported and points the set at the copies, so the
+// project folder is self-contained (e.g. before archiving it).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+
+use super::index::hash_file;
+use crate::als_parser::{self, SampleRef};
+use crate::db::models::CollectResult;
+use crate::db::queries;
+use crate::scanner::sample_location::{locate_sample, LiveLibraries, LOCATION_PROJECT};
+
+/// What collecting a set would do. Built completely before anything is
+/// copied, so a conflict aborts without touching the project.
+#[derive(Debug, Default)]
+struct CollectPlan {
+    /// (sample path in the set, source file, destination)
+    copies: Vec<(String, PathBuf, PathBuf)>,
+    /// (sample path in the set, identical file already in the project)
+    reused: Vec<(String, PathBuf)>,
+    missing: Vec<String>,
+}
+
+/// A collect in progress. Planning and rewriting the set need the database;
+/// copying, the slow part, doesn't, so callers can release it meanwhile.
+pub struct Collect {
+    project_id: i64,
+    project_dir: PathBuf,
+    set_path: String,
+    plan: CollectPlan,
+    result: CollectResult,
+    /// Sample path in the set → its copy in the project
+    replacements: HashMap<String, String>,
+}
+
+impl Collect {
+    /// Plan collecting the current set of a project.
+    pub fn plan(conn: &Connection, project_id: i64) -> Result<Self, String> {
+        let project = queries::get_project_by_id(conn, project_id)?;
+        let set_path = project.current_set_path.ok_or("Project has no .als set")?;
+        let project_dir = PathBuf::from(&project.project_path);
+
+        let metadata = als_parser::parse_als(Path::new(&set_path))?;
+        let plan = plan_collect(&metadata.samples, &project_dir, &LiveLibraries::from_settings(conn)?)?;
+        Ok(Collect {
+            project_id,
+            project_dir,
+            set_path,
+            result: CollectResult {
+                files_reused: plan.reused.len(),
+                missing: plan.missing.clone(),
+                ..Default::default()
+            },
+            plan,
+            replacements: HashMap::new(),
+        })
+    }
+
+    /// Copy the planned samples into the project.
+    pub fn copy(&mut self) -> Result<(), String> {
+        for (sample_path, src, dest) in &self.plan.copies {
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            self.result.bytes_copied += fs::copy(src, dest)
+                .map_err(|e| format!("Failed to copy {}: {}", src.display(), e))?;
+            self.result.files_copied += 1;
+            self.replacements.insert(sample_path.clone(), dest.to_string_lossy().to_string());
+        }
+        for (sample_path, dest) in &self.plan.reused {
+            self.replacements.insert(sample_path.clone(), dest.to_string_lossy().to_string());
+        }
+        Ok(())
+    }
+
+    /// Point the set at the copies.
+    pub fn finish(self, conn: &Connection) -> Result<CollectResult, String> {
+        let outcome = super::rewrite_set(conn, self.project_id, &self.project_dir, &self.set_path, &self.replacements)?;
+        let mut result = self.result;
+        log::info!(
+            "Collected {} samples ({} bytes) into {} ({} already there, {} missing)",
+            result.files_copied, result.bytes_copied, self.project_dir.display(), result.files_reused, result.missing.len()
+        );
+
+        result.set_path = self.set_path;
+        result.backup_path = outcome.backup_path.map(|p| p.to_string_lossy().to_string());
+        Ok(result)
+    }
+}
+
+/// Collect the current set of a project.
+pub fn collect_all(conn: &Connection, project_id: i64) -> Result<CollectResult, String> {
+    let mut collect = Collect::plan(conn, project_id)?;
+    collect.copy()?;
+    collect.finish(conn)
+}
+
+/// Where a sample goes in Samples/Imported.
+enum Placement {
+    Copy(PathBuf),
+    /// An identical file is already there, or planned
+    Reuse(PathBuf),
+    /// A different file already has the sample's name
+    Conflict(PathBuf),
+}
+
+/// Decide where each sample outside the project goes. Samples that share a
+/// filename but differ are numbered ("kick-1.wav"); fails if a file already
+/// in the project has a sample's name but different contents.
+fn plan_collect(samples: &[SampleRef], project_dir: &Path, libraries: &LiveLibraries) -> Result<CollectPlan, String> {
+    let imported = project_dir.join("Samples").join("Imported");
+    let mut plan = CollectPlan::default();
+    // Destination → source of the samples already planned
+    let mut claimed: HashMap<PathBuf, PathBuf> = HashMap::new();
+    let mut conflicts: Vec<String> = Vec::new();
+
+    for sample in samples {
+        let found = locate_sample(sample, project_dir, libraries);
+        let src = match found.resolved_path {
+            Some(_) if found.location == LOCATION_PROJECT => continue,
+            Some(src) => src,
+            None => {
+                plan.missing.push(sample.path.clone());
+                continue;
+            }
+        };
+
+        match place(&claimed, &imported, &sample.filename, &src)? {
+            Placement::Copy(dest) => {
+                plan.copies.push((sample.path.clone(), src.clone(), dest.clone()));
+                claimed.insert(dest, src);
+            }
+            Placement::Reuse(dest) => plan.reused.push((sample.path.clone(), dest)),
+            Placement::Conflict(dest) => conflicts.push(dest.to_string_lossy().to_string()),
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(format!(
+            "Refusing to overwrite {} file(s) in Samples/Imported that differ from the samples being collected: {}",
+            conflicts.len(),
+            conflicts.join(", ")
+        ));
+    }
+    Ok(plan)
+}
+
+/// The first of `filename`, "stem-1.ext", "stem-2.ext"... that is free or
+/// already holds the same contents as `src`. A different file under the
+/// sample's own name on disk is a conflict; numbered names are ours to skip.
+fn place(claimed: &HashMap<PathBuf, PathBuf>, imported: &Path, filename: &str, src: &Path) -> Result<Placement, String> {
+    let name = Path::new(filename);
+    let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = name.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    let mut n = 0;
+    loop {
+        let dest = match n {
+            0 => imported.join(filename),
+            _ => imported.join(format!("{}-{}{}", stem, n, ext)),
+        };
+        let planned = claimed.get(&dest);
+        let existing = planned.cloned().or_else(|| dest.is_file().then(|| dest.clone()));
+        match existing {
+            None => return Ok(Placement::Copy(dest)),
+            Some(other) if same_content(&other, src)? => return Ok(Placement::Reuse(dest)),
+            Some(_) if n == 0 && planned.is_none() => return Ok(Placement::Conflict(dest)),
+            Some(_) => n += 1,
+        }
+    }
+}
+
+fn same_content(a: &Path, b: &Path) -> Result<bool, String> {
+    if a == b {
+        return Ok(true);
+    }
+    let size = |p: &Path| fs::metadata(p).map(|m| m.len()).map_err(|e| format!("{}: {}", p.display(), e));
+    if size(a)? != size(b)? {
+        return Ok(false);
+    }
+    Ok(hash_file(a)? == hash_file(b)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use tempfile::TempDir;
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    /// A project folder with a set referencing the given absolute sample paths.
+    fn setup_project(conn: &Connection, dir: &Path, sample_paths: &[&Path]) -> (i64, PathBuf) {
+        let project = dir.join("Song Project");
+        fs::create_dir_all(&project).unwrap();
+        let refs: String = sample_paths.iter()
+            .map(|p| format!(
+                "<SampleRef><FileRef><Path Value=\"{}\" /></FileRef></SampleRef>",
+                p.to_string_lossy()
+            ))
+            .collect();
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?><Ableton Creator="Ableton Live 11.3.13"><LiveSet>{}</LiveSet></Ableton>"#,
+            refs
+        );
+        let als = project.join("Song.als");
+        let mut encoder = flate2::write::GzEncoder::new(fs::File::create(&als).unwrap(), flate2::Compression::default());
+        encoder.write_all(xml.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let pid = queries::create_project(conn, "Song", &project.to_string_lossy()).unwrap().id;
+        queries::set_current_set(conn, pid, &als.to_string_lossy()).unwrap();
+        (pid, als)
+    }
+
+    #[test]
+    fn test_collect_copies_external_samples() {
+        let conn = test_db();
+        let tmp = TempDir::new().unwrap();
+        let external = tmp.path().join("Splice").join("kick.wav");
+        fs::create_dir_all(external.parent().unwrap()).unwrap();
+        fs::write(&external, b"kick data").unwrap();
+        let gone = tmp.path().join("Gone").join("snare.wav");
+        let (pid, als) = setup_project(&conn, tmp.path(), &[&external, &gone]);
+
+        let result = collect_all(&conn, pid).unwrap();
+        assert_eq!(result.files_copied, 1);
+        assert_eq!(result.bytes_copied, 9);
+        assert_eq!(result.missing, vec![gone.to_string_lossy().to_string()]);
+        assert!(result.backup_path.is_some());
+
+        let copy = als.parent().unwrap().join("Samples").join("Imported").join("kick.wav");
+        assert_eq!(fs::read(&copy).unwrap(), b"kick data");
+        let mut xml = String::new();
+        flate2::read::GzDecoder::new(fs::File::open(&als).unwrap()).read_to_string(&mut xml).unwrap();
+        assert!(xml.contains(&*copy.to_string_lossy()));
+
+        let samples = queries::get_project_samples(&conn, pid).unwrap();
+        let kick = samples.iter().find(|s| s.filename == "kick.wav").unwrap();
+        assert_eq!(kick.location, LOCATION_PROJECT);
+
+        // Running it again has nothing left to collect
+        let again = collect_all(&conn, pid).unwrap();
+        assert_eq!((again.files_copied, again.files_reused), (0, 0));
+        assert!(again.backup_path.is_none());
+    }
+
+    #[test]
+    fn test_collect_reuses_identical_and_refuses_differing_files() {
+        let conn = test_db();
+        let tmp = TempDir::new().unwrap();
+        let kick = tmp.path().join("A").join("kick.wav");
+        let other_kick = tmp.path().join("B").join("kick.wav");
+        for (p, data) in [(&kick, b"one".as_slice()), (&other_kick, b"two".as_slice())] {
+            fs::create_dir_all(p.parent().unwrap()).unwrap();
+            fs::write(p, data).unwrap();
+        }
+
+        let (pid, als) = setup_project(&conn, tmp.path(), &[&kick]);
+        let imported = als.parent().unwrap().join("Samples").join("Imported");
+        fs::create_dir_all(&imported).unwrap();
+        fs::write(imported.join("kick.wav"), b"one").unwrap();
+        let result = collect_all(&conn, pid).unwrap();
+        assert_eq!((result.files_copied, result.files_reused, result.bytes_copied), (0, 1, 0));
+
+        // A different file with the same name is never overwritten
+        let tmp2 = TempDir::new().unwrap();
+        let (pid2, als2) = setup_project(&conn, tmp2.path(), &[&other_kick]);
+        let imported2 = als2.parent().unwrap().join("Samples").join("Imported");
+        fs::create_dir_all(&imported2).unwrap();
+        fs::write(imported2.join("kick.wav"), b"one").unwrap();
+        let before = fs::read(&als2).unwrap();
+        let err = collect_all(&conn, pid2).unwrap_err();
+        assert!(err.contains("Refusing to overwrite"));
+        assert_eq!(fs::read(imported2.join("kick.wav")).unwrap(), b"one");
+        assert_eq!(fs::read(&als2).unwrap(), before);
+    }
+
+    #[test]
+    fn test_collect_numbers_differing_samples_with_the_same_name() {
+        let conn = test_db();
+        let tmp = TempDir::new().unwrap();
+        let kicks: Vec<PathBuf> = ["Pack A", "Pack B", "Pack C"].iter()
+            .map(|pack| tmp.path().join(pack).join("kick.wav"))
+            .collect();
+        for (kick, data) in kicks.iter().zip([b"one", b"two", b"one"]) {
+            fs::create_dir_all(kick.parent().unwrap()).unwrap();
+            fs::write(kick, data).unwrap();
+        }
+        let paths: Vec<&Path> = kicks.iter().map(|p| p.as_path()).collect();
+        let (pid, als) = setup_project(&conn, tmp.path(), &paths);
+
+        let result = collect_all(&conn, pid).unwrap();
+        assert_eq!((result.files_copied, result.files_reused), (2, 1));
+        let imported = als.parent().unwrap().join("Samples").join("Imported");
+        assert_eq!(fs::read(imported.join("kick.wav")).unwrap(), b"one");
+        assert_eq!(fs::read(imported.join("kick-1.wav")).unwrap(), b"two");
+    }
+}
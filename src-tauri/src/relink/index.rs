@@ -0,0 +1,200 @@
+// Sample folder index — every audio file under the user's sample folders,
+// keyed by path with its size and content hash. Re-indexing only hashes
+// files whose size or mtime changed since the last run.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::db::models::{IndexedSample, SampleIndexSummary};
+use crate::db::queries;
+
+/// Audio formats Live can load as samples.
+const SAMPLE_EXTENSIONS: &[&str] = &["wav", "aif", "aiff", "flac", "mp3", "ogg", "m4a"];
+
+/// A walk of the sample folders: the files to (re)write into the index and
+/// what was found. Gathered without the database, since hashing a large
+/// sample library can take minutes.
+pub struct SampleScan {
+    /// New or changed files, hashed
+    changed: Vec<IndexedSample>,
+    seen: HashSet<String>,
+    unreachable: Vec<String>,
+    summary: SampleIndexSummary,
+}
+
+/// The index as it stands, keyed by path.
+pub fn load_index(conn: &Connection) -> Result<HashMap<String, IndexedSample>, String> {
+    Ok(queries::get_indexed_samples(conn)?
+        .into_iter()
+        .map(|sample| (sample.path.clone(), sample))
+        .collect())
+}
+
+/// Walk every sample folder, hashing the files that are new or changed
+/// since `known` was indexed.
+pub fn scan_sample_roots(roots: &[String], known: &HashMap<String, IndexedSample>) -> SampleScan {
+    let mut scan = SampleScan {
+        changed: Vec::new(),
+        seen: HashSet::new(),
+        unreachable: Vec::new(),
+        summary: SampleIndexSummary::default(),
+    };
+
+    for root in roots {
+        if !Path::new(root).is_dir() {
+            scan.summary.errors.push(format!("Sample folder not found: {}", root));
+            scan.unreachable.push(root.clone());
+            continue;
+        }
+        for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() || !is_sample_file(entry.path()) {
+                continue;
+            }
+            let path = entry.path().to_string_lossy().to_string();
+            match read_file(known.get(&path), root, entry.path()) {
+                Ok(changed) => {
+                    scan.summary.files += 1;
+                    if let Some(sample) = changed {
+                        scan.summary.hashed += 1;
+                        scan.changed.push(sample);
+                    }
+                    scan.seen.insert(path);
+                }
+                Err(e) => scan.summary.errors.push(format!("{}: {}", path, e)),
+            }
+        }
+    }
+    scan
+}
+
+/// Write a walk into the index in one transaction. Files that are gone, or
+/// whose folder is no longer configured, are dropped; folders that can't be
+/// reached right now (e.g. an unplugged drive) keep their entries.
+pub fn apply_sample_scan(conn: &Connection, scan: SampleScan) -> Result<SampleIndexSummary, String> {
+    let SampleScan { changed, seen, unreachable, mut summary } = scan;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for sample in &changed {
+        queries::upsert_indexed_sample(&tx, sample)?;
+    }
+    for sample in queries::get_indexed_samples(&tx)? {
+        let keep = seen.contains(&sample.path) || unreachable.iter().any(|root| Path::new(&sample.path).starts_with(root));
+        if !keep {
+            queries::delete_indexed_sample(&tx, &sample.path)?;
+            summary.removed += 1;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+/// Walk every sample folder and bring the index up to date.
+pub fn index_sample_roots(conn: &Connection, roots: &[String]) -> Result<SampleIndexSummary, String> {
+    let scan = scan_sample_roots(roots, &load_index(conn)?);
+    apply_sample_scan(conn, scan)
+}
+
+/// Read one file: its index entry, hashed, if it is new or changed since
+/// `known`; None if it is unchanged.
+fn read_file(known: Option<&IndexedSample>, root: &str, path: &Path) -> Result<Option<IndexedSample>, String> {
+    let meta = fs::metadata(path).map_err(|e| e.to_string())?;
+    let file_size = meta.len() as i64;
+    let modified_time = meta.modified()
+        .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64)
+        .unwrap_or(0);
+
+    if let Some(existing) = known {
+        if existing.file_size == file_size && existing.modified_time == modified_time && existing.root == root {
+            return Ok(None);
+        }
+    }
+
+    let filename = path.file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(Some(IndexedSample {
+        path: path.to_string_lossy().to_string(),
+        root: root.to_string(),
+        filename,
+        file_size,
+        modified_time,
+        content_hash: hash_file(path)?,
+    }))
+}
+
+/// SHA-256 of the file's contents, hex encoded.
+pub fn hash_file(path: &Path) -> Result<String, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut BufReader::new(file), &mut hasher)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn is_sample_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| SAMPLE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_reindex_hashes_only_changes_and_prunes_deleted() {
+        let conn = test_db();
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("Samples");
+        fs::create_dir_all(root.join("Drums")).unwrap();
+        fs::write(root.join("Drums/kick.wav"), b"kick").unwrap();
+        fs::write(root.join("Drums/snare.aif"), b"snare").unwrap();
+        fs::write(root.join("Drums/readme.txt"), b"not audio").unwrap();
+        let roots = vec![root.to_string_lossy().to_string()];
+
+        let first = index_sample_roots(&conn, &roots).unwrap();
+        assert_eq!((first.files, first.hashed, first.removed), (2, 2, 0));
+
+        let second = index_sample_roots(&conn, &roots).unwrap();
+        assert_eq!((second.files, second.hashed, second.removed), (2, 0, 0));
+
+        fs::remove_file(root.join("Drums/snare.aif")).unwrap();
+        let third = index_sample_roots(&conn, &roots).unwrap();
+        assert_eq!((third.files, third.hashed, third.removed), (1, 0, 1));
+
+        let kicks = queries::find_indexed_samples_by_filename(&conn, "KICK.WAV").unwrap();
+        assert_eq!(kicks.len(), 1);
+        assert_eq!(kicks[0].file_size, 4);
+        assert_eq!(kicks[0].content_hash, hash_file(&root.join("Drums/kick.wav")).unwrap());
+    }
+
+    #[test]
+    fn test_unreachable_root_keeps_its_entries() {
+        let conn = test_db();
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("External");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("pad.wav"), b"pad").unwrap();
+        let roots = vec![root.to_string_lossy().to_string()];
+        index_sample_roots(&conn, &roots).unwrap();
+
+        fs::rename(&root, tmp.path().join("Unplugged")).unwrap();
+        let summary = index_sample_roots(&conn, &roots).unwrap();
+        assert_eq!(summary.removed, 0);
+        assert_eq!(summary.errors.len(), 1);
+        assert_eq!(queries::find_indexed_samples_by_filename(&conn, "pad.wav").unwrap().len(), 1);
+    }
+}
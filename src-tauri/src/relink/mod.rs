@@ -0,0 +1,275 @@
+// Missing-sample relinker. Indexes the user's sample folders, proposes a
+// replacement for every sample a set can no longer find, and writes the
+// chosen files back into the .als. Previewing never touches the set;
+// applying keeps the original in the project's Backup folder. `collect`
+// reuses the same rewrite to make a project self-contained.
+
+pub mod collect;
+pub mod index;
+pub mod rewrite;
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use crate::db::models::{IndexedSample, RelinkCandidate, RelinkChoice, RelinkEntry, RelinkReport, RelinkResult};
+use crate::db::queries;
+
+/// Setting holding the folders to search, as a JSON array of paths.
+pub const SAMPLE_ROOTS_SETTING: &str = "sample_roots";
+
+/// The configured sample folders (empty if none are set).
+pub fn sample_roots(conn: &Connection) -> Result<Vec<String>, String> {
+    match queries::get_setting(conn, SAMPLE_ROOTS_SETTING)? {
+        Some(json) if !json.trim().is_empty() => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid {} setting: {}", SAMPLE_ROOTS_SETTING, e)),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Dry run: candidates for each missing sample of the project's current set.
+pub fn preview_relink(conn: &Connection, project_id: i64) -> Result<RelinkReport, String> {
+    let project = queries::get_project_by_id(conn, project_id)?;
+    let set_path = project.current_set_path.ok_or("Project has no .als set")?;
+    let original_sizes = rewrite::original_file_sizes(Path::new(&set_path))?;
+
+    let mut entries = Vec::new();
+    for sample in queries::get_project_samples(conn, project_id)?.into_iter().filter(|s| s.is_missing) {
+        let original_size = original_sizes.get(&sample.path).copied();
+        let indexed = queries::find_indexed_samples_by_filename(conn, &sample.filename)?;
+        let candidates = rank_candidates(&sample.path, original_size, indexed);
+        // A file whose size differs from what the set recorded is a different
+        // recording with the same name — list it, but don't pick it
+        let proposed_path = candidates.first()
+            .filter(|c| c.size_matches != Some(false))
+            .map(|c| c.path.clone());
+        entries.push(RelinkEntry {
+            sample_path: sample.path,
+            filename: sample.filename,
+            original_size,
+            candidates,
+            proposed_path,
+        });
+    }
+
+    Ok(RelinkReport { project_id, set_path, entries })
+}
+
+/// Write the chosen replacements into the project's current set and refresh
+/// its sample list.
+pub fn apply_relink(conn: &Connection, project_id: i64, choices: &[RelinkChoice]) -> Result<RelinkResult, String> {
+    let project = queries::get_project_by_id(conn, project_id)?;
+    let set_path = project.current_set_path.ok_or("Project has no .als set")?;
+
+    for choice in choices {
+        if !Path::new(&choice.new_path).is_file() {
+            return Err(format!("Replacement file not found: {}", choice.new_path));
+        }
+    }
+    let replacements: HashMap<String, String> = choices.iter()
+        .map(|c| (c.sample_path.clone(), c.new_path.clone()))
+        .collect();
+
+    let outcome = rewrite_set(conn, project_id, Path::new(&project.project_path), &set_path, &replacements)?;
+
+    Ok(RelinkResult {
+        set_path,
+        backup_path: outcome.backup_path.map(|p| p.to_string_lossy().to_string()),
+        relinked: outcome.relinked,
+    })
+}
+
+/// Rewrite the set's sample paths and re-parse it so the stored sample list
+/// and missing-deps flag reflect the new paths.
+fn rewrite_set(
+    conn: &Connection,
+    project_id: i64,
+    project_dir: &Path,
+    set_path: &str,
+    replacements: &HashMap<String, String>,
+) -> Result<rewrite::RelinkOutcome, String> {
+    let outcome = rewrite::relink_set(Path::new(set_path), project_dir, replacements)?;
+    if outcome.relinked > 0 {
+        log::info!("Relinked {} samples in {}", outcome.relinked, set_path);
+        queries::clear_als_parsed_at(conn, project_id)?;
+        crate::scanner::walker::parse_als_metadata(conn, project_id, project_dir, set_path);
+    }
+    Ok(outcome)
+}
+
+/// Order the indexed files named like a missing sample, best first: size
+/// matching what the set recorded, then the most parent folders in common
+/// with the old path. Identical copies collapse into one candidate.
+fn rank_candidates(missing_path: &str, original_size: Option<i64>, indexed: Vec<IndexedSample>) -> Vec<RelinkCandidate> {
+    let mut ranked: Vec<(RelinkCandidate, usize)> = indexed.into_iter()
+        .filter(|s| Path::new(&s.path).is_file())
+        .map(|s| {
+            let shared = shared_parent_dirs(missing_path, &s.path);
+            let candidate = RelinkCandidate {
+                size_matches: original_size.map(|size| size == s.file_size),
+                path: s.path,
+                file_size: s.file_size,
+                content_hash: s.content_hash,
+                duplicates: 0,
+            };
+            (candidate, shared)
+        })
+        .collect();
+
+    ranked.sort_by(|(a, a_shared), (b, b_shared)| {
+        let size_rank = |c: &RelinkCandidate| match c.size_matches {
+            Some(true) => 0,
+            None => 1,
+            Some(false) => 2,
+        };
+        (size_rank(a), Reverse(*a_shared), &a.path).cmp(&(size_rank(b), Reverse(*b_shared), &b.path))
+    });
+
+    let mut candidates: Vec<RelinkCandidate> = Vec::new();
+    let mut by_hash: HashMap<String, usize> = HashMap::new();
+    for (candidate, _) in ranked {
+        match by_hash.get(&candidate.content_hash) {
+            Some(&i) => candidates[i].duplicates += 1,
+            None => {
+                by_hash.insert(candidate.content_hash.clone(), candidates.len());
+                candidates.push(candidate);
+            }
+        }
+    }
+    candidates
+}
+
+/// How many parent folder names two paths share, counted from the file up
+/// (case-insensitive, either separator).
+fn shared_parent_dirs(a: &str, b: &str) -> usize {
+    let parents = |p: &str| -> Vec<String> {
+        let mut parts: Vec<String> = p.split(['/', '\\']).filter(|c| !c.is_empty()).map(|c| c.to_lowercase()).collect();
+        parts.pop();
+        parts
+    };
+    parents(a).iter().rev()
+        .zip(parents(b).iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn indexed(path: &Path, size: i64, hash: &str) -> IndexedSample {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"x").unwrap();
+        IndexedSample {
+            path: path.to_string_lossy().to_string(),
+            root: String::new(),
+            filename: path.file_name().unwrap().to_string_lossy().to_string(),
+            file_size: size,
+            modified_time: 0,
+            content_hash: hash.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rank_candidates() {
+        let tmp = TempDir::new().unwrap();
+        let lib = tmp.path();
+        let missing = "D:/Samples/Breaks/Amen/amen.wav";
+        let candidates = rank_candidates(missing, Some(100), vec![
+            indexed(&lib.join("Other/amen.wav"), 90, "h1"),
+            indexed(&lib.join("Loops/amen.wav"), 100, "h2"),
+            indexed(&lib.join("Breaks/Amen/amen.wav"), 100, "h3"),
+            indexed(&lib.join("Copy/Breaks/Amen/amen.wav"), 100, "h3"),
+        ]);
+
+        let paths: Vec<&str> = candidates.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec![
+            lib.join("Breaks/Amen/amen.wav").to_str().unwrap(),
+            lib.join("Loops/amen.wav").to_str().unwrap(),
+            lib.join("Other/amen.wav").to_str().unwrap(),
+        ]);
+        assert_eq!(candidates[0].duplicates, 1);
+        assert_eq!(candidates[2].size_matches, Some(false));
+    }
+
+    #[test]
+    fn test_preview_and_apply_relink() {
+        let conn = test_db();
+        let tmp = TempDir::new().unwrap();
+        let project = tmp.path().join("Break Project");
+        fs::create_dir_all(&project).unwrap();
+        let als = project.join("Break.als");
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Ableton MajorVersion="5" Creator="Ableton Live 11.3.13">
+  <LiveSet>
+    <SampleRef>
+      <FileRef>
+        <RelativePathType Value="1" />
+        <RelativePath Value="../../Splice/amen.wav" />
+        <Path Value="D:/Splice/amen.wav" />
+        <OriginalFileSize Value="4" />
+      </FileRef>
+    </SampleRef>
+  </LiveSet>
+</Ableton>"#;
+        let mut encoder = flate2::write::GzEncoder::new(fs::File::create(&als).unwrap(), flate2::Compression::default());
+        encoder.write_all(xml.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let root = tmp.path().join("Samples");
+        fs::create_dir_all(root.join("Splice")).unwrap();
+        fs::write(root.join("Splice/amen.wav"), b"amen").unwrap();
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)",
+            rusqlite::params![SAMPLE_ROOTS_SETTING, serde_json::to_string(&[root.to_string_lossy()]).unwrap()],
+        ).unwrap();
+        index::index_sample_roots(&conn, &sample_roots(&conn).unwrap()).unwrap();
+
+        let pid = queries::create_project(&conn, "Break", &project.to_string_lossy()).unwrap().id;
+        queries::set_current_set(&conn, pid, &als.to_string_lossy()).unwrap();
+        crate::scanner::walker::parse_als_metadata(&conn, pid, &project, &als.to_string_lossy());
+        assert!(queries::get_project_samples(&conn, pid).unwrap()[0].is_missing);
+
+        let report = preview_relink(&conn, pid).unwrap();
+        assert_eq!(report.entries.len(), 1);
+        let entry = &report.entries[0];
+        assert_eq!(entry.original_size, Some(4));
+        assert_eq!(entry.candidates[0].size_matches, Some(true));
+        let new_path = root.join("Splice").join("amen.wav").to_string_lossy().to_string();
+        assert_eq!(entry.proposed_path.as_deref(), Some(new_path.as_str()));
+
+        let result = apply_relink(&conn, pid, &[RelinkChoice {
+            sample_path: entry.sample_path.clone(),
+            new_path: new_path.clone(),
+        }]).unwrap();
+        assert_eq!(result.relinked, 1);
+        assert!(Path::new(result.backup_path.as_deref().unwrap()).is_file());
+
+        let samples = queries::get_project_samples(&conn, pid).unwrap();
+        assert_eq!(samples[0].path, new_path);
+        assert!(!samples[0].is_missing);
+    }
+
+    #[test]
+    fn test_apply_rejects_missing_replacement() {
+        let conn = test_db();
+        let pid = queries::create_project(&conn, "Song", "/music/Song Project").unwrap().id;
+        queries::set_current_set(&conn, pid, "/music/Song Project/Song.als").unwrap();
+        let err = apply_relink(&conn, pid, &[RelinkChoice {
+            sample_path: "/old/kick.wav".into(),
+            new_path: "/nowhere/kick.wav".into(),
+        }]).unwrap_err();
+        assert!(err.contains("not found"));
+    }
+}
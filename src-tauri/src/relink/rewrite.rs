@@ -0,0 +1,390 @@
+// .als rewriting for the relinker. Events are copied through one by one so
+// everything except the relinked <FileRef>s comes out byte-for-byte as Live
+// wrote it; each <FileRef> is buffered until its end tag because <Path>
+// comes after <RelativePath> in the element order.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, Writer};
+
+/// <RelativePathType> for a path relative to the project folder.
+const RELATIVE_TO_PROJECT: &str = "3";
+
+pub struct RelinkOutcome {
+    pub relinked: usize,
+    /// Copy of the set as it was before relinking; None if nothing changed.
+    pub backup_path: Option<PathBuf>,
+}
+
+/// Point every <FileRef> whose <Path> is a key of `replacements` at the
+/// replacement file, then swap the result in for the original. The original
+/// is copied to the project's Backup folder first, named the way Live names
+/// its own backups.
+pub fn relink_set(
+    als_path: &Path,
+    project_dir: &Path,
+    replacements: &HashMap<String, String>,
+) -> Result<RelinkOutcome, String> {
+    let tmp_path = als_path.with_extension("als.relink");
+    let relinked = match rewrite_to(als_path, &tmp_path, project_dir, replacements) {
+        Ok(n) => n,
+        Err(e) => {
+            fs::remove_file(&tmp_path).ok();
+            return Err(e);
+        }
+    };
+    if relinked == 0 {
+        fs::remove_file(&tmp_path).ok();
+        return Ok(RelinkOutcome { relinked, backup_path: None });
+    }
+
+    let backup_path = backup_set(als_path)?;
+    fs::rename(&tmp_path, als_path)
+        .map_err(|e| format!("Failed to replace {}: {}", als_path.display(), e))?;
+    Ok(RelinkOutcome { relinked, backup_path: Some(backup_path) })
+}
+
+/// <OriginalFileSize> of each sample <FileRef>, keyed by its <Path>.
+/// Live 10+ records it; older sets don't.
+pub fn original_file_sizes(als_path: &Path) -> Result<HashMap<String, i64>, String> {
+    let mut reader = open_reader(als_path)?;
+    reader.config_mut().expand_empty_elements = true;
+
+    let mut sizes = HashMap::new();
+    let mut buf = Vec::new();
+    // Depth below the open <FileRef>, 0 when outside one
+    let mut depth = 0usize;
+    let mut path: Option<String> = None;
+    let mut size: Option<i64> = None;
+    loop {
+        match reader.read_event_into(&mut buf).map_err(xml_error)? {
+            Event::Start(e) => {
+                if depth == 0 && e.local_name().as_ref() == b"FileRef" {
+                    depth = 1;
+                    path = None;
+                    size = None;
+                } else if depth > 0 {
+                    if depth == 1 {
+                        match e.local_name().as_ref() {
+                            b"Path" => path = value_attr(&e)?,
+                            b"OriginalFileSize" => size = value_attr(&e)?.and_then(|v| v.trim().parse().ok()),
+                            _ => {}
+                        }
+                    }
+                    depth += 1;
+                }
+            }
+            Event::End(_) if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    if let (Some(p), Some(s)) = (path.take(), size.take()) {
+                        sizes.insert(p.trim().to_string(), s);
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(sizes)
+}
+
+fn open_reader(als_path: &Path) -> Result<Reader<BufReader<GzDecoder<File>>>, String> {
+    let file = File::open(als_path).map_err(|e| format!("Failed to open .als file: {}", e))?;
+    Ok(Reader::from_reader(BufReader::new(GzDecoder::new(file))))
+}
+
+fn xml_error(e: impl std::fmt::Display) -> String {
+    format!("Failed to parse .als XML: {}", e)
+}
+
+fn write_error(e: impl std::fmt::Display) -> String {
+    format!("Failed to write .als file: {}", e)
+}
+
+/// Copy `als_path` to `out_path` with the replacements applied, gzip
+/// recompressed. Returns how many <FileRef>s were changed.
+fn rewrite_to(
+    als_path: &Path,
+    out_path: &Path,
+    project_dir: &Path,
+    replacements: &HashMap<String, String>,
+) -> Result<usize, String> {
+    let mut reader = open_reader(als_path)?;
+    let out = File::create(out_path).map_err(write_error)?;
+    let mut writer = Writer::new(GzEncoder::new(BufWriter::new(out), Compression::default()));
+
+    let mut relinked = 0;
+    let mut buf = Vec::new();
+    let mut file_ref: Option<Vec<Event<'static>>> = None;
+    loop {
+        let event = reader.read_event_into(&mut buf).map_err(xml_error)?.into_owned();
+        if let Event::Eof = event {
+            break;
+        }
+        match file_ref.as_mut() {
+            Some(events) => {
+                let closes = matches!(&event, Event::End(e) if e.local_name().as_ref() == b"FileRef");
+                events.push(event);
+                if closes {
+                    let mut events = file_ref.take().unwrap_or_default();
+                    if relink_file_ref(&mut events, project_dir, replacements)? {
+                        relinked += 1;
+                    }
+                    for e in events {
+                        writer.write_event(e).map_err(write_error)?;
+                    }
+                }
+            }
+            None => match &event {
+                Event::Start(e) if e.local_name().as_ref() == b"FileRef" => file_ref = Some(vec![event]),
+                _ => writer.write_event(event).map_err(write_error)?,
+            },
+        }
+        buf.clear();
+    }
+
+    let mut out = writer.into_inner().finish().map_err(write_error)?;
+    out.flush().map_err(write_error)?;
+    Ok(relinked)
+}
+
+/// Rewrite one buffered <FileRef> (start tag through end tag) if its <Path>
+/// is being relinked. The relative-path hints are only updated when the new
+/// file is inside the project folder; otherwise the absolute <Path> is what
+/// Live resolves.
+fn relink_file_ref(
+    events: &mut [Event<'static>],
+    project_dir: &Path,
+    replacements: &HashMap<String, String>,
+) -> Result<bool, String> {
+    let mut current_path = None;
+    for_each_child(events, |e| {
+        if e.local_name().as_ref() == b"Path" {
+            current_path = value_attr(e)?;
+        }
+        Ok(None)
+    })?;
+    let new_path = match current_path.and_then(|p| replacements.get(p.trim())) {
+        Some(p) => p.clone(),
+        None => return Ok(false),
+    };
+
+    let new_file = Path::new(&new_path);
+    let relative = new_file.strip_prefix(project_dir).ok().map(|rel| {
+        rel.components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/")
+    });
+    let filename = new_file.file_name().map(|f| f.to_string_lossy().to_string());
+
+    for_each_child(events, |e| {
+        let value = match e.local_name().as_ref() {
+            b"Path" => Some(new_path.clone()),
+            // Live 9 keeps the filename in its own element
+            b"Name" => filename.clone(),
+            b"RelativePath" => relative.clone(),
+            b"RelativePathType" => relative.as_ref().map(|_| RELATIVE_TO_PROJECT.to_string()),
+            _ => None,
+        };
+        // Only elements that carry a Value attribute are rewritten (not Live 9's
+        // <RelativePath> with <RelativePathElement> children)
+        match value {
+            Some(v) if value_attr(e)?.is_some() => Ok(Some(with_value(e, &v)?)),
+            _ => Ok(None),
+        }
+    })?;
+    Ok(true)
+}
+
+/// Call `f` on the start tag of each direct child of the buffered <FileRef>,
+/// replacing the tag when `f` returns a new one.
+fn for_each_child(
+    events: &mut [Event<'static>],
+    mut f: impl FnMut(&BytesStart) -> Result<Option<BytesStart<'static>>, String>,
+) -> Result<(), String> {
+    let mut depth = 0usize;
+    for event in events.iter_mut() {
+        match event {
+            Event::Start(e) => {
+                if depth == 1 {
+                    if let Some(new) = f(e)? {
+                        *e = new;
+                    }
+                }
+                depth += 1;
+            }
+            Event::Empty(e) if depth == 1 => {
+                if let Some(new) = f(e)? {
+                    *e = new;
+                }
+            }
+            Event::End(_) => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Unescaped Value attribute of a tag.
+fn value_attr(e: &BytesStart) -> Result<Option<String>, String> {
+    for a in e.attributes() {
+        let a = a.map_err(xml_error)?;
+        if a.key.local_name().as_ref() == b"Value" {
+            return Ok(Some(a.unescape_value().map_err(xml_error)?.into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+/// Copy of the tag with its Value attribute replaced, other attributes untouched.
+fn with_value(e: &BytesStart, value: &str) -> Result<BytesStart<'static>, String> {
+    let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+    let mut new = BytesStart::new(name);
+    for a in e.attributes() {
+        let a = a.map_err(xml_error)?;
+        if a.key.local_name().as_ref() == b"Value" {
+            new.push_attribute(("Value", value));
+        } else {
+            new.push_attribute(a);
+        }
+    }
+    Ok(new)
+}
+
+/// Copy the set into <project>/Backup as "<name> [YYYY-MM-DD HHMMSS].als".
+fn backup_set(als_path: &Path) -> Result<PathBuf, String> {
+    let project_dir = als_path.parent().ok_or("Set has no parent folder")?;
+    let backup_dir = project_dir.join("Backup");
+    fs::create_dir_all(&backup_dir)
+        .map_err(|e| format!("Failed to create Backup folder: {}", e))?;
+
+    let stem = als_path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let stamp = chrono::Local::now().format("%Y-%m-%d %H%M%S");
+    let mut backup_path = backup_dir.join(format!("{} [{}].als", stem, stamp));
+    let mut n = 2;
+    while backup_path.exists() {
+        backup_path = backup_dir.join(format!("{} [{}] {}.als", stem, stamp, n));
+        n += 1;
+    }
+
+    fs::copy(als_path, &backup_path)
+        .map_err(|e| format!("Failed to back up {}: {}", als_path.display(), e))?;
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    fn write_als(path: &Path, xml: &str) {
+        let mut encoder = GzEncoder::new(File::create(path).unwrap(), Compression::default());
+        encoder.write_all(xml.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+    }
+
+    fn read_als(path: &Path) -> String {
+        let mut xml = String::new();
+        GzDecoder::new(File::open(path).unwrap()).read_to_string(&mut xml).unwrap();
+        xml
+    }
+
+    const SET_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Ableton MajorVersion="5" Creator="Ableton Live 11.3.13">
+	<LiveSet>
+		<SampleRef>
+			<FileRef>
+				<RelativePathType Value="3" />
+				<RelativePath Value="Samples/Imported/amen.wav" />
+				<Path Value="D:/Old/Break Project/Samples/Imported/amen.wav" />
+				<Type Value="1" />
+				<OriginalFileSize Value="1234" />
+			</FileRef>
+		</SampleRef>
+		<SampleRef>
+			<FileRef>
+				<RelativePathType Value="1" />
+				<RelativePath Value="../Splice/R&amp;B hat.wav" />
+				<Path Value="D:/Splice/R&amp;B hat.wav" />
+			</FileRef>
+		</SampleRef>
+		<SampleRef>
+			<FileRef>
+				<Path Value="C:/Keep/me.wav" />
+			</FileRef>
+		</SampleRef>
+	</LiveSet>
+</Ableton>
+"#;
+
+    #[test]
+    fn test_relink_rewrites_only_matching_file_refs() {
+        let tmp = TempDir::new().unwrap();
+        let project = tmp.path().join("Break Project");
+        fs::create_dir_all(&project).unwrap();
+        let als = project.join("Break.als");
+        write_als(&als, SET_XML);
+
+        let in_project = project.join("Samples").join("amen.wav");
+        let outside = tmp.path().join("Library").join("R&B hat.wav");
+        let replacements = HashMap::from([
+            ("D:/Old/Break Project/Samples/Imported/amen.wav".to_string(), in_project.to_string_lossy().to_string()),
+            ("D:/Splice/R&B hat.wav".to_string(), outside.to_string_lossy().to_string()),
+        ]);
+
+        let outcome = relink_set(&als, &project, &replacements).unwrap();
+        assert_eq!(outcome.relinked, 2);
+
+        let xml = read_als(&als);
+        assert!(xml.contains(&format!(r#"<Path Value="{}"/>"#, in_project.to_string_lossy())));
+        assert!(xml.contains(r#"<RelativePath Value="Samples/amen.wav"/>"#));
+        // Outside the project: only the absolute path changes, with '&' re-escaped
+        assert!(xml.contains(&format!(r#"<Path Value="{}"/>"#, outside.to_string_lossy().replace('&', "&amp;"))));
+        assert!(xml.contains(r#"<RelativePath Value="../Splice/R&amp;B hat.wav" />"#));
+        // Untouched elements keep their original bytes
+        assert!(xml.contains(r#"<Path Value="C:/Keep/me.wav" />"#));
+        assert!(xml.contains(r#"<OriginalFileSize Value="1234" />"#));
+
+        let backup = outcome.backup_path.unwrap();
+        assert_eq!(backup.parent().unwrap(), project.join("Backup"));
+        assert_eq!(read_als(&backup), SET_XML);
+        assert!(!als.with_extension("als.relink").exists());
+    }
+
+    #[test]
+    fn test_relink_without_matches_leaves_set_alone() {
+        let tmp = TempDir::new().unwrap();
+        let als = tmp.path().join("Song.als");
+        write_als(&als, SET_XML);
+        let before = fs::read(&als).unwrap();
+
+        let replacements = HashMap::from([("/not/in/set.wav".to_string(), "/x.wav".to_string())]);
+        let outcome = relink_set(&als, tmp.path(), &replacements).unwrap();
+        assert_eq!(outcome.relinked, 0);
+        assert!(outcome.backup_path.is_none());
+        assert_eq!(fs::read(&als).unwrap(), before);
+        assert!(!tmp.path().join("Backup").exists());
+    }
+
+    #[test]
+    fn test_original_file_sizes() {
+        let tmp = TempDir::new().unwrap();
+        let als = tmp.path().join("Song.als");
+        write_als(&als, SET_XML);
+
+        let sizes = original_file_sizes(&als).unwrap();
+        assert_eq!(sizes.len(), 1);
+        assert_eq!(sizes.get("D:/Old/Break Project/Samples/Imported/amen.wav"), Some(&1234));
+    }
+}
@@ -0,0 +1,211 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::audio_decode::PcmLayout;
+use super::audio_info::AudioInfo;
+
+/// Parse an AIFF or AIFF-C file's COMM chunk for its format and duration.
+pub fn parse_aiff_info(path: &Path) -> Result<AudioInfo, String> {
+    let aiff = read_aiff(path, false)?;
+    let sample_format = match &aiff.compression {
+        [0, 0, 0, 0] | b"NONE" | b"sowt" | b"twos" | b"in24" | b"in32" => Some("pcm"),
+        b"fl32" | b"FL32" | b"fl64" | b"FL64" => Some("float"),
+        _ => None,
+    };
+    Ok(AudioInfo {
+        format: "AIFF",
+        duration_seconds: aiff.frames as f64 / aiff.sample_rate,
+        sample_rate: aiff.sample_rate.round() as u32,
+        bit_depth: Some(aiff.bit_depth),
+        channels: aiff.channels,
+        sample_format,
+    })
+}
+
+/// Where an AIFF file's samples are and how they are encoded, for decoding.
+pub(crate) fn aiff_pcm_layout(path: &Path) -> Result<PcmLayout, String> {
+    let aiff = read_aiff(path, true)?;
+    let (data_offset, data_size) = aiff.sound.ok_or("AIFF file missing SSND chunk")?;
+    let (float, big_endian) = match &aiff.compression {
+        [0, 0, 0, 0] | b"NONE" | b"twos" | b"in24" | b"in32" => (false, true),
+        b"sowt" => (false, false),
+        b"fl32" | b"FL32" | b"fl64" | b"FL64" => (true, true),
+        other => return Err(format!("Unsupported AIFF compression '{}'", String::from_utf8_lossy(other))),
+    };
+    Ok(PcmLayout {
+        data_offset,
+        data_size,
+        sample_rate: aiff.sample_rate.round() as u32,
+        channels: aiff.channels,
+        bits_per_sample: aiff.bit_depth,
+        block_align: aiff.channels as u64 * aiff.bit_depth.div_ceil(8) as u64,
+        float,
+        big_endian,
+        unsigned: false,
+    })
+}
+
+/// The COMM chunk fields, and the sample data's offset and size if the
+/// SSND chunk was looked for.
+struct AiffLayout {
+    channels: u16,
+    frames: u32,
+    bit_depth: u16,
+    sample_rate: f64,
+    /// AIFF-C compression type; zeroes for plain AIFF
+    compression: [u8; 4],
+    sound: Option<(u64, u64)>,
+}
+
+/// Walk the chunks of an AIFF file up to COMM, or up to both COMM and SSND
+/// when `find_sound` is set. Chunks are big-endian and padded to an even
+/// length.
+fn read_aiff(path: &Path, find_sound: bool) -> Result<AiffLayout, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open AIFF: {}", e))?;
+    let file_len = file.metadata().map_err(|e| format!("Failed to read AIFF size: {}", e))?.len();
+
+    let mut form_header = [0u8; 12];
+    file.read_exact(&mut form_header).map_err(|e| format!("Failed to read FORM header: {}", e))?;
+    if &form_header[0..4] != b"FORM" || (&form_header[8..12] != b"AIFF" && &form_header[8..12] != b"AIFC") {
+        return Err("Not a valid AIFF file".to_string());
+    }
+
+    let mut layout: Option<AiffLayout> = None;
+    let mut sound: Option<(u64, u64)> = None;
+    loop {
+        let mut chunk_header = [0u8; 8];
+        if file.read_exact(&mut chunk_header).is_err() {
+            break;
+        }
+        let chunk_size = u32::from_be_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]);
+        let mut skip = chunk_size as i64 + (chunk_size % 2) as i64;
+
+        if &chunk_header[0..4] == b"COMM" {
+            if chunk_size < 18 {
+                return Err("AIFF COMM chunk too short".to_string());
+            }
+            // AIFF-C adds a compression type after the sample rate
+            let mut comm = [0u8; 22];
+            let len = if &form_header[8..12] == b"AIFC" && chunk_size >= 22 { 22 } else { 18 };
+            file.read_exact(&mut comm[..len]).map_err(|e| format!("Failed to read COMM chunk: {}", e))?;
+            skip -= len as i64;
+
+            let channels = u16::from_be_bytes([comm[0], comm[1]]);
+            let sample_rate = extended_to_f64(&comm[8..18]);
+            // An exponent of all ones makes an infinity or NaN
+            if channels == 0 || !sample_rate.is_finite() || sample_rate < 1.0 {
+                return Err("Invalid AIFF format parameters".to_string());
+            }
+            layout = Some(AiffLayout {
+                channels,
+                frames: u32::from_be_bytes([comm[2], comm[3], comm[4], comm[5]]),
+                bit_depth: u16::from_be_bytes([comm[6], comm[7]]),
+                sample_rate,
+                compression: [comm[18], comm[19], comm[20], comm[21]],
+                sound: None,
+            });
+        } else if &chunk_header[0..4] == b"SSND" && chunk_size >= 8 {
+            // Offset to the first sample frame, then the block size
+            let mut ssnd = [0u8; 8];
+            file.read_exact(&mut ssnd).map_err(|e| format!("Failed to read SSND chunk: {}", e))?;
+            skip -= 8;
+            let offset = u32::from_be_bytes([ssnd[0], ssnd[1], ssnd[2], ssnd[3]]) as u64;
+            let start = file.stream_position().map_err(|e| format!("Failed to get position: {}", e))? + offset;
+            let size = (chunk_size as u64 - 8).saturating_sub(offset).min(file_len.saturating_sub(start));
+            sound = Some((start, size));
+        }
+
+        if layout.is_some() && (!find_sound || sound.is_some()) {
+            break;
+        }
+        file.seek(SeekFrom::Current(skip)).map_err(|e| format!("Failed to skip chunk: {}", e))?;
+    }
+
+    let mut layout = layout.ok_or("AIFF file missing COMM chunk")?;
+    layout.sound = sound;
+    Ok(layout)
+}
+
+/// Convert an 80-bit IEEE 754 extended-precision float (AIFF's sample rate).
+fn extended_to_f64(bytes: &[u8]) -> f64 {
+    let sign = if bytes[0] & 0x80 != 0 { -1.0 } else { 1.0 };
+    let exponent = (((bytes[0] & 0x7F) as i32) << 8) | bytes[1] as i32;
+    let mantissa = u64::from_be_bytes([bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7], bytes[8], bytes[9]]);
+    if exponent == 0 && mantissa == 0 {
+        return 0.0;
+    }
+    sign * mantissa as f64 * 2f64.powi(exponent - 16383 - 63)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    /// 44.1 kHz as an 80-bit extended float
+    const RATE_44100: [u8; 10] = [0x40, 0x0E, 0xAC, 0x44, 0, 0, 0, 0, 0, 0];
+
+    fn write_aiff(path: &Path, form_type: &[u8; 4], frames: u32) {
+        let mut comm = Vec::new();
+        comm.extend_from_slice(&2u16.to_be_bytes());
+        comm.extend_from_slice(&frames.to_be_bytes());
+        comm.extend_from_slice(&24u16.to_be_bytes());
+        comm.extend_from_slice(&RATE_44100);
+        if form_type == b"AIFC" {
+            comm.extend_from_slice(b"NONE");
+            comm.extend_from_slice(&[14]);
+            comm.extend_from_slice(b"not compressed\0");
+        }
+
+        let mut body = Vec::new();
+        body.extend_from_slice(form_type);
+        // An odd-sized chunk before COMM, to check padding is skipped
+        body.extend_from_slice(b"NAME");
+        body.extend_from_slice(&3u32.to_be_bytes());
+        body.extend_from_slice(b"Mix\0");
+        body.extend_from_slice(b"COMM");
+        body.extend_from_slice(&(comm.len() as u32).to_be_bytes());
+        body.extend_from_slice(&comm);
+        if comm.len() % 2 == 1 {
+            body.push(0);
+        }
+
+        let mut file = File::create(path).unwrap();
+        file.write_all(b"FORM").unwrap();
+        file.write_all(&(body.len() as u32).to_be_bytes()).unwrap();
+        file.write_all(&body).unwrap();
+    }
+
+    #[test]
+    fn test_parse_aiff_and_aifc() {
+        let tmp = TempDir::new().unwrap();
+        for form_type in [b"AIFF", b"AIFC"] {
+            let path = tmp.path().join("mix.aif");
+            write_aiff(&path, form_type, 441_000);
+            let info = parse_aiff_info(&path).unwrap();
+            assert_eq!(info, AudioInfo {
+                format: "AIFF",
+                duration_seconds: 10.0,
+                sample_rate: 44100,
+                bit_depth: Some(24),
+                channels: 2,
+                sample_format: Some("pcm"),
+            });
+        }
+
+        // A NaN sample rate is rejected rather than slipping past the check
+        let nan = tmp.path().join("nan.aif");
+        write_aiff(&nan, b"AIFF", 441_000);
+        let mut bytes = std::fs::read(&nan).unwrap();
+        let rate = bytes.windows(10).position(|w| w == RATE_44100).unwrap();
+        bytes[rate..rate + 10].copy_from_slice(&[0x7F, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0]);
+        std::fs::write(&nan, &bytes).unwrap();
+        assert!(parse_aiff_info(&nan).is_err());
+
+        let bad = tmp.path().join("bad.aif");
+        std::fs::write(&bad, b"RIFF\0\0\0\0WAVE").unwrap();
+        assert!(parse_aiff_info(&bad).is_err());
+    }
+}
@@ -0,0 +1,330 @@
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use super::{aiff_parser, wav_parser};
+
+/// Frames read per block from uncompressed files.
+const BLOCK_FRAMES: u64 = 4096;
+
+/// Where the samples of a WAV or AIFF file are and how they are encoded.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PcmLayout {
+    pub data_offset: u64,
+    pub data_size: u64,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    /// Bytes per frame
+    pub block_align: u64,
+    pub float: bool,
+    pub big_endian: bool,
+    /// 8-bit WAV stores samples offset by 128
+    pub unsigned: bool,
+}
+
+/// Decodes an audio file to interleaved `f32` samples in -1.0..=1.0, a block
+/// at a time. WAV and AIFF are read directly (including RF64 and files over
+/// 4 GB); FLAC and MP3 go through symphonia.
+pub struct AudioDecoder {
+    pub sample_rate: u32,
+    pub channels: u16,
+    source: Source,
+    samples: Vec<f32>,
+}
+
+enum Source {
+    Pcm {
+        reader: BufReader<File>,
+        layout: PcmLayout,
+        /// Bytes of sample data not yet read
+        remaining: u64,
+        bytes: Vec<u8>,
+    },
+    Codec {
+        format: Box<dyn FormatReader>,
+        decoder: Box<dyn Decoder>,
+        track_id: u32,
+        buffer: Option<SampleBuffer<f32>>,
+    },
+}
+
+impl AudioDecoder {
+    /// Open an audio file, choosing the decoder by extension.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let ext = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+        match ext.as_str() {
+            "wav" => Self::open_pcm(path, wav_parser::wav_pcm_layout(path)?),
+            "aif" | "aiff" | "aifc" => Self::open_pcm(path, aiff_parser::aiff_pcm_layout(path)?),
+            "flac" | "mp3" => Self::open_codec(path, &ext),
+            _ => Err(format!("Unsupported audio format: .{}", ext)),
+        }
+    }
+
+    fn open_pcm(path: &Path, layout: PcmLayout) -> Result<Self, String> {
+        let width = sample_width(&layout);
+        let supported = if layout.float { width == 4 || width == 8 } else { (1..=4).contains(&width) };
+        if !supported {
+            return Err(format!("Unsupported sample format: {}-bit {}", layout.bits_per_sample,
+                if layout.float { "float" } else { "integer" }));
+        }
+
+        let mut file = File::open(path).map_err(|e| format!("Failed to open audio: {}", e))?;
+        file.seek(SeekFrom::Start(layout.data_offset)).map_err(|e| format!("Failed to seek to data: {}", e))?;
+        Ok(AudioDecoder {
+            sample_rate: layout.sample_rate,
+            channels: layout.channels,
+            source: Source::Pcm {
+                reader: BufReader::new(file),
+                remaining: layout.data_size - layout.data_size % layout.block_align,
+                layout,
+                bytes: Vec::new(),
+            },
+            samples: Vec::new(),
+        })
+    }
+
+    fn open_codec(path: &Path, ext: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open audio: {}", e))?;
+        let stream = MediaSourceStream::new(Box::new(file), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension(ext);
+
+        let probed = symphonia::default::get_probe()
+            .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+            .map_err(|e| format!("Failed to read audio: {}", e))?;
+        let format = probed.format;
+        let track = format.tracks().iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or("No audio track found")?;
+        let sample_rate = track.codec_params.sample_rate.ok_or("Unknown sample rate")?;
+        let channels = track.codec_params.channels.ok_or("Unknown channel layout")?.count() as u16;
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|e| format!("Failed to create decoder: {}", e))?;
+
+        Ok(AudioDecoder {
+            sample_rate,
+            channels,
+            source: Source::Codec { track_id: track.id, format, decoder, buffer: None },
+            samples: Vec::new(),
+        })
+    }
+
+    /// The next block of interleaved samples, or None at the end of the file.
+    pub fn next_block(&mut self) -> Result<Option<&[f32]>, String> {
+        let AudioDecoder { source, samples, .. } = self;
+        samples.clear();
+        let more = match source {
+            Source::Pcm { reader, layout, remaining, bytes } => read_pcm_block(reader, layout, remaining, bytes, samples)?,
+            Source::Codec { format, decoder, track_id, buffer } => decode_packet(format, decoder, *track_id, buffer, samples)?,
+        };
+        Ok(more.then_some(&self.samples[..]))
+    }
+}
+
+/// Bytes per sample, including any padding in the container.
+fn sample_width(layout: &PcmLayout) -> usize {
+    (layout.block_align / layout.channels.max(1) as u64) as usize
+}
+
+fn read_pcm_block(
+    reader: &mut BufReader<File>,
+    layout: &PcmLayout,
+    remaining: &mut u64,
+    bytes: &mut Vec<u8>,
+    samples: &mut Vec<f32>,
+) -> Result<bool, String> {
+    if *remaining == 0 {
+        return Ok(false);
+    }
+    let len = (*remaining).min(BLOCK_FRAMES * layout.block_align);
+    bytes.resize(len as usize, 0);
+    reader.read_exact(bytes).map_err(|e| format!("Failed to read samples: {}", e))?;
+    *remaining -= len;
+
+    let width = sample_width(layout);
+    for frame in bytes.chunks_exact(layout.block_align as usize) {
+        for sample in frame.chunks_exact(width).take(layout.channels as usize) {
+            samples.push(decode_sample(sample, layout));
+        }
+    }
+    Ok(true)
+}
+
+/// Convert one sample to -1.0..=1.0.
+fn decode_sample(bytes: &[u8], layout: &PcmLayout) -> f32 {
+    // Gather the bytes most significant first
+    let mut be = [0u8; 8];
+    for (i, b) in bytes.iter().enumerate() {
+        be[if layout.big_endian { i } else { bytes.len() - 1 - i }] = *b;
+    }
+    if layout.float {
+        return match bytes.len() {
+            4 => f32::from_be_bytes([be[0], be[1], be[2], be[3]]),
+            _ => f64::from_be_bytes(be) as f32,
+        };
+    }
+    // Left-justify in 32 bits so every width shares one scale
+    let mut value = u32::from_be_bytes([be[0], be[1], be[2], be[3]]) & (u32::MAX << (32 - 8 * bytes.len()));
+    if layout.unsigned {
+        value ^= 0x8000_0000;
+    }
+    value as i32 as f32 / 2_147_483_648.0
+}
+
+fn decode_packet(
+    format: &mut Box<dyn FormatReader>,
+    decoder: &mut Box<dyn Decoder>,
+    track_id: u32,
+    buffer: &mut Option<SampleBuffer<f32>>,
+    samples: &mut Vec<f32>,
+) -> Result<bool, String> {
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => return Ok(false),
+            Err(SymphoniaError::ResetRequired) => return Ok(false),
+            Err(e) => return Err(format!("Failed to read audio: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt frame is skipped, as players do
+            Err(SymphoniaError::DecodeError(e)) => {
+                log::debug!("Skipping undecodable packet: {}", e);
+                continue;
+            }
+            Err(e) => return Err(format!("Failed to decode audio: {}", e)),
+        };
+        if decoded.frames() == 0 {
+            continue;
+        }
+
+        let needed = decoded.capacity() * decoded.spec().channels.count();
+        let buffer = match buffer {
+            Some(b) if b.capacity() >= needed => b,
+            _ => buffer.insert(SampleBuffer::new(decoded.capacity() as u64, *decoded.spec())),
+        };
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buffer.samples());
+        return Ok(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Every sample of `path`, checking the channel count stays whole.
+    fn decode_all(path: &Path) -> (AudioDecoder, Vec<f32>) {
+        let mut decoder = AudioDecoder::open(path).unwrap();
+        let channels = decoder.channels as usize;
+        let mut all = Vec::new();
+        while let Some(block) = decoder.next_block().unwrap() {
+            assert_eq!(block.len() % channels, 0);
+            all.extend_from_slice(block);
+        }
+        (decoder, all)
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0, |p, s| p.max(s.abs()))
+    }
+
+    #[test]
+    fn test_decode_wav_fixtures() {
+        let tmp = TempDir::new().unwrap();
+        // Each fixture is a 0.5-amplitude sine
+        let fixtures: [(&str, &[u8], u32, u16, usize); 4] = [
+            ("pcm16", include_bytes!("../../tests/fixtures/wav/pcm16_stereo.wav"), 44100, 2, 4410),
+            ("extensible", include_bytes!("../../tests/fixtures/wav/extensible_pcm24.wav"), 48000, 2, 4800),
+            ("float", include_bytes!("../../tests/fixtures/wav/float32_mono.wav"), 48000, 1, 12000),
+            ("rf64", include_bytes!("../../tests/fixtures/wav/rf64_pcm24.wav"), 48000, 2, 4800),
+        ];
+        for (name, bytes, rate, channels, frames) in fixtures {
+            let path = tmp.path().join(format!("{}.wav", name));
+            std::fs::write(&path, bytes).unwrap();
+
+            let (decoder, samples) = decode_all(&path);
+            assert_eq!((decoder.sample_rate, decoder.channels), (rate, channels), "{}", name);
+            assert_eq!(samples.len(), frames * channels as usize, "{}", name);
+            assert!((peak(&samples) - 0.5).abs() < 0.01, "{}: peak {}", name, peak(&samples));
+        }
+    }
+
+    #[test]
+    fn test_decode_aiff() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("mix.aif");
+        let mut comm = Vec::new();
+        comm.extend_from_slice(&1u16.to_be_bytes());
+        comm.extend_from_slice(&2u32.to_be_bytes());
+        comm.extend_from_slice(&16u16.to_be_bytes());
+        comm.extend_from_slice(&[0x40, 0x0E, 0xAC, 0x44, 0, 0, 0, 0, 0, 0]);
+        let mut body = b"AIFFCOMM".to_vec();
+        body.extend_from_slice(&(comm.len() as u32).to_be_bytes());
+        body.extend_from_slice(&comm);
+        // SSND: offset, block size, then big-endian samples
+        body.extend_from_slice(b"SSND");
+        body.extend_from_slice(&12u32.to_be_bytes());
+        body.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 0x40, 0x00, 0xC0, 0x00]);
+        let mut data = b"FORM".to_vec();
+        data.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        data.extend_from_slice(&body);
+        std::fs::write(&path, &data).unwrap();
+
+        let (decoder, samples) = decode_all(&path);
+        assert_eq!((decoder.sample_rate, decoder.channels), (44100, 1));
+        assert_eq!(samples, vec![0.5, -0.5]);
+    }
+
+    #[test]
+    fn test_decode_sample_widths_and_byte_orders() {
+        let layout = |bits: u16, float, big_endian, unsigned| PcmLayout {
+            data_offset: 0,
+            data_size: 0,
+            sample_rate: 44100,
+            channels: 1,
+            bits_per_sample: bits,
+            block_align: bits.div_ceil(8) as u64,
+            float,
+            big_endian,
+            unsigned,
+        };
+        assert_eq!(decode_sample(&[0x00, 0x40], &layout(16, false, false, false)), 0.5);
+        assert_eq!(decode_sample(&[0x40, 0x00], &layout(16, false, true, false)), 0.5);
+        assert_eq!(decode_sample(&[0x00, 0x00, 0xC0], &layout(24, false, false, false)), -0.5);
+        assert_eq!(decode_sample(&[0xC0], &layout(8, false, false, true)), 0.5);
+        assert_eq!(decode_sample(&[0x80], &layout(8, false, true, false)), -1.0);
+        assert_eq!(decode_sample(&0.25f32.to_be_bytes(), &layout(32, true, true, false)), 0.25);
+        assert_eq!(decode_sample(&(-0.25f64).to_le_bytes(), &layout(64, true, false, false)), -0.25);
+    }
+
+    #[test]
+    fn test_decode_mp3() {
+        let tmp = TempDir::new().unwrap();
+        let mp3 = tmp.path().join("mix.mp3");
+        let wav = tmp.path().join("mix.wav");
+        std::fs::write(&wav, include_bytes!("../../tests/fixtures/wav/pcm16_stereo.wav")).unwrap();
+        crate::mp3::bounce_to_mp3(&wav, &mp3, &Default::default(), None).unwrap();
+
+        let (decoder, samples) = decode_all(&mp3);
+        assert_eq!((decoder.sample_rate, decoder.channels), (44100, 2));
+        // The encoder pads the start and end with silence
+        assert!(samples.len() >= 4410 * 2);
+        assert!((peak(&samples) - 0.5).abs() < 0.05, "peak {}", peak(&samples));
+    }
+}
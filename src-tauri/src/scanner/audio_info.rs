@@ -0,0 +1,39 @@
+use std::path::Path;
+
+use super::{aiff_parser, flac_parser, mp3_parser, wav_parser};
+
+/// Extensions of the audio files picked up from a project's bounce folder.
+pub const BOUNCE_EXTENSIONS: &[&str] = &["wav", "aif", "aiff", "aifc", "flac", "mp3"];
+
+/// Format and length of an audio file, read from its headers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioInfo {
+    /// "WAV", "RF64", "BW64", "AIFF", "FLAC" or "MP3"
+    pub format: &'static str,
+    pub duration_seconds: f64,
+    pub sample_rate: u32,
+    /// None for lossy formats
+    pub bit_depth: Option<u16>,
+    pub channels: u16,
+    /// "pcm" or "float" for uncompressed audio, None otherwise
+    pub sample_format: Option<&'static str>,
+}
+
+pub fn is_bounce_file(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| BOUNCE_EXTENSIONS.contains(&e.as_str()))
+}
+
+/// Read the format and duration of an audio file, choosing the parser by
+/// extension.
+pub fn read_audio_info(path: &Path) -> Result<AudioInfo, String> {
+    let ext = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+    match ext.as_str() {
+        "wav" => wav_parser::parse_wav_info(path),
+        "aif" | "aiff" | "aifc" => aiff_parser::parse_aiff_info(path),
+        "flac" => flac_parser::parse_flac_info(path),
+        "mp3" => mp3_parser::parse_mp3_info(path),
+        _ => Err(format!("Unsupported audio format: .{}", ext)),
+    }
+}
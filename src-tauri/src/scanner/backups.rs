@@ -0,0 +1,183 @@
+// Live's Backup folder: every save copies the previous version of a set to
+// <project>/Backup/<set name> [YYYY-MM-DD HHMMSS].als. Those copies are
+// indexed as older versions of the set they were taken from, and can be
+// restored next to the current sets.
+
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+
+use super::walker;
+
+pub const BACKUP_FOLDER_NAME: &str = "Backup";
+
+/// A set backup found in a project's Backup folder.
+pub(crate) struct BackupRead {
+    pub path: String,
+    /// Path of the set it was taken from (which may no longer exist)
+    pub set_path: String,
+    pub backed_up_at: Option<String>,
+    pub modified_time: String,
+    pub file_size: Option<i64>,
+}
+
+/// Split a backup filename into the set's filename and the backup time
+/// ("YYYY-MM-DD HH:MM:SS"). Accepts the " 2", " 3"... suffix added when two
+/// backups share a second. None for files not named like a Live backup.
+pub(crate) fn parse_backup_name(file_name: &str) -> Option<(String, String)> {
+    let (name, ext) = file_name.rsplit_once('.')?;
+    if !ext.eq_ignore_ascii_case("als") {
+        return None;
+    }
+    let open = name.rfind(" [")?;
+    let (stem, rest) = name.split_at(open);
+    let (stamp, suffix) = rest[2..].split_once(']')?;
+    let numbered = suffix.strip_prefix(' ').is_some_and(|n| n.parse::<u32>().is_ok());
+    if !(suffix.is_empty() || numbered) {
+        return None;
+    }
+    let time = chrono::NaiveDateTime::parse_from_str(stamp, "%Y-%m-%d %H%M%S").ok()?;
+    Some((format!("{}.{}", stem, ext), time.format("%Y-%m-%d %H:%M:%S").to_string()))
+}
+
+/// The .als files in the project's Backup folder (empty without one).
+pub(crate) fn read_backups(project_dir: &Path) -> Vec<BackupRead> {
+    let mut backups = Vec::new();
+    let entries = match fs::read_dir(project_dir.join(BACKUP_FOLDER_NAME)) {
+        Ok(e) => e,
+        Err(_) => return backups,
+    };
+
+    for entry in entries.flatten() {
+        let file_path = entry.path();
+        let is_als = file_path.extension().is_some_and(|e| e.eq_ignore_ascii_case("als"));
+        if !is_als || !file_path.is_file() {
+            continue;
+        }
+        let file_name = file_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        // Backups not named by Live are still kept, with no known set or time
+        let (set_name, backed_up_at) = match parse_backup_name(&file_name) {
+            Some((set_name, time)) => (set_name, Some(time)),
+            None => (file_name, None),
+        };
+        backups.push(BackupRead {
+            path: file_path.to_string_lossy().to_string(),
+            set_path: project_dir.join(set_name).to_string_lossy().to_string(),
+            backed_up_at,
+            modified_time: walker::get_modified_time(&file_path),
+            file_size: fs::metadata(&file_path).ok().map(|m| m.len() as i64),
+        });
+    }
+    backups
+}
+
+/// Replace the project's recorded backups with `backups`, attaching each to
+/// its set when that set is still in the project.
+pub(crate) fn write_backups(conn: &Connection, project_id: i64, backups: &[BackupRead]) -> Result<(), String> {
+    for backup in backups {
+        conn.execute(
+            "INSERT INTO set_backups (project_id, set_id, backup_path, backed_up_at, modified_time, file_size) \
+             VALUES (?1, (SELECT id FROM ableton_sets WHERE project_id = ?1 AND set_path = ?2), ?3, ?4, ?5, ?6) \
+             ON CONFLICT(backup_path) DO UPDATE SET project_id = ?1, set_id = excluded.set_id, \
+             backed_up_at = ?4, modified_time = ?5, file_size = ?6",
+            params![project_id, backup.set_path, backup.path, backup.backed_up_at, backup.modified_time, backup.file_size],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    // Remove backups that no longer exist
+    let placeholders: Vec<String> = (0..backups.len()).map(|i| format!("?{}", i + 2)).collect();
+    let sql = format!(
+        "DELETE FROM set_backups WHERE project_id = ?1 AND backup_path NOT IN ({})",
+        placeholders.join(",")
+    );
+    let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(project_id)];
+    for backup in backups {
+        param_values.push(Box::new(backup.path.clone()));
+    }
+    let params_refs: Vec<&dyn rusqlite::types::ToSql> = param_values.iter().map(|p| p.as_ref()).collect();
+    conn.execute(&sql, params_refs.as_slice()).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Copy a backup into the project folder as a new set named
+/// "<set name> (restored YYYY-MM-DD HHMMSS).als". Never overwrites: a number
+/// is appended if that name is taken.
+pub fn restore_backup(backup_path: &Path, project_dir: &Path) -> Result<PathBuf, String> {
+    let file_name = backup_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let base = match parse_backup_name(&file_name) {
+        Some((set_name, time)) => {
+            let stem = set_name.rsplit_once('.').map_or(set_name.as_str(), |(stem, _)| stem).to_string();
+            format!("{} (restored {})", stem, time.replace(':', ""))
+        }
+        None => {
+            let stem = backup_path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            format!("{} (restored)", stem)
+        }
+    };
+
+    let mut source = File::open(backup_path)
+        .map_err(|e| format!("Failed to open backup {}: {}", backup_path.display(), e))?;
+    let mut n = 1;
+    loop {
+        let name = if n == 1 { format!("{}.als", base) } else { format!("{} {}.als", base, n) };
+        let target = project_dir.join(name);
+        // create_new fails rather than replacing a file that appeared meanwhile
+        match OpenOptions::new().write(true).create_new(true).open(&target) {
+            Ok(mut file) => {
+                if let Err(e) = io::copy(&mut source, &mut file) {
+                    drop(file);
+                    fs::remove_file(&target).ok();
+                    return Err(format!("Failed to restore {}: {}", backup_path.display(), e));
+                }
+                return Ok(target);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(format!("Failed to create {}: {}", target.display(), e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_backup_name() {
+        assert_eq!(
+            parse_backup_name("My Song [2024-03-05 142530].als"),
+            Some(("My Song.als".to_string(), "2024-03-05 14:25:30".to_string()))
+        );
+        assert_eq!(
+            parse_backup_name("Mix [v2] [2024-03-05 142530] 2.als"),
+            Some(("Mix [v2].als".to_string(), "2024-03-05 14:25:30".to_string()))
+        );
+        assert_eq!(parse_backup_name("My Song.als"), None);
+        assert_eq!(parse_backup_name("My Song [2024-13-05 142530].als"), None);
+        assert_eq!(parse_backup_name("My Song [2024-03-05 142530] copy.als"), None);
+        assert_eq!(parse_backup_name("My Song [2024-03-05 142530].wav"), None);
+    }
+
+    #[test]
+    fn test_restore_backup_never_overwrites() {
+        let tmp = TempDir::new().unwrap();
+        let project = tmp.path();
+        fs::create_dir(project.join(BACKUP_FOLDER_NAME)).unwrap();
+        let backup = project.join(BACKUP_FOLDER_NAME).join("Song [2024-03-05 142530].als");
+        fs::write(&backup, b"old version").unwrap();
+        fs::write(project.join("Song.als"), b"current").unwrap();
+
+        let first = restore_backup(&backup, project).unwrap();
+        assert_eq!(first, project.join("Song (restored 2024-03-05 142530).als"));
+        let second = restore_backup(&backup, project).unwrap();
+        assert_eq!(second, project.join("Song (restored 2024-03-05 142530) 2.als"));
+
+        assert_eq!(fs::read(&first).unwrap(), b"old version");
+        assert_eq!(fs::read(&second).unwrap(), b"old version");
+        assert_eq!(fs::read(project.join("Song.als")).unwrap(), b"current");
+        assert!(backup.exists());
+    }
+}
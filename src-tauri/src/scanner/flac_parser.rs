@@ -0,0 +1,110 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::audio_info::AudioInfo;
+
+/// Parse a FLAC file's STREAMINFO block for its format and duration.
+/// An ID3v2 tag in front of the stream is skipped.
+pub fn parse_flac_info(path: &Path) -> Result<AudioInfo, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open FLAC: {}", e))?;
+
+    let mut marker = [0u8; 4];
+    file.read_exact(&mut marker).map_err(|e| format!("Failed to read FLAC header: {}", e))?;
+    if &marker[0..3] == b"ID3" {
+        let mut id3 = [0u8; 6];
+        file.read_exact(&mut id3).map_err(|e| format!("Failed to read ID3 header: {}", e))?;
+        let footer = if id3[1] & 0x10 != 0 { 10 } else { 0 };
+        let size = syncsafe(&id3[2..6]) + footer;
+        file.seek(SeekFrom::Start(10 + size as u64)).map_err(|e| format!("Failed to skip ID3 tag: {}", e))?;
+        file.read_exact(&mut marker).map_err(|e| format!("Failed to read FLAC header: {}", e))?;
+    }
+    if &marker != b"fLaC" {
+        return Err("Not a valid FLAC file".to_string());
+    }
+
+    // STREAMINFO is always the first metadata block
+    let mut block_header = [0u8; 4];
+    file.read_exact(&mut block_header).map_err(|e| format!("Failed to read metadata block: {}", e))?;
+    let block_len = u32::from_be_bytes([0, block_header[1], block_header[2], block_header[3]]);
+    if block_header[0] & 0x7F != 0 || block_len < 34 {
+        return Err("FLAC file missing STREAMINFO".to_string());
+    }
+    let mut info = [0u8; 34];
+    file.read_exact(&mut info).map_err(|e| format!("Failed to read STREAMINFO: {}", e))?;
+
+    // 20 bits sample rate, 3 bits channels - 1, 5 bits bits per sample - 1,
+    // 36 bits total samples
+    let sample_rate = ((info[10] as u32) << 12) | ((info[11] as u32) << 4) | (info[12] as u32 >> 4);
+    let channels = ((info[12] >> 1) & 0x07) as u16 + 1;
+    let bit_depth = ((((info[12] & 0x01) << 4) | (info[13] >> 4)) + 1) as u16;
+    let total_samples = (((info[13] & 0x0F) as u64) << 32)
+        | u32::from_be_bytes([info[14], info[15], info[16], info[17]]) as u64;
+
+    if sample_rate == 0 {
+        return Err("Invalid FLAC sample rate".to_string());
+    }
+    if total_samples == 0 {
+        return Err("FLAC stream length unknown".to_string());
+    }
+
+    Ok(AudioInfo {
+        format: "FLAC",
+        duration_seconds: total_samples as f64 / sample_rate as f64,
+        sample_rate,
+        bit_depth: Some(bit_depth),
+        channels,
+        sample_format: None,
+    })
+}
+
+/// Decode a 28-bit ID3v2 "syncsafe" integer (7 bits per byte).
+pub(crate) fn syncsafe(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |n, b| (n << 7) | (*b & 0x7F) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn streaminfo(sample_rate: u32, channels: u8, bits: u8, total_samples: u64) -> Vec<u8> {
+        let mut info = vec![0u8; 34];
+        info[0..2].copy_from_slice(&4096u16.to_be_bytes());
+        info[2..4].copy_from_slice(&4096u16.to_be_bytes());
+        info[10] = (sample_rate >> 12) as u8;
+        info[11] = (sample_rate >> 4) as u8;
+        info[12] = ((sample_rate & 0x0F) as u8) << 4 | (channels - 1) << 1 | (bits - 1) >> 4;
+        info[13] = ((bits - 1) & 0x0F) << 4 | (total_samples >> 32) as u8;
+        info[14..18].copy_from_slice(&(total_samples as u32).to_be_bytes());
+        info
+    }
+
+    #[test]
+    fn test_parse_flac_streaminfo() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("mix.flac");
+
+        let mut data = Vec::new();
+        // A 20-byte ID3v2 tag in front, as some taggers write
+        data.extend_from_slice(b"ID3\x04\x00\x00\x00\x00\x00\x14");
+        data.extend_from_slice(&[0u8; 20]);
+        data.extend_from_slice(b"fLaC");
+        data.extend_from_slice(&[0x80, 0, 0, 34]);
+        data.extend_from_slice(&streaminfo(96000, 2, 24, 96000 * 90));
+        std::fs::write(&path, &data).unwrap();
+
+        let info = parse_flac_info(&path).unwrap();
+        assert_eq!(info, AudioInfo {
+            format: "FLAC",
+            duration_seconds: 90.0,
+            sample_rate: 96000,
+            bit_depth: Some(24),
+            channels: 2,
+            sample_format: None,
+        });
+
+        std::fs::write(&path, b"OggS").unwrap();
+        assert!(parse_flac_info(&path).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+// Ignore rules for project discovery: gitignore-style `.setcrateignore` files
+// in any folder of a library root, plus global patterns from settings. Keeps
+// templates, pack demo sets and trash folders out of the library.
+
+use std::path::Path;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use rusqlite::Connection;
+
+use crate::db::queries;
+
+/// Per-folder ignore file, using .gitignore syntax. Patterns apply to the
+/// folders below the one holding the file.
+pub const IGNORE_FILE_NAME: &str = ".setcrateignore";
+
+/// Setting holding global ignore patterns, as a JSON array of gitignore-style
+/// globs matched against folders in every library root.
+pub const IGNORE_PATTERNS_SETTING: &str = "scan_ignore_patterns";
+
+/// The configured global ignore patterns (empty if none are set).
+pub fn ignore_patterns(conn: &Connection) -> Result<Vec<String>, String> {
+    match queries::get_setting(conn, IGNORE_PATTERNS_SETTING)? {
+        Some(json) if !json.trim().is_empty() => serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid {} setting: {}", IGNORE_PATTERNS_SETTING, e)),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// The rules in effect while walking a library root: the global patterns and
+/// the ignore files of the folders from the root down to the current one.
+pub struct IgnoreRules {
+    global: Gitignore,
+    /// One entry per entered folder, outermost first (empty without a file)
+    files: Vec<Gitignore>,
+}
+
+impl IgnoreRules {
+    /// Rules for walking `root`, with the root's own ignore file loaded.
+    pub fn new(root: &Path, patterns: &[String]) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in patterns {
+            if let Err(e) = builder.add_line(None, pattern) {
+                log::warn!("Invalid scan ignore pattern '{}': {}", pattern, e);
+            }
+        }
+        let global = builder.build().unwrap_or_else(|e| {
+            log::warn!("Invalid scan ignore patterns: {}", e);
+            Gitignore::empty()
+        });
+
+        let mut rules = IgnoreRules { global, files: Vec::new() };
+        rules.enter(root);
+        rules
+    }
+
+    /// Rules for walking `dir` inside `root`, with the ignore files of every
+    /// folder on the way loaded. Fails with the skip reason if `dir` or a
+    /// folder above it is ignored.
+    pub fn for_dir(root: &Path, patterns: &[String], dir: &Path) -> Result<Self, String> {
+        let mut rules = Self::new(root, patterns);
+        if let Ok(relative) = dir.strip_prefix(root) {
+            let mut current = root.to_path_buf();
+            for component in relative.components() {
+                current.push(component);
+                if let Some(reason) = rules.skip_reason(&current) {
+                    return Err(format!("{} {}", current.display(), reason));
+                }
+                rules.enter(&current);
+            }
+        }
+        Ok(rules)
+    }
+
+    /// Load `dir`'s ignore file for walking its contents. Every call must be
+    /// paired with `leave`.
+    pub fn enter(&mut self, dir: &Path) {
+        let file = dir.join(IGNORE_FILE_NAME);
+        let ignore = if file.is_file() {
+            let (ignore, err) = Gitignore::new(&file);
+            if let Some(e) = err {
+                log::warn!("Problem reading {}: {}", file.display(), e);
+            }
+            ignore
+        } else {
+            Gitignore::empty()
+        };
+        self.files.push(ignore);
+    }
+
+    pub fn leave(&mut self) {
+        self.files.pop();
+    }
+
+    /// Why the folder at `path` should be skipped, if it should. The nearest
+    /// ignore file with a matching pattern decides (so a `!pattern` can
+    /// re-include a folder); the global patterns come last.
+    pub fn skip_reason(&self, path: &Path) -> Option<String> {
+        for file in self.files.iter().rev() {
+            match file.matched(path, true) {
+                Match::Ignore(glob) => {
+                    let from = glob.from().map_or_else(|| IGNORE_FILE_NAME.to_string(), |p| p.display().to_string());
+                    return Some(format!("matches '{}' in {}", glob.original(), from));
+                }
+                Match::Whitelist(_) => return None,
+                Match::None => {}
+            }
+        }
+        match self.global.matched(path, true) {
+            Match::Ignore(glob) => Some(format!("matches '{}' in the scan ignore settings", glob.original())),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_ignore_files_and_global_patterns() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        let techno = root.join("Techno");
+        fs::create_dir_all(techno.join("Keep Template")).unwrap();
+        fs::write(root.join(IGNORE_FILE_NAME), "*Template*\n").unwrap();
+        fs::write(techno.join(IGNORE_FILE_NAME), "!Keep Template\nDemo*\n").unwrap();
+
+        let mut rules = IgnoreRules::new(root, &["Trash/".to_string()]);
+        assert!(rules.skip_reason(&root.join("Techno")).is_none());
+        assert!(rules.skip_reason(&root.join("Trash")).unwrap().contains("scan ignore settings"));
+        assert!(rules.skip_reason(&root.join("Live Template")).unwrap().contains("*Template*"));
+
+        rules.enter(&techno);
+        assert!(rules.skip_reason(&techno.join("Keep Template")).is_none(), "nearer files win");
+        assert!(rules.skip_reason(&techno.join("Demo Set")).is_some());
+        assert!(rules.skip_reason(&techno.join("Old Template")).is_some());
+        assert!(rules.skip_reason(&techno.join("Trash")).is_some(), "global patterns match at any depth");
+        rules.leave();
+        assert!(rules.skip_reason(&root.join("Demo Set")).is_none(), "a folder's file only covers its contents");
+
+        assert!(IgnoreRules::for_dir(root, &[], &techno.join("Keep Template")).is_ok());
+        assert!(IgnoreRules::for_dir(root, &[], &root.join("Old Template").join("Song")).is_err());
+    }
+}
@@ -1,2 +1,15 @@
+pub mod aiff_parser;
+pub mod audio_decode;
+pub mod audio_info;
+pub mod backups;
+pub mod flac_parser;
+pub mod ignore_rules;
+pub mod moves;
+pub mod mp3_parser;
+pub mod peaks;
+pub mod riff_metadata;
+pub mod sample_location;
+pub mod snapshot;
 pub mod walker;
+pub mod watcher;
 pub mod wav_parser;
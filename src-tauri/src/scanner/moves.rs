@@ -0,0 +1,198 @@
+// Move detection: when a new project folder turns up while a library
+// project's folder has vanished, match the two by their .als files and
+// re-point the existing project row, so renaming or moving a folder keeps its
+// notes, tags, sessions, markers and sync ids.
+
+use std::collections::HashMap;
+use std::path::{Path, MAIN_SEPARATOR_STR};
+use rusqlite::{params, Connection};
+use crate::db::models::LibraryRoot;
+use crate::db::queries;
+use super::walker::{self, SetRead};
+
+/// A library project whose folder has moved.
+#[derive(Debug, PartialEq)]
+pub(crate) struct MovedProject {
+    pub id: i64,
+    pub old_path: String,
+}
+
+/// (project id, project path, set path, file size, content hash)
+type SetRow = (i64, String, String, Option<i64>, Option<String>);
+
+/// A set recorded for a library project.
+struct KnownSet {
+    filename: String,
+    file_size: Option<i64>,
+    content_hash: Option<String>,
+}
+
+impl KnownSet {
+    /// Same content, or (for sets recorded before hashing) same filename and size.
+    fn matches(&self, set: &SetRead) -> bool {
+        match (&self.content_hash, &set.content_hash) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.filename == set_filename(&set.path) && self.file_size.is_some() && self.file_size == set.file_size,
+        }
+    }
+}
+
+/// Find the library project that the folder at `new_path` was moved from.
+/// Candidates are non-archived projects whose folder no longer holds a set
+/// (on a drive that is still online) and that share at least one set with
+/// `sets`; the one sharing the most wins. Ties are left alone rather than
+/// guessed at.
+pub(crate) fn find_moved_project(conn: &Connection, new_path: &str, sets: &[SetRead]) -> Result<Option<MovedProject>, String> {
+    let sizes: Vec<i64> = sets.iter().filter_map(|s| s.file_size).collect();
+    let hashes: Vec<&String> = sets.iter().filter_map(|s| s.content_hash.as_ref()).collect();
+    if sizes.is_empty() && hashes.is_empty() {
+        return Ok(None);
+    }
+
+    let size_placeholders: Vec<String> = (0..sizes.len()).map(|i| format!("?{}", i + 2)).collect();
+    let hash_placeholders: Vec<String> = (0..hashes.len()).map(|i| format!("?{}", i + 2 + sizes.len())).collect();
+    let sql = format!(
+        "SELECT p.id, p.project_path, s.set_path, s.file_size, s.content_hash \
+         FROM ableton_sets s JOIN projects p ON p.id = s.project_id \
+         WHERE p.archived = 0 AND p.project_path != ?1 \
+         AND (s.file_size IN ({}) OR s.content_hash IN ({}))",
+        size_placeholders.join(","),
+        hash_placeholders.join(",")
+    );
+    let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+    param_values.push(Box::new(new_path.to_string()));
+    for size in &sizes {
+        param_values.push(Box::new(*size));
+    }
+    for hash in &hashes {
+        param_values.push(Box::new((*hash).clone()));
+    }
+    let params_refs: Vec<&dyn rusqlite::types::ToSql> = param_values.iter().map(|p| p.as_ref()).collect();
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows: Vec<SetRow> = stmt.query_map(params_refs.as_slice(), |row| {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+    }).map_err(|e| e.to_string())?
+      .filter_map(|r| r.ok())
+      .collect();
+
+    // Group the candidate sets by project, keeping only vanished folders
+    let roots = queries::get_library_roots(conn)?;
+    let mut candidates: HashMap<i64, (String, Vec<KnownSet>)> = HashMap::new();
+    for (id, project_path, set_path, file_size, content_hash) in rows {
+        let set = KnownSet { filename: set_filename(&set_path), file_size, content_hash };
+        if let Some((_, known)) = candidates.get_mut(&id) {
+            known.push(set);
+        } else if has_vanished(&roots, Path::new(&project_path)) {
+            candidates.insert(id, (project_path, vec![set]));
+        }
+    }
+
+    let mut scored: Vec<(usize, i64, String)> = candidates.into_iter()
+        .map(|(id, (old_path, known))| {
+            let score = sets.iter().filter(|s| known.iter().any(|k| k.matches(s))).count();
+            (score, id, old_path)
+        })
+        .filter(|(score, _, _)| *score > 0)
+        .collect();
+    scored.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+
+    match scored.as_slice() {
+        [] => Ok(None),
+        [(best, ..), (runner_up, _, _), ..] if best == runner_up => {
+            log::warn!("{} matches several moved projects; adding it as a new project", new_path);
+            Ok(None)
+        }
+        [(_, id, old_path), ..] => Ok(Some(MovedProject { id: *id, old_path: old_path.clone() })),
+    }
+}
+
+/// Re-point a moved project at its new folder. Paths of its sets, bounces,
+/// backups and current set follow the folder; the name and genre label
+/// follow too unless they were edited. The set is re-parsed so samples are
+/// located from the new folder.
+pub(crate) fn relocate_project(
+    conn: &Connection,
+    moved: &MovedProject,
+    new_path: &str,
+    new_name: &str,
+    new_genre_label: &str,
+) -> Result<(), String> {
+    let old_path = Path::new(&moved.old_path);
+    let old_name = old_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    // The label the old folder was given, if it was in a library root
+    let roots = queries::get_library_roots(conn)?;
+    let old_genre_label = queries::library_root_for(&roots, old_path)
+        .map(|root| walker::derive_genre_label(Path::new(&root.path), old_path, walker::genre_depth(root)));
+    let old_prefix = format!("{}{}", moved.old_path, MAIN_SEPARATOR_STR);
+    let new_prefix = format!("{}{}", new_path, MAIN_SEPARATOR_STR);
+
+    conn.execute(
+        "UPDATE projects SET project_path = ?1, missing = 0, als_parsed_at = NULL, \
+         name = CASE WHEN name = ?2 THEN ?3 ELSE name END, \
+         genre_label = CASE WHEN genre_label = ?7 THEN ?8 ELSE genre_label END, \
+         current_set_path = CASE WHEN substr(current_set_path, 1, length(?4)) = ?4 \
+             THEN ?5 || substr(current_set_path, length(?4) + 1) ELSE current_set_path END, \
+         updated_at = datetime('now') WHERE id = ?6",
+        params![new_path, old_name, new_name, old_prefix, new_prefix, moved.id, old_genre_label, new_genre_label],
+    ).map_err(|e| e.to_string())?;
+
+    for (table, column) in [("ableton_sets", "set_path"), ("bounces", "bounce_path"), ("set_backups", "backup_path")] {
+        conn.execute(
+            &format!(
+                "UPDATE {table} SET {column} = ?2 || substr({column}, length(?1) + 1) \
+                 WHERE project_id = ?3 AND substr({column}, 1, length(?1)) = ?1"
+            ),
+            params![old_prefix, new_prefix, moved.id],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    conn.execute("DELETE FROM scan_snapshots WHERE dir_path = ?1", params![moved.old_path])
+        .map_err(|e| e.to_string())?;
+
+    queries::rebuild_fts_tags(conn, moved.id)?;
+    queries::mark_dirty(conn, "projects", moved.id);
+
+    log::info!("Project {} moved: {} -> {}", moved.id, moved.old_path, new_path);
+    Ok(())
+}
+
+fn set_filename(path: &str) -> String {
+    Path::new(path).file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+/// Whether a project folder is gone, as opposed to sitting on an offline drive.
+fn has_vanished(roots: &[LibraryRoot], path: &Path) -> bool {
+    if queries::library_root_for(roots, path).is_some_and(|r| !r.online) {
+        return false;
+    }
+    !path.is_dir() || !walker::has_als_files(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(path: &str, size: i64, hash: Option<&str>) -> SetRead {
+        SetRead {
+            path: path.to_string(),
+            modified_time: "2026-01-01 00:00:00".to_string(),
+            file_size: Some(size),
+            content_hash: hash.map(|h| h.to_string()),
+        }
+    }
+
+    fn known(filename: &str, size: i64, hash: Option<&str>) -> KnownSet {
+        KnownSet { filename: filename.to_string(), file_size: Some(size), content_hash: hash.map(|h| h.to_string()) }
+    }
+
+    #[test]
+    fn test_known_set_matches() {
+        let moved = set("/music/Track A (final)/Track A.als", 100, Some("abc"));
+        assert!(known("Track A.als", 100, Some("abc")).matches(&moved));
+        assert!(known("Renamed.als", 200, Some("abc")).matches(&moved), "same content under another name");
+        assert!(!known("Track A.als", 100, Some("def")).matches(&moved), "hashes decide when both are known");
+        assert!(known("Track A.als", 100, None).matches(&moved), "unhashed sets fall back to filename and size");
+        assert!(!known("Track A.als", 101, None).matches(&moved));
+    }
+}
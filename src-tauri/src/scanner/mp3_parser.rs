@@ -0,0 +1,232 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::audio_info::AudioInfo;
+use super::flac_parser::syncsafe;
+
+/// Layer III bitrates in kbps by bitrate index, for MPEG-1 and MPEG-2/2.5.
+const BITRATES_V1: [u32; 15] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+const BITRATES_V2: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+/// How much audio is read: plenty to find the first frame and its Xing/VBRI
+/// header past any junk. Files no longer than this get their frames counted.
+const PREFIX_LEN: u64 = 1 << 20;
+
+/// An MPEG audio frame header (Layer III only).
+#[derive(Debug, Clone, Copy)]
+struct FrameHeader {
+    mpeg1: bool,
+    sample_rate: u32,
+    /// Bits per second
+    bitrate: u32,
+    channels: u16,
+    frame_len: usize,
+}
+
+impl FrameHeader {
+    fn parse(b: &[u8]) -> Option<Self> {
+        if b.len() < 4 || b[0] != 0xFF || b[1] & 0xE0 != 0xE0 {
+            return None;
+        }
+        let version = (b[1] >> 3) & 0x03; // 3 = MPEG-1, 2 = MPEG-2, 0 = MPEG-2.5
+        let layer = (b[1] >> 1) & 0x03; // 1 = Layer III
+        let bitrate_index = (b[2] >> 4) as usize;
+        let rate_index = ((b[2] >> 2) & 0x03) as usize;
+        if version == 1 || layer != 1 || bitrate_index == 0 || bitrate_index == 15 || rate_index == 3 {
+            return None;
+        }
+
+        let mpeg1 = version == 3;
+        let base_rate = [44100, 48000, 32000][rate_index];
+        let sample_rate = match version {
+            3 => base_rate,
+            2 => base_rate / 2,
+            _ => base_rate / 4,
+        };
+        let bitrate = if mpeg1 { BITRATES_V1[bitrate_index] } else { BITRATES_V2[bitrate_index] } * 1000;
+        let padding = ((b[2] >> 1) & 0x01) as u32;
+        let frame_len = if mpeg1 { 144 * bitrate / sample_rate } else { 72 * bitrate / sample_rate } + padding;
+        let channels = if b[3] >> 6 == 3 { 1 } else { 2 };
+
+        Some(FrameHeader { mpeg1, sample_rate, bitrate, channels, frame_len: frame_len as usize })
+    }
+
+    fn samples_per_frame(&self) -> u32 {
+        if self.mpeg1 { 1152 } else { 576 }
+    }
+
+    /// Offset of a Xing/Info tag from the frame start (after the side info).
+    fn xing_offset(&self) -> usize {
+        4 + match (self.mpeg1, self.channels) {
+            (true, 1) => 17,
+            (true, _) => 32,
+            (false, 1) => 9,
+            (false, _) => 17,
+        }
+    }
+}
+
+/// Parse an MP3 file for its format and duration. The frame count comes from
+/// a Xing/Info or VBRI header when the encoder wrote one; otherwise short
+/// files have every frame counted and longer ones are taken to be CBR.
+pub fn parse_mp3_info(path: &Path) -> Result<AudioInfo, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open MP3: {}", e))?;
+    let file_len = file.metadata().map_err(|e| format!("Failed to read MP3 size: {}", e))?.len();
+
+    // Skip an ID3v2 tag
+    let mut tag_len = 0;
+    let mut id3 = [0u8; 10];
+    if file.read_exact(&mut id3).is_ok() && &id3[0..3] == b"ID3" {
+        let footer = if id3[5] & 0x10 != 0 { 10 } else { 0 };
+        tag_len = 10 + syncsafe(&id3[6..10]) as u64 + footer;
+    }
+    file.seek(SeekFrom::Start(tag_len)).map_err(|e| format!("Failed to seek MP3: {}", e))?;
+    let mut data = Vec::new();
+    (&mut file).take(PREFIX_LEN).read_to_end(&mut data).map_err(|e| format!("Failed to read MP3: {}", e))?;
+    let whole_file = tag_len + data.len() as u64 >= file_len;
+
+    let mut pos = 0;
+
+    // Find the first frame whose successor is where its length says
+    let (start, first) = loop {
+        if pos + 4 > data.len() {
+            return Err("No MPEG audio frames found".to_string());
+        }
+        if let Some(header) = FrameHeader::parse(&data[pos..]) {
+            let next = pos + header.frame_len;
+            if next + 4 > data.len() || FrameHeader::parse(&data[next..]).is_some() {
+                break (pos, header);
+            }
+        }
+        pos += 1;
+    };
+
+    let frames = match vbr_frame_count(&data[start..], &first) {
+        Some(frames) => frames,
+        None if whole_file => count_frames(&data[start..]),
+        None => {
+            let id3v1 = if has_id3v1(&mut file, file_len) { 128 } else { 0 };
+            let audio_len = file_len.saturating_sub(tag_len + start as u64 + id3v1);
+            let frame_bits = first.bitrate as f64 * first.samples_per_frame() as f64 / first.sample_rate as f64;
+            (audio_len as f64 * 8.0 / frame_bits).round() as u64
+        }
+    };
+    if frames == 0 {
+        return Err("No MPEG audio frames found".to_string());
+    }
+
+    Ok(AudioInfo {
+        format: "MP3",
+        duration_seconds: frames as f64 * first.samples_per_frame() as f64 / first.sample_rate as f64,
+        sample_rate: first.sample_rate,
+        bit_depth: None,
+        channels: first.channels,
+        sample_format: None,
+    })
+}
+
+/// Frame count from a Xing/Info or VBRI header in the first frame. The
+/// header frame itself holds no audio.
+fn vbr_frame_count(frame: &[u8], header: &FrameHeader) -> Option<u64> {
+    let xing = header.xing_offset();
+    if let Some(tag) = frame.get(xing..xing + 12) {
+        if &tag[0..4] == b"Xing" || &tag[0..4] == b"Info" {
+            let flags = u32::from_be_bytes([tag[4], tag[5], tag[6], tag[7]]);
+            if flags & 0x01 != 0 {
+                return Some(u32::from_be_bytes([tag[8], tag[9], tag[10], tag[11]]) as u64);
+            }
+        }
+    }
+    // VBRI always sits 32 bytes after the frame header
+    if let Some(tag) = frame.get(36..54) {
+        if &tag[0..4] == b"VBRI" {
+            return Some(u32::from_be_bytes([tag[14], tag[15], tag[16], tag[17]]) as u64);
+        }
+    }
+    None
+}
+
+/// Whether the file ends in a 128-byte ID3v1 tag.
+fn has_id3v1(file: &mut File, file_len: u64) -> bool {
+    let mut tag = [0u8; 3];
+    file_len >= 128
+        && file.seek(SeekFrom::Start(file_len - 128)).is_ok()
+        && file.read_exact(&mut tag).is_ok()
+        && &tag == b"TAG"
+}
+
+/// Count frames by walking their headers, resyncing past any junk.
+fn count_frames(data: &[u8]) -> u64 {
+    let mut frames = 0;
+    let mut pos = 0;
+    while pos + 4 <= data.len() {
+        if &data[pos..pos + 3] == b"TAG" && data.len() - pos == 128 {
+            break; // ID3v1 tag at the end
+        }
+        match FrameHeader::parse(&data[pos..]) {
+            Some(header) if pos + header.frame_len <= data.len() => {
+                frames += 1;
+                pos += header.frame_len;
+            }
+            _ => pos += 1,
+        }
+    }
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// MPEG-1 Layer III, 128 kbps, 44.1 kHz, joint stereo: 417-byte frames
+    const HEADER: [u8; 4] = [0xFF, 0xFB, 0x90, 0x44];
+
+    fn frame(tag: Option<(&[u8; 4], u32)>) -> Vec<u8> {
+        let mut frame = vec![0u8; 417];
+        frame[0..4].copy_from_slice(&HEADER);
+        if let Some((id, frames)) = tag {
+            frame[36..40].copy_from_slice(id);
+            frame[40..44].copy_from_slice(&1u32.to_be_bytes());
+            frame[44..48].copy_from_slice(&frames.to_be_bytes());
+        }
+        frame
+    }
+
+    #[test]
+    fn test_parse_mp3_by_frame_scan_and_xing() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("mix.mp3");
+
+        let mut data = b"ID3\x03\x00\x00\x00\x00\x00\x05hello".to_vec();
+        for _ in 0..100 {
+            data.extend_from_slice(&frame(None));
+        }
+        std::fs::write(&path, &data).unwrap();
+        let info = parse_mp3_info(&path).unwrap();
+        assert_eq!((info.format, info.sample_rate, info.channels, info.bit_depth), ("MP3", 44100, 2, None));
+        assert!((info.duration_seconds - 100.0 * 1152.0 / 44100.0).abs() < 1e-9);
+
+        // A Xing header's frame count wins over the frames present
+        let mut data = frame(Some((b"Xing", 44100)));
+        data.extend_from_slice(&frame(None));
+        std::fs::write(&path, &data).unwrap();
+        assert!((parse_mp3_info(&path).unwrap().duration_seconds - 1152.0).abs() < 1e-9);
+
+        // Past the prefix, a stream without one is taken to be CBR
+        let mut data = Vec::new();
+        for _ in 0..3000 {
+            data.extend_from_slice(&frame(None));
+        }
+        data.extend_from_slice(b"TAG");
+        data.resize(data.len() + 125, 0);
+        std::fs::write(&path, &data).unwrap();
+        let seconds = parse_mp3_info(&path).unwrap().duration_seconds;
+        // Unpadded frames run a little short of the bitrate's average
+        assert!((seconds / (3000.0 * 1152.0 / 44100.0) - 1.0).abs() < 0.01, "{}", seconds);
+
+        std::fs::write(&path, b"not audio at all").unwrap();
+        assert!(parse_mp3_info(&path).is_err());
+    }
+}
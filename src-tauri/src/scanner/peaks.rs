@@ -0,0 +1,311 @@
+// Waveform peaks for bounces: min/max pairs at a few resolutions, so the
+// frontend can draw a waveform without loading the audio. Peaks are computed
+// when a bounce is scanned and cached in <app data>/peaks, one file per
+// bounce, named by a hash of its path and modification time.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::audio_decode::AudioDecoder;
+
+/// Frames summarised by each peak at the finest resolution.
+const BASE_FRAMES_PER_PEAK: u32 = 256;
+/// Each coarser level merges this many peaks of the level below.
+const LEVEL_FACTOR: usize = 4;
+/// 256, 1024, 4096 and 16384 frames per peak.
+const LEVEL_COUNT: usize = 4;
+
+const CACHE_MAGIC: &[u8; 4] = b"SCPK";
+const CACHE_VERSION: u8 = 1;
+const CACHE_EXTENSION: &str = "peaks";
+/// Cache files are written under this extension, then renamed.
+const PARTIAL_EXTENSION: &str = "partial";
+const PARTIAL_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WaveformPeaks {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub frames: u64,
+    /// Finest resolution first
+    pub levels: Vec<PeakLevel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeakLevel {
+    pub frames_per_peak: u32,
+    /// A min/max pair per peak, taken over all channels together (the
+    /// waveform is drawn mono), scaled to -127..=127
+    pub peaks: Vec<i8>,
+}
+
+pub fn cache_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("peaks")
+}
+
+/// Cache file for an audio file in its current state.
+fn cache_path(cache_dir: &Path, audio_path: &Path) -> Result<PathBuf, String> {
+    let mtime = fs::metadata(audio_path)
+        .and_then(|m| m.modified())
+        .map_err(|e| format!("Failed to read {}: {}", audio_path.display(), e))?;
+    let nanos = mtime.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+
+    let mut hasher = Sha256::new();
+    hasher.update(audio_path.to_string_lossy().as_bytes());
+    hasher.update(nanos.to_le_bytes());
+    Ok(cache_dir.join(format!("{:x}.{}", hasher.finalize(), CACHE_EXTENSION)))
+}
+
+/// Cache the peaks of an audio file as it is now.
+pub fn store_peaks(cache_dir: &Path, audio_path: &Path, peaks: &WaveformPeaks) -> Result<(), String> {
+    write_cache(&cache_path(cache_dir, audio_path)?, peaks)
+}
+
+/// The peaks of an audio file, from the cache or computed (and cached) now.
+pub fn load_peaks(cache_dir: &Path, audio_path: &Path) -> Result<WaveformPeaks, String> {
+    let path = cache_path(cache_dir, audio_path)?;
+    if let Ok(bytes) = fs::read(&path) {
+        match decode_peaks(&bytes) {
+            Ok(peaks) => return Ok(peaks),
+            Err(e) => log::warn!("Ignoring peak cache {}: {}", path.display(), e),
+        }
+    }
+    let peaks = compute_peaks(audio_path)?;
+    write_cache(&path, &peaks)?;
+    Ok(peaks)
+}
+
+/// Delete cache files that belong to none of `audio_paths` as they are now,
+/// and partial files left by writes that never finished. Files that can't be
+/// read (e.g. on an offline drive) lose their peaks, which are computed again
+/// when they next get scanned. Returns the number of files deleted.
+pub fn prune_cache(cache_dir: &Path, audio_paths: &[String]) -> usize {
+    let keep: HashSet<PathBuf> = audio_paths.iter()
+        .filter_map(|p| cache_path(cache_dir, Path::new(p)).ok())
+        .collect();
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return 0;
+    };
+
+    let mut deleted = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let stale = match path.extension() {
+            Some(e) if e == CACHE_EXTENSION => !keep.contains(&path),
+            // Old enough that no write can still be in progress
+            Some(e) if e == PARTIAL_EXTENSION => entry.metadata()
+                .and_then(|m| m.modified())
+                .is_ok_and(|t| t.elapsed().is_ok_and(|age| age > PARTIAL_MAX_AGE)),
+            _ => false,
+        };
+        if stale && fs::remove_file(&path).is_ok() {
+            deleted += 1;
+        }
+    }
+    deleted
+}
+
+fn write_cache(path: &Path, peaks: &WaveformPeaks) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create peak cache dir: {}", e))?;
+    }
+    // Written aside and renamed, so a reader never sees half a file
+    let partial = path.with_extension(PARTIAL_EXTENSION);
+    fs::write(&partial, encode_peaks(peaks)).map_err(|e| format!("Failed to write peak cache: {}", e))?;
+    fs::rename(&partial, path).map_err(|e| format!("Failed to write peak cache: {}", e))
+}
+
+/// Decode an audio file and summarise it at every resolution.
+pub fn compute_peaks(audio_path: &Path) -> Result<WaveformPeaks, String> {
+    let mut decoder = AudioDecoder::open(audio_path)?;
+    let mut builder = PeakBuilder::new(decoder.channels);
+    while let Some(block) = decoder.next_block()? {
+        builder.add(block);
+    }
+    Ok(builder.finish(decoder.sample_rate))
+}
+
+/// Summarises interleaved samples into peaks as they are decoded.
+pub struct PeakBuilder {
+    channels: u16,
+    base: Vec<(i8, i8)>,
+    frames: u64,
+    min: f32,
+    max: f32,
+    in_peak: u32,
+}
+
+impl PeakBuilder {
+    pub fn new(channels: u16) -> Self {
+        PeakBuilder {
+            channels: channels.max(1),
+            base: Vec::new(),
+            frames: 0,
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            in_peak: 0,
+        }
+    }
+
+    /// Add a block of interleaved samples.
+    pub fn add(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels as usize) {
+            for &sample in frame {
+                self.min = self.min.min(sample);
+                self.max = self.max.max(sample);
+            }
+            self.frames += 1;
+            self.in_peak += 1;
+            if self.in_peak == BASE_FRAMES_PER_PEAK {
+                self.base.push(quantize(self.min, self.max));
+                (self.min, self.max, self.in_peak) = (f32::INFINITY, f32::NEG_INFINITY, 0);
+            }
+        }
+    }
+
+    pub fn finish(mut self, sample_rate: u32) -> WaveformPeaks {
+        if self.in_peak > 0 {
+            self.base.push(quantize(self.min, self.max));
+        }
+
+        let mut levels = vec![self.base];
+        while levels.len() < LEVEL_COUNT {
+            let finer = &levels[levels.len() - 1];
+            let coarser = finer.chunks(LEVEL_FACTOR)
+                .map(|c| c.iter().fold((i8::MAX, i8::MIN), |(lo, hi), &(min, max)| (min.min(lo), max.max(hi))))
+                .collect();
+            levels.push(coarser);
+        }
+
+        WaveformPeaks {
+            sample_rate,
+            channels: self.channels,
+            frames: self.frames,
+            levels: levels.into_iter().enumerate().map(|(i, pairs)| PeakLevel {
+                frames_per_peak: BASE_FRAMES_PER_PEAK * LEVEL_FACTOR.pow(i as u32) as u32,
+                peaks: pairs.into_iter().flat_map(|(min, max)| [min, max]).collect(),
+            }).collect(),
+        }
+    }
+}
+
+/// Scale a min/max pair to 8 bits, rounding outwards so quiet passages still
+/// show.
+fn quantize(min: f32, max: f32) -> (i8, i8) {
+    let scale = |v: f32| v.clamp(-1.0, 1.0) * 127.0;
+    (scale(min).floor() as i8, scale(max).ceil() as i8)
+}
+
+/// Cache layout (little-endian): magic, version, sample rate (u32), channels
+/// (u16), frames (u64), level count (u8), then for each level its frames per
+/// peak (u32), pair count (u32) and the min/max pairs.
+fn encode_peaks(peaks: &WaveformPeaks) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(CACHE_MAGIC);
+    out.push(CACHE_VERSION);
+    out.extend_from_slice(&peaks.sample_rate.to_le_bytes());
+    out.extend_from_slice(&peaks.channels.to_le_bytes());
+    out.extend_from_slice(&peaks.frames.to_le_bytes());
+    out.push(peaks.levels.len() as u8);
+    for level in &peaks.levels {
+        out.extend_from_slice(&level.frames_per_peak.to_le_bytes());
+        out.extend_from_slice(&((level.peaks.len() / 2) as u32).to_le_bytes());
+        out.extend(level.peaks.iter().map(|&p| p as u8));
+    }
+    out
+}
+
+fn decode_peaks(bytes: &[u8]) -> Result<WaveformPeaks, String> {
+    let mut reader = Cursor::new(bytes);
+    let mut read = |len: usize| -> Result<Vec<u8>, String> {
+        let mut buf = vec![0u8; len];
+        reader.read_exact(&mut buf).map_err(|_| "Peak cache truncated".to_string())?;
+        Ok(buf)
+    };
+
+    if read(4)? != CACHE_MAGIC || read(1)?[0] != CACHE_VERSION {
+        return Err("Not a peak cache of this version".to_string());
+    }
+    let sample_rate = u32::from_le_bytes(read(4)?.try_into().unwrap_or_default());
+    let channels = u16::from_le_bytes(read(2)?.try_into().unwrap_or_default());
+    let frames = u64::from_le_bytes(read(8)?.try_into().unwrap_or_default());
+    let level_count = read(1)?[0];
+
+    let mut levels = Vec::new();
+    for _ in 0..level_count {
+        let frames_per_peak = u32::from_le_bytes(read(4)?.try_into().unwrap_or_default());
+        let pairs = u32::from_le_bytes(read(4)?.try_into().unwrap_or_default()) as usize;
+        if pairs > bytes.len() {
+            return Err("Peak cache truncated".to_string());
+        }
+        let peaks = read(pairs * 2)?.into_iter().map(|b| b as i8).collect();
+        levels.push(PeakLevel { frames_per_peak, peaks });
+    }
+
+    Ok(WaveformPeaks { sample_rate, channels, frames, levels })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_peaks_at_each_resolution() {
+        let tmp = TempDir::new().unwrap();
+        let wav = tmp.path().join("mix.wav");
+        // 0.1 s of a 0.5-amplitude sine at 44.1 kHz: 4410 frames
+        fs::write(&wav, include_bytes!("../../tests/fixtures/wav/pcm16_stereo.wav")).unwrap();
+
+        let peaks = compute_peaks(&wav).unwrap();
+        assert_eq!((peaks.sample_rate, peaks.channels, peaks.frames), (44100, 2, 4410));
+        let shape: Vec<(u32, usize)> = peaks.levels.iter().map(|l| (l.frames_per_peak, l.peaks.len() / 2)).collect();
+        assert_eq!(shape, vec![(256, 18), (1024, 5), (4096, 2), (16384, 1)]);
+        // The coarsest peak spans the whole file
+        assert_eq!(peaks.levels[3].peaks, vec![-64, 64]);
+
+        assert_eq!(decode_peaks(&encode_peaks(&peaks)).unwrap(), peaks);
+        assert!(decode_peaks(&encode_peaks(&peaks)[..40]).is_err());
+    }
+
+    #[test]
+    fn test_cache_follows_path_and_mtime() {
+        let tmp = TempDir::new().unwrap();
+        let cache = tmp.path().join("peaks");
+        let wav = tmp.path().join("mix.wav");
+        fs::write(&wav, include_bytes!("../../tests/fixtures/wav/pcm16_stereo.wav")).unwrap();
+
+        assert!(!cache_path(&cache, &wav).unwrap().exists());
+        store_peaks(&cache, &wav, &compute_peaks(&wav).unwrap()).unwrap();
+        assert!(cache_path(&cache, &wav).unwrap().exists());
+        let cached = cache_path(&cache, &wav).unwrap();
+        assert_eq!(load_peaks(&cache, &wav).unwrap(), compute_peaks(&wav).unwrap());
+
+        // A re-bounce gets its own cache file; the old one is pruned
+        let file = fs::File::options().write(true).open(&wav).unwrap();
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(1_700_000_000)).unwrap();
+        assert_ne!(cache_path(&cache, &wav).unwrap(), cached);
+        load_peaks(&cache, &wav).unwrap();
+
+        // Partial files from an interrupted write go once they are old
+        let abandoned = cache.join("abandoned.partial");
+        let in_flight = cache.join("in_flight.partial");
+        fs::write(&abandoned, b"").unwrap();
+        fs::write(&in_flight, b"").unwrap();
+        let file = fs::File::options().write(true).open(&abandoned).unwrap();
+        file.set_modified(SystemTime::now() - PARTIAL_MAX_AGE * 2).unwrap();
+
+        assert_eq!(prune_cache(&cache, &[wav.to_string_lossy().to_string()]), 2);
+        assert!(!cached.exists());
+        assert!(!abandoned.exists());
+        assert!(in_flight.exists());
+        assert!(cache_path(&cache, &wav).unwrap().exists());
+    }
+}
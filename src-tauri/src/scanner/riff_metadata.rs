@@ -0,0 +1,608 @@
+// Metadata embedded in WAV bounces: the Broadcast Wave bext chunk (EBU Tech
+// 3285), iXML and LIST/INFO tags. Reading walks the chunk list; writing makes
+// a copy with those chunks replaced and every other chunk — the audio data
+// above all — copied byte for byte.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+
+use super::wav_parser::{data_chunk_size, SIZE_PLACEHOLDER};
+use crate::db::models::{BextInfo, BounceMetadata, BounceTags};
+
+/// Length of the fixed part of a bext chunk, before the coding history.
+const BEXT_FIXED_LEN: usize = 602;
+/// Metadata chunks larger than this have a corrupt size.
+const MAX_METADATA_CHUNK: u64 = 1 << 20;
+/// Written to the Originator field of a bext chunk SetCrate creates.
+const ORIGINATOR: &str = "SetCrate";
+/// The document a new iXML chunk starts from.
+const IXML_TEMPLATE: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<BWFXML><IXML_VERSION>2.10</IXML_VERSION></BWFXML>";
+
+/// A chunk of a RIFF/RF64/BW64 WAVE file.
+struct RiffChunk {
+    id: [u8; 4],
+    /// The size as its header states it, which may be a placeholder
+    size_field: u32,
+    /// Where the payload starts
+    offset: u64,
+    /// The payload size, resolved and clamped to the file
+    size: u64,
+    /// The list type of a LIST chunk ("INFO", "adtl")
+    list_type: Option<[u8; 4]>,
+}
+
+impl RiffChunk {
+    /// Whether the chunk holds metadata that a tagged copy replaces.
+    fn is_replaced(&self) -> bool {
+        &self.id == b"bext" || &self.id == b"iXML" || (&self.id == b"LIST" && self.list_type == Some(*b"INFO"))
+    }
+}
+
+/// The "RIFF", "RF64" or "BW64" magic and the chunks of a WAVE file.
+struct RiffFile {
+    magic: [u8; 4],
+    chunks: Vec<RiffChunk>,
+}
+
+/// Read the bext, iXML and LIST/INFO metadata of a WAV file. The result is
+/// empty (the default) when the file has none.
+pub fn read_wav_metadata(path: &Path) -> Result<BounceMetadata, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open WAV: {}", e))?;
+    let file_len = file.metadata().map_err(|e| format!("Failed to read WAV size: {}", e))?.len();
+    let riff = read_chunk_list(&mut file, file_len)?;
+
+    let mut metadata = BounceMetadata::default();
+    for chunk in &riff.chunks {
+        match &chunk.id {
+            b"bext" => metadata.bext = Some(parse_bext(&read_payload(&mut file, chunk)?)),
+            b"iXML" => metadata.ixml = parse_ixml(&text_field(&read_payload(&mut file, chunk)?)),
+            b"LIST" if chunk.list_type == Some(*b"INFO") => {
+                metadata.info.extend(parse_info(&read_payload(&mut file, chunk)?[4..]));
+            }
+            _ => {}
+        }
+    }
+    Ok(metadata)
+}
+
+/// Copy a WAV file to `dest` with `tags` written into its bext, iXML and
+/// LIST/INFO chunks. Fields the tags don't cover keep their existing values;
+/// all other chunks are copied unchanged.
+pub fn write_tagged_copy(source: &Path, dest: &Path, tags: &BounceTags) -> Result<(), String> {
+    let mut file = File::open(source).map_err(|e| format!("Failed to open WAV: {}", e))?;
+    let file_len = file.metadata().map_err(|e| format!("Failed to read WAV size: {}", e))?.len();
+    let riff = read_chunk_list(&mut file, file_len)?;
+    if !riff.chunks.iter().any(|c| &c.id == b"data") {
+        return Err("WAV file has no data chunk".to_string());
+    }
+
+    let mut bext = None;
+    let mut ixml = None;
+    let mut info = BTreeMap::new();
+    for chunk in riff.chunks.iter().filter(|c| c.is_replaced()) {
+        let payload = read_payload(&mut file, chunk)?;
+        match &chunk.id {
+            b"bext" => bext = Some(payload),
+            b"iXML" => ixml = Some(text_field(&payload)),
+            _ => info.extend(parse_info(&payload[4..])),
+        }
+    }
+    let metadata_chunks = [
+        (*b"bext", build_bext(bext.as_deref(), tags)),
+        (*b"iXML", build_ixml(ixml.as_deref(), tags)?),
+        (*b"LIST", build_info(info, tags)),
+    ];
+
+    let partial = dest.with_extension("partial");
+    if let Err(e) = write_copy(&mut file, &riff, &metadata_chunks, &partial) {
+        fs::remove_file(&partial).ok();
+        return Err(e);
+    }
+    fs::rename(&partial, dest).map_err(|e| format!("Failed to write tagged copy: {}", e))
+}
+
+/// Walk the chunk list of WAVE data of `file_len` bytes.
+fn read_chunk_list<R: Read + Seek>(reader: &mut R, file_len: u64) -> Result<RiffFile, String> {
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header).map_err(|e| format!("Failed to read RIFF header: {}", e))?;
+    let magic = [header[0], header[1], header[2], header[3]];
+    if !matches!(&magic, b"RIFF" | b"RF64" | b"BW64") || &header[8..12] != b"WAVE" {
+        return Err("Not a valid WAV file".to_string());
+    }
+
+    let mut chunks = Vec::new();
+    let mut ds64_data_size = None;
+    let mut position = 12u64;
+    while position + 8 <= file_len {
+        reader.seek(SeekFrom::Start(position)).map_err(|e| format!("Failed to seek: {}", e))?;
+        let mut chunk_header = [0u8; 8];
+        reader.read_exact(&mut chunk_header).map_err(|e| format!("Failed to read chunk header: {}", e))?;
+        let id = [chunk_header[0], chunk_header[1], chunk_header[2], chunk_header[3]];
+        let size_field = u32::from_le_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]);
+        let offset = position + 8;
+        let available = file_len - offset;
+        let size = if &id == b"data" {
+            data_chunk_size(reader, size_field, ds64_data_size, available)?
+        } else {
+            (size_field as u64).min(available)
+        };
+
+        let mut chunk = RiffChunk { id, size_field, offset, size, list_type: None };
+        if &id == b"ds64" {
+            // 64-bit RIFF size, then the data size
+            let ds64 = read_payload(reader, &chunk)?;
+            if ds64.len() >= 16 {
+                ds64_data_size = Some(u64::from_le_bytes(ds64[8..16].try_into().unwrap_or_default()));
+            }
+        } else if &id == b"LIST" && size >= 4 {
+            let mut list_type = [0u8; 4];
+            reader.read_exact(&mut list_type).map_err(|e| format!("Failed to read LIST chunk: {}", e))?;
+            chunk.list_type = Some(list_type);
+        }
+        chunks.push(chunk);
+        position = offset + size + size % 2;
+    }
+    Ok(RiffFile { magic, chunks })
+}
+
+fn read_payload<R: Read + Seek>(reader: &mut R, chunk: &RiffChunk) -> Result<Vec<u8>, String> {
+    let name = String::from_utf8_lossy(&chunk.id).trim().to_string();
+    if chunk.size > MAX_METADATA_CHUNK {
+        return Err(format!("WAV {} chunk too large", name));
+    }
+    reader.seek(SeekFrom::Start(chunk.offset)).map_err(|e| format!("Failed to seek: {}", e))?;
+    let mut payload = vec![0u8; chunk.size as usize];
+    reader.read_exact(&mut payload).map_err(|e| format!("Failed to read {} chunk: {}", name, e))?;
+    Ok(payload)
+}
+
+/// Write the header, then every chunk of `riff` with the replaced metadata
+/// chunks left out and `metadata_chunks` inserted before the data chunk.
+fn write_copy(source: &mut File, riff: &RiffFile, metadata_chunks: &[([u8; 4], Vec<u8>)], path: &Path) -> Result<(), String> {
+    let write_error = |e: io::Error| format!("Failed to write tagged copy: {}", e);
+    let mut out = BufWriter::new(File::create(path).map_err(write_error)?);
+    out.write_all(&riff.magic).map_err(write_error)?;
+    out.write_all(&SIZE_PLACEHOLDER.to_le_bytes()).map_err(write_error)?; // Patched below
+    out.write_all(b"WAVE").map_err(write_error)?;
+    let mut written = 12u64;
+    let mut ds64_offset = None;
+
+    for chunk in riff.chunks.iter().filter(|c| !c.is_replaced()) {
+        if &chunk.id == b"data" {
+            for (id, payload) in metadata_chunks {
+                out.write_all(id).map_err(write_error)?;
+                out.write_all(&(payload.len() as u32).to_le_bytes()).map_err(write_error)?;
+                out.write_all(payload).map_err(write_error)?;
+                if payload.len() % 2 == 1 {
+                    out.write_all(&[0]).map_err(write_error)?;
+                }
+                written += 8 + payload.len() as u64 + payload.len() as u64 % 2;
+            }
+        }
+
+        let size_field = if &chunk.id == b"data" && &riff.magic == b"RIFF" {
+            // State the size found, for headers that were never finalised
+            u32::try_from(chunk.size).unwrap_or(SIZE_PLACEHOLDER)
+        } else {
+            chunk.size_field
+        };
+        if &chunk.id == b"ds64" {
+            ds64_offset = Some(written + 8);
+        }
+        out.write_all(&chunk.id).map_err(write_error)?;
+        out.write_all(&size_field.to_le_bytes()).map_err(write_error)?;
+        source.seek(SeekFrom::Start(chunk.offset)).map_err(|e| format!("Failed to seek: {}", e))?;
+        let copied = io::copy(&mut (&mut *source).take(chunk.size), &mut out).map_err(write_error)?;
+        if copied != chunk.size {
+            return Err("WAV file ended early".to_string());
+        }
+        if chunk.size % 2 == 1 {
+            out.write_all(&[0]).map_err(write_error)?;
+        }
+        written += 8 + chunk.size + chunk.size % 2;
+    }
+
+    // RF64/BW64 keep the placeholder and carry the real RIFF size in ds64
+    let mut file = out.into_inner().map_err(|e| write_error(e.into_error()))?;
+    let riff_size = written - 8;
+    if &riff.magic == b"RIFF" {
+        file.seek(SeekFrom::Start(4)).map_err(write_error)?;
+        file.write_all(&u32::try_from(riff_size).unwrap_or(SIZE_PLACEHOLDER).to_le_bytes()).map_err(write_error)?;
+    } else if let Some(offset) = ds64_offset {
+        file.seek(SeekFrom::Start(offset)).map_err(write_error)?;
+        file.write_all(&riff_size.to_le_bytes()).map_err(write_error)?;
+    }
+    Ok(())
+}
+
+/// Text up to the first NUL, trimmed.
+fn text_field(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
+/// Write `text` into a fixed-size NUL-padded field, cut at a character
+/// boundary if it doesn't fit.
+fn put_text(field: &mut [u8], text: &str) {
+    field.fill(0);
+    let mut len = text.len().min(field.len());
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    field[..len].copy_from_slice(&text.as_bytes()[..len]);
+}
+
+fn parse_bext(payload: &[u8]) -> BextInfo {
+    let field = |start: usize, len: usize| text_field(payload.get(start..start + len).unwrap_or_default());
+    BextInfo {
+        description: field(0, 256),
+        originator: field(256, 32),
+        originator_reference: field(288, 32),
+        origination_date: field(320, 10),
+        origination_time: field(330, 8),
+        time_reference: payload
+            .get(338..346)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap_or_default()))
+            .unwrap_or(0),
+        coding_history: text_field(payload.get(BEXT_FIXED_LEN..).unwrap_or_default()),
+    }
+}
+
+/// The tagged description, over an existing bext chunk if there is one so
+/// its time reference, UMID, loudness and coding history carry over.
+fn build_bext(existing: Option<&[u8]>, tags: &BounceTags) -> Vec<u8> {
+    let mut bext = match existing {
+        Some(raw) if raw.len() >= BEXT_FIXED_LEN => raw.to_vec(),
+        _ => {
+            let mut bext = vec![0u8; BEXT_FIXED_LEN];
+            put_text(&mut bext[256..288], ORIGINATOR);
+            bext[346..348].copy_from_slice(&1u16.to_le_bytes()); // Version
+            bext
+        }
+    };
+    let description = match &tags.comment {
+        Some(comment) => format!("{}: {}", tags.title, comment),
+        None => tags.title.clone(),
+    };
+    put_text(&mut bext[..256], &description);
+    bext
+}
+
+/// The tags of a LIST/INFO payload (after the "INFO" list type).
+fn parse_info(payload: &[u8]) -> BTreeMap<String, String> {
+    let mut info = BTreeMap::new();
+    let mut pos = 0;
+    while pos + 8 <= payload.len() {
+        let id = &payload[pos..pos + 4];
+        let size = u32::from_le_bytes([payload[pos + 4], payload[pos + 5], payload[pos + 6], payload[pos + 7]]) as usize;
+        let value = payload.get(pos + 8..pos + 8 + size).unwrap_or(&payload[pos + 8..]);
+        if id.iter().all(|b| b.is_ascii_alphanumeric()) {
+            let value = text_field(value);
+            if !value.is_empty() {
+                info.insert(String::from_utf8_lossy(id).to_string(), value);
+            }
+        }
+        pos += 8 + size + size % 2;
+    }
+    info
+}
+
+/// A LIST/INFO payload with the title, artist and comment set.
+fn build_info(mut info: BTreeMap<String, String>, tags: &BounceTags) -> Vec<u8> {
+    info.insert("INAM".to_string(), tags.title.clone());
+    for (id, value) in [("IART", &tags.artist), ("ICMT", &tags.comment)] {
+        if let Some(value) = value {
+            info.insert(id.to_string(), value.clone());
+        }
+    }
+
+    let mut payload = b"INFO".to_vec();
+    for (id, value) in info.iter().filter(|(id, _)| id.len() == 4) {
+        // Values are NUL-terminated
+        let size = value.len() + 1;
+        payload.extend_from_slice(id.as_bytes());
+        payload.extend_from_slice(&(size as u32).to_le_bytes());
+        payload.extend_from_slice(value.as_bytes());
+        payload.push(0);
+        if size % 2 == 1 {
+            payload.push(0);
+        }
+    }
+    payload
+}
+
+/// Leaf elements of an iXML document by path below the root. Repeated
+/// elements (track lists) keep their first value.
+fn parse_ixml(doc: &str) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    let Ok(doc) = roxmltree::Document::parse(doc) else {
+        return fields;
+    };
+    let root = doc.root_element();
+    for node in root.descendants().filter(|n| n.is_element() && *n != root) {
+        if node.children().any(|c| c.is_element()) {
+            continue;
+        }
+        let Some(text) = node.text().map(str::trim).filter(|t| !t.is_empty()) else {
+            continue;
+        };
+        let mut path: Vec<&str> = node
+            .ancestors()
+            .take_while(|a| *a != root)
+            .map(|a| a.tag_name().name())
+            .collect();
+        path.reverse();
+        fields.entry(path.join("/")).or_insert_with(|| text.to_string());
+    }
+    fields
+}
+
+/// An iXML document with the tags written into PROJECT and NOTE and, per
+/// the ASWG extension (iXML 2.10), artist, tempo and inKey. An existing
+/// document keeps everything else; one that can't be parsed is replaced.
+fn build_ixml(existing: Option<&str>, tags: &BounceTags) -> Result<Vec<u8>, String> {
+    let top: Vec<(&str, String)> = [("PROJECT", Some(tags.title.clone())), ("NOTE", tags.comment.clone())]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|v| (name, v)))
+        .collect();
+    let aswg: Vec<(&str, String)> = [
+        ("artist", tags.artist.clone()),
+        ("tempo", tags.bpm.map(|bpm| ((bpm * 100.0).round() / 100.0).to_string())),
+        ("inKey", tags.key.clone()),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| value.map(|v| (name, v)))
+    .collect();
+
+    if let Some(doc) = existing {
+        match rewrite_ixml(doc, &top, &aswg) {
+            Ok(ixml) => return Ok(ixml),
+            Err(e) => log::debug!("Replacing unreadable iXML: {}", e),
+        }
+    }
+    rewrite_ixml(IXML_TEMPLATE, &top, &aswg)
+}
+
+/// Copy an iXML document, dropping the `top` elements of BWFXML and the
+/// `aswg` elements of BWFXML/ASWG, and writing the new values at the end of
+/// their parents.
+fn rewrite_ixml(doc: &str, top: &[(&str, String)], aswg: &[(&str, String)]) -> Result<Vec<u8>, String> {
+    let xml_error = |e: &dyn std::fmt::Display| format!("Invalid iXML: {}", e);
+    let replaced = |path: &[Vec<u8>]| match path {
+        [root, name] if root == b"BWFXML" => top.iter().any(|(n, _)| n.as_bytes() == name.as_slice()),
+        [root, group, name] if root == b"BWFXML" && group == b"ASWG" => {
+            aswg.iter().any(|(n, _)| n.as_bytes() == name.as_slice())
+        }
+        _ => false,
+    };
+    let is_aswg = |path: &[Vec<u8>]| matches!(path, [root, group] if root == b"BWFXML" && group == b"ASWG");
+
+    let mut reader = Reader::from_str(doc);
+    let mut writer = Writer::new(Vec::new());
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    // Depth of the element being dropped
+    let mut skipping: Option<usize> = None;
+    let mut found_root = false;
+    let mut found_aswg = false;
+
+    loop {
+        let event = reader.read_event().map_err(|e| xml_error(&e))?;
+        match &event {
+            Event::Eof => break,
+            Event::Start(e) => {
+                path.push(e.name().as_ref().to_vec());
+                if path.len() == 1 {
+                    if path[0] != b"BWFXML" {
+                        return Err("iXML root isn't BWFXML".to_string());
+                    }
+                    found_root = true;
+                }
+                found_aswg |= is_aswg(&path);
+                if skipping.is_none() && replaced(&path) {
+                    skipping = Some(path.len());
+                }
+                if skipping.is_some() {
+                    continue;
+                }
+            }
+            Event::End(_) => {
+                let depth = path.len();
+                if let Some(skip_depth) = skipping {
+                    if skip_depth == depth {
+                        skipping = None;
+                    }
+                    path.pop();
+                    continue;
+                }
+                if is_aswg(&path) {
+                    write_elements(&mut writer, aswg)?;
+                }
+                if depth == 1 {
+                    write_elements(&mut writer, top)?;
+                    if !found_aswg && !aswg.is_empty() {
+                        write_group(&mut writer, "ASWG", aswg)?;
+                    }
+                }
+                path.pop();
+            }
+            Event::Empty(e) => {
+                if skipping.is_some() {
+                    continue;
+                }
+                let mut element_path = path.clone();
+                element_path.push(e.name().as_ref().to_vec());
+                if replaced(&element_path) {
+                    continue;
+                }
+                if is_aswg(&element_path) {
+                    found_aswg = true;
+                    write_group(&mut writer, "ASWG", aswg)?;
+                    continue;
+                }
+            }
+            _ => {
+                if skipping.is_some() {
+                    continue;
+                }
+            }
+        }
+        writer.write_event(event).map_err(|e| xml_error(&e))?;
+    }
+
+    if !found_root {
+        return Err("iXML has no BWFXML element".to_string());
+    }
+    Ok(writer.into_inner())
+}
+
+fn write_elements(writer: &mut Writer<Vec<u8>>, elements: &[(&str, String)]) -> Result<(), String> {
+    for (name, value) in elements {
+        writer.write_event(Event::Start(BytesStart::new(*name))).map_err(|e| e.to_string())?;
+        writer.write_event(Event::Text(BytesText::new(value))).map_err(|e| e.to_string())?;
+        writer.write_event(Event::End(BytesEnd::new(*name))).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn write_group(writer: &mut Writer<Vec<u8>>, group: &str, elements: &[(&str, String)]) -> Result<(), String> {
+    writer.write_event(Event::Start(BytesStart::new(group))).map_err(|e| e.to_string())?;
+    write_elements(writer, elements)?;
+    writer.write_event(Event::End(BytesEnd::new(group))).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::wav_parser::{parse_wav_info, wav_pcm_layout};
+    use tempfile::TempDir;
+
+    fn tags() -> BounceTags {
+        BounceTags {
+            title: "Night Drive".to_string(),
+            artist: Some("Kay".to_string()),
+            bpm: Some(124.0),
+            key: Some("F# Minor".to_string()),
+            comment: Some("Mix 3 — louder kick".to_string()),
+            cover_path: None,
+        }
+    }
+
+    /// The payload of the data chunk.
+    fn audio_data(path: &Path) -> Vec<u8> {
+        let layout = wav_pcm_layout(path).unwrap();
+        let bytes = fs::read(path).unwrap();
+        bytes[layout.data_offset as usize..(layout.data_offset + layout.data_size) as usize].to_vec()
+    }
+
+    fn chunk(id: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(payload);
+        if payload.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    /// The PCM fixture with an iXML chunk from a field recorder, and INFO
+    /// tags and a cue chunk after the data.
+    fn tagged_fixture(dir: &TempDir) -> std::path::PathBuf {
+        let mut bytes = include_bytes!("../../tests/fixtures/wav/pcm16_stereo.wav").to_vec();
+        let ixml = b"<?xml version=\"1.0\"?><BWFXML><IXML_VERSION>1.5</IXML_VERSION><PROJECT>old</PROJECT>\
+            <SCENE>12A</SCENE><TRACK_LIST><TRACK><NAME>Mix L</NAME></TRACK></TRACK_LIST></BWFXML>";
+        let data_at = bytes.windows(4).position(|w| w == b"data").unwrap();
+        bytes.splice(data_at..data_at, chunk(b"iXML", ixml));
+        let mut info = b"INFO".to_vec();
+        info.extend(chunk(b"ISFT", b"Ableton Live 12\0"));
+        info.extend(chunk(b"INAM", b"Old Title\0"));
+        bytes.extend(chunk(b"LIST", &info));
+        bytes.extend(chunk(b"cue ", &[1, 0, 0, 0, 7, 7, 7]));
+        let riff_size = (bytes.len() - 8) as u32;
+        bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+        let path = dir.path().join("source.wav");
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_tagged_copy_keeps_audio_and_other_chunks() {
+        let dir = TempDir::new().unwrap();
+        let source = tagged_fixture(&dir);
+        let original = read_wav_metadata(&source).unwrap();
+        assert_eq!(original.info.get("INAM").map(String::as_str), Some("Old Title"));
+        assert_eq!(original.ixml.get("TRACK_LIST/TRACK/NAME").map(String::as_str), Some("Mix L"));
+        assert_eq!(original.bext, None);
+
+        let copy = dir.path().join("tagged.wav");
+        write_tagged_copy(&source, &copy, &tags()).unwrap();
+        assert_eq!(audio_data(&copy), audio_data(&source));
+        assert_eq!(parse_wav_info(&copy).unwrap(), parse_wav_info(&source).unwrap());
+        let copy_bytes = fs::read(&copy).unwrap();
+        assert_eq!(u32::from_le_bytes(copy_bytes[4..8].try_into().unwrap()) as usize, copy_bytes.len() - 8);
+        let cue = chunk(b"cue ", &[1, 0, 0, 0, 7, 7, 7]);
+        assert!(copy_bytes.windows(cue.len()).any(|w| w == cue.as_slice()));
+
+        let tagged = read_wav_metadata(&copy).unwrap();
+        let bext = tagged.bext.unwrap();
+        assert_eq!(bext.description, "Night Drive: Mix 3 — louder kick");
+        assert_eq!(bext.originator, "SetCrate");
+        assert_eq!(tagged.info.get("INAM").map(String::as_str), Some("Night Drive"));
+        assert_eq!(tagged.info.get("IART").map(String::as_str), Some("Kay"));
+        assert_eq!(tagged.info.get("ICMT").map(String::as_str), Some("Mix 3 — louder kick"));
+        assert_eq!(tagged.info.get("ISFT").map(String::as_str), Some("Ableton Live 12"));
+        assert_eq!(tagged.ixml.get("PROJECT").map(String::as_str), Some("Night Drive"));
+        assert_eq!(tagged.ixml.get("NOTE").map(String::as_str), Some("Mix 3 — louder kick"));
+        assert_eq!(tagged.ixml.get("SCENE").map(String::as_str), Some("12A"));
+        assert_eq!(tagged.ixml.get("TRACK_LIST/TRACK/NAME").map(String::as_str), Some("Mix L"));
+        assert_eq!(tagged.ixml.get("ASWG/tempo").map(String::as_str), Some("124"));
+        assert_eq!(tagged.ixml.get("ASWG/inKey").map(String::as_str), Some("F# Minor"));
+        assert_eq!(tagged.ixml.get("ASWG/artist").map(String::as_str), Some("Kay"));
+
+        // Tagging a tagged file replaces its metadata rather than adding more
+        let retagged = dir.path().join("retagged.wav");
+        write_tagged_copy(&copy, &retagged, &BounceTags { title: "Night Drive II".to_string(), ..tags() }).unwrap();
+        let retagged_bytes = fs::read(&retagged).unwrap();
+        assert_eq!(retagged_bytes.windows(4).filter(|w| w == b"bext").count(), 1);
+        assert_eq!(retagged_bytes.windows(4).filter(|w| w == b"iXML").count(), 1);
+        assert_eq!(audio_data(&retagged), audio_data(&source));
+        let metadata = read_wav_metadata(&retagged).unwrap();
+        assert_eq!(metadata.ixml.get("PROJECT").map(String::as_str), Some("Night Drive II"));
+        assert_eq!(metadata.bext.unwrap().description, "Night Drive II: Mix 3 — louder kick");
+    }
+
+    #[test]
+    fn test_tagged_rf64_copy() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source.wav");
+        fs::write(&source, include_bytes!("../../tests/fixtures/wav/rf64_pcm24.wav")).unwrap();
+        let copy = dir.path().join("tagged.wav");
+        write_tagged_copy(&source, &copy, &BounceTags { artist: None, comment: None, ..tags() }).unwrap();
+
+        assert_eq!(audio_data(&copy), audio_data(&source));
+        assert_eq!(parse_wav_info(&copy).unwrap(), parse_wav_info(&source).unwrap());
+        // The real RIFF size is in ds64, the header keeps the placeholder
+        let bytes = fs::read(&copy).unwrap();
+        assert_eq!(&bytes[4..8], &SIZE_PLACEHOLDER.to_le_bytes());
+        let ds64 = bytes.windows(4).position(|w| w == b"ds64").unwrap() + 8;
+        assert_eq!(u64::from_le_bytes(bytes[ds64..ds64 + 8].try_into().unwrap()), bytes.len() as u64 - 8);
+
+        let metadata = read_wav_metadata(&copy).unwrap();
+        assert_eq!(metadata.bext.unwrap().description, "Night Drive");
+        assert_eq!(metadata.info.get("IART"), None);
+        assert_eq!(metadata.ixml.get("NOTE"), None);
+    }
+
+    #[test]
+    fn test_untagged_file_has_no_metadata() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source.wav");
+        fs::write(&source, include_bytes!("../../tests/fixtures/wav/extensible_pcm24.wav")).unwrap();
+        assert_eq!(read_wav_metadata(&source).unwrap(), BounceMetadata::default());
+    }
+}
@@ -0,0 +1,234 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+
+use crate::als_parser::SampleRef;
+use crate::db::queries;
+
+/// <RelativePathType> values: what Live wrote the <RelativePath> against.
+/// "External" files are relative to the folder holding the set.
+const RELATIVE_TO_SET_FOLDER: i64 = 1;
+const RELATIVE_TO_USER_LIBRARY: i64 = 2;
+const RELATIVE_TO_PROJECT: i64 = 3;
+const RELATIVE_TO_CORE_LIBRARY: i64 = 5;
+
+/// Where a sample lives relative to the project, as stored in project_samples.location.
+pub const LOCATION_PROJECT: &str = "project";
+pub const LOCATION_USER_LIBRARY: &str = "user_library";
+pub const LOCATION_CORE_LIBRARY: &str = "core_library";
+pub const LOCATION_PACK: &str = "pack";
+pub const LOCATION_EXTERNAL: &str = "external";
+
+/// Settings holding where this machine keeps Live's content folders.
+pub const USER_LIBRARY_SETTING: &str = "user_library_path";
+pub const CORE_LIBRARY_SETTING: &str = "core_library_path";
+pub const PACKS_SETTING: &str = "packs_path";
+
+/// Live's content folders on this machine, from settings. Folders that
+/// aren't configured are recognised by their usual names instead
+/// ("User Library", "Core Library", "Factory Packs").
+#[derive(Debug, Clone, Default)]
+pub struct LiveLibraries {
+    pub user_library: Option<PathBuf>,
+    pub core_library: Option<PathBuf>,
+    pub packs: Option<PathBuf>,
+}
+
+impl LiveLibraries {
+    pub fn from_settings(conn: &Connection) -> Result<Self, String> {
+        let path = |key: &str| -> Result<Option<PathBuf>, String> {
+            Ok(queries::get_setting(conn, key)?.filter(|v| !v.trim().is_empty()).map(PathBuf::from))
+        };
+        Ok(LiveLibraries {
+            user_library: path(USER_LIBRARY_SETTING)?,
+            core_library: path(CORE_LIBRARY_SETTING)?,
+            packs: path(PACKS_SETTING)?,
+        })
+    }
+}
+
+/// Result of looking a sample reference up on disk.
+#[derive(Debug, PartialEq)]
+pub struct SampleLocation {
+    /// Where the file was actually found, None if it is missing.
+    pub resolved_path: Option<PathBuf>,
+    pub location: &'static str,
+}
+
+/// Find a sample on disk and classify where it lives.
+///
+/// The absolute <Path> is tried first. Sets that were moved to another
+/// machine or drive still carry the old absolute path, so the <RelativePath>
+/// is then tried against the folder its <RelativePathType> names: the
+/// project, the User Library or the Core Library.
+pub fn locate_sample(sample: &SampleRef, project_dir: &Path, libraries: &LiveLibraries) -> SampleLocation {
+    let absolute = PathBuf::from(&sample.path);
+    let base = match sample.relative_path_type {
+        // Sets from before Live recorded the type are project-relative
+        None | Some(RELATIVE_TO_SET_FOLDER | RELATIVE_TO_PROJECT) => Some(project_dir),
+        Some(RELATIVE_TO_USER_LIBRARY) => libraries.user_library.as_deref(),
+        Some(RELATIVE_TO_CORE_LIBRARY) => libraries.core_library.as_deref(),
+        Some(_) => None,
+    };
+    let relative = base.zip(sample.relative_path.as_deref()).map(|(base, rel)| join_relative(base, rel));
+
+    let resolved_path = std::iter::once(absolute.clone())
+        .chain(relative)
+        .find(|p| p.is_file());
+
+    let from_pack = sample.pack_name.is_some();
+    let location = match &resolved_path {
+        Some(found) => classify_path(found, from_pack, project_dir, libraries),
+        // A missing file is still classified by where the set expects it
+        None => match sample.relative_path_type {
+            Some(RELATIVE_TO_PROJECT) => LOCATION_PROJECT,
+            _ if from_pack => LOCATION_PACK,
+            Some(RELATIVE_TO_USER_LIBRARY) => LOCATION_USER_LIBRARY,
+            Some(RELATIVE_TO_CORE_LIBRARY) => LOCATION_CORE_LIBRARY,
+            _ => classify_path(&absolute, from_pack, project_dir, libraries),
+        },
+    };
+
+    SampleLocation { resolved_path, location }
+}
+
+/// Join a Live relative path (always '/'-separated, sometimes '\\' on
+/// Windows) onto `base` one component at a time.
+fn join_relative(base: &Path, relative: &str) -> PathBuf {
+    relative
+        .split(['/', '\\'])
+        .filter(|c| !c.is_empty() && *c != ".")
+        .fold(base.to_path_buf(), |acc, c| acc.join(c))
+}
+
+/// Classify by path: inside the project folder, then inside one of Live's
+/// content folders. Anything else is external.
+fn classify_path(path: &Path, from_pack: bool, project_dir: &Path, libraries: &LiveLibraries) -> &'static str {
+    let normalized = normalize(&path.to_string_lossy());
+    let is_inside = |dir: &Path| {
+        let dir = normalize(&dir.to_string_lossy());
+        !dir.is_empty() && normalized.starts_with(&format!("{}/", dir))
+    };
+    if is_inside(project_dir) {
+        return LOCATION_PROJECT;
+    }
+    if from_pack {
+        return LOCATION_PACK;
+    }
+
+    let has_component = |name: &str| normalized.split('/').any(|c| c == name);
+    let folders = [
+        (&libraries.packs, "factory packs", LOCATION_PACK),
+        (&libraries.core_library, "core library", LOCATION_CORE_LIBRARY),
+        (&libraries.user_library, "user library", LOCATION_USER_LIBRARY),
+    ];
+    folders.into_iter()
+        .find(|(configured, name, _)| match configured {
+            Some(dir) => is_inside(dir),
+            None => has_component(name),
+        })
+        .map_or(LOCATION_EXTERNAL, |(_, _, location)| location)
+}
+
+/// Lowercase with '/' separators and no trailing separator, so Windows and
+/// macOS paths compare the same way.
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/").trim_end_matches('/').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn sample(path: &str, relative_path: Option<&str>, relative_path_type: Option<i64>) -> SampleRef {
+        SampleRef {
+            path: path.to_string(),
+            filename: path.rsplit(['/', '\\']).next().unwrap().to_string(),
+            relative_path: relative_path.map(|r| r.to_string()),
+            relative_path_type,
+            pack_name: None,
+        }
+    }
+
+    #[test]
+    fn test_moved_project_resolves_relative_path() {
+        let tmp = TempDir::new().unwrap();
+        let project = tmp.path().join("Song Project");
+        fs::create_dir_all(project.join("Samples/Recorded")).unwrap();
+        fs::write(project.join("Samples/Recorded/vox.wav"), b"RIFF").unwrap();
+
+        // Absolute path from the machine the set was made on
+        let s = sample("D:/Old Drive/Song Project/Samples/Recorded/vox.wav", Some("Samples/Recorded/vox.wav"), Some(3));
+        let found = locate_sample(&s, &project, &LiveLibraries::default());
+        assert_eq!(found.resolved_path, Some(project.join("Samples").join("Recorded").join("vox.wav")));
+        assert_eq!(found.location, LOCATION_PROJECT);
+    }
+
+    #[test]
+    fn test_absolute_path_wins_when_present() {
+        let tmp = TempDir::new().unwrap();
+        let project = tmp.path().join("Song Project");
+        fs::create_dir_all(&project).unwrap();
+        let external = tmp.path().join("Splice").join("kick.wav");
+        fs::create_dir_all(external.parent().unwrap()).unwrap();
+        fs::write(&external, b"RIFF").unwrap();
+
+        let s = sample(&external.to_string_lossy(), Some("../Splice/kick.wav"), Some(1));
+        let found = locate_sample(&s, &project, &LiveLibraries::default());
+        assert_eq!(found.resolved_path, Some(external));
+        assert_eq!(found.location, LOCATION_EXTERNAL);
+    }
+
+    #[test]
+    fn test_missing_sample_is_still_classified() {
+        let project = Path::new("/nonexistent/Song Project");
+
+        let s = sample("D:/Old/Song Project/Samples/Imported/amen.wav", Some("Samples/Imported/amen.wav"), Some(3));
+        assert_eq!(locate_sample(&s, project, &LiveLibraries::default()), SampleLocation { resolved_path: None, location: LOCATION_PROJECT });
+
+        let s = sample("C:/Users/me/Documents/Ableton/User Library/Samples/snare.wav", None, None);
+        assert_eq!(locate_sample(&s, project, &LiveLibraries::default()).location, LOCATION_USER_LIBRARY);
+
+        let s = sample("/Applications/Ableton Live 12 Suite.app/Contents/App-Resources/Core Library/Samples/hat.aif", None, None);
+        assert_eq!(locate_sample(&s, project, &LiveLibraries::default()).location, LOCATION_CORE_LIBRARY);
+
+        let s = sample("C:\\Users\\me\\Music\\Ableton\\Factory Packs\\Drum Booth\\Samples\\tom.wav", None, None);
+        assert_eq!(locate_sample(&s, project, &LiveLibraries::default()).location, LOCATION_PACK);
+
+        let mut s = sample("/Volumes/Packs/Chop/sample.wav", None, None);
+        s.pack_name = Some("Chop Shop".to_string());
+        assert_eq!(locate_sample(&s, project, &LiveLibraries::default()).location, LOCATION_PACK);
+
+        let s = sample("/Volumes/Sounds/loop.wav", None, None);
+        assert_eq!(locate_sample(&s, project, &LiveLibraries::default()).location, LOCATION_EXTERNAL);
+    }
+
+    #[test]
+    fn test_configured_libraries() {
+        let tmp = TempDir::new().unwrap();
+        let project = tmp.path().join("Song Project");
+        let user_library = tmp.path().join("Sounds").join("My Live Stuff");
+        fs::create_dir_all(user_library.join("Samples")).unwrap();
+        fs::write(user_library.join("Samples").join("snare.wav"), b"RIFF").unwrap();
+        let libraries = LiveLibraries { user_library: Some(user_library.clone()), ..LiveLibraries::default() };
+
+        // A User Library outside the usual place isn't external
+        let s = sample(&user_library.join("Samples").join("snare.wav").to_string_lossy(), None, None);
+        assert_eq!(locate_sample(&s, &project, &libraries).location, LOCATION_USER_LIBRARY);
+        assert_eq!(locate_sample(&s, &project, &LiveLibraries::default()).location, LOCATION_EXTERNAL);
+
+        // Found on this machine from its path relative to the User Library
+        let s = sample("C:/Users/me/Documents/Ableton/User Library/Samples/snare.wav", Some("Samples/snare.wav"), Some(2));
+        let found = locate_sample(&s, &project, &libraries);
+        assert_eq!(found.resolved_path, Some(user_library.join("Samples").join("snare.wav")));
+        assert_eq!(found.location, LOCATION_USER_LIBRARY);
+
+        // Not looked for in the project, whatever the relative path says
+        fs::create_dir_all(project.join("Samples")).unwrap();
+        fs::write(project.join("Samples").join("hat.wav"), b"RIFF").unwrap();
+        let s = sample("/Old/Core Library/Samples/hat.wav", Some("Samples/hat.wav"), Some(5));
+        assert_eq!(locate_sample(&s, &project, &libraries), SampleLocation { resolved_path: None, location: LOCATION_CORE_LIBRARY });
+    }
+}
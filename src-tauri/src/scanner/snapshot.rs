@@ -0,0 +1,92 @@
+// Filesystem snapshot of project folders, persisted between scans so that
+// unchanged projects can be skipped without opening any of their files.
+
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::db::models::DirSnapshot;
+use super::backups::BACKUP_FOLDER_NAME;
+
+/// Fingerprint of everything a scan reads from a project folder: the folder
+/// itself, its top-level files (the .als sets), the bounce folder and Live's
+/// Backup folder.
+/// Editing, adding or removing any of them changes the latest mtime, the
+/// total size or the file count.
+pub fn snapshot_project_dir(project_dir: &Path, bounce_folder_name: &str) -> Option<DirSnapshot> {
+    let mut snapshot = DirSnapshot {
+        mtime: mtime_nanos(&fs::metadata(project_dir).ok()?),
+        size: 0,
+        file_count: 0,
+    };
+    add_files(&mut snapshot, project_dir);
+
+    for subdir in [bounce_folder_name, BACKUP_FOLDER_NAME] {
+        let dir = project_dir.join(subdir);
+        if let Ok(meta) = fs::metadata(&dir) {
+            if meta.is_dir() {
+                snapshot.mtime = snapshot.mtime.max(mtime_nanos(&meta));
+                add_files(&mut snapshot, &dir);
+            }
+        }
+    }
+    Some(snapshot)
+}
+
+fn add_files(snapshot: &mut DirSnapshot, dir: &Path) {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let meta = match entry.metadata() {
+            Ok(m) if m.is_file() => m,
+            _ => continue,
+        };
+        snapshot.mtime = snapshot.mtime.max(mtime_nanos(&meta));
+        snapshot.size += meta.len() as i64;
+        snapshot.file_count += 1;
+    }
+}
+
+/// Modification time in Unix nanoseconds, so edits within the same second
+/// still count as a change.
+fn mtime_nanos(meta: &fs::Metadata) -> i64 {
+    meta.modified()
+        .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_snapshot_changes_with_project_files() {
+        let tmp = TempDir::new().unwrap();
+        let project = tmp.path().join("Song Project");
+        fs::create_dir_all(project.join("Samples")).unwrap();
+        fs::write(project.join("Song.als"), b"set").unwrap();
+        fs::write(project.join("Samples").join("kick.wav"), b"kick").unwrap();
+
+        let first = snapshot_project_dir(&project, "Bounces").unwrap();
+        assert_eq!((first.size, first.file_count), (3, 1), "only top-level files count");
+        assert_eq!(snapshot_project_dir(&project, "Bounces"), Some(first.clone()));
+
+        fs::create_dir(project.join("Bounces")).unwrap();
+        fs::write(project.join("Bounces").join("Song.wav"), b"audio").unwrap();
+        let with_bounce = snapshot_project_dir(&project, "Bounces").unwrap();
+        assert_eq!((with_bounce.size, with_bounce.file_count), (8, 2));
+
+        fs::write(project.join("Song.als"), b"edited set").unwrap();
+        let edited = snapshot_project_dir(&project, "Bounces").unwrap();
+        assert_ne!(edited, with_bounce);
+
+        fs::create_dir(project.join("Backup")).unwrap();
+        fs::write(project.join("Backup").join("Song [2026-01-01 120000].als"), b"set").unwrap();
+        assert_eq!(snapshot_project_dir(&project, "Bounces").unwrap().file_count, edited.file_count + 1);
+
+        assert_eq!(snapshot_project_dir(&tmp.path().join("Gone"), "Bounces"), None);
+    }
+}
@@ -1,24 +1,102 @@
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::sync::{Mutex, MutexGuard};
 use std::time::Duration;
+use rayon::prelude::*;
 use rusqlite::{params, Connection};
 use tauri::AppHandle;
 use tauri::Emitter;
-use crate::db::models::ScanSummary;
+use crate::als_parser::AlsMetadata;
+use crate::analysis::{BounceAnalysis, BounceAnalyzer};
+use crate::db::models::{BounceMetadata, DirSnapshot, LibraryRoot, SampleWithStatus, ScanSummary};
 use crate::cover_gen;
+use super::audio_decode::AudioDecoder;
+use super::audio_info::{self, AudioInfo};
+use super::backups::{self, BackupRead};
+use super::ignore_rules::{self, IgnoreRules};
+use super::sample_location::{self, LiveLibraries};
+use super::{peaks, riff_metadata, snapshot};
 
 #[derive(Clone, serde::Serialize)]
 pub struct ScanProgress {
     pub current: usize,
     pub total: usize,
     pub project_name: String,
-    pub stage: String, // "scanning" | "generating_covers" | "complete"
+    pub stage: String, // "scanning" | "generating_covers" | "updating" | "complete"
 }
 
-pub fn scan_library(conn: &Connection, root_folder: &str, bounce_folder_name: &str, app_data_dir: &Path, app: &AppHandle) -> Result<ScanSummary, String> {
-    let root = Path::new(root_folder);
-    if !root.exists() || !root.is_dir() {
-        return Err(format!("Root folder does not exist: {}", root_folder));
+/// Pause after each processed project so the frontend can render each note
+/// on the piano roll.
+const PROGRESS_ANIMATION_DELAY: Duration = Duration::from_millis(15);
+
+/// Project folders read per batch. Each batch is read in parallel with the
+/// database unlocked, then written in one transaction, so a scan holds at
+/// most one batch of reads (MIDI notes included) in memory.
+const SCAN_BATCH_SIZE: usize = 64;
+
+/// How a scan treats unchanged projects and paces its progress events.
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// Process every project, even those unchanged since the last scan.
+    pub full: bool,
+    /// Pause after each processed project (skipped ones never wait).
+    pub progress_delay: Option<Duration>,
+    /// Global ignore patterns, on top of any `.setcrateignore` files.
+    pub ignore_patterns: Vec<String>,
+    /// Where bounce waveform peaks are cached; None skips computing them.
+    pub peak_cache_dir: Option<PathBuf>,
+    /// Live's content folders, for locating and classifying samples.
+    pub libraries: LiveLibraries,
+}
+
+impl ScanOptions {
+    /// Options for a scan started from the UI. The progress animation can be
+    /// turned off with the `scan_animation` setting; ignore patterns come from
+    /// the `scan_ignore_patterns` setting and Live's folders from theirs.
+    pub fn from_settings(conn: &Connection, full: bool) -> Self {
+        let animate = crate::db::queries::get_setting(conn, "scan_animation")
+            .ok()
+            .flatten()
+            .is_none_or(|v| v != "false");
+        let ignore_patterns = ignore_rules::ignore_patterns(conn).unwrap_or_else(|e| {
+            log::warn!("{}", e);
+            Vec::new()
+        });
+        let libraries = LiveLibraries::from_settings(conn).unwrap_or_else(|e| {
+            log::warn!("{}", e);
+            LiveLibraries::default()
+        });
+        ScanOptions {
+            full,
+            progress_delay: animate.then_some(PROGRESS_ANIMATION_DELAY),
+            ignore_patterns,
+            peak_cache_dir: None,
+            libraries,
+        }
+    }
+}
+
+pub fn scan_library(db: &Mutex<Connection>, roots: &[LibraryRoot], app: &AppHandle, options: &ScanOptions) -> Result<ScanSummary, String> {
+    scan_roots(db, roots, options, |progress| {
+        app.emit("scan-progress", progress).ok();
+    })
+}
+
+/// Discover and process the projects under the online library roots,
+/// reporting each one to `on_progress`. Project folders are read in parallel
+/// batches without holding the database lock, each batch then written in one
+/// transaction. Projects whose folder matches the snapshot from the last scan
+/// are skipped unless `options.full` is set. Offline roots are left alone, so
+/// their projects are not flagged missing.
+pub(crate) fn scan_roots(
+    db: &Mutex<Connection>,
+    roots: &[LibraryRoot],
+    options: &ScanOptions,
+    on_progress: impl FnMut(ScanProgress),
+) -> Result<ScanSummary, String> {
+    if roots.is_empty() {
+        return Err("No library folders configured. Please add one in Settings.".to_string());
     }
 
     let mut summary = ScanSummary {
@@ -26,39 +104,235 @@ pub fn scan_library(conn: &Connection, root_folder: &str, bounce_folder_name: &s
         new: 0,
         updated: 0,
         missing: 0,
+        changed: 0,
+        skipped: 0,
         errors: Vec::new(),
     };
 
     // Recursively discover all project directories (dirs containing .als files)
-    let mut project_dirs: Vec<(PathBuf, String)> = Vec::new();
-    discover_project_dirs(root, root, 0, &mut project_dirs);
+    let mut candidates: Vec<ProjectCandidate> = Vec::new();
+    for root in roots {
+        if !root.online {
+            log::warn!("Library folder offline, skipping: {}", root.path);
+            continue;
+        }
+        let root_path = Path::new(&root.path);
+        let mut project_dirs: Vec<(PathBuf, String)> = Vec::new();
+        let mut ignore = IgnoreRules::new(root_path, &options.ignore_patterns);
+        discover_project_dirs(root_path, genre_depth(root), &mut ignore, root_path, 0, &mut project_dirs);
+        candidates.extend(project_dirs.iter().map(|(path, genre_label)| {
+            ProjectCandidate::new(path, genre_label, &root.bounce_folder_name)
+        }));
+    }
+    let found_paths: Vec<String> = candidates.iter().map(|c| c.path.clone()).collect();
+    summary.found = candidates.len();
 
-    // Track found project paths
-    let mut found_paths: Vec<String> = Vec::new();
+    let known = load_known_projects(&*lock_db(db)?)?;
+    process_candidates(db, &candidates, &known, options, &mut summary, on_progress)?;
 
-    for (project_path, genre_label) in &project_dirs {
-        summary.found += 1;
-        let path_str = project_path.to_string_lossy().to_string();
-        found_paths.push(path_str.clone());
+    let mut conn = lock_db(db)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-        let project_name = project_path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
+    // Flag missing projects (paths that no longer exist)
+    match flag_missing_projects(&tx, &found_paths, roots, &options.ignore_patterns) {
+        Ok(count) => summary.missing = count,
+        Err(e) => summary.errors.push(format!("Failed to flag missing projects: {}", e)),
+    }
+    tx.commit().map_err(|e| e.to_string())?;
 
-        app.emit("scan-progress", ScanProgress {
-            current: summary.found,
-            total: project_dirs.len(),
-            project_name: project_name.clone(),
-            stage: "scanning".to_string(),
-        }).ok();
+    if let Some(ref cache_dir) = options.peak_cache_dir {
+        prune_peak_cache(&conn, cache_dir);
+    }
+
+    log::info!(
+        "Scan complete: {} found, {} changed ({} new, {} updated), {} skipped, {} missing, {} errors",
+        summary.found, summary.changed, summary.new, summary.updated, summary.skipped, summary.missing, summary.errors.len()
+    );
+
+    Ok(summary)
+}
+
+/// Drop cached peaks of bounces that were deleted or re-bounced.
+fn prune_peak_cache(conn: &Connection, cache_dir: &Path) {
+    let bounce_paths: Result<Vec<String>, _> = conn.prepare("SELECT bounce_path FROM bounces")
+        .and_then(|mut stmt| stmt.query_map([], |row| row.get(0))?.collect());
+    match bounce_paths {
+        Ok(paths) => {
+            let deleted = peaks::prune_cache(cache_dir, &paths);
+            if deleted > 0 {
+                log::info!("Pruned {} stale waveform peak files", deleted);
+            }
+        }
+        Err(e) => log::warn!("Failed to list bounces for peak cache: {}", e),
+    }
+}
 
-        // Brief pause so the frontend can render each note on the piano roll
-        std::thread::sleep(Duration::from_millis(15));
+fn lock_db(db: &Mutex<Connection>) -> Result<MutexGuard<'_, Connection>, String> {
+    db.lock().map_err(|e| e.to_string())
+}
 
-        match process_project(conn, &path_str, &project_name, genre_label, bounce_folder_name, app_data_dir) {
+/// A project folder queued for scanning.
+struct ProjectCandidate {
+    path: String,
+    name: String,
+    genre_label: String,
+    bounce_folder_name: String,
+}
+
+impl ProjectCandidate {
+    fn new(path: &Path, genre_label: &str, bounce_folder_name: &str) -> Self {
+        ProjectCandidate {
+            path: path.to_string_lossy().to_string(),
+            name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            genre_label: genre_label.to_string(),
+            bounce_folder_name: bounce_folder_name.to_string(),
+        }
+    }
+}
+
+pub(crate) fn genre_depth(root: &LibraryRoot) -> usize {
+    root.genre_depth.max(0) as usize
+}
+
+/// What the library already knows about a project folder, loaded up front so
+/// the read phase never touches the database.
+struct KnownProject {
+    als_parsed_at: Option<i64>,
+    /// Has a current set that was never parsed (migrations clear
+    /// `als_parsed_at` to force a re-parse)
+    needs_parse: bool,
+    snapshot: Option<DirSnapshot>,
+    /// Bounce path → the modified time its analysis was measured at
+    analysed_bounces: HashMap<String, String>,
+}
+
+fn load_known_projects(conn: &Connection) -> Result<HashMap<String, KnownProject>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT p.project_path, p.als_parsed_at, \
+         p.current_set_path IS NOT NULL AND p.als_parsed_at IS NULL, \
+         s.mtime, s.size, s.file_count \
+         FROM projects p LEFT JOIN scan_snapshots s ON s.dir_path = p.project_path"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([], |row| {
+        let snapshot = match (row.get(3)?, row.get(4)?, row.get(5)?) {
+            (Some(mtime), Some(size), Some(file_count)) => Some(DirSnapshot { mtime, size, file_count }),
+            _ => None,
+        };
+        Ok((row.get::<_, String>(0)?, KnownProject {
+            als_parsed_at: row.get(1)?,
+            needs_parse: row.get(2)?,
+            snapshot,
+            analysed_bounces: HashMap::new(),
+        }))
+    }).map_err(|e| e.to_string())?;
+    let mut known: HashMap<String, KnownProject> = rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+
+    for (project_path, bounce_path, measured_mtime) in load_analysed_bounces(conn, None)? {
+        if let Some(project) = known.get_mut(&project_path) {
+            project.analysed_bounces.insert(bounce_path, measured_mtime);
+        }
+    }
+    Ok(known)
+}
+
+/// Project path, bounce path and measured modified time of every analysed
+/// bounce, or of those of one project.
+fn load_analysed_bounces(conn: &Connection, project_path: Option<&str>) -> Result<Vec<(String, String, String)>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT p.project_path, b.bounce_path, b.loudness_mtime \
+         FROM bounces b JOIN projects p ON p.id = b.project_id \
+         WHERE b.loudness_mtime IS NOT NULL AND (?1 IS NULL OR p.project_path = ?1)"
+    ).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![project_path], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+/// Read and store the candidate folders a batch at a time, skipping those
+/// unchanged since their last scan. Every candidate is reported to
+/// `on_progress`, with a pause after each processed one when
+/// `options.progress_delay` is set.
+fn process_candidates(
+    db: &Mutex<Connection>,
+    candidates: &[ProjectCandidate],
+    known: &HashMap<String, KnownProject>,
+    options: &ScanOptions,
+    summary: &mut ScanSummary,
+    mut on_progress: impl FnMut(ScanProgress),
+) -> Result<(), String> {
+    let total = candidates.len();
+    let mut current = 0;
+    for batch in candidates.chunks(SCAN_BATCH_SIZE) {
+        let reads = read_projects(batch, known, options);
+
+        // Paced here rather than on the worker pool, so the animation never
+        // holds up reading
+        for (candidate, read) in batch.iter().zip(&reads) {
+            current += 1;
+            on_progress(ScanProgress {
+                current,
+                total,
+                project_name: candidate.name.clone(),
+                stage: "scanning".to_string(),
+            });
+            if let (Some(_), Some(delay)) = (read, options.progress_delay) {
+                std::thread::sleep(delay);
+            }
+        }
+        summary.skipped += reads.iter().filter(|r| r.is_none()).count();
+        let reads: Vec<ProjectRead> = reads.into_iter().flatten().collect();
+
+        let mut conn = lock_db(db)?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        write_projects(&tx, &reads, summary);
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Read the candidate folders on the worker pool, in order. Folders
+/// unchanged since their last scan are skipped (None).
+fn read_projects(
+    candidates: &[ProjectCandidate],
+    known: &HashMap<String, KnownProject>,
+    options: &ScanOptions,
+) -> Vec<Option<ProjectRead>> {
+    candidates.par_iter().map(|candidate| {
+        let known = known.get(&candidate.path);
+        // Taken before reading anything, so changes made during the scan are
+        // picked up by the next one
+        let dir_snapshot = snapshot::snapshot_project_dir(Path::new(&candidate.path), &candidate.bounce_folder_name);
+        let unchanged = known.is_some_and(|k| !k.needs_parse && dir_snapshot.is_some() && k.snapshot == dir_snapshot);
+        if unchanged && !options.full {
+            return None;
+        }
+
+        let no_bounces = HashMap::new();
+        Some(read_project(
+            candidate,
+            dir_snapshot,
+            known.and_then(|k| k.als_parsed_at),
+            options.peak_cache_dir.as_deref(),
+            known.map_or(&no_bounces, |k| &k.analysed_bounces),
+            known.is_none(),
+            &options.libraries,
+        ))
+    }).collect()
+}
+
+/// Store each read project, counting it into the summary. A project whose
+/// write fails part-way is rolled back on its own.
+fn write_projects(conn: &Connection, reads: &[ProjectRead], summary: &mut ScanSummary) {
+    for read in reads {
+        let result = conn.execute_batch("SAVEPOINT write_project")
+            .map_err(|e| e.to_string())
+            .and_then(|_| write_project(conn, read));
+        let end = if result.is_ok() { "RELEASE write_project" } else { "ROLLBACK TO write_project; RELEASE write_project" };
+        conn.execute_batch(end).ok();
+        match result {
             Ok(is_new) => {
+                summary.changed += 1;
                 if is_new {
                     summary.new += 1;
                 } else {
@@ -66,23 +340,10 @@ pub fn scan_library(conn: &Connection, root_folder: &str, bounce_folder_name: &s
                 }
             }
             Err(e) => {
-                summary.errors.push(format!("{}: {}", project_name, e));
+                summary.errors.push(format!("{}: {}", read.name, e));
             }
         }
     }
-
-    // Flag missing projects (paths that no longer exist)
-    match flag_missing_projects(conn, &found_paths) {
-        Ok(count) => summary.missing = count,
-        Err(e) => summary.errors.push(format!("Failed to flag missing projects: {}", e)),
-    }
-
-    log::info!(
-        "Scan complete: {} found, {} new, {} updated, {} missing, {} errors",
-        summary.found, summary.new, summary.updated, summary.missing, summary.errors.len()
-    );
-
-    Ok(summary)
 }
 
 pub(crate) fn has_als_files(dir: &Path) -> bool {
@@ -107,7 +368,15 @@ const MAX_SCAN_DEPTH: usize = 10;
 /// Recursively walk `dir` looking for directories that contain .als files.
 /// Once a directory is identified as a project (has .als files), we do NOT
 /// recurse into it — this avoids treating Backup/ subfolders as separate projects.
-fn discover_project_dirs(root: &Path, dir: &Path, depth: usize, results: &mut Vec<(PathBuf, String)>) {
+/// Folders matched by `ignore` are skipped along with everything below them.
+pub(crate) fn discover_project_dirs(
+    root: &Path,
+    genre_depth: usize,
+    ignore: &mut IgnoreRules,
+    dir: &Path,
+    depth: usize,
+    results: &mut Vec<(PathBuf, String)>,
+) {
     if depth > MAX_SCAN_DEPTH {
         return;
     }
@@ -129,54 +398,180 @@ fn discover_project_dirs(root: &Path, dir: &Path, depth: usize, results: &mut Ve
             continue;
         }
 
+        if let Some(reason) = ignore.skip_reason(&path) {
+            log::info!("Skipping {}: {}", path.display(), reason);
+            continue;
+        }
+
         if has_als_files(&path) {
             // This is a project — record it and don't recurse deeper
-            let genre_label = derive_genre_label(root, &path);
+            let genre_label = derive_genre_label(root, &path, genre_depth);
             results.push((path, genre_label));
         } else {
             // Not a project — recurse deeper
-            discover_project_dirs(root, &path, depth + 1, results);
+            ignore.enter(&path);
+            discover_project_dirs(root, genre_depth, ignore, &path, depth + 1, results);
+            ignore.leave();
         }
     }
 }
 
-/// Derive the genre label from the first directory component after root.
-/// e.g. root/Techno/Deep/Track A → "Techno"
-///      root/Track A             → ""
-fn derive_genre_label(root: &Path, project_path: &Path) -> String {
+/// Derive the genre label from the directory `genre_depth` levels below root.
+/// e.g. with depth 1: root/Techno/Deep/Track A → "Techno"
+///                    root/Track A             → ""
+///      with depth 2: root/Artist/Techno/Track A → "Techno"
+/// Depth 0 turns genre labels off.
+pub(crate) fn derive_genre_label(root: &Path, project_path: &Path, genre_depth: usize) -> String {
+    if genre_depth == 0 {
+        return String::new();
+    }
     if let Ok(relative) = project_path.strip_prefix(root) {
-        let mut components = relative.components();
-        if let Some(first) = components.next() {
-            // If there's a second component, then `first` is a genre folder
-            if components.next().is_some() {
-                return first.as_os_str().to_string_lossy().to_string();
-            }
+        let components: Vec<_> = relative.components().collect();
+        // The last component is the project itself, so the genre folder must
+        // sit above it
+        if components.len() > genre_depth {
+            return components[genre_depth - 1].as_os_str().to_string_lossy().to_string();
         }
     }
-    // Project is directly in root — no genre label
+    // Project is too close to the root — no genre label
     String::new()
 }
 
-pub(crate) fn process_project(
-    conn: &Connection,
+/// Read and store one project folder, reading it (and parsing its set) with
+/// the database unlocked. Returns whether the project is new.
+pub(crate) fn process_project_unlocked(
+    db: &Mutex<Connection>,
     project_path: &str,
     project_name: &str,
     genre_label: &str,
     bounce_folder_name: &str,
-    _app_data_dir: &Path,
+    app_data_dir: &Path,
 ) -> Result<bool, String> {
-    let path = Path::new(project_path);
+    let pending = PendingProject::load(&*lock_db(db)?, project_path, project_name, genre_label, bounce_folder_name, app_data_dir)?;
+    let read = pending.read();
+    write_project(&*lock_db(db)?, &read)
+}
+
+/// One project folder to process, with what the library knows about it.
+struct PendingProject {
+    candidate: ProjectCandidate,
+    als_parsed_at: Option<i64>,
+    analysed_bounces: HashMap<String, String>,
+    is_new: bool,
+    libraries: LiveLibraries,
+    peak_cache_dir: PathBuf,
+}
+
+impl PendingProject {
+    fn load(
+        conn: &Connection,
+        project_path: &str,
+        project_name: &str,
+        genre_label: &str,
+        bounce_folder_name: &str,
+        app_data_dir: &Path,
+    ) -> Result<Self, String> {
+        let als_parsed_at: Option<i64> = conn.query_row(
+            "SELECT als_parsed_at FROM projects WHERE project_path = ?1",
+            params![project_path],
+            |row| row.get(0),
+        ).optional().map_err(|e| e.to_string())?.flatten();
+        let analysed_bounces = load_analysed_bounces(conn, Some(project_path))?
+            .into_iter()
+            .map(|(_, bounce_path, measured_mtime)| (bounce_path, measured_mtime))
+            .collect();
+        Ok(PendingProject {
+            candidate: ProjectCandidate {
+                path: project_path.to_string(),
+                name: project_name.to_string(),
+                genre_label: genre_label.to_string(),
+                bounce_folder_name: bounce_folder_name.to_string(),
+            },
+            als_parsed_at,
+            analysed_bounces,
+            is_new: !project_exists_in_db(conn, project_path)?,
+            libraries: LiveLibraries::from_settings(conn)?,
+            peak_cache_dir: peaks::cache_dir(app_data_dir),
+        })
+    }
 
-    // Find .als files (non-recursive, skip Backup folder)
-    let mut als_files: Vec<(String, String)> = Vec::new(); // (path, modified_time)
+    fn read(&self) -> ProjectRead {
+        let candidate = &self.candidate;
+        let dir_snapshot = snapshot::snapshot_project_dir(Path::new(&candidate.path), &candidate.bounce_folder_name);
+        read_project(
+            candidate,
+            dir_snapshot,
+            self.als_parsed_at,
+            Some(&self.peak_cache_dir),
+            &self.analysed_bounces,
+            self.is_new,
+            &self.libraries,
+        )
+    }
+}
+
+/// Everything a scan reads from one project folder, gathered without the
+/// database so folders can be read in parallel.
+struct ProjectRead {
+    path: String,
+    name: String,
+    genre_label: String,
+    snapshot: Option<DirSnapshot>,
+    /// .als files, newest first
+    sets: Vec<SetRead>,
+    /// None without a bounce folder
+    bounces: Option<Vec<BounceRead>>,
+    /// Copies of the sets in the Backup folder
+    backups: Vec<BackupRead>,
+    /// The newest set, unless unchanged since it was last parsed
+    als: Option<AlsRead>,
+}
+
+/// An .als file in a project folder.
+pub(crate) struct SetRead {
+    pub path: String,
+    pub modified_time: String,
+    pub file_size: Option<i64>,
+    /// SHA-256 of the file, used to recognise the project after a move.
+    /// Only taken for folders new to the library, as those are the ones
+    /// that may have been moved
+    pub content_hash: Option<String>,
+}
+
+/// A parsed .als file and where each of its samples was found.
+struct AlsRead {
+    path: String,
+    /// Set mtime in Unix seconds, recorded as `als_parsed_at`
+    mtime: i64,
+    metadata: Result<AlsMetadata, String>,
+    samples: Vec<SampleWithStatus>,
+}
+
+fn read_project(
+    candidate: &ProjectCandidate,
+    dir_snapshot: Option<DirSnapshot>,
+    als_parsed_at: Option<i64>,
+    peak_cache_dir: Option<&Path>,
+    analysed_bounces: &HashMap<String, String>,
+    hash_sets: bool,
+    libraries: &LiveLibraries,
+) -> ProjectRead {
+    let path = Path::new(&candidate.path);
+
+    // Find .als files (non-recursive; the Backup folder is read separately)
+    let mut sets: Vec<SetRead> = Vec::new();
     if let Ok(entries) = fs::read_dir(path) {
         for entry in entries.flatten() {
             let file_path = entry.path();
             if file_path.is_file() {
                 if let Some(ext) = file_path.extension() {
                     if ext.to_string_lossy().to_lowercase() == "als" {
-                        let modified = get_modified_time(&file_path);
-                        als_files.push((file_path.to_string_lossy().to_string(), modified));
+                        sets.push(SetRead {
+                            path: file_path.to_string_lossy().to_string(),
+                            modified_time: get_modified_time(&file_path),
+                            file_size: fs::metadata(&file_path).ok().map(|m| m.len() as i64),
+                            content_hash: hash_sets.then(|| crate::relink::index::hash_file(&file_path).ok()).flatten(),
+                        });
                     }
                 }
             }
@@ -184,15 +579,46 @@ pub(crate) fn process_project(
     }
 
     // Determine current set (newest .als by modified time)
-    als_files.sort_by(|a, b| b.1.cmp(&a.1));
-    let newest_set = als_files.first().map(|(p, _)| p.clone());
-    let last_worked_on = als_files.first().map(|(_, t)| t.clone());
+    sets.sort_by(|a, b| b.modified_time.cmp(&a.modified_time));
+
+    // Scan bounces subfolder
+    let bounces_dir = path.join(&candidate.bounce_folder_name);
+    let bounces = (bounces_dir.exists() && bounces_dir.is_dir()).then(|| read_bounces(&bounces_dir, peak_cache_dir, analysed_bounces));
+
+    let backups = backups::read_backups(path);
+
+    // Parse .als file for metadata (BPM, key, plugins, samples)
+    let als = sets.first().and_then(|set| read_als(path, &set.path, als_parsed_at, libraries));
+
+    ProjectRead {
+        path: candidate.path.clone(),
+        name: candidate.name.clone(),
+        genre_label: candidate.genre_label.clone(),
+        snapshot: dir_snapshot,
+        sets,
+        bounces,
+        backups,
+        als,
+    }
+}
+
+/// Store a read project folder. Returns whether the project is new.
+fn write_project(conn: &Connection, read: &ProjectRead) -> Result<bool, String> {
+    let newest_set = read.sets.first().map(|s| s.path.clone());
+    let last_worked_on = read.sets.first().map(|s| s.modified_time.clone());
+
+    // A folder new to the library may be a project that was renamed or moved
+    if !project_exists_in_db(conn, &read.path)? {
+        if let Some(moved) = super::moves::find_moved_project(conn, &read.path, &read.sets)? {
+            super::moves::relocate_project(conn, &moved, &read.path, &read.name, &read.genre_label)?;
+        }
+    }
 
     // Upsert project: INSERT OR IGNORE then UPDATE technical fields only
     let is_new = {
         let existing: Option<i64> = conn.query_row(
             "SELECT id FROM projects WHERE project_path = ?1",
-            params![project_path],
+            params![read.path],
             |row| row.get(0),
         ).optional().map_err(|e| e.to_string())?;
 
@@ -201,7 +627,7 @@ pub(crate) fn process_project(
             conn.execute(
                 "UPDATE projects SET missing = 0, last_worked_on = COALESCE(?1, last_worked_on), \
                  updated_at = datetime('now') WHERE project_path = ?2",
-                params![last_worked_on, project_path],
+                params![last_worked_on, read.path],
             ).map_err(|e| e.to_string())?;
 
             // Update current_set_path only if not manually overridden
@@ -210,7 +636,7 @@ pub(crate) fn process_project(
                     "UPDATE projects SET current_set_path = ?1 WHERE project_path = ?2 \
                      AND (current_set_path IS NULL OR current_set_path NOT IN \
                      (SELECT set_path FROM ableton_sets WHERE project_id = (SELECT id FROM projects WHERE project_path = ?2)))",
-                    params![set_path, project_path],
+                    params![set_path, read.path],
                 ).ok(); // Ignore errors on this optional update
             }
 
@@ -219,13 +645,13 @@ pub(crate) fn process_project(
             conn.execute(
                 "INSERT INTO projects (name, project_path, genre_label, current_set_path, last_worked_on) \
                  VALUES (?1, ?2, ?3, ?4, ?5)",
-                params![project_name, project_path, genre_label, newest_set, last_worked_on],
+                params![read.name, read.path, read.genre_label, newest_set, last_worked_on],
             ).map_err(|e| e.to_string())?;
 
             // Populate FTS index for the new project (no auto-trigger in standalone FTS)
             let new_id: i64 = conn.query_row(
                 "SELECT id FROM projects WHERE project_path = ?1",
-                params![project_path],
+                params![read.path],
                 |row| row.get(0),
             ).map_err(|e| e.to_string())?;
             crate::db::queries::rebuild_fts_tags(conn, new_id)?;
@@ -237,22 +663,23 @@ pub(crate) fn process_project(
     // Get project ID
     let project_id: i64 = conn.query_row(
         "SELECT id FROM projects WHERE project_path = ?1",
-        params![project_path],
+        params![read.path],
         |row| row.get(0),
     ).map_err(|e| e.to_string())?;
 
-    // Upsert .als files (with file_size)
-    for (set_path, modified_time) in &als_files {
-        let file_size: Option<i64> = fs::metadata(set_path).ok().map(|m| m.len() as i64);
+    // Upsert .als files (with file_size). A stored hash is kept while its set
+    // is unchanged; one edited since falls back to matching by name and size
+    for set in &read.sets {
         conn.execute(
-            "INSERT INTO ableton_sets (project_id, set_path, modified_time, file_size) VALUES (?1, ?2, ?3, ?4) \
-             ON CONFLICT(set_path) DO UPDATE SET modified_time = ?3, file_size = ?4",
-            params![project_id, set_path, modified_time, file_size],
+            "INSERT INTO ableton_sets (project_id, set_path, modified_time, file_size, content_hash) VALUES (?1, ?2, ?3, ?4, ?5) \
+             ON CONFLICT(set_path) DO UPDATE SET content_hash = CASE WHEN ?5 IS NOT NULL THEN ?5 \
+             WHEN modified_time = ?3 AND file_size IS ?4 THEN content_hash END, modified_time = ?3, file_size = ?4",
+            params![project_id, set.path, set.modified_time, set.file_size, set.content_hash],
         ).map_err(|e| e.to_string())?;
     }
 
     // Remove sets that no longer exist on disk
-    let existing_set_paths: Vec<String> = als_files.iter().map(|(p, _)| p.clone()).collect();
+    let existing_set_paths: Vec<String> = read.sets.iter().map(|s| s.path.clone()).collect();
     if !existing_set_paths.is_empty() {
         let placeholders: Vec<String> = (0..existing_set_paths.len()).map(|i| format!("?{}", i + 2)).collect();
         let sql = format!(
@@ -269,15 +696,21 @@ pub(crate) fn process_project(
         stmt.execute(params_refs.as_slice()).map_err(|e| e.to_string())?;
     }
 
-    // Scan bounces subfolder
-    let bounces_dir = path.join(bounce_folder_name);
-    if bounces_dir.exists() && bounces_dir.is_dir() {
-        scan_bounces(conn, project_id, &bounces_dir)?;
+    if let Some(ref bounces) = read.bounces {
+        write_bounces(conn, project_id, bounces)?;
     }
 
-    // Parse .als file for metadata (BPM, key, plugins, samples)
-    if let Some(ref als_path) = newest_set {
-        parse_als_metadata(conn, project_id, als_path);
+    backups::write_backups(conn, project_id, &read.backups)?;
+
+    if let Some(ref als) = read.als {
+        store_als_metadata(conn, project_id, als);
+    }
+
+    // Bounce audio fills in tempo and key the .als (and the user) left empty
+    crate::db::queries::fill_tempo_key_from_bounces(conn, project_id)?;
+
+    if let Some(ref dir_snapshot) = read.snapshot {
+        crate::db::queries::set_scan_snapshot(conn, &read.path, dir_snapshot)?;
     }
 
     Ok(is_new)
@@ -373,33 +806,109 @@ pub fn generate_missing_covers(conn: &Connection, app_data_dir: &Path, app: &App
     log::info!("Generated {} covers", generated);
 }
 
-fn scan_bounces(conn: &Connection, project_id: i64, bounces_dir: &Path) -> Result<(), String> {
-    let mut bounce_paths: Vec<String> = Vec::new();
+/// An audio file in a project's bounce folder.
+struct BounceRead {
+    path: String,
+    modified_time: String,
+    /// None when the headers couldn't be read
+    info: Option<AudioInfo>,
+    /// Measured when the bounce is new or changed
+    analysis: Option<BounceAnalysis>,
+    /// bext, iXML and LIST/INFO chunks of a WAV bounce; None if it has none
+    metadata: Option<BounceMetadata>,
+}
+
+/// The audio files (WAV, AIFF, FLAC, MP3) in a bounce folder. With a
+/// `peak_cache_dir`, readable bounces not analysed at their current modified
+/// time (per `analysed_bounces`) are analysed.
+fn read_bounces(bounces_dir: &Path, peak_cache_dir: Option<&Path>, analysed_bounces: &HashMap<String, String>) -> Vec<BounceRead> {
+    let mut bounces = Vec::new();
 
     if let Ok(entries) = fs::read_dir(bounces_dir) {
         for entry in entries.flatten() {
             let file_path = entry.path();
-            if file_path.is_file() {
-                if let Some(ext) = file_path.extension() {
-                    if ext.to_string_lossy().to_lowercase() == "wav" {
-                        let path_str = file_path.to_string_lossy().to_string();
-                        let modified = get_modified_time(&file_path);
-                        let duration = super::wav_parser::parse_wav_duration(&file_path).ok();
-
-                        conn.execute(
-                            "INSERT INTO bounces (project_id, bounce_path, modified_time, duration_seconds) \
-                             VALUES (?1, ?2, ?3, ?4) \
-                             ON CONFLICT(bounce_path) DO UPDATE SET modified_time = ?3, duration_seconds = ?4",
-                            params![project_id, path_str, modified, duration],
-                        ).map_err(|e| e.to_string())?;
-
-                        bounce_paths.push(path_str);
+            if file_path.is_file() && audio_info::is_bounce_file(&file_path) {
+                let info = match audio_info::read_audio_info(&file_path) {
+                    Ok(info) => Some(info),
+                    Err(e) => {
+                        log::debug!("Can't read {}: {}", file_path.display(), e);
+                        None
                     }
-                }
+                };
+                let path = file_path.to_string_lossy().to_string();
+                // Taken before decoding, so a change made meanwhile counts as
+                // unanalysed
+                let modified_time = get_modified_time(&file_path);
+                let analysed = analysed_bounces.get(&path) == Some(&modified_time);
+                let analysis = match (peak_cache_dir, &info) {
+                    (Some(cache_dir), Some(_)) if !analysed => analyze_bounce(cache_dir, &file_path)
+                        .map_err(|e| log::debug!("Can't analyse {}: {}", file_path.display(), e))
+                        .ok(),
+                    _ => None,
+                };
+                let metadata = match &info {
+                    Some(i) if matches!(i.format, "WAV" | "RF64" | "BW64") => riff_metadata::read_wav_metadata(&file_path)
+                        .map_err(|e| log::debug!("Can't read metadata of {}: {}", file_path.display(), e))
+                        .ok()
+                        .filter(|m| *m != BounceMetadata::default()),
+                    _ => None,
+                };
+                bounces.push(BounceRead {
+                    path,
+                    modified_time,
+                    info,
+                    analysis,
+                    metadata,
+                });
             }
         }
     }
 
+    bounces
+}
+
+/// Cache the waveform peaks of a bounce and measure its loudness, tempo and
+/// key, in one decoding pass.
+fn analyze_bounce(peak_cache_dir: &Path, path: &Path) -> Result<BounceAnalysis, String> {
+    let mut decoder = AudioDecoder::open(path)?;
+    let mut peak_builder = peaks::PeakBuilder::new(decoder.channels);
+    let mut analyzer = BounceAnalyzer::new(decoder.sample_rate, decoder.channels);
+    while let Some(block) = decoder.next_block()? {
+        peak_builder.add(block);
+        analyzer.add(block);
+    }
+    peaks::store_peaks(peak_cache_dir, path, &peak_builder.finish(decoder.sample_rate))?;
+    Ok(analyzer.finish())
+}
+
+fn write_bounces(conn: &Connection, project_id: i64, bounces: &[BounceRead]) -> Result<(), String> {
+    for bounce in bounces {
+        let info = bounce.info.as_ref();
+        conn.execute(
+            "INSERT INTO bounces (project_id, bounce_path, modified_time, duration_seconds, format, sample_rate, bit_depth, channels, sample_format, embedded_metadata) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10) \
+             ON CONFLICT(bounce_path) DO UPDATE SET modified_time = ?3, duration_seconds = ?4, \
+             format = ?5, sample_rate = ?6, bit_depth = ?7, channels = ?8, sample_format = ?9, embedded_metadata = ?10",
+            params![
+                project_id,
+                bounce.path,
+                bounce.modified_time,
+                info.map(|i| i.duration_seconds),
+                info.map(|i| i.format),
+                info.map(|i| i.sample_rate),
+                info.and_then(|i| i.bit_depth),
+                info.map(|i| i.channels),
+                info.and_then(|i| i.sample_format),
+                bounce.metadata.as_ref().and_then(|m| serde_json::to_string(m).ok()),
+            ],
+        ).map_err(|e| e.to_string())?;
+        if let Some(ref analysis) = bounce.analysis {
+            crate::db::queries::set_bounce_analysis(conn, &bounce.path, analysis, &bounce.modified_time)?;
+        }
+    }
+    crate::db::queries::clear_stale_analysis(conn, project_id)?;
+    let bounce_paths: Vec<String> = bounces.iter().map(|b| b.path.clone()).collect();
+
     // Remove bounces that no longer exist
     if !bounce_paths.is_empty() {
         let placeholders: Vec<String> = (0..bounce_paths.len()).map(|i| format!("?{}", i + 2)).collect();
@@ -424,35 +933,51 @@ fn scan_bounces(conn: &Connection, project_id: i64, bounces_dir: &Path) -> Resul
     Ok(())
 }
 
-fn flag_missing_projects(conn: &Connection, found_paths: &[String]) -> Result<usize, String> {
-    if found_paths.is_empty() {
-        // If no projects found at all, mark all as missing
-        let count = conn.execute("UPDATE projects SET missing = 1 WHERE missing = 0", [])
-            .map_err(|e| e.to_string())?;
-        return Ok(count);
-    }
+/// Flag the projects that are gone: those in an online root that the scan
+/// didn't find, and those outside every root whose folder no longer exists.
+/// Projects on offline roots are left alone, as are those the scan skipped
+/// because an ignore rule now matches them (they were added before the rule
+/// was); found ones are unflagged.
+fn flag_missing_projects(
+    conn: &Connection,
+    found_paths: &[String],
+    roots: &[LibraryRoot],
+    ignore_patterns: &[String],
+) -> Result<usize, String> {
+    let found: HashSet<&str> = found_paths.iter().map(|p| p.as_str()).collect();
+    let mut stmt = conn.prepare("SELECT project_path, missing FROM projects")
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<(String, bool)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
 
-    let placeholders: Vec<String> = (0..found_paths.len()).map(|i| format!("?{}", i + 1)).collect();
-    let sql = format!(
-        "UPDATE projects SET missing = 1 WHERE project_path NOT IN ({}) AND missing = 0",
-        placeholders.join(",")
-    );
-    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
-    let params: Vec<&dyn rusqlite::types::ToSql> = found_paths.iter().map(|p| p as &dyn rusqlite::types::ToSql).collect();
-    let count = stmt.execute(params.as_slice()).map_err(|e| e.to_string())?;
-
-    // Also unflag previously missing projects that are found again
-    let sql2 = format!(
-        "UPDATE projects SET missing = 0 WHERE project_path IN ({}) AND missing = 1",
-        placeholders.join(",")
-    );
-    let mut stmt2 = conn.prepare(&sql2).map_err(|e| e.to_string())?;
-    stmt2.execute(params.as_slice()).map_err(|e| e.to_string())?;
+    let mut count = 0;
+    for (path, missing) in rows {
+        if found.contains(path.as_str()) {
+            // Unflag previously missing projects that are found again
+            if missing {
+                conn.execute("UPDATE projects SET missing = 0 WHERE project_path = ?1", params![path])
+                    .map_err(|e| e.to_string())?;
+            }
+            continue;
+        }
+        let gone = match crate::db::queries::library_root_for(roots, Path::new(&path)) {
+            Some(root) => root.online
+                && IgnoreRules::for_dir(Path::new(&root.path), ignore_patterns, Path::new(&path)).is_ok(),
+            None => !Path::new(&path).is_dir(),
+        };
+        if gone && !missing {
+            conn.execute("UPDATE projects SET missing = 1 WHERE project_path = ?1", params![path])
+                .map_err(|e| e.to_string())?;
+            count += 1;
+        }
+    }
 
     Ok(count)
 }
 
-fn get_modified_time(path: &Path) -> String {
+pub(crate) fn get_modified_time(path: &Path) -> String {
     fs::metadata(path)
         .and_then(|m| m.modified())
         .map(|t| {
@@ -466,85 +991,102 @@ use rusqlite::OptionalExtension;
 use crate::db::models::DiscoveredProject;
 
 /// Refresh metadata for all non-archived projects already in the DB.
-/// Does NOT discover new projects.
-pub fn refresh_library(conn: &Connection, bounce_folder_name: &str, app_data_dir: &Path, app: &AppHandle) -> Result<ScanSummary, String> {
+/// Does NOT discover new projects. Unchanged projects are skipped unless
+/// `options.full` is set. Like a scan, folders are read in parallel batches
+/// and each batch written in one transaction.
+pub fn refresh_library(db: &Mutex<Connection>, app: &AppHandle, options: &ScanOptions) -> Result<ScanSummary, String> {
     let mut summary = ScanSummary {
         found: 0,
         new: 0,
         updated: 0,
         missing: 0,
+        changed: 0,
+        skipped: 0,
         errors: Vec::new(),
     };
 
     // Get all non-archived projects from DB
-    let mut stmt = conn.prepare(
-        "SELECT id, project_path, name, genre_label FROM projects WHERE archived = 0"
-    ).map_err(|e| e.to_string())?;
-
-    let rows: Vec<(i64, String, String, String)> = stmt.query_map([], |row| {
-        Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
-    }).map_err(|e| e.to_string())?
-      .filter_map(|r| r.ok())
-      .collect();
+    let (rows, known, roots) = {
+        let conn = lock_db(db)?;
+        let roots = crate::db::queries::get_library_roots(&conn)?;
+        let default_bounce = crate::db::queries::get_setting(&conn, "bounce_folder_name")?
+            .unwrap_or_else(|| "Bounces".to_string());
+        let mut stmt = conn.prepare(
+            "SELECT project_path, name, genre_label FROM projects WHERE archived = 0"
+        ).map_err(|e| e.to_string())?;
 
-    for (_id, project_path, project_name, genre_label) in &rows {
-        summary.found += 1;
+        let rows: Vec<ProjectCandidate> = stmt.query_map([], |row| {
+            let path: String = row.get(0)?;
+            let bounce_folder_name = crate::db::queries::library_root_for(&roots, Path::new(&path))
+                .map_or_else(|| default_bounce.clone(), |r| r.bounce_folder_name.clone());
+            Ok(ProjectCandidate { path, name: row.get(1)?, genre_label: row.get(2)?, bounce_folder_name })
+        }).map_err(|e| e.to_string())?
+          .filter_map(|r| r.ok())
+          .collect();
+        (rows, load_known_projects(&conn)?, roots)
+    };
+    summary.found = rows.len();
 
-        app.emit("scan-progress", ScanProgress {
-            current: summary.found,
-            total: rows.len(),
-            project_name: project_name.clone(),
-            stage: "scanning".to_string(),
-        }).ok();
+    // Projects on an offline drive are neither refreshed nor flagged missing
+    let (online, offline): (Vec<ProjectCandidate>, Vec<ProjectCandidate>) = rows.into_iter()
+        .partition(|c| crate::db::queries::library_root_for(&roots, Path::new(&c.path)).is_none_or(|r| r.online));
+    summary.skipped += offline.len();
 
-        // Brief pause so the frontend can render each note on the piano roll
-        std::thread::sleep(Duration::from_millis(15));
+    let (present, gone): (Vec<ProjectCandidate>, Vec<ProjectCandidate>) = online.into_iter()
+        .partition(|c| Path::new(&c.path).is_dir());
 
-        let path = Path::new(project_path);
+    let mut conn = lock_db(db)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-        if !path.exists() || !path.is_dir() {
-            // Mark as missing
-            conn.execute(
-                "UPDATE projects SET missing = 1, updated_at = datetime('now') WHERE project_path = ?1",
-                params![project_path],
-            ).ok();
-            summary.missing += 1;
-            continue;
-        }
+    for project in &gone {
+        // Mark as missing
+        tx.execute(
+            "UPDATE projects SET missing = 1, updated_at = datetime('now') WHERE project_path = ?1",
+            params![project.path],
+        ).ok();
+        summary.missing += 1;
+    }
 
+    for project in &present {
         // Unflag missing if it was previously missing
-        conn.execute(
+        tx.execute(
             "UPDATE projects SET missing = 0 WHERE project_path = ?1 AND missing = 1",
-            params![project_path],
+            params![project.path],
         ).ok();
+    }
 
-        match process_project(conn, project_path, project_name, &genre_label, bounce_folder_name, app_data_dir) {
-            Ok(_) => {
-                summary.updated += 1;
-            }
-            Err(e) => {
-                summary.errors.push(format!("{}: {}", project_name, e));
-            }
-        }
+    tx.commit().map_err(|e| e.to_string())?;
+    drop(conn);
+
+    process_candidates(db, &present, &known, options, &mut summary, |progress| {
+        app.emit("scan-progress", progress).ok();
+    })?;
+
+    if let Some(ref cache_dir) = options.peak_cache_dir {
+        prune_peak_cache(&*lock_db(db)?, cache_dir);
     }
 
     log::info!(
-        "Refresh complete: {} checked, {} updated, {} missing, {} errors",
-        summary.found, summary.updated, summary.missing, summary.errors.len()
+        "Refresh complete: {} checked, {} updated, {} skipped, {} missing, {} errors",
+        summary.found, summary.updated, summary.skipped, summary.missing, summary.errors.len()
     );
 
     Ok(summary)
 }
 
-/// Recursively walk root folder and return projects NOT already in DB.
-pub fn discover_untracked_projects(conn: &Connection, root_folder: &str) -> Result<Vec<DiscoveredProject>, String> {
-    let root = Path::new(root_folder);
-    if !root.exists() || !root.is_dir() {
-        return Err(format!("Root folder does not exist: {}", root_folder));
+/// Recursively walk the online library roots and return projects NOT already in DB.
+pub fn discover_untracked_projects(conn: &Connection, roots: &[LibraryRoot]) -> Result<Vec<DiscoveredProject>, String> {
+    if roots.is_empty() {
+        return Err("No library folders configured. Please add one in Settings.".to_string());
     }
 
+    let patterns = ignore_rules::ignore_patterns(conn)?;
     let mut project_dirs: Vec<(PathBuf, String)> = Vec::new();
-    discover_project_dirs(root, root, 0, &mut project_dirs);
+    for root in roots.iter().filter(|r| r.online) {
+        let root_path = Path::new(&root.path);
+        let mut ignore = IgnoreRules::new(root_path, &patterns);
+        discover_project_dirs(root_path, genre_depth(root), &mut ignore, root_path, 0, &mut project_dirs);
+    }
 
     let mut discovered: Vec<DiscoveredProject> = Vec::new();
     for (path, genre_label) in project_dirs {
@@ -566,66 +1108,64 @@ pub fn discover_untracked_projects(conn: &Connection, root_folder: &str) -> Resu
 }
 
 /// Add a single project folder (any folder, .als not required).
-pub fn add_single_project(conn: &Connection, folder_path: &str, bounce_folder_name: &str, app_data_dir: &Path, _app: &AppHandle) -> Result<ScanSummary, String> {
+pub fn add_single_project(
+    db: &Mutex<Connection>,
+    folder_path: &str,
+    options: &ScanOptions,
+    on_progress: impl FnMut(ScanProgress),
+) -> Result<ScanSummary, String> {
     let path = Path::new(folder_path);
     if !path.exists() || !path.is_dir() {
         return Err(format!("Folder does not exist: {}", folder_path));
     }
 
-    let project_name = path.file_name()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_string();
-
-    // Try to infer genre_label from parent directory name if it's not the root
-    let genre_label = String::new();
-
-    let mut summary = ScanSummary {
-        found: 1,
-        new: 0,
-        updated: 0,
-        missing: 0,
-        errors: Vec::new(),
+    let project = DiscoveredProject {
+        path: folder_path.to_string(),
+        name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        genre_label: String::new(),
     };
-
-    match process_project(conn, folder_path, &project_name, &genre_label, bounce_folder_name, app_data_dir) {
-        Ok(is_new) => {
-            if is_new { summary.new = 1; } else { summary.updated = 1; }
-        }
-        Err(e) => {
-            summary.errors.push(format!("{}: {}", project_name, e));
-        }
-    }
-
-    Ok(summary)
+    import_projects(db, &[project], options, on_progress)
 }
 
-/// Import multiple discovered projects.
-pub fn import_projects(conn: &Connection, projects: &[DiscoveredProject], bounce_folder_name: &str, app_data_dir: &Path, _app: &AppHandle) -> Result<ScanSummary, String> {
+/// Import multiple discovered projects. Like a scan, their folders are read
+/// in parallel batches without holding the database lock, and every one is
+/// processed whether or not it changed.
+pub fn import_projects(
+    db: &Mutex<Connection>,
+    projects: &[DiscoveredProject],
+    options: &ScanOptions,
+    on_progress: impl FnMut(ScanProgress),
+) -> Result<ScanSummary, String> {
     let mut summary = ScanSummary {
         found: projects.len(),
         new: 0,
         updated: 0,
         missing: 0,
+        changed: 0,
+        skipped: 0,
         errors: Vec::new(),
     };
 
-    for project in projects {
-        let path = Path::new(&project.path);
-        if !path.exists() || !path.is_dir() {
-            summary.errors.push(format!("{}: folder does not exist", project.name));
-            continue;
-        }
-
-        match process_project(conn, &project.path, &project.name, &project.genre_label, bounce_folder_name, app_data_dir) {
-            Ok(is_new) => {
-                if is_new { summary.new += 1; } else { summary.updated += 1; }
-            }
-            Err(e) => {
-                summary.errors.push(format!("{}: {}", project.name, e));
+    let (candidates, known) = {
+        let conn = lock_db(db)?;
+        let mut candidates = Vec::new();
+        for project in projects {
+            let path = Path::new(&project.path);
+            if !path.exists() || !path.is_dir() {
+                summary.errors.push(format!("{}: folder does not exist", project.name));
+                continue;
             }
+            candidates.push(ProjectCandidate {
+                path: project.path.clone(),
+                name: project.name.clone(),
+                genre_label: project.genre_label.clone(),
+                bounce_folder_name: crate::db::queries::bounce_folder_for(&conn, &project.path)?,
+            });
         }
-    }
+        (candidates, load_known_projects(&conn)?)
+    };
+    let options = ScanOptions { full: true, ..options.clone() };
+    process_candidates(db, &candidates, &known, &options, &mut summary, on_progress)?;
 
     log::info!(
         "Import complete: {} processed, {} new, {} updated, {} errors",
@@ -635,38 +1175,77 @@ pub fn import_projects(conn: &Connection, projects: &[DiscoveredProject], bounce
     Ok(summary)
 }
 
-/// Parse the newest .als file for a project, extracting BPM, key, plugins, and samples.
+/// Parse the newest .als file for a project, extracting BPM, key, plugins, tracks,
+/// locators, and samples.
 /// Skips parsing if the file hasn't changed since last parse (based on mtime).
-fn parse_als_metadata(conn: &Connection, project_id: i64, als_path: &str) {
+pub(crate) fn parse_als_metadata(conn: &Connection, project_id: i64, project_dir: &Path, als_path: &str) {
+    let parsed_at = crate::db::queries::get_als_parsed_at(conn, project_id).ok().flatten();
+    let libraries = LiveLibraries::from_settings(conn).unwrap_or_else(|e| {
+        log::warn!("{}", e);
+        LiveLibraries::default()
+    });
+    if let Some(als) = read_als(project_dir, als_path, parsed_at, &libraries) {
+        store_als_metadata(conn, project_id, &als);
+    }
+}
+
+/// Parse a set and locate its samples. Returns None if the set is missing or
+/// unchanged since `parsed_at`.
+fn read_als(project_dir: &Path, als_path: &str, parsed_at: Option<i64>, libraries: &LiveLibraries) -> Option<AlsRead> {
     use crate::als_parser;
-    use crate::db::queries;
-    use crate::db::models::SampleWithStatus;
     use std::time::UNIX_EPOCH;
 
     let als_file = Path::new(als_path);
     if !als_file.exists() {
-        return;
+        return None;
     }
 
     // Get file mtime as Unix seconds
-    let current_mtime = match fs::metadata(als_file)
+    let current_mtime = fs::metadata(als_file)
         .and_then(|m| m.modified())
         .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64)
-    {
-        Ok(t) => t,
-        Err(_) => return,
-    };
+        .ok()?;
 
     // Check if already parsed at this mtime
-    if let Ok(Some(parsed_at)) = queries::get_als_parsed_at(conn, project_id) {
-        if parsed_at == current_mtime {
-            return; // File unchanged, skip
-        }
+    if parsed_at == Some(current_mtime) {
+        return None; // File unchanged, skip
     }
 
-    log::info!("Parsing .als metadata for project {}: {}", project_id, als_path);
+    log::info!("Parsing .als metadata: {}", als_path);
+
+    let metadata = als_parser::parse_als(als_file);
+
+    // Find each sample (absolute path, then relative to where the set says)
+    let samples: Vec<SampleWithStatus> = metadata.iter().flat_map(|m| &m.samples).map(|s| {
+        let found = sample_location::locate_sample(s, project_dir, libraries);
+        SampleWithStatus {
+            path: s.path.clone(),
+            filename: s.filename.clone(),
+            is_missing: found.resolved_path.is_none(),
+            relative_path: s.relative_path.clone(),
+            resolved_path: found.resolved_path
+                .map(|p| p.to_string_lossy().to_string())
+                .filter(|p| *p != s.path),
+            location: found.location.to_string(),
+        }
+    }).collect();
+
+    Some(AlsRead {
+        path: als_path.to_string(),
+        mtime: current_mtime,
+        metadata,
+        samples,
+    })
+}
 
-    match als_parser::parse_als(als_file) {
+fn store_als_metadata(conn: &Connection, project_id: i64, als: &AlsRead) {
+    use crate::db::queries;
+    use crate::db::models::ArrangementStats;
+
+    let current_mtime = als.mtime;
+    let samples_with_status = &als.samples;
+
+    match &als.metadata {
         Err(e) => {
             log::warn!("Failed to parse .als for project {}: {}", project_id, e);
             // Record mtime anyway so we don't retry on every scan
@@ -678,27 +1257,47 @@ fn parse_als_metadata(conn: &Connection, project_id: i64, als_path: &str) {
                 queries::set_bpm_if_empty(conn, project_id, bpm).ok();
             }
 
-            // Set key if not already set by user
-            if let (Some(tonic), Some(scale)) = (&metadata.key_tonic, &metadata.key_scale) {
-                let key_str = format!("{} {}", tonic, scale);
-                queries::set_key_if_empty(conn, project_id, &key_str).ok();
+            // Set key if not already set by user. Sets without a <KeySignature>
+            // (older Live versions) fall back to the key estimated from their MIDI notes.
+            let estimated_key = metadata.estimated_key.as_ref().map(|k| k.to_string());
+            let als_key = match (&metadata.key_tonic, &metadata.key_scale) {
+                (Some(tonic), Some(scale)) => Some(format!("{} {}", tonic, scale)),
+                _ => estimated_key.clone(),
+            };
+            if let Some(key_str) = &als_key {
+                queries::set_key_if_empty(conn, project_id, key_str).ok();
             }
+            queries::set_als_tempo_key(conn, project_id, metadata.bpm, als_key.as_deref()).ok();
 
-            // Store plugins
+            // Store plugins and tracks
             queries::replace_project_plugins(conn, project_id, &metadata.plugins).ok();
-
-            // Check sample existence and store
-            let samples_with_status: Vec<SampleWithStatus> = metadata.samples.iter().map(|s| {
-                let is_missing = !Path::new(&s.path).exists();
-                SampleWithStatus {
-                    path: s.path.clone(),
-                    filename: s.filename.clone(),
-                    is_missing,
-                }
-            }).collect();
+            queries::replace_project_tracks(conn, project_id, &metadata.tracks).ok();
+            queries::replace_project_midi_clips(conn, project_id, &metadata.midi_clips).ok();
+            queries::set_pitch_profile(conn, project_id, &metadata.pitch_class_histogram, estimated_key.as_deref()).ok();
+
+            // Arrangement length, time signature and clip counts
+            let stats = ArrangementStats {
+                arrangement_length_bars: metadata.arrangement_length_bars,
+                arrangement_length_seconds: metadata.arrangement_length_seconds,
+                time_signature: metadata.time_signature.map(|t| t.to_string()),
+                scene_count: Some(metadata.scene_count),
+                session_clip_count: Some(metadata.session_clip_count),
+                arrangement_clip_count: Some(metadata.arrangement_clip_count),
+                live_version: metadata.live_version.clone(),
+                tempo_min: metadata.tempo_min,
+                tempo_max: metadata.tempo_max,
+                tempo_avg: metadata.tempo_avg,
+            };
+            queries::set_arrangement_stats(conn, project_id, &stats).ok();
+            queries::set_set_tempo_map(conn, &als.path, &metadata.tempo_map).ok();
+
+            // Mirror arrangement locators as 'locator' markers (user markers untouched)
+            if let Err(e) = queries::sync_locator_markers(conn, project_id, &metadata.locators) {
+                log::warn!("Failed to sync locators for project {}: {}", project_id, e);
+            }
 
             let any_missing = samples_with_status.iter().any(|s| s.is_missing);
-            queries::replace_project_samples(conn, project_id, &samples_with_status).ok();
+            queries::replace_project_samples(conn, project_id, samples_with_status).ok();
             queries::set_has_missing_deps(conn, project_id, any_missing).ok();
 
             // Record parse time
@@ -708,8 +1307,10 @@ fn parse_als_metadata(conn: &Connection, project_id: i64, als_path: &str) {
             queries::rebuild_fts_tags(conn, project_id).ok();
 
             log::info!(
-                "Parsed .als for project {}: {} plugins, {} samples ({} missing)",
+                "Parsed .als for project {}: {} tracks, {} locators, {} plugins, {} samples ({} missing)",
                 project_id,
+                metadata.tracks.len(),
+                metadata.locators.len(),
                 metadata.plugins.len(),
                 samples_with_status.len(),
                 samples_with_status.iter().filter(|s| s.is_missing).count()
@@ -738,10 +1339,20 @@ mod tests {
         fs::write(dir.join(name), b"").unwrap();
     }
 
+    fn library_root(path: &Path) -> LibraryRoot {
+        LibraryRoot {
+            id: 1,
+            path: path.to_string_lossy().to_string(),
+            bounce_folder_name: "Bounces".to_string(),
+            genre_depth: 1,
+            online: path.is_dir(),
+        }
+    }
+
     /// Helper: run discover_project_dirs and return sorted results for deterministic comparison.
     fn discover_sorted(root: &Path) -> Vec<(PathBuf, String)> {
         let mut results = Vec::new();
-        discover_project_dirs(root, root, 0, &mut results);
+        discover_project_dirs(root, 1, &mut IgnoreRules::new(root, &[]), root, 0, &mut results);
         results.sort_by(|a, b| a.0.cmp(&b.0));
         results
     }
@@ -885,24 +1496,438 @@ mod tests {
         assert!(results.is_empty(), "Projects deeper than MAX_SCAN_DEPTH should not be found");
     }
 
+    #[test]
+    fn test_discover_honours_ignore_rules() {
+        // root/.setcrateignore ignores "*Template*"
+        // root/Trash/Old/song.als           → ignored by the default settings
+        // root/Live Template/song.als       → ignored by the root's file
+        // root/Packs/.setcrateignore ignores "Demo*"
+        // root/Packs/Demo Song/song.als     → ignored
+        // root/Packs/My Song/song.als       → found
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        for dir in ["Trash/Old", "Live Template", "Packs/Demo Song", "Packs/My Song"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            touch_als(&root.join(dir), "song.als");
+        }
+        fs::write(root.join(ignore_rules::IGNORE_FILE_NAME), "*Template*\n").unwrap();
+        fs::write(root.join("Packs").join(ignore_rules::IGNORE_FILE_NAME), "Demo*\n").unwrap();
+
+        let discovered = discover_untracked_projects(&conn, &[library_root(root)]).unwrap();
+        let names: Vec<&str> = discovered.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["My Song"]);
+
+        conn.execute("UPDATE settings SET value = '[]' WHERE key = ?1", params![ignore_rules::IGNORE_PATTERNS_SETTING]).unwrap();
+        let discovered = discover_untracked_projects(&conn, &[library_root(root)]).unwrap();
+        assert_eq!(discovered.len(), 2, "Trash is only ignored by the settings patterns");
+    }
+
+    #[test]
+    fn test_scan_leaves_projects_under_new_ignore_rules_alone() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("Projects");
+        fs::create_dir_all(root.join("Templates").join("Starter")).unwrap();
+        touch_als(&root.join("Templates").join("Starter"), "starter.als");
+        crate::db::queries::add_library_root(&conn, &root.to_string_lossy()).unwrap();
+        let db = Mutex::new(conn);
+        let roots = crate::db::queries::get_library_roots(&db.lock().unwrap()).unwrap();
+        assert_eq!(scan_roots(&db, &roots, &ScanOptions::default(), |_| {}).unwrap().new, 1);
+
+        // A project added before "Templates/" was ignored is skipped, not missing
+        let options = ScanOptions { ignore_patterns: vec!["Templates/".to_string()], ..ScanOptions::default() };
+        let summary = scan_roots(&db, &roots, &options, |_| {}).unwrap();
+        assert_eq!((summary.found, summary.missing), (0, 0));
+    }
+
+    #[test]
+    fn test_read_projects_keeps_candidate_order() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        let db = Mutex::new(conn);
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("Projects");
+        let candidates: Vec<ProjectCandidate> = (0..12).map(|i| {
+            let dir = root.join(format!("Song {:02}", i));
+            fs::create_dir_all(&dir).unwrap();
+            touch_als(&dir, "song.als");
+            ProjectCandidate::new(&dir, "", "Bounces")
+        }).collect();
+        scan_roots(&db, &[library_root(&root)], &ScanOptions::default(), |_| {}).unwrap();
+
+        // Only the changed folder is read again; the rest are skipped in place
+        touch_als(&root.join("Song 05"), "song 2.als");
+        let known = load_known_projects(&db.lock().unwrap()).unwrap();
+        let reads = read_projects(&candidates, &known, &ScanOptions::default());
+        assert_eq!(reads.len(), candidates.len());
+        let read: Vec<usize> = reads.iter().enumerate().filter(|(_, r)| r.is_some()).map(|(i, _)| i).collect();
+        assert_eq!(read, vec![5]);
+        assert_eq!(reads[5].as_ref().unwrap().sets.len(), 2);
+
+        let reads = read_projects(&candidates, &known, &ScanOptions { full: true, ..ScanOptions::default() });
+        let paths: Vec<&str> = reads.iter().map(|r| r.as_ref().unwrap().path.as_str()).collect();
+        let expected: Vec<&str> = candidates.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, expected);
+    }
+
+    #[test]
+    fn test_scan_writes_in_batches_without_holding_the_lock() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        let db = Mutex::new(conn);
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("Projects");
+        let count = SCAN_BATCH_SIZE + 3;
+        for i in 0..count {
+            let dir = root.join(format!("Song {:03}", i));
+            fs::create_dir_all(&dir).unwrap();
+            touch_als(&dir, "song.als");
+        }
+
+        let mut reported = 0;
+        let summary = scan_roots(&db, &[library_root(&root)], &ScanOptions::default(), |progress| {
+            reported += 1;
+            assert_eq!(progress.total, count);
+            assert!(db.try_lock().is_ok(), "progress is reported with the database unlocked");
+        }).unwrap();
+        assert_eq!((reported, summary.new), (count, count));
+        let stored: usize = db.lock().unwrap().query_row("SELECT COUNT(*) FROM projects", [], |row| row.get(0)).unwrap();
+        assert_eq!(stored, count);
+    }
+
+    #[test]
+    fn test_import_reads_projects_without_holding_the_lock() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        let db = Mutex::new(conn);
+        let tmp = TempDir::new().unwrap();
+        let song = tmp.path().join("Song");
+        fs::create_dir_all(&song).unwrap();
+        touch_als(&song, "song.als");
+        let projects = [
+            DiscoveredProject { path: song.to_string_lossy().to_string(), name: "Song".to_string(), genre_label: "House".to_string() },
+            DiscoveredProject { path: tmp.path().join("Gone").to_string_lossy().to_string(), name: "Gone".to_string(), genre_label: String::new() },
+        ];
+
+        let mut reported = 0;
+        let summary = import_projects(&db, &projects, &ScanOptions::default(), |_| {
+            reported += 1;
+            assert!(db.try_lock().is_ok(), "progress is reported with the database unlocked");
+        }).unwrap();
+        assert_eq!((reported, summary.found, summary.new), (1, 2, 1));
+        assert_eq!(summary.errors, vec!["Gone: folder does not exist"]);
+
+        // Adding it again updates it, even though nothing changed
+        let summary = add_single_project(&db, &song.to_string_lossy(), &ScanOptions::default(), |_| {}).unwrap();
+        assert_eq!((summary.updated, summary.skipped), (1, 0));
+        assert!(add_single_project(&db, &tmp.path().join("Gone").to_string_lossy(), &ScanOptions::default(), |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_failed_project_write_is_rolled_back() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        // Storing the broken project's set fails after its project row went in
+        conn.execute_batch(
+            "CREATE TRIGGER fail_broken BEFORE INSERT ON ableton_sets WHEN NEW.set_path LIKE '%Broken%' \
+             BEGIN SELECT RAISE(ABORT, 'disk full'); END;"
+        ).unwrap();
+        let db = Mutex::new(conn);
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("Projects");
+        for name in ["Broken", "Fine"] {
+            fs::create_dir_all(root.join(name)).unwrap();
+            touch_als(&root.join(name), "song.als");
+        }
+
+        let summary = scan_roots(&db, &[library_root(&root)], &ScanOptions::default(), |_| {}).unwrap();
+        assert_eq!(summary.new, 1);
+        assert_eq!(summary.errors.len(), 1);
+        assert!(summary.errors[0].contains("disk full"));
+        let names: Vec<String> = db.lock().unwrap().prepare("SELECT name FROM projects").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(names, vec!["Fine"]);
+    }
+
+    #[test]
+    fn test_scan_skips_unchanged_projects() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        let db = Mutex::new(conn);
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("Projects");
+        let (song, sketch) = (root.join("Song"), root.join("Techno").join("Sketch"));
+        fs::create_dir_all(&song).unwrap();
+        fs::create_dir_all(&sketch).unwrap();
+        touch_als(&song, "song.als");
+        touch_als(&sketch, "sketch.als");
+
+        let scan = |options: &ScanOptions| {
+            let mut events = 0;
+            let summary = scan_roots(&db, &[library_root(&root)], options, |_| events += 1).unwrap();
+            assert_eq!(events, 2, "every project is reported, skipped or not");
+            summary
+        };
+        let incremental = ScanOptions::default();
+
+        let first = scan(&incremental);
+        assert_eq!((first.new, first.changed, first.skipped), (2, 2, 0));
+
+        let second = scan(&incremental);
+        assert_eq!((second.changed, second.skipped, second.updated), (0, 2, 0));
+
+        fs::create_dir(song.join("Bounces")).unwrap();
+        fs::write(song.join("Bounces").join("song.wav"), b"RIFF").unwrap();
+        let third = scan(&incremental);
+        assert_eq!((third.changed, third.updated, third.skipped), (1, 1, 1));
+
+        // A forced re-parse (as after a migration) is never skipped
+        db.lock().unwrap().execute("UPDATE projects SET als_parsed_at = NULL WHERE project_path = ?1", params![sketch.to_string_lossy()]).unwrap();
+        assert_eq!(scan(&incremental).changed, 1);
+
+        let full = scan(&ScanOptions { full: true, ..ScanOptions::default() });
+        assert_eq!((full.changed, full.skipped), (2, 0));
+    }
+
+    #[test]
+    fn test_scan_moves_renamed_project() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        let db = Mutex::new(conn);
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("Projects");
+        let track = root.join("Techno").join("Track A");
+        fs::create_dir_all(&track).unwrap();
+        fs::write(track.join("Track A.als"), b"not a real set").unwrap();
+
+        let scan = || scan_roots(&db, &[library_root(&root)], &ScanOptions::default(), |_| {}).unwrap();
+        scan();
+        let id: i64 = {
+            let conn = db.lock().unwrap();
+            conn.execute("UPDATE projects SET notes = 'mix the bass', remote_id = 42", []).unwrap();
+            conn.execute(
+                "INSERT INTO version_notes (set_id, project_id, note) SELECT id, project_id, 'first draft' FROM ableton_sets",
+                [],
+            ).unwrap();
+            conn.query_row("SELECT id FROM projects", [], |row| row.get(0)).unwrap()
+        };
+
+        let renamed = root.join("Techno").join("Track A (final)");
+        fs::rename(&track, &renamed).unwrap();
+        let summary = scan();
+        assert_eq!((summary.new, summary.updated, summary.missing), (0, 1, 0));
+
+        let conn = db.lock().unwrap();
+        let row: (i64, String, String, String, i64, bool) = conn.query_row(
+            "SELECT id, project_path, name, notes, remote_id, missing FROM projects",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+        ).unwrap();
+        assert_eq!(row, (id, renamed.to_string_lossy().to_string(), "Track A (final)".to_string(), "mix the bass".to_string(), 42, false));
+
+        let set_path: String = conn.query_row(
+            "SELECT s.set_path FROM version_notes v JOIN ableton_sets s ON s.id = v.set_id",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(set_path, renamed.join("Track A.als").to_string_lossy());
+        drop(conn);
+
+        // Moving it to another genre folder relabels it, unless the label was edited
+        db.lock().unwrap().execute("INSERT INTO library_roots (path) VALUES (?1)", params![root.to_string_lossy()]).unwrap();
+        let house = root.join("House").join("Track A (final)");
+        fs::create_dir_all(house.parent().unwrap()).unwrap();
+        fs::rename(&renamed, &house).unwrap();
+        scan();
+        let genre = || -> String { db.lock().unwrap().query_row("SELECT genre_label FROM projects", [], |row| row.get(0)).unwrap() };
+        assert_eq!(genre(), "House");
+
+        db.lock().unwrap().execute("UPDATE projects SET genre_label = 'Deep House'", []).unwrap();
+        fs::create_dir_all(root.join("Techno")).unwrap();
+        fs::rename(&house, root.join("Techno").join("Track A (final)")).unwrap();
+        scan();
+        assert_eq!(genre(), "Deep House");
+    }
+
+    #[test]
+    fn test_scan_indexes_backups() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        let db = Mutex::new(conn);
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("Projects");
+        let song = root.join("Song");
+        fs::create_dir_all(song.join("Backup")).unwrap();
+        touch_als(&song, "Song.als");
+        touch_als(&song.join("Backup"), "Song [2024-01-01 120000].als");
+        touch_als(&song.join("Backup"), "Demo [2024-01-02 120000].als");
+
+        let scan = || scan_roots(&db, &[library_root(&root)], &ScanOptions::default(), |_| {}).unwrap();
+        let first = scan();
+        assert_eq!(first.found, 1, "the Backup folder is not a project");
+
+        let backups = |conn: &Connection| -> Vec<(String, Option<i64>)> {
+            let mut stmt = conn.prepare(
+                "SELECT b.backed_up_at, s.id FROM set_backups b LEFT JOIN ableton_sets s ON s.id = b.set_id ORDER BY b.backed_up_at"
+            ).unwrap();
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap().map(|r| r.unwrap()).collect()
+        };
+        {
+            let conn = db.lock().unwrap();
+            let set_id: i64 = conn.query_row("SELECT id FROM ableton_sets", [], |row| row.get(0)).unwrap();
+            assert_eq!(backups(&conn), vec![
+                ("2024-01-01 12:00:00".to_string(), Some(set_id)),
+                ("2024-01-02 12:00:00".to_string(), None),
+            ]);
+        }
+
+        // Removing a backup changes the folder snapshot, so the project is re-read
+        fs::remove_file(song.join("Backup").join("Demo [2024-01-02 120000].als")).unwrap();
+        assert_eq!(scan().changed, 1);
+        assert_eq!(backups(&db.lock().unwrap()).len(), 1);
+    }
+
+    #[test]
+    fn test_scan_caches_bounce_peaks() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        let db = Mutex::new(conn);
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("Projects");
+        let song = root.join("Song");
+        fs::create_dir_all(song.join("Bounces")).unwrap();
+        touch_als(&song, "Song.als");
+        let bounce = song.join("Bounces").join("Song.wav");
+        fs::write(&bounce, include_bytes!("../../tests/fixtures/wav/pcm16_stereo.wav")).unwrap();
+        fs::write(song.join("Bounces").join("notes.mp3"), b"not audio").unwrap();
+
+        let cache_dir = tmp.path().join("peaks");
+        let options = ScanOptions { peak_cache_dir: Some(cache_dir.clone()), ..ScanOptions::default() };
+        let cached = || fs::read_dir(&cache_dir).map_or(0, |entries| entries.count());
+        // Peaks cached by the waveform view don't stand in for an analysis
+        peaks::load_peaks(&cache_dir, &bounce).unwrap();
+        scan_roots(&db, &[library_root(&root)], &options, |_| {}).unwrap();
+        assert_eq!(cached(), 1, "only the readable bounce gets peaks");
+        assert_eq!(peaks::load_peaks(&cache_dir, &bounce).unwrap().frames, 4410);
+
+        // Loudness is measured in the same pass; 0.1 s is too short to gate
+        let loudness = |db: &Mutex<Connection>| -> (Option<f64>, Option<f64>, bool) {
+            db.lock().unwrap().query_row(
+                "SELECT integrated_lufs, sample_peak_dbfs, loudness_mtime IS modified_time FROM bounces WHERE bounce_path LIKE '%.wav'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            ).unwrap()
+        };
+        let (integrated, sample_peak, current) = loudness(&db);
+        assert_eq!((integrated, current), (None, true));
+        assert!((sample_peak.unwrap() + 6.02).abs() < 0.01);
+
+        // A changed bounce loses its measurement until it is analysed again
+        db.lock().unwrap().execute("UPDATE bounces SET modified_time = '2000-01-01 00:00:00'", []).unwrap();
+        crate::db::queries::clear_stale_analysis(&db.lock().unwrap(), 1).unwrap();
+        assert_eq!(loudness(&db), (None, None, false));
+
+        // ...which the next scan of its folder does, peaks cached or not
+        db.lock().unwrap().execute("DELETE FROM scan_snapshots", []).unwrap();
+        scan_roots(&db, &[library_root(&root)], &options, |_| {}).unwrap();
+        assert!(loudness(&db).2);
+
+        fs::remove_file(&bounce).unwrap();
+        scan_roots(&db, &[library_root(&root)], &options, |_| {}).unwrap();
+        assert_eq!(cached(), 0, "peaks of a deleted bounce are pruned");
+    }
+
     #[test]
     fn test_derive_genre_label_direct_child() {
         let root = Path::new("/music");
         let project = Path::new("/music/TrackA");
-        assert_eq!(derive_genre_label(root, project), "");
+        assert_eq!(derive_genre_label(root, project, 1), "");
     }
 
     #[test]
     fn test_derive_genre_label_two_levels() {
         let root = Path::new("/music");
         let project = Path::new("/music/Techno/TrackA");
-        assert_eq!(derive_genre_label(root, project), "Techno");
+        assert_eq!(derive_genre_label(root, project, 1), "Techno");
     }
 
     #[test]
     fn test_derive_genre_label_three_levels() {
         let root = Path::new("/music");
         let project = Path::new("/music/2026/EP/TrackA");
-        assert_eq!(derive_genre_label(root, project), "2026");
+        assert_eq!(derive_genre_label(root, project, 1), "2026");
+    }
+
+    #[test]
+    fn test_derive_genre_label_custom_depth() {
+        let root = Path::new("/music");
+        let project = Path::new("/music/Artist/Techno/TrackA");
+        assert_eq!(derive_genre_label(root, project, 2), "Techno");
+        assert_eq!(derive_genre_label(root, project, 0), "");
+        assert_eq!(derive_genre_label(root, Path::new("/music/Artist/TrackA"), 2), "");
+    }
+
+    #[test]
+    fn test_scan_leaves_offline_roots_alone() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        let db = Mutex::new(conn);
+        let tmp = TempDir::new().unwrap();
+        let (ssd, nas) = (tmp.path().join("SSD"), tmp.path().join("NAS"));
+        fs::create_dir_all(ssd.join("Song")).unwrap();
+        fs::create_dir_all(nas.join("Techno").join("Jam")).unwrap();
+        touch_als(&ssd.join("Song"), "song.als");
+        touch_als(&nas.join("Techno").join("Jam"), "jam.als");
+        fs::create_dir(nas.join("Techno").join("Jam").join("Renders")).unwrap();
+        fs::write(nas.join("Techno").join("Jam").join("Renders").join("jam.wav"), b"RIFF").unwrap();
+
+        let mut roots = vec![library_root(&ssd), library_root(&nas)];
+        roots[1].bounce_folder_name = "Renders".to_string();
+        let first = scan_roots(&db, &roots, &ScanOptions::default(), |_| {}).unwrap();
+        assert_eq!(first.new, 2);
+
+        // Unmount the NAS: its project is not flagged missing
+        roots[1].online = false;
+        let second = scan_roots(&db, &roots, &ScanOptions::default(), |_| {}).unwrap();
+        assert_eq!((second.found, second.missing), (1, 0));
+
+        // A project deleted from an online root is
+        fs::remove_dir_all(ssd.join("Song")).unwrap();
+        let third = scan_roots(&db, &roots, &ScanOptions::default(), |_| {}).unwrap();
+        assert_eq!(third.missing, 1);
+
+        let (genre, bounces): (String, i64) = db.lock().unwrap().query_row(
+            "SELECT genre_label, (SELECT COUNT(*) FROM bounces b WHERE b.project_id = p.id) FROM projects p WHERE name = 'Jam'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!((genre.as_str(), bounces), ("Techno", 1), "the NAS uses its own bounce folder name");
+    }
+
+    #[test]
+    fn test_scan_treats_bare_mount_point_as_offline() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        let tmp = TempDir::new().unwrap();
+        let nas = tmp.path().join("NAS");
+        fs::create_dir_all(nas.join("Jam")).unwrap();
+        touch_als(&nas.join("Jam"), "jam.als");
+        crate::db::queries::add_library_root(&conn, &nas.to_string_lossy()).unwrap();
+        let db = Mutex::new(conn);
+        let roots = || crate::db::queries::get_library_roots(&db.lock().unwrap()).unwrap();
+        assert_eq!(scan_roots(&db, &roots(), &ScanOptions::default(), |_| {}).unwrap().new, 1);
+
+        // Unmounting the drive leaves its mount point behind, empty
+        fs::remove_dir_all(&nas).unwrap();
+        fs::create_dir(&nas).unwrap();
+        let roots = roots();
+        assert!(!roots[0].online);
+        let summary = scan_roots(&db, &roots, &ScanOptions::default(), |_| {}).unwrap();
+        assert_eq!((summary.found, summary.missing), (0, 0));
     }
 }
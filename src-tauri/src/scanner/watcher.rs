@@ -0,0 +1,488 @@
+// Live library watcher. Watches the online library roots and, once a burst of
+// filesystem events has settled, re-processes the project folders they
+// touched with the same logic as a scan: new or saved sets and bounces update
+// the project, renamed or deleted folders flag their projects missing.
+
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rusqlite::{params, Connection};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::ignore_rules::{self, IgnoreRules};
+use super::walker::{self, ScanProgress};
+use crate::db::models::{LibraryRoot, ScanSummary};
+use crate::db::queries;
+use crate::db::DbState;
+
+/// Quiet period after the last event before a batch is processed. Saving a
+/// set or rendering a bounce produces many events over a second or two.
+pub const WATCH_DEBOUNCE: Duration = Duration::from_millis(1500);
+
+/// Longest a change waits for its batch, in debounce periods. Recording into
+/// a project or copying onto the library drive never goes quiet for long.
+const MAX_BATCH_DELAY: u32 = 10;
+
+/// Managed Tauri state holding the running watcher (None when no library
+/// root is online).
+pub struct WatcherState(pub Mutex<Option<LibraryWatcher>>);
+
+/// A running watcher. Dropping it stops watching and ends its thread.
+pub struct LibraryWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl LibraryWatcher {
+    /// Watch `roots` recursively. `on_batch` runs on a background thread with
+    /// the changed paths once no event has arrived for `debounce`, or once
+    /// the oldest of them has waited `MAX_BATCH_DELAY` times that.
+    pub fn start(
+        roots: &[PathBuf],
+        debounce: Duration,
+        on_batch: impl FnMut(Vec<PathBuf>) + Send + 'static,
+    ) -> Result<Self, String> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |res| {
+            tx.send(res).ok();
+        }).map_err(|e| format!("Failed to create file watcher: {}", e))?;
+        for root in roots {
+            watcher.watch(root, RecursiveMode::Recursive)
+                .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+        }
+        std::thread::spawn(move || debounce_loop(rx, debounce, on_batch));
+        Ok(LibraryWatcher { _watcher: watcher })
+    }
+}
+
+/// Collect event paths until the channel has been quiet for `debounce`, or
+/// the oldest path has waited `MAX_BATCH_DELAY` debounce periods, then hand
+/// them over. Ends when the watcher (and with it the sender) is dropped.
+fn debounce_loop(
+    rx: Receiver<notify::Result<Event>>,
+    debounce: Duration,
+    mut on_batch: impl FnMut(Vec<PathBuf>),
+) {
+    let max_delay = debounce * MAX_BATCH_DELAY;
+    let mut pending: BTreeSet<PathBuf> = BTreeSet::new();
+    // When the oldest pending path came in
+    let mut since: Option<Instant> = None;
+    loop {
+        let next = match since {
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            Some(since) => rx.recv_timeout(debounce.min(max_delay.saturating_sub(since.elapsed()))),
+        };
+        let quiet = match next {
+            Ok(Ok(event)) => {
+                if !matches!(event.kind, EventKind::Access(_)) && !event.paths.is_empty() {
+                    since.get_or_insert_with(Instant::now);
+                    pending.extend(event.paths);
+                }
+                false
+            }
+            Ok(Err(e)) => {
+                log::warn!("Library watcher error: {}", e);
+                false
+            }
+            Err(RecvTimeoutError::Timeout) => true,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let overdue = since.is_some_and(|since| since.elapsed() >= max_delay);
+        if quiet || overdue {
+            on_batch(std::mem::take(&mut pending).into_iter().collect());
+            since = None;
+        }
+    }
+}
+
+/// (Re)start the watcher for the online library roots, or stop it if there
+/// are none. Called at startup and whenever the library roots change.
+pub fn restart_watcher(app: &AppHandle) {
+    let roots = {
+        let db = app.state::<DbState>();
+        let conn = match db.0.lock() {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to lock DB for watcher: {e}");
+                return;
+            }
+        };
+        queries::get_library_roots(&conn).unwrap_or_default()
+    };
+
+    let state = app.state::<WatcherState>();
+    let mut current = match state.0.lock() {
+        Ok(w) => w,
+        Err(e) => {
+            log::error!("Failed to lock watcher state: {e}");
+            return;
+        }
+    };
+    // Stop the old watcher before starting a new one
+    *current = None;
+
+    let roots: Vec<LibraryRoot> = roots.into_iter().filter(|r| r.online).collect();
+    if roots.is_empty() {
+        return;
+    }
+    let paths: Vec<PathBuf> = roots.iter().map(|r| PathBuf::from(&r.path)).collect();
+    let handle = app.clone();
+    match LibraryWatcher::start(&paths, WATCH_DEBOUNCE, move |changed| {
+        handle_batch(&handle, &roots, &changed);
+    }) {
+        Ok(watcher) => {
+            log::info!("Watching {} library folders for changes", paths.len());
+            *current = Some(watcher);
+        }
+        Err(e) => log::warn!("{}", e),
+    }
+}
+
+fn handle_batch(app: &AppHandle, roots: &[LibraryRoot], paths: &[PathBuf]) {
+    let app_data_dir = match app.path().app_data_dir() {
+        Ok(d) => d,
+        Err(e) => {
+            log::error!("Failed to get app data dir: {e}");
+            return;
+        }
+    };
+    let db = app.state::<DbState>();
+    let summary = apply_changes(&db.0, roots, paths, &app_data_dir, |progress| {
+        app.emit("watch-progress", progress).ok();
+    });
+    if summary.changed > 0 {
+        match db.0.lock() {
+            Ok(conn) => walker::generate_missing_covers(&conn, &app_data_dir, app),
+            Err(e) => log::error!("Failed to lock DB for covers: {e}"),
+        }
+    }
+    if summary.changed > 0 || summary.missing > 0 {
+        app.emit("watch-progress", ScanProgress {
+            current: 0, total: 0,
+            project_name: String::new(),
+            stage: "complete".to_string(),
+        }).ok();
+    }
+}
+
+/// Project folders a batch of changed paths may affect. A set or bounce
+/// points at its project; any folder (or vanished path) under a root may be
+/// a project or a folder of projects that was created, renamed or deleted.
+/// Other files, and anything inside hidden folders, are ignored.
+pub(crate) fn affected_dirs(roots: &[LibraryRoot], paths: &[PathBuf]) -> BTreeSet<PathBuf> {
+    let mut dirs = BTreeSet::new();
+    for path in paths {
+        let library_root = match queries::library_root_for(roots, path) {
+            Some(r) => r,
+            None => continue,
+        };
+        let root = Path::new(&library_root.path);
+        if path == root {
+            continue;
+        }
+        let bounce_folder_name = library_root.bounce_folder_name.as_str();
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        if relative.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.')) {
+            continue;
+        }
+
+        let parent = path.parent().unwrap_or(root);
+        let parent_name = parent.file_name().map(|n| n.to_string_lossy().to_string());
+        let is_als = path.extension().is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case("als"));
+        let dir = if parent_name.as_deref() == Some(bounce_folder_name) || (is_als && parent_name.as_deref() == Some("Backup")) {
+            parent.parent().unwrap_or(root)
+        } else if is_als {
+            parent
+        } else if path.is_dir() || !path.exists() {
+            path.as_path()
+        } else {
+            continue;
+        };
+        if dir != root {
+            dirs.insert(dir.to_path_buf());
+        }
+    }
+    dirs
+}
+
+/// Update the library for a batch of changed paths: process every project
+/// folder found in the affected folders, and flag the library's projects in
+/// them that are gone (deleted, renamed away or left without a set) missing.
+/// Folders are read with the database unlocked.
+pub(crate) fn apply_changes(
+    db: &Mutex<Connection>,
+    roots: &[LibraryRoot],
+    paths: &[PathBuf],
+    app_data_dir: &Path,
+    mut on_progress: impl FnMut(ScanProgress),
+) -> ScanSummary {
+    let mut summary = ScanSummary {
+        found: 0,
+        new: 0,
+        updated: 0,
+        missing: 0,
+        changed: 0,
+        skipped: 0,
+        errors: Vec::new(),
+    };
+
+    let (patterns, online_roots) = match db.lock() {
+        Ok(conn) => {
+            let patterns = ignore_rules::ignore_patterns(&conn).unwrap_or_else(|e| {
+                log::warn!("{}", e);
+                Vec::new()
+            });
+            // A vanished root (or a bare mount point left behind) means the
+            // drive went offline, not that its projects were deleted
+            let online: HashSet<&str> = roots.iter()
+                .filter(|r| queries::root_is_online(&conn, &r.path))
+                .map(|r| r.path.as_str())
+                .collect();
+            (patterns, online)
+        }
+        Err(e) => {
+            summary.errors.push(e.to_string());
+            return summary;
+        }
+    };
+    let mut project_dirs: Vec<(PathBuf, String)> = Vec::new();
+    let mut gone: Vec<PathBuf> = Vec::new();
+    for dir in affected_dirs(roots, paths) {
+        let library_root = match queries::library_root_for(roots, &dir) {
+            Some(r) => r,
+            None => continue,
+        };
+        let root = Path::new(&library_root.path);
+        let genre_depth = walker::genre_depth(library_root);
+        let mut ignore = match IgnoreRules::for_dir(root, &patterns, &dir) {
+            Ok(ignore) => ignore,
+            Err(reason) => {
+                log::info!("Skipping {}", reason);
+                continue;
+            }
+        };
+        if dir.is_dir() && walker::has_als_files(&dir) {
+            let genre_label = walker::derive_genre_label(root, &dir, genre_depth);
+            project_dirs.push((dir, genre_label));
+        } else {
+            if dir.is_dir() {
+                walker::discover_project_dirs(root, genre_depth, &mut ignore, &dir, 0, &mut project_dirs);
+            }
+            if online_roots.contains(library_root.path.as_str()) {
+                gone.push(dir);
+            }
+        }
+    }
+    project_dirs.sort();
+    project_dirs.dedup();
+
+    let total = project_dirs.len();
+    for (project_dir, genre_label) in &project_dirs {
+        let bounce_folder_name = queries::library_root_for(roots, project_dir)
+            .map_or("Bounces", |r| r.bounce_folder_name.as_str());
+        summary.found += 1;
+        let path_str = project_dir.to_string_lossy().to_string();
+        let project_name = project_dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+        on_progress(ScanProgress {
+            current: summary.found,
+            total,
+            project_name: project_name.clone(),
+            stage: "updating".to_string(),
+        });
+        match walker::process_project_unlocked(db, &path_str, &project_name, genre_label, bounce_folder_name, app_data_dir) {
+            Ok(is_new) => {
+                summary.changed += 1;
+                if is_new { summary.new += 1; } else { summary.updated += 1; }
+            }
+            Err(e) => summary.errors.push(format!("{}: {}", project_name, e)),
+        }
+    }
+
+    for dir in &gone {
+        let flagged = db.lock().map_err(|e| e.to_string()).and_then(|conn| flag_gone_projects(&conn, dir));
+        match flagged {
+            Ok(count) => summary.missing += count,
+            Err(e) => summary.errors.push(format!("{}: {}", dir.display(), e)),
+        }
+    }
+
+    if summary.changed > 0 || summary.missing > 0 {
+        log::info!(
+            "Watcher: {} projects updated ({} new), {} missing, {} errors",
+            summary.changed, summary.new, summary.missing, summary.errors.len()
+        );
+    }
+    summary
+}
+
+/// Flag the projects at or below `dir` that are no longer project folders.
+fn flag_gone_projects(conn: &Connection, dir: &Path) -> Result<usize, String> {
+    let dir_str = dir.to_string_lossy().to_string();
+    let mut stmt = conn.prepare(
+        "SELECT project_path FROM projects WHERE missing = 0 AND (project_path = ?1 OR project_path LIKE ?2 ESCAPE '\\')"
+    ).map_err(|e| e.to_string())?;
+    let prefix = format!("{}{}%", queries::escape_like(&dir_str), queries::escape_like(std::path::MAIN_SEPARATOR_STR));
+    let paths: Vec<String> = stmt.query_map(params![dir_str, prefix], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut count = 0;
+    for path in paths {
+        let p = Path::new(&path);
+        if !(p.is_dir() && walker::has_als_files(p)) {
+            conn.execute(
+                "UPDATE projects SET missing = 1, updated_at = datetime('now') WHERE project_path = ?1",
+                params![path],
+            ).map_err(|e| e.to_string())?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrations::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn library_root(path: &Path) -> LibraryRoot {
+        LibraryRoot {
+            id: 1,
+            path: path.to_string_lossy().to_string(),
+            bounce_folder_name: "Bounces".to_string(),
+            genre_depth: 1,
+            online: true,
+        }
+    }
+
+    fn project_state(conn: &Connection, path: &Path) -> Option<(bool, i64)> {
+        conn.query_row(
+            "SELECT missing, (SELECT COUNT(*) FROM bounces b WHERE b.project_id = p.id) FROM projects p WHERE project_path = ?1",
+            params![path.to_string_lossy()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).ok()
+    }
+
+    #[test]
+    fn test_affected_dirs() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_path_buf();
+        let song = root.join("Techno").join("Song Project");
+        fs::create_dir_all(song.join("Samples")).unwrap();
+        fs::write(song.join("Samples").join("kick.wav"), b"").unwrap();
+
+        let dirs = affected_dirs(&[library_root(&root)], &[
+            song.join("Song.als"),
+            song.join("Bounces").join("Song.wav"),
+            song.join("Backup").join("Song [2024-01-01 120000].als"),
+            song.join("Samples").join("kick.wav"),
+            root.join("Old Name"),
+            root.join(".git").join("HEAD"),
+            root.clone(),
+        ]);
+        assert_eq!(dirs.into_iter().collect::<Vec<_>>(), vec![root.join("Old Name"), song]);
+    }
+
+    #[test]
+    fn test_apply_changes_adds_updates_and_flags_projects() {
+        let conn = test_db();
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("Projects");
+        let roots = vec![library_root(&root)];
+        let song = root.join("Song Project");
+        fs::create_dir_all(&song).unwrap();
+        fs::write(song.join("Song.als"), b"").unwrap();
+
+        let db = Mutex::new(conn);
+        let apply = |paths: &[PathBuf]| apply_changes(&db, &roots, paths, tmp.path(), |_| {});
+        let project_state = |path: &Path| project_state(&db.lock().unwrap(), path);
+
+        let summary = apply(&[song.join("Song.als")]);
+        assert_eq!((summary.new, summary.changed), (1, 1));
+        assert_eq!(project_state(&song), Some((false, 0)));
+
+        fs::create_dir(song.join("Bounces")).unwrap();
+        fs::write(song.join("Bounces").join("Song.wav"), b"").unwrap();
+        let summary = apply(&[song.join("Bounces").join("Song.wav")]);
+        assert_eq!((summary.new, summary.updated), (0, 1));
+        assert_eq!(project_state(&song), Some((false, 1)));
+
+        // Renaming the folder moves the project to the new path
+        let renamed = root.join("Song Final");
+        fs::rename(&song, &renamed).unwrap();
+        let summary = apply(&[song.clone(), renamed.clone()]);
+        assert_eq!((summary.new, summary.updated, summary.missing), (0, 1, 0));
+        assert_eq!(project_state(&song), None);
+        assert_eq!(project_state(&renamed), Some((false, 1)));
+
+        // Deleting a folder of projects flags everything inside it
+        let genre = root.join("House");
+        for name in ["A", "B"] {
+            fs::create_dir_all(genre.join(name)).unwrap();
+            fs::write(genre.join(name).join("set.als"), b"").unwrap();
+        }
+        let summary = apply(std::slice::from_ref(&genre));
+        assert_eq!(summary.new, 2);
+        fs::remove_dir_all(&genre).unwrap();
+        let summary = apply(std::slice::from_ref(&genre));
+        assert_eq!(summary.missing, 2);
+        assert_eq!(project_state(&genre.join("A")), Some((true, 0)));
+    }
+
+    #[test]
+    fn test_watcher_reports_changes_in_temp_dir() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let _watcher = LibraryWatcher::start(std::slice::from_ref(&root), Duration::from_millis(200), move |paths| {
+            tx.send(paths).ok();
+        }).unwrap();
+
+        let project = root.join("New Project");
+        fs::create_dir(&project).unwrap();
+        fs::write(project.join("New.als"), b"").unwrap();
+
+        // The new folder may be reported on its own, before its files are
+        // watched; either way it maps to the project
+        let mut seen: Vec<PathBuf> = Vec::new();
+        while !affected_dirs(&[library_root(&root)], &seen).contains(&project) {
+            let batch = rx.recv_timeout(Duration::from_secs(10)).expect("no watcher batch");
+            seen.extend(batch);
+        }
+    }
+
+    #[test]
+    fn test_watcher_flushes_during_steady_activity() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let debounce = Duration::from_millis(100);
+        let _watcher = LibraryWatcher::start(std::slice::from_ref(&root), debounce, move |paths| {
+            tx.send(paths).ok();
+        }).unwrap();
+
+        // A recording that writes more often than the debounce period never
+        // goes quiet, yet its changes still come through
+        let recording = root.join("Take 1.wav");
+        let started = Instant::now();
+        let batch = loop {
+            fs::write(&recording, started.elapsed().as_millis().to_string()).unwrap();
+            if let Ok(batch) = rx.recv_timeout(Duration::from_millis(20)) {
+                break batch;
+            }
+            assert!(started.elapsed() < debounce * MAX_BATCH_DELAY * 5, "no batch while busy");
+        };
+        assert!(batch.contains(&recording));
+    }
+}
@@ -2,75 +2,359 @@ use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
 
+use super::audio_decode::PcmLayout;
+use super::audio_info::AudioInfo;
+
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Tail shared by the KSDATAFORMAT_SUBTYPE_* GUIDs; the first two bytes of
+/// the GUID hold the plain format tag.
+const SUBTYPE_GUID_TAIL: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+];
+
+/// Chunk size written as a placeholder: by RF64/BW64 (the real size is in
+/// the ds64 chunk) and by recorders that never finalised the header.
+pub(crate) const SIZE_PLACEHOLDER: u32 = 0xFFFF_FFFF;
+
 /// Parse WAV file to get duration in seconds.
-/// Properly iterates RIFF chunks - does NOT assume data chunk is at fixed offset.
 pub fn parse_wav_duration(path: &Path) -> Result<f64, String> {
+    parse_wav_info(path).map(|info| info.duration_seconds)
+}
+
+/// Parse WAV file to get its format and duration.
+pub fn parse_wav_info(path: &Path) -> Result<AudioInfo, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open WAV: {}", e))?;
+    let file_len = file.metadata().map_err(|e| format!("Failed to read WAV size: {}", e))?.len();
+    parse_wav(&mut file, file_len)
+}
+
+/// Where a WAV file's samples are and how they are encoded, for decoding.
+pub(crate) fn wav_pcm_layout(path: &Path) -> Result<PcmLayout, String> {
     let mut file = File::open(path).map_err(|e| format!("Failed to open WAV: {}", e))?;
+    let file_len = file.metadata().map_err(|e| format!("Failed to read WAV size: {}", e))?.len();
+    let wav = read_wav(&mut file, file_len)?;
+    let float = match wav.format.tag {
+        WAVE_FORMAT_PCM => false,
+        WAVE_FORMAT_IEEE_FLOAT => true,
+        tag => return Err(format!("Unsupported WAV format tag 0x{:04X}", tag)),
+    };
+    Ok(PcmLayout {
+        data_offset: wav.data_offset,
+        data_size: wav.data_size,
+        sample_rate: wav.format.sample_rate,
+        channels: wav.format.channels,
+        bits_per_sample: wav.format.bits_per_sample,
+        block_align: wav.block_align(),
+        float,
+        big_endian: false,
+        // 8-bit WAV samples are unsigned
+        unsigned: wav.format.bits_per_sample <= 8,
+    })
+}
+
+/// The `fmt ` chunk fields the duration and format details come from.
+struct WavFormat {
+    /// Format tag, taken from the sub-format GUID for WAVE_FORMAT_EXTENSIBLE
+    tag: u16,
+    channels: u16,
+    sample_rate: u32,
+    block_align: u16,
+    bits_per_sample: u16,
+}
+
+/// The container, format and data chunk of a WAVE file.
+struct WavLayout {
+    /// "WAV", "RF64" or "BW64"
+    container: &'static str,
+    format: WavFormat,
+    data_offset: u64,
+    data_size: u64,
+}
+
+impl WavLayout {
+    /// Bytes per frame: a sample for every channel, each padded to whole bytes
+    fn block_align(&self) -> u64 {
+        match self.format.block_align {
+            0 => self.format.channels as u64 * self.format.bits_per_sample.div_ceil(8) as u64,
+            n => n as u64,
+        }
+    }
+}
+
+/// Parse RIFF, RF64 or BW64 WAVE data of `file_len` bytes.
+pub(crate) fn parse_wav<R: Read + Seek>(reader: &mut R, file_len: u64) -> Result<AudioInfo, String> {
+    let wav = read_wav(reader, file_len)?;
+    let frames = wav.data_size / wav.block_align();
+    let sample_format = match wav.format.tag {
+        WAVE_FORMAT_PCM => Some("pcm"),
+        WAVE_FORMAT_IEEE_FLOAT => Some("float"),
+        _ => None,
+    };
 
+    Ok(AudioInfo {
+        format: wav.container,
+        duration_seconds: frames as f64 / wav.format.sample_rate as f64,
+        sample_rate: wav.format.sample_rate,
+        bit_depth: Some(wav.format.bits_per_sample),
+        channels: wav.format.channels,
+        sample_format,
+    })
+}
+
+/// Find the fmt and data chunks of WAVE data of `file_len` bytes.
+/// Properly iterates chunks - does NOT assume data chunk is at fixed offset.
+fn read_wav<R: Read + Seek>(reader: &mut R, file_len: u64) -> Result<WavLayout, String> {
     // Read RIFF header (12 bytes)
     let mut riff_header = [0u8; 12];
-    file.read_exact(&mut riff_header).map_err(|e| format!("Failed to read RIFF header: {}", e))?;
+    reader.read_exact(&mut riff_header).map_err(|e| format!("Failed to read RIFF header: {}", e))?;
 
-    // Verify RIFF + WAVE signature
-    if &riff_header[0..4] != b"RIFF" || &riff_header[8..12] != b"WAVE" {
+    let container = match &riff_header[0..4] {
+        b"RIFF" => "WAV",
+        b"RF64" => "RF64",
+        b"BW64" => "BW64",
+        _ => return Err("Not a valid WAV file".to_string()),
+    };
+    if &riff_header[8..12] != b"WAVE" {
         return Err("Not a valid WAV file".to_string());
     }
 
-    let mut sample_rate: u32 = 0;
-    let mut channels: u16 = 0;
-    let mut bits_per_sample: u16 = 0;
-    let mut data_size: u32 = 0;
-    let mut found_fmt = false;
-    let mut found_data = false;
+    let mut format: Option<WavFormat> = None;
+    let mut ds64_data_size: Option<u64> = None;
+    let mut data: Option<(u64, u64)> = None;
 
     // Iterate through chunks
     loop {
         let mut chunk_header = [0u8; 8];
-        match file.read_exact(&mut chunk_header) {
-            Ok(_) => {}
-            Err(_) => break, // End of file
+        if reader.read_exact(&mut chunk_header).is_err() {
+            break; // End of file
         }
-
         let chunk_id = &chunk_header[0..4];
         let chunk_size = u32::from_le_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]);
 
-        if chunk_id == b"fmt " {
-            let mut fmt_data = vec![0u8; chunk_size as usize];
-            file.read_exact(&mut fmt_data).map_err(|e| format!("Failed to read fmt chunk: {}", e))?;
-
-            if fmt_data.len() >= 16 {
-                channels = u16::from_le_bytes([fmt_data[2], fmt_data[3]]);
-                sample_rate = u32::from_le_bytes([fmt_data[4], fmt_data[5], fmt_data[6], fmt_data[7]]);
-                bits_per_sample = u16::from_le_bytes([fmt_data[14], fmt_data[15]]);
-                found_fmt = true;
+        if chunk_id == b"ds64" {
+            // 64-bit RIFF size, data size and sample count, then a table
+            // of other chunk sizes
+            let ds64 = read_chunk(reader, chunk_size, "ds64")?;
+            if ds64.len() < 16 {
+                return Err("WAV ds64 chunk too short".to_string());
             }
+            ds64_data_size = Some(u64::from_le_bytes(ds64[8..16].try_into().unwrap_or_default()));
+        } else if chunk_id == b"fmt " {
+            let fmt = read_chunk(reader, chunk_size, "fmt")?;
+            format = Some(parse_fmt(&fmt)?);
         } else if chunk_id == b"data" {
-            data_size = chunk_size;
-            found_data = true;
-            break; // We have what we need
+            let data_offset = reader.stream_position().map_err(|e| format!("Failed to get position: {}", e))?;
+            let size = data_chunk_size(reader, chunk_size, ds64_data_size, file_len.saturating_sub(data_offset))?;
+            data = Some((data_offset, size));
+            if format.is_some() {
+                break; // We have what we need
+            }
+            reader.seek(SeekFrom::Current((size + size % 2) as i64))
+                .map_err(|e| format!("Failed to skip data chunk: {}", e))?;
         } else {
             // Skip unknown chunk
-            let skip = if chunk_size % 2 == 1 { chunk_size + 1 } else { chunk_size };
-            file.seek(SeekFrom::Current(skip as i64))
-                .map_err(|e| format!("Failed to skip chunk: {}", e))?;
+            let skip = chunk_size as i64 + (chunk_size % 2) as i64;
+            reader.seek(SeekFrom::Current(skip)).map_err(|e| format!("Failed to skip chunk: {}", e))?;
         }
     }
 
-    if !found_fmt || !found_data {
-        return Err("WAV file missing fmt or data chunk".to_string());
-    }
+    let (format, (data_offset, data_size)) = match (format, data) {
+        (Some(f), Some(d)) => (f, d),
+        _ => return Err("WAV file missing fmt or data chunk".to_string()),
+    };
 
-    if sample_rate == 0 || channels == 0 || bits_per_sample == 0 {
+    if format.sample_rate == 0 || format.channels == 0 || format.bits_per_sample == 0 {
         return Err("Invalid WAV format parameters".to_string());
     }
 
-    let bytes_per_sample = bits_per_sample as u32 / 8;
-    let bytes_per_second = sample_rate * channels as u32 * bytes_per_sample;
+    Ok(WavLayout { container, format, data_offset, data_size })
+}
+
+/// The size of a data chunk whose header says `chunk_size`, given the ds64
+/// data size (RF64/BW64) and the bytes `available` after the header.
+/// `reader` must be at the start of the chunk's data; it is left there.
+pub(crate) fn data_chunk_size<R: Read + Seek>(
+    reader: &mut R,
+    chunk_size: u32,
+    ds64_data_size: Option<u64>,
+    available: u64,
+) -> Result<u64, String> {
+    let declared = match (chunk_size, ds64_data_size) {
+        (SIZE_PLACEHOLDER, Some(size)) => size,
+        // Header never finalised: the data runs to the end of the file
+        (SIZE_PLACEHOLDER, None) => available,
+        // Either a header never finalised or a chunk that really is empty;
+        // only the first ends the file rather than being followed by others
+        (0, None) if chunk_follows(reader, available)? => 0,
+        (0, None) => available,
+        // A plain RIFF size can't describe more than 4 GB of data, so
+        // a larger remainder means the size wrapped around
+        (_, None) if available > u32::MAX as u64 => available,
+        (size, _) => size as u64,
+    };
+    // A recording cut short holds less than its header claims
+    Ok(declared.min(available))
+}
+
+/// Whether the `available` bytes at the reader start with a chunk header:
+/// a printable ASCII ID and a size that fits. The reader is left in place.
+fn chunk_follows<R: Read + Seek>(reader: &mut R, available: u64) -> Result<bool, String> {
+    if available < 8 {
+        return Ok(false);
+    }
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header).map_err(|e| format!("Failed to read chunk header: {}", e))?;
+    reader.seek(SeekFrom::Current(-8)).map_err(|e| format!("Failed to seek: {}", e))?;
+    let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+    Ok(header[..4].iter().all(|b| (0x20..=0x7E).contains(b)) && size + 8 <= available)
+}
+
+fn read_chunk<R: Read + Seek>(reader: &mut R, chunk_size: u32, name: &str) -> Result<Vec<u8>, String> {
+    // Header chunks are small; anything huge is a corrupt size
+    if chunk_size > 1 << 20 {
+        return Err(format!("WAV {} chunk too large", name));
+    }
+    let mut data = vec![0u8; chunk_size as usize];
+    reader.read_exact(&mut data).map_err(|e| format!("Failed to read {} chunk: {}", name, e))?;
+    if chunk_size % 2 == 1 {
+        reader.seek(SeekFrom::Current(1)).map_err(|e| format!("Failed to skip padding: {}", e))?;
+    }
+    Ok(data)
+}
+
+fn parse_fmt(fmt: &[u8]) -> Result<WavFormat, String> {
+    if fmt.len() < 16 {
+        return Err("WAV fmt chunk too short".to_string());
+    }
+    let mut tag = u16::from_le_bytes([fmt[0], fmt[1]]);
+    let channels = u16::from_le_bytes([fmt[2], fmt[3]]);
+    let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+    let block_align = u16::from_le_bytes([fmt[12], fmt[13]]);
+    let bits_per_sample = u16::from_le_bytes([fmt[14], fmt[15]]);
+
+    // WAVE_FORMAT_EXTENSIBLE: cbSize, valid bits, channel mask, then the
+    // sub-format GUID
+    if tag == WAVE_FORMAT_EXTENSIBLE && fmt.len() >= 40 {
+        let guid = &fmt[24..40];
+        if guid[2..16] == SUBTYPE_GUID_TAIL {
+            tag = u16::from_le_bytes([guid[0], guid[1]]);
+        }
+    }
+
+    Ok(WavFormat { tag, channels, sample_rate, block_align, bits_per_sample })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn parse_fixture(bytes: &[u8]) -> AudioInfo {
+        parse_wav(&mut Cursor::new(bytes), bytes.len() as u64).unwrap()
+    }
+
+    fn assert_duration(info: &AudioInfo, seconds: f64) {
+        assert!((info.duration_seconds - seconds).abs() < 1e-9, "{} != {}", info.duration_seconds, seconds);
+    }
+
+    #[test]
+    fn test_pcm16_with_odd_chunk_before_data() {
+        let info = parse_fixture(include_bytes!("../../tests/fixtures/wav/pcm16_stereo.wav"));
+        assert_eq!((info.format, info.sample_rate, info.channels), ("WAV", 44100, 2));
+        assert_eq!((info.bit_depth, info.sample_format), (Some(16), Some("pcm")));
+        assert_duration(&info, 0.1);
+    }
+
+    #[test]
+    fn test_extensible_pcm24() {
+        let info = parse_fixture(include_bytes!("../../tests/fixtures/wav/extensible_pcm24.wav"));
+        assert_eq!((info.format, info.sample_rate, info.channels), ("WAV", 48000, 2));
+        assert_eq!((info.bit_depth, info.sample_format), (Some(24), Some("pcm")));
+        assert_duration(&info, 0.1);
+    }
+
+    #[test]
+    fn test_float32() {
+        let info = parse_fixture(include_bytes!("../../tests/fixtures/wav/float32_mono.wav"));
+        assert_eq!((info.sample_rate, info.channels, info.bit_depth), (48000, 1, Some(32)));
+        assert_eq!(info.sample_format, Some("float"));
+        assert_duration(&info, 0.25);
+    }
 
-    if bytes_per_second == 0 {
-        return Err("Invalid WAV byte rate".to_string());
+    #[test]
+    fn test_extensible_float32() {
+        let info = parse_fixture(include_bytes!("../../tests/fixtures/wav/extensible_float32.wav"));
+        assert_eq!((info.sample_rate, info.channels, info.bit_depth), (96000, 2, Some(32)));
+        assert_eq!(info.sample_format, Some("float"));
+        assert_duration(&info, 0.05);
     }
 
-    let duration = data_size as f64 / bytes_per_second as f64;
-    Ok(duration)
+    #[test]
+    fn test_rf64_uses_ds64_sizes() {
+        let info = parse_fixture(include_bytes!("../../tests/fixtures/wav/rf64_pcm24.wav"));
+        assert_eq!((info.format, info.sample_rate, info.channels, info.bit_depth), ("RF64", 48000, 2, Some(24)));
+        assert_duration(&info, 0.1);
+    }
+
+    #[test]
+    fn test_unfinalised_recording_runs_to_end_of_file() {
+        // RIFF and data sizes both 0xFFFFFFFF, as left by an interrupted recording
+        let info = parse_fixture(include_bytes!("../../tests/fixtures/wav/recording_unfinalised.wav"));
+        assert_eq!((info.format, info.sample_rate, info.channels), ("WAV", 44100, 2));
+        assert_duration(&info, 0.2);
+    }
+
+    #[test]
+    fn test_truncated_data_is_clamped_to_file() {
+        let bytes = include_bytes!("../../tests/fixtures/wav/pcm16_stereo.wav");
+        let half = &bytes[..bytes.len() - 8820];
+        assert_duration(&parse_fixture(half), 0.05);
+    }
+
+    #[test]
+    fn test_empty_data_chunk() {
+        let bytes = include_bytes!("../../tests/fixtures/wav/pcm16_stereo.wav");
+        let data = bytes.windows(4).position(|w| w == b"data").unwrap() + 4;
+        let data_len = u32::from_le_bytes(bytes[data..data + 4].try_into().unwrap()) as usize;
+        let mut wav = bytes[..data + 4 + data_len].to_vec();
+        wav[data..data + 4].copy_from_slice(&0u32.to_le_bytes());
+
+        // A zero size with audio after it is a header never finalised
+        assert_duration(&parse_fixture(&wav), 0.1);
+
+        // Followed by another chunk, the data chunk really is empty
+        wav.truncate(data + 4);
+        wav.extend_from_slice(b"LIST\x04\x00\x00\x00INFO");
+        assert_duration(&parse_fixture(&wav), 0.0);
+    }
+
+    /// Header of `fixture` up to its audio, reported as `data_len` bytes
+    /// longer than it is.
+    fn parse_large(fixture: &[u8], data_len: u64) -> AudioInfo {
+        let data_start = fixture.windows(4).position(|w| w == b"data").unwrap() + 8;
+        let header = &fixture[..data_start];
+        parse_wav(&mut Cursor::new(header), data_start as u64 + data_len).unwrap()
+    }
+
+    #[test]
+    fn test_files_over_4gb() {
+        // 5 GiB of 48 kHz 24-bit stereo (6-byte frames)
+        let data_len = 5 * (1u64 << 30) / 6 * 6;
+        let seconds = (data_len / 6) as f64 / 48000.0;
+
+        // RF64 whose ds64 data size is patched to the long length
+        let mut rf64 = include_bytes!("../../tests/fixtures/wav/rf64_pcm24.wav").to_vec();
+        let ds64 = rf64.windows(4).position(|w| w == b"ds64").unwrap() + 8;
+        rf64[ds64 + 8..ds64 + 16].copy_from_slice(&data_len.to_le_bytes());
+        assert_duration(&parse_large(&rf64, data_len), seconds);
+
+        // Plain RIFF whose 32-bit data size wrapped around
+        let mut riff = include_bytes!("../../tests/fixtures/wav/extensible_pcm24.wav").to_vec();
+        let data = riff.windows(4).position(|w| w == b"data").unwrap() + 4;
+        riff[data..data + 4].copy_from_slice(&(data_len as u32).to_le_bytes());
+        assert_duration(&parse_large(&riff, data_len), seconds);
+    }
 }
@@ -18,9 +18,20 @@ const FIELD_OPTIONS = [
   { value: 'status', label: 'Status', type: 'string' },
   { value: 'tag', label: 'Tag', type: 'tag' },
   { value: 'plugin', label: 'Plugin', type: 'plugin' },
+  { value: 'device_chain', label: 'Device Chain', type: 'chain' },
   { value: 'in_rotation', label: 'In Rotation', type: 'boolean' },
   { value: 'has_missing_deps', label: 'Missing Deps', type: 'boolean' },
   { value: 'last_worked_on', label: 'Last Worked On', type: 'date' },
+  { value: 'arrangement_bars', label: 'Arrangement Bars', type: 'number' },
+  { value: 'arrangement_seconds', label: 'Arrangement Seconds', type: 'number' },
+  { value: 'time_signature', label: 'Time Signature', type: 'string' },
+  { value: 'tempo_min', label: 'Min Tempo', type: 'number' },
+  { value: 'tempo_max', label: 'Max Tempo', type: 'number' },
+  { value: 'tempo_avg', label: 'Avg Tempo', type: 'number' },
+  { value: 'scene_count', label: 'Scenes', type: 'number' },
+  { value: 'session_clips', label: 'Session Clips', type: 'number' },
+  { value: 'arrangement_clips', label: 'Arrangement Clips', type: 'number' },
+  { value: 'live_version', label: 'Live Version', type: 'version' },
 ];
 
 const OPERATORS_BY_TYPE: Record<string, { value: string; label: string }[]> = {
@@ -43,6 +54,14 @@ const OPERATORS_BY_TYPE: Record<string, { value: string; label: string }[]> = {
   plugin: [
     { value: 'contains', label: 'contains' },
   ],
+  chain: [
+    { value: 'matches', label: 'matches' },
+  ],
+  version: [
+    { value: 'starts_with', label: 'starts with' },
+    { value: 'is', label: 'is' },
+    { value: 'is_not', label: 'is not' },
+  ],
   boolean: [
     { value: 'is', label: 'is' },
   ],
@@ -147,7 +166,7 @@ export function SmartCollectionEditor({ collectionId, isOpen, onClose }: SmartCo
                     type={fieldType === 'number' || fieldType === 'date' ? 'number' : 'text'}
                     value={rule.value}
                     onChange={(e) => updateRule(i, { value: e.target.value })}
-                    placeholder={fieldType === 'date' ? 'days' : 'value'}
+                    placeholder={fieldType === 'date' ? 'days' : fieldType === 'chain' ? 'bass: Serum > OTT' : 'value'}
                     className="flex-1 rounded border border-border-default bg-bg-elevated px-2 py-1.5 text-sm text-text-primary placeholder-text-muted"
                   />
                 )}
@@ -1,9 +1,19 @@
-import { useProjectPlugins, useProjectSamples } from '../../hooks/useProjectPlugins';
+import { useProjectPlugins, useProjectSamples, useCollectProjectSamples } from '../../hooks/useProjectPlugins';
+import { Button } from '../ui/Button';
+import { SampleRelinkPanel } from './SampleRelinkPanel';
 
 interface PluginsTabProps {
   projectId: number;
 }
 
+const SAMPLE_LOCATION_LABELS: Record<string, string> = {
+  project: 'Project',
+  user_library: 'User Library',
+  core_library: 'Core Library',
+  pack: 'Pack',
+  external: 'External',
+};
+
 const PLUGIN_TYPE_LABELS: Record<string, string> = {
   vst2: 'VST2',
   vst3: 'VST3',
@@ -12,11 +22,20 @@ const PLUGIN_TYPE_LABELS: Record<string, string> = {
   unknown: 'Unknown',
 };
 
+function formatBytes(bytes: number): string {
+  if (bytes >= 1024 * 1024 * 1024) return `${(bytes / (1024 * 1024 * 1024)).toFixed(1)} GB`;
+  if (bytes >= 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+  return `${Math.round(bytes / 1024)} KB`;
+}
+
 export function PluginsTab({ projectId }: PluginsTabProps) {
   const { data: plugins, isLoading: pluginsLoading } = useProjectPlugins(projectId);
   const { data: samples, isLoading: samplesLoading } = useProjectSamples(projectId);
 
+  const collect = useCollectProjectSamples(projectId);
+
   const missingCount = samples?.filter(s => s.is_missing).length ?? 0;
+  const externalCount = samples?.filter(s => !s.is_missing && s.location !== 'project').length ?? 0;
   // Sort missing samples to top
   const sortedSamples = samples ? [...samples].sort((a, b) => {
     if (a.is_missing === b.is_missing) return a.filename.localeCompare(b.filename);
@@ -71,8 +90,31 @@ export function PluginsTab({ projectId }: PluginsTabProps) {
               {missingCount} missing
             </span>
           )}
+          {externalCount > 0 && (
+            <Button
+              variant="secondary"
+              size="sm"
+              className="ml-auto"
+              onClick={() => collect.mutate()}
+              disabled={collect.isPending}
+              title="Copy samples from outside the project into Samples/Imported and save the set"
+            >
+              {collect.isPending ? 'Collecting...' : `Collect All and Save (${externalCount})`}
+            </Button>
+          )}
         </div>
 
+        {collect.data && (
+          <p className="mb-3 text-xs text-green-400" title={collect.data.backup_path ?? undefined}>
+            Copied {collect.data.files_copied} file{collect.data.files_copied !== 1 ? 's' : ''} ({formatBytes(collect.data.bytes_copied)})
+            {collect.data.files_reused > 0 && `, ${collect.data.files_reused} already in the project`}
+            {collect.data.missing.length > 0 && `, ${collect.data.missing.length} missing left as is`}
+          </p>
+        )}
+        {collect.isError && <p className="mb-3 text-xs text-red-400">{String(collect.error)}</p>}
+
+        {missingCount > 0 && <SampleRelinkPanel projectId={projectId} />}
+
         {samplesLoading ? (
           <p className="text-sm text-text-muted">Loading...</p>
         ) : !samples || samples.length === 0 ? (
@@ -94,8 +136,11 @@ export function PluginsTab({ projectId }: PluginsTabProps) {
                     NOT FOUND
                   </span>
                 )}
-                <span className="truncate max-w-[300px] text-text-muted text-[10px]" title={sample.path}>
-                  {sample.path}
+                <span className="shrink-0 rounded bg-bg-surface px-1.5 py-0.5 text-[10px] text-text-muted">
+                  {SAMPLE_LOCATION_LABELS[sample.location] ?? sample.location}
+                </span>
+                <span className="truncate max-w-[300px] text-text-muted text-[10px]" title={sample.resolved_path ?? sample.path}>
+                  {sample.resolved_path ?? sample.path}
                 </span>
               </div>
             ))}
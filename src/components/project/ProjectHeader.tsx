@@ -7,6 +7,7 @@ import { formatTimestamp } from '../../lib/utils';
 import type { Project } from '../../types';
 import { useState, useRef, useEffect } from 'react';
 import { generateSongName } from '../../lib/songNameGenerator';
+import { useProjectTempoKeyCheck } from '../../hooks/useProjectPlugins';
 
 interface ProjectHeaderProps {
   project: Project;
@@ -19,6 +20,7 @@ export function ProjectHeader({ project, onUpdate }: ProjectHeaderProps) {
   const [editingName, setEditingName] = useState(false);
   const [nameValue, setNameValue] = useState(project.name);
   const nameInputRef = useRef<HTMLInputElement>(null);
+  const { data: tempoKeyCheck } = useProjectTempoKeyCheck(project.id);
 
   useEffect(() => {
     setNameValue(project.name);
@@ -226,6 +228,18 @@ export function ProjectHeader({ project, onUpdate }: ProjectHeaderProps) {
           </div>
         </div>
 
+        {tempoKeyCheck && (tempoKeyCheck.tempo_mismatch || tempoKeyCheck.key_mismatch) && (
+          <p
+            className="text-xs text-amber-400 mt-2"
+            title={`Detected from ${tempoKeyCheck.bounce_path?.split(/[/\\]/).pop() ?? 'the newest bounce'}`}
+          >
+            The bounce doesn't match the set:
+            {tempoKeyCheck.tempo_mismatch && ` ${tempoKeyCheck.detected_bpm} BPM (set: ${tempoKeyCheck.als_bpm})`}
+            {tempoKeyCheck.tempo_mismatch && tempoKeyCheck.key_mismatch && ','}
+            {tempoKeyCheck.key_mismatch && ` ${tempoKeyCheck.detected_key} (set: ${tempoKeyCheck.als_key})`}
+          </p>
+        )}
+
         {project.last_worked_on && (
           <p className="text-xs text-text-muted mt-2">
             Last worked on: {formatTimestamp(project.last_worked_on)}
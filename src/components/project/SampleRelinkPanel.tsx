@@ -0,0 +1,120 @@
+import { useState } from 'react';
+import { Button } from '../ui/Button';
+import {
+  useIndexSampleFolders,
+  usePreviewSampleRelink,
+  useApplySampleRelink,
+} from '../../hooks/useProjectPlugins';
+import type { RelinkReport } from '../../types';
+
+interface SampleRelinkPanelProps {
+  projectId: number;
+}
+
+function formatSize(bytes: number): string {
+  if (bytes >= 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+  return `${Math.max(1, Math.round(bytes / 1024))} KB`;
+}
+
+/** Dry-run report for relinking missing samples, then apply the chosen files. */
+export function SampleRelinkPanel({ projectId }: SampleRelinkPanelProps) {
+  const indexFolders = useIndexSampleFolders();
+  const preview = usePreviewSampleRelink(projectId);
+  const apply = useApplySampleRelink(projectId);
+
+  const [report, setReport] = useState<RelinkReport | null>(null);
+  // sample_path → chosen replacement ('' = leave as is)
+  const [choices, setChoices] = useState<Record<string, string>>({});
+
+  const runPreview = async () => {
+    apply.reset();
+    await indexFolders.mutateAsync();
+    const result = await preview.mutateAsync();
+    setReport(result);
+    setChoices(Object.fromEntries(result.entries.map(e => [e.sample_path, e.proposed_path ?? ''])));
+  };
+
+  const selected = Object.entries(choices).filter(([, path]) => path !== '');
+
+  const runApply = () => {
+    apply.mutate(
+      selected.map(([sample_path, new_path]) => ({ sample_path, new_path })),
+      { onSuccess: () => setReport(null) },
+    );
+  };
+
+  const busy = indexFolders.isPending || preview.isPending;
+  const error = indexFolders.error ?? preview.error ?? apply.error;
+
+  return (
+    <div className="mb-3 rounded-lg border border-border-default bg-bg-elevated/50 p-3 space-y-3">
+      <div className="flex items-center gap-3">
+        <Button variant="secondary" size="sm" onClick={runPreview} disabled={busy || apply.isPending}>
+          {indexFolders.isPending ? 'Indexing sample folders...' : preview.isPending ? 'Searching...' : 'Find Missing Samples'}
+        </Button>
+        {indexFolders.data && indexFolders.data.errors.length > 0 && (
+          <span className="text-[10px] text-amber-400" title={indexFolders.data.errors.join('\n')}>
+            {indexFolders.data.errors.length} folder issue{indexFolders.data.errors.length !== 1 ? 's' : ''}
+          </span>
+        )}
+        {apply.data && (
+          <span className="text-xs text-green-400" title={apply.data.backup_path ?? undefined}>
+            Relinked {apply.data.relinked} sample{apply.data.relinked !== 1 ? 's' : ''}
+            {apply.data.backup_path && ' — original saved in Backup'}
+          </span>
+        )}
+      </div>
+
+      {error && <p className="text-xs text-red-400">{String(error)}</p>}
+
+      {report && (
+        <>
+          {report.entries.every(e => e.candidates.length === 0) ? (
+            <p className="text-xs text-text-muted">
+              No matches found. Add the folders you keep samples in under Settings → Sample Folders.
+            </p>
+          ) : (
+            <div className="space-y-2">
+              {report.entries.map(entry => (
+                <div key={entry.sample_path} className="text-xs">
+                  <div className="flex items-center gap-2">
+                    <span className="font-medium text-text-primary truncate">{entry.filename}</span>
+                    {entry.original_size !== null && (
+                      <span className="text-[10px] text-text-muted">{formatSize(entry.original_size)}</span>
+                    )}
+                  </div>
+                  {entry.candidates.length === 0 ? (
+                    <p className="text-[10px] text-text-muted">No candidates</p>
+                  ) : (
+                    <select
+                      value={choices[entry.sample_path] ?? ''}
+                      onChange={(e) => setChoices(prev => ({ ...prev, [entry.sample_path]: e.target.value }))}
+                      className="mt-1 w-full rounded border border-border-default bg-bg-surface px-2 py-1 text-[11px] text-text-secondary"
+                    >
+                      <option value="">Leave missing</option>
+                      {entry.candidates.map(c => (
+                        <option key={c.path} value={c.path}>
+                          {c.path} ({formatSize(c.file_size)}
+                          {c.size_matches === false && ', different size'}
+                          {c.duplicates > 0 && `, +${c.duplicates} identical`})
+                        </option>
+                      ))}
+                    </select>
+                  )}
+                </div>
+              ))}
+            </div>
+          )}
+          <div className="flex items-center gap-3">
+            <Button size="sm" onClick={runApply} disabled={selected.length === 0 || apply.isPending}>
+              {apply.isPending ? 'Relinking...' : `Relink ${selected.length} Sample${selected.length !== 1 ? 's' : ''}`}
+            </Button>
+            <span className="text-[10px] text-text-muted">
+              Rewrites {report.set_path.split(/[\\/]/).pop()} and keeps the original in the project's Backup folder.
+            </span>
+          </div>
+        </>
+      )}
+    </div>
+  );
+}
@@ -1,6 +1,7 @@
 import { useState } from 'react';
-import { useVersionTimeline, useUpsertVersionNote, useDeleteVersionNote } from '../../hooks/useVersionTimeline';
+import { useVersionTimeline, useUpsertVersionNote, useDeleteVersionNote, useSetDiff, useSetBackups, useRestoreSetBackup } from '../../hooks/useVersionTimeline';
 import { formatTimestamp, getRelativeTime } from '../../lib/utils';
+import type { SetBackup } from '../../types';
 
 interface VersionTimelineProps {
   projectId: number;
@@ -18,8 +19,70 @@ function getFileName(setPath: string): string {
   return parts[parts.length - 1] || setPath;
 }
 
+/** Auto-summary of what changed since the previous (older) version. */
+function VersionDiffSummary({ previousSetId, setId }: { previousSetId: number; setId: number }) {
+  const { data: diff, isLoading, isError } = useSetDiff(previousSetId, setId);
+
+  if (isLoading) {
+    return <p className="mt-1 text-xs text-text-muted">Comparing with previous version...</p>;
+  }
+  if (isError || !diff) return null;
+
+  return <p className="mt-1 text-xs text-text-secondary">{diff.summary}</p>;
+}
+
+/** Older copies of a set from Live's Backup folder, collapsed by default. */
+function BackupList({ label, backups }: { label: string; backups: SetBackup[] }) {
+  const restoreBackup = useRestoreSetBackup();
+  const [expanded, setExpanded] = useState(false);
+  const [restoredId, setRestoredId] = useState<number | null>(null);
+
+  if (backups.length === 0) return null;
+
+  const handleRestore = (backup: SetBackup) => {
+    restoreBackup.mutate(backup, { onSuccess: () => setRestoredId(backup.id) });
+  };
+
+  return (
+    <div className="mt-2">
+      <button
+        onClick={() => setExpanded(!expanded)}
+        className="text-xs text-text-muted hover:text-text-secondary"
+      >
+        {expanded ? '▾' : '▸'} {label}
+      </button>
+      {expanded && (
+        <div className="mt-1 space-y-1">
+          {backups.map((backup) => {
+            const time = backup.backed_up_at ?? backup.modified_time;
+            return (
+              <div key={backup.id} className="flex items-center gap-3 pl-3 text-xs text-text-muted">
+                <span className="truncate" title={backup.backup_path}>{getFileName(backup.backup_path)}</span>
+                <span className="shrink-0">{getRelativeTime(time)}</span>
+                <span className="shrink-0">{formatFileSize(backup.file_size)}</span>
+                <button
+                  onClick={() => handleRestore(backup)}
+                  disabled={restoreBackup.isPending}
+                  className="ml-auto shrink-0 text-brand-400 hover:text-brand-300 disabled:opacity-50"
+                  title="Copy this backup into the project folder as a new set"
+                >
+                  {restoredId === backup.id ? 'Restored' : 'Restore'}
+                </button>
+              </div>
+            );
+          })}
+          {restoreBackup.isError && (
+            <p className="pl-3 text-xs text-red-400">{String(restoreBackup.error)}</p>
+          )}
+        </div>
+      )}
+    </div>
+  );
+}
+
 export function VersionTimeline({ projectId }: VersionTimelineProps) {
   const { data: entries, isLoading } = useVersionTimeline(projectId);
+  const { data: backups = [] } = useSetBackups(projectId);
   const upsertNote = useUpsertVersionNote();
   const deleteNote = useDeleteVersionNote();
   const [editingSetId, setEditingSetId] = useState<number | null>(null);
@@ -52,6 +115,10 @@ export function VersionTimeline({ projectId }: VersionTimelineProps) {
     setNoteText('');
   };
 
+  const backupsFor = (setId: number) => backups.filter((b) => b.set_id === setId);
+  const otherBackups = backups.filter((b) => b.set_id === null);
+  const backupLabel = (count: number) => `${count} backup${count === 1 ? '' : 's'}`;
+
   return (
     <div className="relative">
       {/* Vertical line */}
@@ -84,6 +151,13 @@ export function VersionTimeline({ projectId }: VersionTimelineProps) {
                 )}
               </div>
 
+              {/* Entries are newest first, so the previous version is the next one */}
+              {idx < entries.length - 1 && (
+                <VersionDiffSummary previousSetId={entries[idx + 1].set.id} setId={entry.set.id} />
+              )}
+
+              <BackupList label={backupLabel(backupsFor(entry.set.id).length)} backups={backupsFor(entry.set.id)} />
+
               {/* Note area */}
               {editingSetId === entry.set.id ? (
                 <div className="mt-2">
@@ -137,6 +211,16 @@ export function VersionTimeline({ projectId }: VersionTimelineProps) {
             </div>
           </div>
         ))}
+
+        {otherBackups.length > 0 && (
+          <div className="relative pl-9">
+            <div className="absolute left-1.5 top-2 w-3 h-3 rounded-full border-2 bg-bg-primary border-border-default border-dashed" />
+            <div className="rounded-lg border border-dashed border-border-default p-3">
+              <p className="text-sm text-text-secondary">Backups of sets no longer in the project</p>
+              <BackupList label={backupLabel(otherBackups.length)} backups={otherBackups} />
+            </div>
+          </div>
+        )}
       </div>
     </div>
   );
@@ -0,0 +1,100 @@
+import { useState, useEffect } from 'react';
+import { open } from '@tauri-apps/plugin-dialog';
+import { Button } from '../ui/Button';
+import { Select } from '../ui/Select';
+import { useLibraryRoots, useAddLibraryRoot, useUpdateLibraryRoot, useRemoveLibraryRoot } from '../../hooks/useSettings';
+import { useScanLibrary } from '../../hooks/useProjects';
+import type { LibraryRoot } from '../../types';
+
+const GENRE_DEPTH_OPTIONS = [
+  { value: '0', label: 'No genre' },
+  { value: '1', label: 'Genre = 1st subfolder' },
+  { value: '2', label: 'Genre = 2nd subfolder' },
+  { value: '3', label: 'Genre = 3rd subfolder' },
+];
+
+export function LibraryRootsSection() {
+  const { data: roots = [] } = useLibraryRoots();
+  const addRoot = useAddLibraryRoot();
+  const scanLibrary = useScanLibrary();
+
+  const pickRoot = async () => {
+    const selected = await open({ directory: true, multiple: false, title: 'Add Library Folder' });
+    if (selected && !roots.some((r) => r.path === selected)) {
+      await addRoot.mutateAsync(selected as string);
+      // Full scan to discover and import the new folder's projects
+      scanLibrary.mutate();
+    }
+  };
+
+  return (
+    <div>
+      <label className="block text-sm font-medium text-text-secondary mb-1">Library Folders</label>
+      {roots.length > 0 && (
+        <div className="mb-2 rounded-lg border border-border-default bg-bg-elevated/50">
+          {roots.map((root) => (
+            <LibraryRootRow key={root.id} root={root} />
+          ))}
+        </div>
+      )}
+      <Button variant="secondary" size="sm" onClick={pickRoot} disabled={addRoot.isPending}>Add Folder</Button>
+      <p className="mt-1 text-xs text-text-muted">
+        Folders containing your Ableton project directories (or genre subfolders). Projects on an offline drive are kept, not marked missing.
+      </p>
+    </div>
+  );
+}
+
+function LibraryRootRow({ root }: { root: LibraryRoot }) {
+  const updateRoot = useUpdateLibraryRoot();
+  const removeRoot = useRemoveLibraryRoot();
+  const [bounceFolderName, setBounceFolderName] = useState(root.bounce_folder_name);
+
+  useEffect(() => {
+    setBounceFolderName(root.bounce_folder_name);
+  }, [root.bounce_folder_name]);
+
+  const saveBounceFolderName = () => {
+    const name = bounceFolderName.trim();
+    if (name && name !== root.bounce_folder_name) {
+      updateRoot.mutate({ ...root, bounce_folder_name: name });
+    } else {
+      setBounceFolderName(root.bounce_folder_name);
+    }
+  };
+
+  return (
+    <div className="px-3 py-2 border-b border-border-default/50 last:border-b-0">
+      <div className="flex items-center gap-2">
+        <span
+          className={`h-2 w-2 shrink-0 rounded-full ${root.online ? 'bg-green-400' : 'bg-text-muted'}`}
+          title={root.online ? 'Online' : 'Offline'}
+        />
+        <span className="flex-1 truncate text-sm text-text-primary" title={root.path}>{root.path}</span>
+        {!root.online && <span className="text-xs text-text-muted">Offline</span>}
+        <button
+          className="text-xs text-text-muted hover:text-red-400"
+          onClick={() => removeRoot.mutate(root.id)}
+        >
+          Remove
+        </button>
+      </div>
+      <div className="mt-2 flex items-center gap-2">
+        <input
+          value={bounceFolderName}
+          onChange={(e) => setBounceFolderName(e.target.value)}
+          onBlur={saveBounceFolderName}
+          placeholder="Bounces"
+          title="Bounce subfolder name"
+          className="w-40 rounded-lg border border-border-default bg-bg-elevated px-2 py-1 text-xs text-text-primary placeholder-text-muted"
+        />
+        <Select
+          value={String(root.genre_depth)}
+          onChange={(e) => updateRoot.mutate({ ...root, genre_depth: Number(e.target.value) })}
+          options={GENRE_DEPTH_OPTIONS}
+          className="py-1 text-xs"
+        />
+      </div>
+    </div>
+  );
+}
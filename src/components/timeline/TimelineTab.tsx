@@ -1,17 +1,19 @@
 import { useState, useEffect, useRef, useCallback } from 'react';
 import { convertFileSrc } from '@tauri-apps/api/core';
+import { save } from '@tauri-apps/plugin-dialog';
 import WaveSurfer from 'wavesurfer.js';
 import RegionsPlugin from 'wavesurfer.js/dist/plugins/regions.js';
 import { useAudioStore } from '../../stores/audioStore';
 import { useAudioPlayer } from '../../hooks/useAudioPlayer';
 import { useMarkers, useCreateMarker, useUpdateMarker, useDeleteMarker } from '../../hooks/useMarkers';
 import { useCreateTask } from '../../hooks/useTasks';
+import { useAnalyzeBounce, useBouncePeaks, useExportTaggedBounce } from '../../hooks/useBounces';
 import { tauriInvoke } from '../../hooks/useTauriInvoke';
 import { useSoundCloudAuthStatus, useSoundCloudLogin, useSoundCloudUpload } from '../../hooks/useSoundCloud';
 import { MARKER_TYPES } from '../../lib/constants';
 import { MarkerPopover } from './MarkerPopover';
 import { MarkerList } from './MarkerList';
-import type { Bounce, Project, Marker, MarkerType } from '../../types';
+import type { Bounce, BounceMetadata, Project, Marker, MarkerType, WaveformPeaks } from '../../types';
 
 interface TimelineTabProps {
   project: Project;
@@ -44,6 +46,8 @@ export function TimelineTab({ project, bounces }: TimelineTabProps) {
   const updateMarker = useUpdateMarker(project.id);
   const deleteMarker = useDeleteMarker(project.id);
   const createTask = useCreateTask(project.id);
+  const exportTagged = useExportTaggedBounce();
+  const { data: peaks, isPending: peaksPending } = useBouncePeaks(selectedBounce);
   const scAuth = useSoundCloudAuthStatus();
   const scLogin = useSoundCloudLogin();
   const scUpload = useSoundCloudUpload();
@@ -55,9 +59,11 @@ export function TimelineTab({ project, bounces }: TimelineTabProps) {
     }
   }, [selectedBounce]); // eslint-disable-line react-hooks/exhaustive-deps
 
-  // Initialize WaveSurfer
+  // Initialize WaveSurfer once the cached peaks are in, so it doesn't decode
+  // the whole bounce itself (it still does if they failed to load)
   useEffect(() => {
-    if (!waveformRef.current || !selectedBounce) return;
+    if (!waveformRef.current || !selectedBounce || peaksPending) return;
+    const cached = peaks ? waveSurferPeaks(peaks) : undefined;
 
     const regions = RegionsPlugin.create();
     regionsRef.current = regions;
@@ -73,6 +79,8 @@ export function TimelineTab({ project, bounces }: TimelineTabProps) {
       barGap: 1,
       barRadius: 2,
       media: audioElement,
+      peaks: cached?.peaks,
+      duration: cached?.duration,
       plugins: [regions],
     });
 
@@ -81,7 +89,7 @@ export function TimelineTab({ project, bounces }: TimelineTabProps) {
     // If audio element doesn't have the right source, load it
     const expectedSrc = convertFileSrc(selectedBounce.bounce_path);
     if (!audioElement.src.includes(selectedBounce.bounce_path.replace(/\\/g, '/')) && audioElement.src !== expectedSrc) {
-      ws.load(expectedSrc);
+      ws.load(expectedSrc, cached?.peaks, cached?.duration);
     }
 
     // Click-to-seek is built in via the `media` option
@@ -109,7 +117,7 @@ export function TimelineTab({ project, bounces }: TimelineTabProps) {
       wsRef.current = null;
       regionsRef.current = null;
     };
-  }, [selectedBounce?.id]); // eslint-disable-line react-hooks/exhaustive-deps
+  }, [selectedBounce?.id, peaks, peaksPending]); // eslint-disable-line react-hooks/exhaustive-deps
 
   // Render markers as regions
   useEffect(() => {
@@ -242,7 +250,7 @@ export function TimelineTab({ project, bounces }: TimelineTabProps) {
       regions.un('region-clicked', handleRegionClick);
       regions.un('region-updated', handleRegionUpdate);
     };
-  }, [markers, updateMarker]); // eslint-disable-line react-hooks/exhaustive-deps
+  }, [markers, updateMarker, peaksPending]); // eslint-disable-line react-hooks/exhaustive-deps
 
   // Keyboard shortcuts (M, N, P)
   const handleCreateMarkerAtPlayhead = useCallback(() => {
@@ -336,7 +344,7 @@ export function TimelineTab({ project, bounces }: TimelineTabProps) {
     setIsSharing(true);
     setShowCopied(false);
     try {
-      await tauriInvoke('share_bounce', { bouncePath: selectedBounce.bounce_path });
+      await tauriInvoke('share_bounce', { id: selectedBounce.id });
       setShowCopied(true);
       setTimeout(() => setShowCopied(false), 2000);
     } catch (err) {
@@ -346,6 +354,18 @@ export function TimelineTab({ project, bounces }: TimelineTabProps) {
     }
   };
 
+  const handleExportTagged = async () => {
+    if (!selectedBounce) return;
+    const destPath = await save({
+      title: 'Export Tagged WAV',
+      defaultPath: selectedBounce.bounce_path.replace(/(\.[^./\\]+)?$/, ' (tagged)$1'),
+      filters: [{ name: 'WAV', extensions: ['wav'] }],
+    });
+    if (destPath) {
+      exportTagged.mutate({ id: selectedBounce.id, destPath });
+    }
+  };
+
   const handleSoundCloudUpload = async () => {
     if (!selectedBounce || isUploading) return;
     setIsUploading(true);
@@ -414,6 +434,15 @@ export function TimelineTab({ project, bounces }: TimelineTabProps) {
             </option>
           ))}
         </select>
+        {selectedBounce?.format && (
+          <span className="text-xs text-text-muted">{describeAudioFormat(selectedBounce)}</span>
+        )}
+        {selectedBounce?.embedded_metadata && (
+          <span className="text-xs text-text-muted cursor-help" title={describeEmbeddedMetadata(selectedBounce.embedded_metadata)}>
+            Tagged
+          </span>
+        )}
+        {selectedBounce && <BounceAnalysis bounce={selectedBounce} onMeasured={setSelectedBounce} />}
         <button
           onClick={handleShare}
           disabled={isSharing || !selectedBounce}
@@ -438,6 +467,20 @@ export function TimelineTab({ project, bounces }: TimelineTabProps) {
             MP3 copied!
           </span>
         )}
+        {selectedBounce && ['WAV', 'RF64', 'BW64'].includes(selectedBounce.format ?? '') && (
+          <button
+            onClick={handleExportTagged}
+            disabled={exportTagged.isPending}
+            title={
+              exportTagged.isError
+                ? `Export failed: ${exportTagged.error}`
+                : 'Export a copy tagged with the project name, BPM, key, artist and notes'
+            }
+            className={`text-xs hover:text-text-primary disabled:opacity-50 transition-colors ${exportTagged.isError ? 'text-red-400' : 'text-text-muted'}`}
+          >
+            {exportTagged.isPending ? 'Exporting…' : exportTagged.isSuccess ? 'Exported' : 'Export tagged'}
+          </button>
+        )}
         <button
           onClick={handleSoundCloudUpload}
           disabled={isUploading || !selectedBounce || scLogin.isPending}
@@ -513,3 +556,71 @@ export function TimelineTab({ project, bounces }: TimelineTabProps) {
     </div>
   );
 }
+
+/** e.g. "WAV · 48 kHz · 32-bit float · Stereo" */
+function describeAudioFormat(bounce: Bounce): string {
+  const parts = [bounce.format ?? ''];
+  if (bounce.sample_rate) parts.push(`${bounce.sample_rate / 1000} kHz`);
+  if (bounce.bit_depth) parts.push(`${bounce.bit_depth}-bit${bounce.sample_format === 'float' ? ' float' : ''}`);
+  if (bounce.channels) parts.push(bounce.channels === 1 ? 'Mono' : bounce.channels === 2 ? 'Stereo' : `${bounce.channels} ch`);
+  return parts.join(' · ');
+}
+
+/** One line per embedded field, for a tooltip. */
+function describeEmbeddedMetadata(metadata: BounceMetadata): string {
+  const lines: string[] = [];
+  const { bext, ixml, info } = metadata;
+  if (info.INAM || ixml.PROJECT) lines.push(`Title: ${info.INAM || ixml.PROJECT}`);
+  if (info.IART || ixml['ASWG/artist']) lines.push(`Artist: ${info.IART || ixml['ASWG/artist']}`);
+  if (ixml['ASWG/tempo']) lines.push(`Tempo: ${ixml['ASWG/tempo']} BPM`);
+  if (ixml['ASWG/inKey']) lines.push(`Key: ${ixml['ASWG/inKey']}`);
+  if (bext?.description) lines.push(`Description: ${bext.description}`);
+  if (bext?.originator) lines.push(`Originator: ${bext.originator}`);
+  if (bext?.origination_date) lines.push(`Recorded: ${bext.origination_date} ${bext.origination_time}`.trim());
+  if (info.ICMT || ixml.NOTE) lines.push(`Comment: ${info.ICMT || ixml.NOTE}`);
+  if (info.ISFT) lines.push(`Software: ${info.ISFT}`);
+  return lines.length > 0 ? lines.join('\n') : 'Embedded metadata';
+}
+
+/** WaveSurfer's peaks and duration from the finest cached level. */
+function waveSurferPeaks({ sample_rate, frames, levels }: WaveformPeaks) {
+  return {
+    peaks: [(levels[0]?.peaks ?? []).map((v) => v / 127)],
+    duration: frames / sample_rate,
+  };
+}
+
+/** Loudness, tempo and key of a bounce; true peaks above -1 dBTP (the usual streaming limit) are flagged. */
+function BounceAnalysis({ bounce, onMeasured }: { bounce: Bounce; onMeasured: (bounce: Bounce) => void }) {
+  const analyze = useAnalyzeBounce();
+  const measured = bounce.loudness_mtime !== null && bounce.loudness_mtime === bounce.modified_time;
+
+  if (!measured) {
+    return (
+      <button
+        onClick={() => analyze.mutate(bounce.id, { onSuccess: onMeasured })}
+        disabled={analyze.isPending}
+        title={analyze.isError ? `Analysis failed: ${analyze.error}` : 'Measure loudness, true peak, tempo and key'}
+        className={`text-xs hover:text-text-primary disabled:opacity-50 transition-colors ${analyze.isError ? 'text-red-400' : 'text-text-muted'}`}
+      >
+        {analyze.isPending ? 'Analyzing…' : 'Analyze audio'}
+      </button>
+    );
+  }
+
+  const format = (value: number | null, unit: string) => (value === null ? '—' : `${value.toFixed(1)} ${unit}`);
+  const hot = bounce.true_peak_dbtp !== null && bounce.true_peak_dbtp > -1;
+  return (
+    <span className="flex items-center gap-2 text-xs text-text-muted">
+      <span title="Integrated loudness">{format(bounce.integrated_lufs, 'LUFS')}</span>
+      <span title="Short-term maximum">max {format(bounce.short_term_max_lufs, 'LUFS')}</span>
+      <span title="Loudness range">LRA {format(bounce.loudness_range_lu, 'LU')}</span>
+      <span title="Sample peak">{format(bounce.sample_peak_dbfs, 'dBFS')}</span>
+      <span title="True peak" className={hot ? 'text-amber-400' : undefined}>
+        {format(bounce.true_peak_dbtp, 'dBTP')}
+      </span>
+      {bounce.detected_bpm !== null && <span title="Detected tempo">{bounce.detected_bpm} BPM</span>}
+      {bounce.detected_key && <span title="Detected key">{bounce.detected_key}</span>}
+    </span>
+  );
+}
@@ -1,6 +1,6 @@
-import { useMutation, useQueryClient } from '@tanstack/react-query';
+import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query';
 import { tauriInvoke } from './useTauriInvoke';
-import type { Bounce } from '../types';
+import type { Bounce, WaveformPeaks } from '../types';
 
 export function useUpdateBounceNotes() {
   const queryClient = useQueryClient();
@@ -12,3 +12,31 @@ export function useUpdateBounceNotes() {
     },
   });
 }
+
+export function useAnalyzeBounce() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: (id: number) => tauriInvoke<Bounce>('analyze_bounce', { id }),
+    onSuccess: (bounce) => {
+      queryClient.invalidateQueries({ queryKey: ['project', bounce.project_id] });
+      queryClient.invalidateQueries({ queryKey: ['project_tempo_key_check', bounce.project_id] });
+    },
+  });
+}
+
+export function useExportTaggedBounce() {
+  return useMutation({
+    mutationFn: (args: { id: number; destPath: string }) => tauriInvoke('export_tagged_bounce', args),
+  });
+}
+
+export function useBouncePeaks(bounce: Bounce | null) {
+  return useQuery({
+    // The modified time is part of the key so a re-bounce fetches fresh peaks
+    queryKey: ['bounce-peaks', bounce?.bounce_path, bounce?.modified_time],
+    queryFn: () =>
+      tauriInvoke<WaveformPeaks>('get_bounce_peaks', { bouncePath: bounce!.bounce_path }),
+    enabled: bounce !== null,
+    staleTime: Infinity,
+  });
+}
@@ -1,5 +1,6 @@
-import { useQuery } from '@tanstack/react-query';
+import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query';
 import { tauriInvoke } from './useTauriInvoke';
+import type { RelinkChoice } from '../types';
 
 export function useProjectPlugins(projectId: number) {
   return useQuery({
@@ -9,6 +10,14 @@ export function useProjectPlugins(projectId: number) {
   });
 }
 
+export function useProjectTracks(projectId: number) {
+  return useQuery({
+    queryKey: ['project_tracks', projectId],
+    queryFn: () => tauriInvoke('get_project_tracks', { projectId }),
+    enabled: projectId > 0,
+  });
+}
+
 export function useProjectSamples(projectId: number) {
   return useQuery({
     queryKey: ['project_samples', projectId],
@@ -16,3 +25,72 @@ export function useProjectSamples(projectId: number) {
     enabled: projectId > 0,
   });
 }
+
+export function useProjectMidiClips(projectId: number) {
+  return useQuery({
+    queryKey: ['project_midi_clips', projectId],
+    queryFn: () => tauriInvoke('get_project_midi_clips', { projectId }),
+    enabled: projectId > 0,
+  });
+}
+
+export function useProjectPitchProfile(projectId: number) {
+  return useQuery({
+    queryKey: ['project_pitch_profile', projectId],
+    queryFn: () => tauriInvoke('get_project_pitch_profile', { projectId }),
+    enabled: projectId > 0,
+  });
+}
+
+export function useProjectTempoKeyCheck(projectId: number) {
+  return useQuery({
+    queryKey: ['project_tempo_key_check', projectId],
+    queryFn: () => tauriInvoke('get_project_tempo_key_check', { projectId }),
+    enabled: projectId > 0,
+  });
+}
+
+export function useSetTempoMap(projectId: number) {
+  return useQuery({
+    queryKey: ['set_tempo_map', projectId],
+    queryFn: () => tauriInvoke('get_set_tempo_map', { projectId }),
+    enabled: projectId > 0,
+  });
+}
+
+export function useIndexSampleFolders() {
+  return useMutation({
+    mutationFn: () => tauriInvoke('index_sample_folders'),
+  });
+}
+
+export function usePreviewSampleRelink(projectId: number) {
+  return useMutation({
+    mutationFn: () => tauriInvoke('preview_sample_relink', { projectId }),
+  });
+}
+
+export function useApplySampleRelink(projectId: number) {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: (choices: RelinkChoice[]) =>
+      tauriInvoke('apply_sample_relink', { projectId, choices }),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['project_samples', projectId] });
+      queryClient.invalidateQueries({ queryKey: ['project', projectId] });
+      queryClient.invalidateQueries({ queryKey: ['projects'] });
+    },
+  });
+}
+
+export function useCollectProjectSamples(projectId: number) {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: () => tauriInvoke('collect_project_samples', { projectId }),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['project_samples', projectId] });
+      queryClient.invalidateQueries({ queryKey: ['project', projectId] });
+      queryClient.invalidateQueries({ queryKey: ['projects'] });
+    },
+  });
+}
@@ -3,6 +3,7 @@ import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query';
 import { tauriInvoke } from './useTauriInvoke';
 import type { Project, ProjectDetail, ProjectFilters, ScanSummary, DiscoveredProject } from '../types';
 import { useLibraryStore } from '../stores/libraryStore';
+import { SKETCH_MAX_BARS } from '../lib/constants';
 
 export function useProjects() {
   const searchQuery = useLibraryStore(s => s.searchQuery);
@@ -35,6 +36,7 @@ export function useProjects() {
         case 'last_7_days': f.updated_since_days = 7; break;
         case 'last_30_days': f.updated_since_days = 30; break;
         case 'near_done': f.statuses = [...(f.statuses || []), 'Mix', 'Master']; break;
+        case 'sketches': f.max_arrangement_bars = SKETCH_MAX_BARS; break;
       }
     }
     return f;
@@ -82,14 +84,14 @@ export function useScanLibrary() {
     // scan_library now returns immediately and runs in a background thread.
     // The "scan-progress" event with stage "complete" (handled in AppLayout)
     // triggers the project list refresh when the scan actually finishes.
-    mutationFn: () => tauriInvoke<void>('scan_library'),
+    mutationFn: (full?: boolean) => tauriInvoke<void>('scan_library', { full }),
   });
 }
 
 export function useRefreshLibrary() {
   const queryClient = useQueryClient();
   return useMutation({
-    mutationFn: () => tauriInvoke<ScanSummary>('refresh_library'),
+    mutationFn: (full?: boolean) => tauriInvoke<ScanSummary>('refresh_library', { full }),
     onSuccess: () => {
       queryClient.invalidateQueries({ queryKey: ['projects'] });
     },
@@ -1,6 +1,6 @@
 import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query';
 import { tauriInvoke } from './useTauriInvoke';
-import type { Setting } from '../types';
+import type { LibraryRoot, Setting } from '../types';
 
 export function useSettings() {
   return useQuery({
@@ -20,6 +20,37 @@ export function useUpdateSettings() {
   });
 }
 
+export function useLibraryRoots() {
+  return useQuery({
+    queryKey: ['library-roots'],
+    queryFn: () => tauriInvoke('get_library_roots'),
+  });
+}
+
+function useLibraryRootMutation<T>(mutationFn: (arg: T) => Promise<unknown>) {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn,
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ['library-roots'] });
+      // Roots keep the root_folder setting pointed at the first one
+      queryClient.invalidateQueries({ queryKey: ['settings'] });
+    },
+  });
+}
+
+export function useAddLibraryRoot() {
+  return useLibraryRootMutation((path: string) => tauriInvoke('add_library_root', { path }));
+}
+
+export function useUpdateLibraryRoot() {
+  return useLibraryRootMutation((root: LibraryRoot) => tauriInvoke('update_library_root', { root }));
+}
+
+export function useRemoveLibraryRoot() {
+  return useLibraryRootMutation((id: number) => tauriInvoke('remove_library_root', { id }));
+}
+
 export function getSettingValue(settings: Setting[] | undefined, key: string): string {
   return settings?.find(s => s.key === key)?.value ?? '';
 }
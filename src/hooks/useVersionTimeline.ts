@@ -1,6 +1,6 @@
 import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query';
 import { tauriInvoke } from './useTauriInvoke';
-import type { VersionTimelineEntry, VersionNote } from '../types';
+import type { VersionTimelineEntry, VersionNote, SetDiff, SetBackup, AbletonSet } from '../types';
 
 export function useVersionTimeline(projectId: number) {
   return useQuery({
@@ -10,6 +10,17 @@ export function useVersionTimeline(projectId: number) {
   });
 }
 
+/** Structural diff between two versions. Parses both .als files, so it is cached for the session. */
+export function useSetDiff(oldSetId: number | null, newSetId: number) {
+  return useQuery({
+    queryKey: ['set-diff', oldSetId, newSetId],
+    queryFn: () => tauriInvoke<SetDiff>('diff_ableton_sets', { oldSetId: oldSetId!, newSetId }),
+    enabled: oldSetId !== null && newSetId > 0,
+    staleTime: Infinity,
+    retry: false,
+  });
+}
+
 export function useUpsertVersionNote() {
   const queryClient = useQueryClient();
   return useMutation({
@@ -31,3 +42,24 @@ export function useDeleteVersionNote() {
     },
   });
 }
+
+export function useSetBackups(projectId: number) {
+  return useQuery({
+    queryKey: ['set-backups', projectId],
+    queryFn: () => tauriInvoke<SetBackup[]>('get_set_backups', { projectId }),
+    enabled: projectId > 0,
+  });
+}
+
+/** Copies a backup into the project folder as a new set; nothing is overwritten. */
+export function useRestoreSetBackup() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: (backup: SetBackup) =>
+      tauriInvoke<AbletonSet>('restore_set_backup', { backupId: backup.id }),
+    onSuccess: (_, backup) => {
+      queryClient.invalidateQueries({ queryKey: ['version-timeline', backup.project_id] });
+      queryClient.invalidateQueries({ queryKey: ['project', backup.project_id] });
+    },
+  });
+}
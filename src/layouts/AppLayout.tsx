@@ -40,6 +40,17 @@ export function AppLayout() {
     return () => { unlistenPromise.then((fn) => fn()); };
   }, [queryClient]);
 
+  // Background watcher updates — refresh quietly, without the scan modal
+  useEffect(() => {
+    const unlistenPromise = listen<ScanProgress>('watch-progress', (event) => {
+      if (event.payload.stage === 'complete') {
+        queryClient.invalidateQueries({ queryKey: ['projects'] });
+        queryClient.invalidateQueries({ queryKey: ['project'] });
+      }
+    });
+    return () => { unlistenPromise.then((fn) => fn()); };
+  }, [queryClient]);
+
   // Restore Supabase session on mount
   const restoreSession = useRestoreSession();
   useEffect(() => {
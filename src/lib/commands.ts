@@ -17,10 +17,12 @@
 // ============================================================================
 
 import type {
+  LibraryRoot,
   Project,
   ProjectDetail,
   ProjectFilters,
   Bounce,
+  WaveformPeaks,
   AbletonSet,
   Tag,
   Session,
@@ -42,8 +44,20 @@ import type {
   LicenseInfo,
   PluginInfo,
   SampleInfo,
+  SampleIndexSummary,
+  RelinkReport,
+  RelinkChoice,
+  RelinkResult,
+  CollectResult,
+  TrackInfo,
+  MidiClip,
+  PitchProfile,
+  TempoKeyCheck,
+  SetTempoMap,
   VersionTimelineEntry,
   VersionNote,
+  SetBackup,
+  SetDiff,
   Collection,
   SmartCollectionRule,
   SmartCollectionRuleInput,
@@ -65,6 +79,22 @@ export type CommandMap = {
     args: { settings: Setting[] };
     return: void;
   };
+  get_library_roots: {
+    args: Record<string, never>;
+    return: LibraryRoot[];
+  };
+  add_library_root: {
+    args: { path: string };
+    return: LibraryRoot;
+  };
+  update_library_root: {
+    args: { root: LibraryRoot };
+    return: void;
+  };
+  remove_library_root: {
+    args: { id: number };
+    return: void;
+  };
 
   // --- Projects ---
   get_projects: {
@@ -150,11 +180,11 @@ export type CommandMap = {
 
   // --- Scanner ---
   scan_library: {
-    args: Record<string, never>;
+    args: { full?: boolean };
     return: ScanSummary;
   };
   refresh_library: {
-    args: Record<string, never>;
+    args: { full?: boolean };
     return: ScanSummary;
   };
   discover_untracked_projects: {
@@ -214,6 +244,14 @@ export type CommandMap = {
     args: { id: number };
     return: void;
   };
+  seconds_to_bars: {
+    args: { projectId: number; seconds: number[] };
+    return: number[];
+  };
+  bars_to_seconds: {
+    args: { projectId: number; bars: number[] };
+    return: number[];
+  };
 
   // --- Tasks ---
   get_tasks: {
@@ -392,7 +430,7 @@ export type CommandMap = {
 
   // --- Share ---
   share_bounce: {
-    args: { bouncePath: string };
+    args: { id: number };
     return: string;
   };
 
@@ -431,10 +469,30 @@ export type CommandMap = {
     args: { projectId: number };
     return: PluginInfo[];
   };
+  get_project_tracks: {
+    args: { projectId: number };
+    return: TrackInfo[];
+  };
   get_project_samples: {
     args: { projectId: number };
     return: SampleInfo[];
   };
+  get_project_midi_clips: {
+    args: { projectId: number };
+    return: MidiClip[];
+  };
+  get_project_pitch_profile: {
+    args: { projectId: number };
+    return: PitchProfile;
+  };
+  get_project_tempo_key_check: {
+    args: { projectId: number };
+    return: TempoKeyCheck;
+  };
+  get_set_tempo_map: {
+    args: { projectId: number };
+    return: SetTempoMap | null;
+  };
 
   // --- License ---
   get_license_status: {
@@ -459,6 +517,18 @@ export type CommandMap = {
     args: { id: number; notes: string };
     return: Bounce;
   };
+  get_bounce_peaks: {
+    args: { bouncePath: string };
+    return: WaveformPeaks;
+  };
+  analyze_bounce: {
+    args: { id: number };
+    return: Bounce;
+  };
+  export_tagged_bounce: {
+    args: { id: number; destPath: string };
+    return: void;
+  };
 
   // --- Version Timeline (v1.1.0) ---
   get_version_timeline: {
@@ -473,6 +543,18 @@ export type CommandMap = {
     args: { setId: number };
     return: void;
   };
+  diff_ableton_sets: {
+    args: { oldSetId: number; newSetId: number };
+    return: SetDiff;
+  };
+  get_set_backups: {
+    args: { projectId: number };
+    return: SetBackup[];
+  };
+  restore_set_backup: {
+    args: { backupId: number };
+    return: AbletonSet;
+  };
 
   // --- Project Quick-Create (v1.1.0) ---
   quick_create_project: {
@@ -550,6 +632,24 @@ export type CommandMap = {
     return: LibraryHealth;
   };
 
+  // --- Missing-Sample Relinker ---
+  index_sample_folders: {
+    args: Record<string, never>;
+    return: SampleIndexSummary;
+  };
+  preview_sample_relink: {
+    args: { projectId: number };
+    return: RelinkReport;
+  };
+  apply_sample_relink: {
+    args: { projectId: number; choices: RelinkChoice[] };
+    return: RelinkResult;
+  };
+  collect_project_samples: {
+    args: { projectId: number };
+    return: CollectResult;
+  };
+
   // --- Update Checker ---
   check_for_update: {
     args: Record<string, never>;
@@ -50,8 +50,12 @@ export const SORT_OPTIONS = [
   { value: 'updated_at', label: 'Updated' },
   { value: 'in_rotation', label: 'In Rotation' },
   { value: 'progress', label: '% Done' },
+  { value: 'arrangement_length', label: 'Arrangement Length' },
 ] as const;
 
+/** The "Sketches" smart filter matches arrangements up to this many bars. */
+export const SKETCH_MAX_BARS = 32;
+
 export type TableColumnKey =
   | 'name'
   | 'status'
@@ -108,6 +112,7 @@ export const MARKER_TYPES: { value: MarkerType; label: string; color: string }[]
   { value: 'task', label: 'Task', color: '#a855f7' },       // purple
   { value: 'idea', label: 'Idea', color: '#22c55e' },       // green
   { value: 'issue', label: 'Issue/Flag', color: '#ef4444' }, // red
+  { value: 'locator', label: 'Ableton Locator', color: '#0ea5e9' }, // sky — imported from .als
 ];
 
 export const TASK_CATEGORIES: TaskCategory[] = [
@@ -1,7 +1,7 @@
 import { create } from 'zustand';
 import { persist } from 'zustand/middleware';
 import type { ProjectFilters } from '../types';
-import { DEFAULT_VISIBLE_COLUMNS, type TableColumnKey } from '../lib/constants';
+import { DEFAULT_VISIBLE_COLUMNS, SKETCH_MAX_BARS, type TableColumnKey } from '../lib/constants';
 
 interface SmartFilter {
   key: string;
@@ -60,6 +60,7 @@ export const useLibraryStore = create<LibraryState>()(
         { key: 'last_7_days', label: 'Last 7 Days', active: false },
         { key: 'last_30_days', label: 'Last 30 Days', active: false },
         { key: 'near_done', label: 'Near Done', active: false },
+        { key: 'sketches', label: 'Sketches', active: false },
       ],
       focusedCardIndex: -1,
       viewMode: 'table',
@@ -135,6 +136,7 @@ export const useLibraryStore = create<LibraryState>()(
             { key: 'last_7_days', label: 'Last 7 Days', active: false },
             { key: 'last_30_days', label: 'Last 30 Days', active: false },
             { key: 'near_done', label: 'Near Done', active: false },
+            { key: 'sketches', label: 'Sketches', active: false },
           ],
           tableSortDir: 'desc',
           activeCollectionId: null,
@@ -186,6 +188,9 @@ export const useLibraryStore = create<LibraryState>()(
             case 'near_done':
               filters.statuses = [...(filters.statuses || []), 'Mix', 'Master'];
               break;
+            case 'sketches':
+              filters.max_arrangement_bars = SKETCH_MAX_BARS;
+              break;
           }
         }
 
@@ -215,6 +220,14 @@ export const useLibraryStore = create<LibraryState>()(
             merged.visibleColumns = [...stored, ...missing];
           }
         }
+        // Ensure newly added smart filters appear in stored smartFilters
+        const storedSmart = (persisted as any)?.smartFilters as SmartFilter[] | undefined;
+        if (storedSmart) {
+          const missingSmart = current.smartFilters.filter((d) => !storedSmart.some((sf) => sf.key === d.key));
+          if (missingSmart.length > 0) {
+            merged.smartFilters = [...storedSmart, ...missingSmart];
+          }
+        }
         return merged;
       },
     }
@@ -28,6 +28,16 @@ export interface Project {
   cover_updated_at: string | null;
   has_missing_deps: boolean;
   als_parsed_at: number | null;
+  arrangement_length_bars: number | null;
+  arrangement_length_seconds: number | null;
+  time_signature: string | null;
+  scene_count: number | null;
+  session_clip_count: number | null;
+  arrangement_clip_count: number | null;
+  live_version: string | null;
+  tempo_min: number | null;
+  tempo_max: number | null;
+  tempo_avg: number | null;
 }
 
 export interface ProjectDetail {
@@ -45,6 +55,17 @@ export interface AbletonSet {
   file_size: number | null;
 }
 
+/** A timestamped copy of a set from the project's Backup folder. */
+export interface SetBackup {
+  id: number;
+  project_id: number;
+  set_id: number | null; // null when the set it was taken from is gone
+  backup_path: string;
+  backed_up_at: string | null;
+  modified_time: string;
+  file_size: number | null;
+}
+
 export interface Bounce {
   id: number;
   project_id: number;
@@ -52,6 +73,51 @@ export interface Bounce {
   modified_time: string;
   duration_seconds: number | null;
   notes: string;
+  format: string | null; // 'WAV' | 'RF64' | 'BW64' | 'AIFF' | 'FLAC' | 'MP3'
+  sample_rate: number | null;
+  bit_depth: number | null; // null for MP3
+  channels: number | null;
+  sample_format: 'pcm' | 'float' | null;
+  integrated_lufs: number | null;
+  short_term_max_lufs: number | null;
+  loudness_range_lu: number | null;
+  sample_peak_dbfs: number | null;
+  true_peak_dbtp: number | null;
+  loudness_mtime: string | null; // modified_time the bounce was analysed at
+  detected_bpm: number | null;
+  detected_key: string | null; // "A Minor"
+  embedded_metadata: BounceMetadata | null;
+}
+
+/** Metadata embedded in a WAV bounce's bext, iXML and LIST/INFO chunks. */
+export interface BounceMetadata {
+  bext: BextInfo | null;
+  /** iXML leaf elements by path below the root ("PROJECT", "ASWG/tempo") */
+  ixml: Record<string, string>;
+  /** LIST/INFO tags by ID ("INAM" is the title, "IART" the artist) */
+  info: Record<string, string>;
+}
+
+export interface BextInfo {
+  description: string;
+  originator: string;
+  originator_reference: string;
+  origination_date: string;
+  origination_time: string;
+  time_reference: number;
+  coding_history: string;
+}
+
+export interface PeakLevel {
+  frames_per_peak: number;
+  peaks: number[]; // a min/max pair per peak over all channels, -127..127
+}
+
+export interface WaveformPeaks {
+  sample_rate: number;
+  channels: number;
+  frames: number;
+  levels: PeakLevel[]; // finest resolution first
 }
 
 export interface Tag {
@@ -73,11 +139,26 @@ export interface Setting {
   value: string;
 }
 
+/** A folder of projects scanned into the library. */
+export interface LibraryRoot {
+  id: number;
+  path: string;
+  bounce_folder_name: string;
+  /** Folder level below the root that names the genre (0 = no genre labels). */
+  genre_depth: number;
+  /** Whether the folder is reachable right now. */
+  online: boolean;
+}
+
 export interface ScanSummary {
   found: number;
   new: number;
   updated: number;
   missing: number;
+  /** Projects processed because they were new or changed on disk. */
+  changed: number;
+  /** Projects left alone because nothing changed since the last scan. */
+  skipped: number;
   errors: string[];
 }
 
@@ -99,6 +180,11 @@ export interface ProjectFilters {
   sort_by?: string;
   sort_dir?: string;
   collection_id?: number;
+  min_arrangement_bars?: number;
+  max_arrangement_bars?: number;
+  time_signature?: string;
+  min_bpm?: number;
+  max_bpm?: number;
 }
 
 export interface IncompleteSession {
@@ -108,7 +194,7 @@ export interface IncompleteSession {
 
 // ── Studio Timeline types ──
 
-export type MarkerType = 'note' | 'mix' | 'task' | 'idea' | 'issue';
+export type MarkerType = 'note' | 'mix' | 'task' | 'idea' | 'issue' | 'locator';
 
 export interface Marker {
   id: number;
@@ -215,10 +301,147 @@ export interface PluginInfo {
   plugin_type: string;
 }
 
+export type SampleLocation = 'project' | 'user_library' | 'core_library' | 'pack' | 'external';
+
 export interface SampleInfo {
   path: string;
   filename: string;
   is_missing: boolean;
+  relative_path: string | null;
+  resolved_path: string | null;
+  location: SampleLocation;
+}
+
+export interface SampleIndexSummary {
+  files: number;
+  hashed: number;
+  removed: number;
+  errors: string[];
+}
+
+export interface RelinkCandidate {
+  path: string;
+  file_size: number;
+  content_hash: string;
+  size_matches: boolean | null;
+  duplicates: number;
+}
+
+export interface RelinkEntry {
+  sample_path: string;
+  filename: string;
+  original_size: number | null;
+  candidates: RelinkCandidate[];
+  proposed_path: string | null;
+}
+
+export interface RelinkReport {
+  project_id: number;
+  set_path: string;
+  entries: RelinkEntry[];
+}
+
+export interface RelinkChoice {
+  sample_path: string;
+  new_path: string;
+}
+
+export interface RelinkResult {
+  set_path: string;
+  backup_path: string | null;
+  relinked: number;
+}
+
+export interface CollectResult {
+  set_path: string;
+  files_copied: number;
+  files_reused: number;
+  bytes_copied: number;
+  missing: string[];
+  backup_path: string | null;
+}
+
+export type TrackType = 'audio' | 'midi' | 'group' | 'return';
+
+export interface TrackInfo {
+  name: string;
+  track_type: TrackType;
+  color_index: number | null;
+  is_muted: boolean;
+  is_soloed: boolean;
+  is_frozen: boolean;
+  devices: string[];
+  device_chain: DeviceInfo[];
+}
+
+export type DeviceType = 'native' | 'rack' | 'vst2' | 'vst3' | 'au' | 'max_for_live';
+
+export interface DeviceInfo {
+  chain_order: number;
+  depth: number;
+  parent_order: number | null;
+  branch_name: string | null;
+  name: string;
+  user_name: string | null;
+  device_type: DeviceType;
+  is_on: boolean;
+  preset_name: string | null;
+}
+
+export interface MidiNote {
+  key: number;
+  time: number;
+  duration: number;
+  velocity: number;
+  is_enabled: boolean;
+}
+
+export interface MidiClip {
+  track_index: number;
+  name: string;
+  in_arrangement: boolean;
+  start: number | null;
+  end: number | null;
+  loop_start: number | null;
+  loop_end: number | null;
+  loop_on: boolean;
+  notes: MidiNote[];
+}
+
+export interface PitchProfile {
+  /** Beats of note time per pitch class, C first. */
+  histogram: number[];
+  estimated_key: string | null;
+  midi_clip_count: number;
+  note_count: number;
+}
+
+/** The .als tempo and key next to those detected from the newest analysed bounce. */
+export interface TempoKeyCheck {
+  als_bpm: number | null;
+  als_key: string | null;
+  bounce_path: string | null;
+  detected_bpm: number | null;
+  detected_key: string | null;
+  tempo_mismatch: boolean;
+  key_mismatch: boolean;
+}
+
+export interface TempoPoint {
+  beat: number;
+  bpm: number;
+}
+
+export interface TimeSignatureChange {
+  beat: number;
+  numerator: number;
+  denominator: number;
+}
+
+/** Tempo (ramping linearly between points) and time signature changes of a set. */
+export interface SetTempoMap {
+  tempo_points: TempoPoint[];
+  time_signatures: TimeSignatureChange[];
 }
 
 // ── Scan Progress types ──
@@ -227,7 +450,8 @@ export interface ScanProgress {
   current: number;
   total: number;
   project_name: string;
-  stage: 'scanning' | 'generating_covers' | 'complete';
+  /** 'updating' is only sent by the background watcher ("watch-progress"). */
+  stage: 'scanning' | 'generating_covers' | 'updating' | 'complete';
 }
 
 export type AssetType = 'image' | 'audio' | 'generic';
@@ -287,6 +511,26 @@ export interface VersionTimelineEntry {
   note_id: number | null;
 }
 
+export interface ValueChange<T> {
+  before: T | null;
+  after: T | null;
+}
+
+export interface SetDiff {
+  tracks_added: string[];
+  tracks_removed: string[];
+  tracks_renamed: { from: string; to: string }[];
+  plugins_added: PluginInfo[];
+  plugins_removed: PluginInfo[];
+  tempo: ValueChange<number> | null;
+  key: ValueChange<string> | null;
+  time_signature: ValueChange<string> | null;
+  arrangement_length_seconds: ValueChange<number> | null;
+  samples_added: string[];
+  samples_removed: string[];
+  summary: string;
+}
+
 // ── Collections types ──
 
 export type CollectionType = 'smart' | 'manual';
@@ -4,28 +4,45 @@ import { Button } from '../components/ui/Button';
 import { Input } from '../components/ui/Input';
 import { Toggle } from '../components/ui/Toggle';
 import { Select } from '../components/ui/Select';
-import { useSettings, useUpdateSettings, getSettingValue } from '../hooks/useSettings';
-import { useScanLibrary, useRefreshLibrary, useDiscoverProjects, useImportProjects } from '../hooks/useProjects';
+import { useSettings, useUpdateSettings, useLibraryRoots, getSettingValue } from '../hooks/useSettings';
+import { useRefreshLibrary, useDiscoverProjects, useImportProjects } from '../hooks/useProjects';
 import { useSoundCloudAuthStatus, useSoundCloudLogout } from '../hooks/useSoundCloud';
 import { CloudSyncSection } from '../components/settings/CloudSyncSection';
+import { LibraryRootsSection } from '../components/settings/LibraryRootsSection';
 import { LicenseSettings } from '../components/license/LicenseSettings';
 import { IS_MAC, MOD_KEY_LABEL } from '../lib/platform';
 import type { DiscoveredProject } from '../types';
 
+/** Settings for Live's content folders, used to locate and classify samples. */
+const LIVE_FOLDERS = [
+  { key: 'user_library_path', label: 'User Library' },
+  { key: 'core_library_path', label: 'Core Library' },
+  { key: 'packs_path', label: 'Packs' },
+];
+
 export function SettingsView() {
   const { data: settings, isLoading } = useSettings();
+  const { data: libraryRoots = [] } = useLibraryRoots();
   const updateSettings = useUpdateSettings();
-  const scanLibrary = useScanLibrary();
   const refreshLibrary = useRefreshLibrary();
   const discoverProjects = useDiscoverProjects();
   const importProjects = useImportProjects();
 
-  const [rootFolder, setRootFolder] = useState('');
   const [abletonPath, setAbletonPath] = useState('');
   const [bounceFolderName, setBounceFolderName] = useState('Bounces');
+  const [artistName, setArtistName] = useState('');
   const [scanOnLaunch, setScanOnLaunch] = useState(true);
+  const [scanAnimation, setScanAnimation] = useState(true);
   const [randomProjectMode, setRandomProjectMode] = useState('preview');
   const [scPublicUpload, setScPublicUpload] = useState(false);
+  const [mp3Mode, setMp3Mode] = useState('cbr');
+  const [mp3Bitrate, setMp3Bitrate] = useState('320');
+  const [mp3VbrQuality, setMp3VbrQuality] = useState('2');
+  const [mp3SampleRate, setMp3SampleRate] = useState('');
+  const [mp3Mono, setMp3Mono] = useState(false);
+  const [sampleRoots, setSampleRoots] = useState<string[]>([]);
+  const [liveFolders, setLiveFolders] = useState<Record<string, string>>({});
+  const [ignorePatterns, setIgnorePatterns] = useState('');
   const [saved, setSaved] = useState(false);
 
   // Import checklist state
@@ -34,29 +51,26 @@ export function SettingsView() {
 
   useEffect(() => {
     if (settings) {
-      setRootFolder(getSettingValue(settings, 'root_folder'));
       setAbletonPath(getSettingValue(settings, 'ableton_exe_path'));
       setBounceFolderName(getSettingValue(settings, 'bounce_folder_name') || 'Bounces');
+      setArtistName(getSettingValue(settings, 'artist_name'));
       setScanOnLaunch(getSettingValue(settings, 'scan_on_launch') !== 'false');
+      setScanAnimation(getSettingValue(settings, 'scan_animation') !== 'false');
       setRandomProjectMode(getSettingValue(settings, 'random_project_mode') || 'preview');
       setScPublicUpload(getSettingValue(settings, 'soundcloud_public_upload') === 'true');
+      setMp3Mode(getSettingValue(settings, 'mp3_mode') || 'cbr');
+      setMp3Bitrate(getSettingValue(settings, 'mp3_bitrate') || '320');
+      setMp3VbrQuality(getSettingValue(settings, 'mp3_vbr_quality') || '2');
+      setMp3SampleRate(getSettingValue(settings, 'mp3_sample_rate'));
+      setMp3Mono(getSettingValue(settings, 'mp3_mono') === 'true');
+      setSampleRoots(parseStringList(getSettingValue(settings, 'sample_roots')));
+      setLiveFolders(Object.fromEntries(
+        LIVE_FOLDERS.map(({ key }) => [key, getSettingValue(settings, key)]),
+      ));
+      setIgnorePatterns(parseStringList(getSettingValue(settings, 'scan_ignore_patterns')).join('\n'));
     }
   }, [settings]);
 
-  const pickRootFolder = async () => {
-    const selected = await open({ directory: true, multiple: false, title: 'Select Root Project Folder' });
-    if (selected) {
-      const folder = selected as string;
-      setRootFolder(folder);
-      // Auto-save the root folder immediately so the user doesn't have to click Save
-      await updateSettings.mutateAsync([
-        { key: 'root_folder', value: folder },
-      ]);
-      // Full scan to discover and import all projects
-      scanLibrary.mutate();
-    }
-  };
-
   const pickAbletonExe = async () => {
     const selected = await open({
       multiple: false,
@@ -75,14 +89,50 @@ export function SettingsView() {
     }
   };
 
+  const saveSampleRoots = async (roots: string[]) => {
+    setSampleRoots(roots);
+    await updateSettings.mutateAsync([
+      { key: 'sample_roots', value: JSON.stringify(roots) },
+    ]);
+  };
+
+  const addSampleRoot = async () => {
+    const selected = await open({ directory: true, multiple: false, title: 'Add Sample Folder' });
+    if (selected && !sampleRoots.includes(selected as string)) {
+      await saveSampleRoots([...sampleRoots, selected as string]);
+    }
+  };
+
+  const saveLiveFolder = async (key: string, path: string) => {
+    setLiveFolders((prev) => ({ ...prev, [key]: path }));
+    await updateSettings.mutateAsync([{ key, value: path }]);
+  };
+
+  const pickLiveFolder = async (key: string, label: string) => {
+    const selected = await open({ directory: true, multiple: false, title: `Select ${label} Folder` });
+    if (selected) {
+      await saveLiveFolder(key, selected as string);
+    }
+  };
+
   const handleSave = async () => {
     await updateSettings.mutateAsync([
-      { key: 'root_folder', value: rootFolder },
       { key: 'ableton_exe_path', value: abletonPath },
       { key: 'bounce_folder_name', value: bounceFolderName },
+      { key: 'artist_name', value: artistName.trim() },
       { key: 'scan_on_launch', value: scanOnLaunch.toString() },
+      { key: 'scan_animation', value: scanAnimation.toString() },
+      {
+        key: 'scan_ignore_patterns',
+        value: JSON.stringify(ignorePatterns.split('\n').map((p) => p.trim()).filter(Boolean)),
+      },
       { key: 'random_project_mode', value: randomProjectMode },
       { key: 'soundcloud_public_upload', value: scPublicUpload.toString() },
+      { key: 'mp3_mode', value: mp3Mode },
+      { key: 'mp3_bitrate', value: mp3Bitrate },
+      { key: 'mp3_vbr_quality', value: mp3VbrQuality },
+      { key: 'mp3_sample_rate', value: mp3SampleRate },
+      { key: 'mp3_mono', value: mp3Mono.toString() },
     ]);
     setSaved(true);
     setTimeout(() => setSaved(false), 2000);
@@ -119,7 +169,7 @@ export function SettingsView() {
     return <div className="text-text-secondary">Loading settings...</div>;
   }
 
-  const noRootFolder = !getSettingValue(settings, 'root_folder');
+  const noRootFolder = libraryRoots.length === 0;
   const selectedCount = selectedPaths.size;
 
   return (
@@ -129,7 +179,7 @@ export function SettingsView() {
       {noRootFolder && (
         <div className="mb-6 rounded-lg border border-brand-500/30 bg-brand-500/10 p-4">
           <p className="text-sm text-brand-300">
-            Welcome! Set your root project folder below to get started. This is the folder that contains your Ableton project directories.
+            Welcome! Add a library folder below to get started. This is a folder that contains your Ableton project directories.
           </p>
         </div>
       )}
@@ -143,22 +193,8 @@ export function SettingsView() {
 
         <div className="border-t border-border-default" />
 
-        {/* Root Folder */}
-        <div>
-          <label className="block text-sm font-medium text-text-secondary mb-1">Root Project Folder</label>
-          <div className="flex gap-2">
-            <input
-              readOnly
-              value={rootFolder}
-              placeholder="Select your root project folder..."
-              className="flex-1 rounded-lg border border-border-default bg-bg-elevated px-3 py-2 text-sm text-text-primary placeholder-text-muted"
-            />
-            <Button variant="secondary" onClick={pickRootFolder}>Browse</Button>
-          </div>
-          <p className="mt-1 text-xs text-text-muted">
-            The folder containing your Ableton project directories (or genre subfolders).
-          </p>
-        </div>
+        {/* Library Folders */}
+        <LibraryRootsSection />
 
         {/* Ableton Exe Path */}
         <div>
@@ -177,12 +213,103 @@ export function SettingsView() {
         </div>
 
         {/* Bounce Folder Name */}
-        <Input
-          label="Bounce Subfolder Name"
-          value={bounceFolderName}
-          onChange={(e) => setBounceFolderName(e.target.value)}
-          placeholder="Bounces"
-        />
+        <div>
+          <Input
+            label="Default Bounce Subfolder Name"
+            value={bounceFolderName}
+            onChange={(e) => setBounceFolderName(e.target.value)}
+            placeholder="Bounces"
+          />
+          <p className="mt-1 text-xs text-text-muted">
+            Used for newly added library folders and for projects outside them.
+          </p>
+        </div>
+
+        {/* Artist Name */}
+        <div>
+          <Input
+            label="Artist Name"
+            value={artistName}
+            onChange={(e) => setArtistName(e.target.value)}
+            placeholder="Your artist name"
+          />
+          <p className="mt-1 text-xs text-text-muted">
+            Written into the metadata of exported bounces.
+          </p>
+        </div>
+
+        {/* Sample Folders */}
+        <div>
+          <label className="block text-sm font-medium text-text-secondary mb-1">Sample Folders</label>
+          {sampleRoots.length > 0 && (
+            <div className="mb-2 rounded-lg border border-border-default bg-bg-elevated/50">
+              {sampleRoots.map((root) => (
+                <div
+                  key={root}
+                  className="flex items-center gap-2 px-3 py-1.5 border-b border-border-default/50 last:border-b-0"
+                >
+                  <span className="flex-1 truncate text-sm text-text-primary" title={root}>{root}</span>
+                  <button
+                    className="text-xs text-text-muted hover:text-red-400"
+                    onClick={() => saveSampleRoots(sampleRoots.filter((r) => r !== root))}
+                  >
+                    Remove
+                  </button>
+                </div>
+              ))}
+            </div>
+          )}
+          <Button variant="secondary" size="sm" onClick={addSampleRoot}>Add Folder</Button>
+          <p className="mt-1 text-xs text-text-muted">
+            Folders searched when relinking a project's missing samples.
+          </p>
+        </div>
+
+        {/* Live Library Folders */}
+        <div>
+          <label className="block text-sm font-medium text-text-secondary mb-1">Live Library Folders</label>
+          <div className="space-y-2">
+            {LIVE_FOLDERS.map(({ key, label }) => (
+              <div key={key} className="flex items-center gap-2">
+                <span className="w-28 shrink-0 text-xs text-text-muted">{label}</span>
+                <input
+                  readOnly
+                  value={liveFolders[key] ?? ''}
+                  placeholder="Found by folder name"
+                  className="flex-1 rounded-lg border border-border-default bg-bg-elevated px-3 py-1.5 text-sm text-text-primary placeholder-text-muted"
+                />
+                <Button variant="secondary" size="sm" onClick={() => pickLiveFolder(key, label)}>Browse</Button>
+                {liveFolders[key] && (
+                  <button
+                    className="text-xs text-text-muted hover:text-red-400"
+                    onClick={() => saveLiveFolder(key, '')}
+                  >
+                    Clear
+                  </button>
+                )}
+              </div>
+            ))}
+          </div>
+          <p className="mt-1 text-xs text-text-muted">
+            Where Live keeps its content on this computer. Used to find samples from sets made elsewhere and to tell library samples from external ones.
+          </p>
+        </div>
+
+        {/* Scan Ignore Patterns */}
+        <div>
+          <label className="block text-sm font-medium text-text-secondary mb-1">Scan Ignore Patterns</label>
+          <textarea
+            value={ignorePatterns}
+            onChange={(e) => setIgnorePatterns(e.target.value)}
+            rows={4}
+            spellCheck={false}
+            placeholder={'Trash/\nTemplates/'}
+            className="w-full rounded-lg border border-border-default bg-bg-elevated px-3 py-2 font-mono text-xs text-text-primary placeholder-text-muted"
+          />
+          <p className="mt-1 text-xs text-text-muted">
+            One .gitignore-style pattern per line. Matching folders are never scanned as projects. A .setcrateignore file in any folder adds patterns for the folders below it.
+          </p>
+        </div>
 
         {/* Refresh on Launch */}
         <Toggle
@@ -192,6 +319,14 @@ export function SettingsView() {
           description="Automatically refresh metadata for existing projects when the app starts."
         />
 
+        {/* Scan Animation */}
+        <Toggle
+          label="Animate Scan Progress"
+          checked={scanAnimation}
+          onChange={setScanAnimation}
+          description="Pause briefly on each changed project so the scan progress can be followed. Turn off for faster scans."
+        />
+
         {/* Random Project Behavior */}
         <div>
           <Select
@@ -208,6 +343,62 @@ export function SettingsView() {
           </p>
         </div>
 
+        {/* MP3 Encoding */}
+        <div className="border-t border-border-default pt-6 space-y-3">
+          <h3 className="text-sm font-medium text-text-secondary">MP3 Encoding</h3>
+          <p className="text-xs text-text-muted">
+            Used when sharing bounces and uploading them to the cloud. MP3s are tagged with the project name, BPM, key, cover, your artist name and the bounce's notes.
+          </p>
+          <div className="grid grid-cols-2 gap-3">
+            <Select
+              label="Bitrate Mode"
+              value={mp3Mode}
+              onChange={(e) => setMp3Mode(e.target.value)}
+              options={[
+                { value: 'cbr', label: 'Constant (CBR)' },
+                { value: 'vbr', label: 'Variable (VBR)' },
+              ]}
+            />
+            {mp3Mode === 'vbr' ? (
+              <Select
+                label="VBR Quality"
+                value={mp3VbrQuality}
+                onChange={(e) => setMp3VbrQuality(e.target.value)}
+                options={[
+                  { value: '0', label: 'V0 — ~245 kbps' },
+                  { value: '2', label: 'V2 — ~190 kbps' },
+                  { value: '4', label: 'V4 — ~165 kbps' },
+                  { value: '6', label: 'V6 — ~115 kbps' },
+                ]}
+              />
+            ) : (
+              <Select
+                label="Bitrate"
+                value={mp3Bitrate}
+                onChange={(e) => setMp3Bitrate(e.target.value)}
+                options={['320', '256', '192', '160', '128'].map((kbps) => ({ value: kbps, label: `${kbps} kbps` }))}
+              />
+            )}
+            <Select
+              label="Sample Rate"
+              value={mp3SampleRate}
+              onChange={(e) => setMp3SampleRate(e.target.value)}
+              options={[
+                { value: '', label: 'Same as bounce' },
+                { value: '48000', label: '48 kHz' },
+                { value: '44100', label: '44.1 kHz' },
+                { value: '32000', label: '32 kHz' },
+              ]}
+            />
+          </div>
+          <Toggle
+            label="Mono"
+            checked={mp3Mono}
+            onChange={setMp3Mono}
+            description="Fold bounces down to a single channel."
+          />
+        </div>
+
         {/* SoundCloud */}
         <div className="border-t border-border-default pt-6">
           <h3 className="text-sm font-medium text-text-secondary mb-3">SoundCloud</h3>
@@ -231,7 +422,7 @@ export function SettingsView() {
         <div className="border-t border-border-default pt-6">
           <h3 className="text-sm font-medium text-text-secondary mb-2">Refresh Library</h3>
           <p className="text-xs text-text-muted mb-3">
-            Update metadata (sets, bounces, timestamps) for all existing projects. Does not add new projects.
+            Update metadata (sets, bounces, timestamps) for existing projects that changed since the last scan. Does not add new projects.
           </p>
           <div className="flex items-center gap-3">
             <Button
@@ -241,9 +432,16 @@ export function SettingsView() {
             >
               {refreshLibrary.isPending ? 'Refreshing...' : 'Refresh Now'}
             </Button>
+            <Button
+              variant="secondary"
+              onClick={() => refreshLibrary.mutate(true)}
+              disabled={refreshLibrary.isPending}
+            >
+              Full Refresh
+            </Button>
             {refreshLibrary.data && (
               <span className="text-sm text-text-secondary">
-                Checked {refreshLibrary.data.found} projects ({refreshLibrary.data.updated} updated
+                Checked {refreshLibrary.data.found} projects ({refreshLibrary.data.updated} updated, {refreshLibrary.data.skipped} unchanged
                 {refreshLibrary.data.missing > 0 && `, ${refreshLibrary.data.missing} missing`}
                 {refreshLibrary.data.errors.length > 0 && `, ${refreshLibrary.data.errors.length} errors`})
               </span>
@@ -258,13 +456,13 @@ export function SettingsView() {
         <div className="border-t border-border-default pt-6">
           <h3 className="text-sm font-medium text-text-secondary mb-2">Import Projects</h3>
           <p className="text-xs text-text-muted mb-3">
-            Discover Ableton project folders in your root folder that aren't in the library yet.
+            Discover Ableton project folders in your library folders that aren't in the library yet.
           </p>
           <div className="flex items-center gap-3">
             <Button
               variant="secondary"
               onClick={handleDiscover}
-              disabled={discoverProjects.isPending || !rootFolder}
+              disabled={discoverProjects.isPending || noRootFolder}
             >
               {discoverProjects.isPending ? 'Discovering...' : 'Discover Untracked'}
             </Button>
@@ -339,7 +537,7 @@ export function SettingsView() {
           {/* Show message when discover returned empty */}
           {discoverProjects.isSuccess && discoveredList.length === 0 && (
             <p className="mt-3 text-sm text-text-muted">
-              No untracked projects found. All projects in your root folder are already in the library.
+              No untracked projects found. All projects in your library folders are already in the library.
             </p>
           )}
         </div>
@@ -348,6 +546,15 @@ export function SettingsView() {
   );
 }
 
+function parseStringList(value: string): string[] {
+  try {
+    const parsed = JSON.parse(value || '[]');
+    return Array.isArray(parsed) ? parsed.filter((r): r is string => typeof r === 'string') : [];
+  } catch {
+    return [];
+  }
+}
+
 function SoundCloudSection({ scPublicUpload, setScPublicUpload }: { scPublicUpload: boolean; setScPublicUpload: (v: boolean) => void }) {
   const scAuth = useSoundCloudAuthStatus();
   const scLogout = useSoundCloudLogout();